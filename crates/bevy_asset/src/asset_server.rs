@@ -1,17 +1,20 @@
 use crate::{
-    path::{AssetPath, AssetPathId, SourcePathId},
+    get_meta_path,
+    path::{hash_bytes, AssetPath, AssetPathId, SourcePathId},
     Asset, AssetIo, AssetIoError, AssetLifecycle, AssetLifecycleChannel, AssetLifecycleEvent,
-    AssetLoader, Assets, Handle, HandleId, HandleUntyped, LabelId, LoadContext, LoadState,
+    AssetLoadFailed, AssetLoader, AssetMemoryBudget, AssetReader, AssetSaver, Assets, Handle,
+    HandleId, HandleUntyped, LabelId, LoadContext, LoadFailure, LoadState, LoaderSettings,
     RefChange, RefChangeChannel, SourceInfo, SourceMeta,
 };
 use anyhow::Result;
+use bevy_app::EventWriter;
 use bevy_ecs::system::{Res, ResMut};
 use bevy_log::warn;
 use bevy_tasks::TaskPool;
-use bevy_utils::{HashMap, Uuid};
+use bevy_utils::{HashMap, HashSet, Uuid};
 use crossbeam_channel::TryRecvError;
 use parking_lot::{Mutex, RwLock};
-use std::{collections::hash_map::Entry, path::Path, sync::Arc};
+use std::{any::Any, collections::hash_map::Entry, path::Path, sync::Arc};
 use thiserror::Error;
 
 /// Errors that occur while loading assets with an AssetServer
@@ -23,10 +26,18 @@ pub enum AssetServerError {
     MissingAssetLoader { extensions: Vec<String> },
     #[error("the given type does not match the type of the loaded asset")]
     IncorrectHandleType,
-    #[error("encountered an error while loading an asset: {0}")]
+    #[error("encountered an error while loading an asset: {0:#}")]
     AssetLoaderError(anyhow::Error),
     #[error("encountered an error while reading an asset: {0}")]
     AssetIoError(#[from] AssetIoError),
+    #[error("no `AssetIo` registered for asset source: {0}")]
+    MissingAssetSource(String),
+    #[error("no `AssetSaver` found{}", format_missing_asset_ext(.extensions))]
+    MissingAssetSaver { extensions: Vec<String> },
+    #[error("the asset to save is not loaded")]
+    MissingAsset,
+    #[error("encountered an error while saving an asset: {0:#}")]
+    AssetSaverError(anyhow::Error),
 }
 
 fn format_missing_asset_ext(exts: &[String]) -> String {
@@ -50,12 +61,20 @@ pub(crate) struct AssetRefCounter {
 
 pub struct AssetServerInternal {
     pub(crate) asset_io: Box<dyn AssetIo>,
+    pub(crate) named_asset_io: RwLock<HashMap<String, Arc<dyn AssetIo>>>,
     pub(crate) asset_ref_counter: AssetRefCounter,
     pub(crate) asset_sources: Arc<RwLock<HashMap<SourcePathId, SourceInfo>>>,
     pub(crate) asset_lifecycles: Arc<RwLock<HashMap<Uuid, Box<dyn AssetLifecycle>>>>,
     loaders: RwLock<Vec<Arc<dyn AssetLoader>>>,
     extension_to_loader_index: RwLock<HashMap<String, usize>>,
     handle_to_path: Arc<RwLock<HashMap<HandleId, AssetPath<'static>>>>,
+    loader_settings: RwLock<HashMap<SourcePathId, Arc<dyn LoaderSettings>>>,
+    // holds a `Vec<Arc<dyn AssetSaver<Asset = T>>>` per asset type
+    savers: RwLock<HashMap<Uuid, Box<dyn Any + Send + Sync>>>,
+    pending_saves: Mutex<HashMap<Uuid, Vec<(HandleId, AssetPath<'static>)>>>,
+    saved_content_hashes: Mutex<HashMap<SourcePathId, u64>>,
+    load_failures: Mutex<Vec<AssetLoadFailed>>,
+    memory_budgeted_types: RwLock<HashSet<Uuid>>,
     task_pool: TaskPool,
 }
 
@@ -86,8 +105,15 @@ impl AssetServer {
                 asset_ref_counter: Default::default(),
                 handle_to_path: Default::default(),
                 asset_lifecycles: Default::default(),
+                loader_settings: Default::default(),
+                savers: Default::default(),
+                pending_saves: Default::default(),
+                saved_content_hashes: Default::default(),
+                load_failures: Default::default(),
+                memory_budgeted_types: Default::default(),
                 task_pool,
                 asset_io,
+                named_asset_io: Default::default(),
             }),
         }
     }
@@ -115,8 +141,62 @@ impl AssetServer {
         loaders.push(Arc::new(loader));
     }
 
+    pub fn add_saver<T>(&self, saver: T)
+    where
+        T: AssetSaver,
+    {
+        self.server
+            .savers
+            .write()
+            .entry(T::Asset::TYPE_UUID)
+            .or_insert_with(|| Box::new(Vec::<Arc<dyn AssetSaver<Asset = T::Asset>>>::new()))
+            .downcast_mut::<Vec<Arc<dyn AssetSaver<Asset = T::Asset>>>>()
+            .unwrap()
+            .push(Arc::new(saver));
+    }
+
+    /// Registers `asset_io` as the named asset source `name`, which is addressed by asset paths
+    /// of the form `"name://path/to/asset"`.
+    pub fn add_source<T: AssetIo>(&self, name: impl Into<String>, asset_io: T) {
+        self.add_boxed_source(name, Box::new(asset_io));
+    }
+
+    pub fn add_boxed_source(&self, name: impl Into<String>, asset_io: Box<dyn AssetIo>) {
+        self.server
+            .named_asset_io
+            .write()
+            .insert(name.into(), Arc::from(asset_io));
+    }
+
+    /// Returns the `AssetIo` registered for the named asset source `name`.
+    pub fn get_source(&self, name: &str) -> Option<Arc<dyn AssetIo>> {
+        self.server.named_asset_io.read().get(name).cloned()
+    }
+
+    /// Opens the asset at `path` for incremental reading through the [`AssetIo`] of its source.
+    ///
+    /// Unlike [`AssetServer::load`], this reads on the calling thread and does not create an
+    /// asset. It is meant for assets that are consumed as a stream, such as long audio tracks.
+    pub fn open_path<'a, P: Into<AssetPath<'a>>>(
+        &self,
+        path: P,
+    ) -> Result<Box<dyn AssetReader>, AssetServerError> {
+        let asset_path: AssetPath = path.into();
+        let reader = match asset_path.source() {
+            Some(source) => self
+                .get_source(source)
+                .ok_or_else(|| AssetServerError::MissingAssetSource(source.to_string()))?
+                .open_path(asset_path.path())?,
+            None => self.server.asset_io.open_path(asset_path.path())?,
+        };
+        Ok(reader)
+    }
+
     pub fn watch_for_changes(&self) -> Result<(), AssetServerError> {
         self.server.asset_io.watch_for_changes()?;
+        for asset_io in self.server.named_asset_io.read().values() {
+            asset_io.watch_for_changes()?;
+        }
         Ok(())
     }
 
@@ -173,6 +253,38 @@ impl AssetServer {
         })
     }
 
+    fn get_path_asset_saver<T: Asset>(
+        &self,
+        path: &Path,
+    ) -> Result<Arc<dyn AssetSaver<Asset = T>>, AssetServerError> {
+        let file_name = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .map(|file_name| file_name.to_lowercase())
+            .unwrap_or_default();
+        let savers = self.server.savers.read();
+        let savers = savers
+            .get(&T::TYPE_UUID)
+            .and_then(|savers| savers.downcast_ref::<Vec<Arc<dyn AssetSaver<Asset = T>>>>());
+
+        let mut exts = Vec::new();
+        let mut ext = file_name.as_str();
+        while let Some(idx) = ext.find('.') {
+            ext = &ext[idx + 1..];
+            exts.push(ext);
+            if let Some(saver) = savers
+                .into_iter()
+                .flatten()
+                .find(|saver| saver.extensions().contains(&ext))
+            {
+                return Ok(saver.clone());
+            }
+        }
+        Err(AssetServerError::MissingAssetSaver {
+            extensions: exts.into_iter().map(String::from).collect(),
+        })
+    }
+
     pub fn get_handle_path<H: Into<HandleId>>(&self, handle: H) -> Option<AssetPath<'_>> {
         self.server
             .handle_to_path
@@ -181,6 +293,17 @@ impl AssetServer {
             .cloned()
     }
 
+    /// Returns true if strong handles to the asset exist, as counted the last time unused
+    /// assets were marked, at the start of the frame.
+    pub fn has_strong_handles<H: Into<HandleId>>(&self, handle: H) -> bool {
+        self.server
+            .asset_ref_counter
+            .ref_counts
+            .read()
+            .get(&handle.into())
+            .map_or(false, |count| *count > 0)
+    }
+
     pub fn get_load_state<H: Into<HandleId>>(&self, handle: H) -> LoadState {
         match handle.into() {
             HandleId::AssetPathId(id) => {
@@ -193,6 +316,79 @@ impl AssetServer {
         }
     }
 
+    /// Returns the error the asset failed to load with, if its last load failed.
+    pub fn get_load_error<H: Into<HandleId>>(&self, handle: H) -> Option<String> {
+        match handle.into() {
+            HandleId::AssetPathId(id) => {
+                let asset_sources = self.server.asset_sources.read();
+                asset_sources
+                    .get(&id.source_path_id())
+                    .and_then(|info| info.load_error.clone())
+            }
+            HandleId::Id(_, _) => None,
+        }
+    }
+
+    /// Returns the load state of an asset together with all of its dependencies, recursively.
+    ///
+    /// This is [`LoadState::Loaded`] once the asset and every asset it depends on have loaded,
+    /// and [`LoadState::Failed`] as soon as any of them failed to load.
+    pub fn get_recursive_load_state<H: Into<HandleId>>(&self, handle: H) -> LoadState {
+        self.get_recursive_load_state_impl(handle.into()).0
+    }
+
+    /// Returns the first asset that failed to load among an asset and all of its dependencies,
+    /// recursively.
+    pub fn get_recursive_load_failure<H: Into<HandleId>>(&self, handle: H) -> Option<LoadFailure> {
+        let failed_source_path_id = self.get_recursive_load_state_impl(handle.into()).1?;
+        let asset_sources = self.server.asset_sources.read();
+        let source_info = asset_sources.get(&failed_source_path_id)?;
+        Some(LoadFailure {
+            path: source_info.asset_path(),
+            error: source_info.load_error.clone().unwrap_or_default(),
+        })
+    }
+
+    fn get_recursive_load_state_impl(&self, handle: HandleId) -> (LoadState, Option<SourcePathId>) {
+        let root_source_path_id = match handle {
+            HandleId::AssetPathId(id) => id.source_path_id(),
+            HandleId::Id(_, _) => return (LoadState::NotLoaded, None),
+        };
+
+        let asset_sources = self.server.asset_sources.read();
+        let mut visited = HashSet::default();
+        let mut stack = vec![root_source_path_id];
+        let mut load_state = LoadState::Loaded;
+        while let Some(source_path_id) = stack.pop() {
+            if !visited.insert(source_path_id) {
+                continue;
+            }
+
+            let source_info = asset_sources.get(&source_path_id);
+            let state = source_info.map_or(LoadState::NotLoaded, |info| info.load_state);
+            match state {
+                LoadState::Loaded => {
+                    let meta = source_info.and_then(|info| info.meta.as_ref());
+                    for asset_meta in meta.iter().flat_map(|meta| meta.assets.iter()) {
+                        for dependency in asset_meta.dependencies.iter() {
+                            stack.push(dependency.get_id().source_path_id());
+                        }
+                    }
+                }
+                LoadState::Failed => return (LoadState::Failed, Some(source_path_id)),
+                // dependencies are requested once their parent has loaded, so a dependency that
+                // has not started loading yet is about to
+                LoadState::NotLoaded if source_path_id != root_source_path_id => {
+                    load_state = LoadState::Loading;
+                }
+                LoadState::Loading => load_state = LoadState::Loading,
+                LoadState::NotLoaded | LoadState::Unloaded => return (state, None),
+            }
+        }
+
+        (load_state, None)
+    }
+
     pub fn get_group_load_state(&self, handles: impl IntoIterator<Item = HandleId>) -> LoadState {
         let mut load_state = LoadState::Loaded;
         for handle_id in handles {
@@ -231,6 +427,63 @@ impl AssetServer {
         self.load_untyped(path).typed()
     }
 
+    /// Loads an Asset at the provided relative path like [`AssetServer::load`], passing `settings`
+    /// to its [`AssetLoader`] instead of the settings in the asset's `.meta` file.
+    ///
+    /// The settings are kept and used again whenever the asset is reloaded.
+    #[must_use = "not using the returned strong handle may result in the unexpected release of the asset"]
+    pub fn load_with_settings<'a, T: Asset, S: LoaderSettings, P: Into<AssetPath<'a>>>(
+        &self,
+        path: P,
+        settings: S,
+    ) -> Handle<T> {
+        let asset_path = path.into();
+        self.server
+            .loader_settings
+            .write()
+            .insert(asset_path.get_id().source_path_id(), Arc::new(settings));
+        // force a load, as the asset may already be loaded with different settings
+        let handle_id = self.load_untracked(asset_path, true);
+        self.get_handle(handle_id)
+    }
+
+    async fn load_loader_settings(
+        &self,
+        asset_io: &dyn AssetIo,
+        asset_loader: &dyn AssetLoader,
+        asset_path: &AssetPath<'_>,
+    ) -> Result<Option<Arc<dyn LoaderSettings>>, AssetServerError> {
+        // settings passed from code take precedence over the `.meta` file
+        let source_path_id = asset_path.get_id().source_path_id();
+        let settings = self
+            .server
+            .loader_settings
+            .read()
+            .get(&source_path_id)
+            .cloned();
+        if settings.is_some() || !asset_loader.has_settings() {
+            return Ok(settings);
+        }
+
+        // the `.meta` file is watched even if it doesn't exist, so creating it reloads the asset
+        let meta_path = get_meta_path(asset_path.path());
+        asset_io.watch_path_for_changes(&meta_path)?;
+        match asset_io.load_path(&meta_path).await {
+            Ok(bytes) => match asset_loader.deserialize_settings(&bytes) {
+                Ok(settings) => Ok(settings.map(Arc::from)),
+                Err(err) => {
+                    warn!(
+                        "failed to read the settings in {:?}, the default settings are used: {}",
+                        meta_path, err
+                    );
+                    Ok(None)
+                }
+            },
+            Err(AssetIoError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     async fn load_async(
         &self,
         asset_path: AssetPath<'_>,
@@ -249,8 +502,10 @@ impl AssetServer {
                     committed_assets: Default::default(),
                     load_state: LoadState::NotLoaded,
                     meta: None,
+                    source: asset_path.source().map(|source| source.to_string()),
                     path: asset_path.path().to_owned(),
                     version: 0,
+                    load_error: None,
                 }),
             };
 
@@ -268,42 +523,80 @@ impl AssetServer {
             source_info.committed_assets.clear();
             source_info.version += 1;
             source_info.meta = None;
+            source_info.load_error = None;
             source_info.version
         };
 
-        let set_asset_failed = || {
+        let set_asset_failed = |err: &AssetServerError| {
             let mut asset_sources = self.server.asset_sources.write();
             let source_info = asset_sources
                 .get_mut(&asset_path_id.source_path_id())
                 .expect("`AssetSource` should exist at this point.");
             source_info.load_state = LoadState::Failed;
+            source_info.load_error = Some(err.to_string());
+            self.server.load_failures.lock().push(AssetLoadFailed {
+                id: asset_path_id.into(),
+                path: source_info.asset_path(),
+                error: err.to_string(),
+            });
         };
 
         // get the according asset loader
         let asset_loader = match self.get_path_asset_loader(asset_path.path()) {
             Ok(loader) => loader,
             Err(err) => {
-                set_asset_failed();
+                set_asset_failed(&err);
                 return Err(err);
             }
         };
 
+        // get the asset io of the source the asset lives in
+        let named_asset_io;
+        let asset_io = match asset_path.source() {
+            Some(source) => match self.get_source(source) {
+                Some(source_io) => {
+                    named_asset_io = source_io;
+                    &*named_asset_io
+                }
+                None => {
+                    let err = AssetServerError::MissingAssetSource(source.to_string());
+                    set_asset_failed(&err);
+                    return Err(err);
+                }
+            },
+            None => &*self.server.asset_io,
+        };
+
         // load the asset bytes
-        let bytes = match self.server.asset_io.load_path(asset_path.path()).await {
+        let bytes = match asset_io.load_path(asset_path.path()).await {
             Ok(bytes) => bytes,
             Err(err) => {
-                set_asset_failed();
-                return Err(AssetServerError::AssetIoError(err));
+                let err = AssetServerError::AssetIoError(err);
+                set_asset_failed(&err);
+                return Err(err);
+            }
+        };
+
+        let settings = match self
+            .load_loader_settings(asset_io, &*asset_loader, &asset_path)
+            .await
+        {
+            Ok(settings) => settings,
+            Err(err) => {
+                set_asset_failed(&err);
+                return Err(err);
             }
         };
 
         // load the asset source using the corresponding AssetLoader
         let mut load_context = LoadContext::new(
+            asset_path.source(),
             asset_path.path(),
             &self.server.asset_ref_counter.channel,
-            &*self.server.asset_io,
+            asset_io,
             version,
             &self.server.task_pool,
+            settings,
         );
 
         if let Err(err) = asset_loader
@@ -311,10 +604,21 @@ impl AssetServer {
             .await
             .map_err(AssetServerError::AssetLoaderError)
         {
-            set_asset_failed();
+            set_asset_failed(&err);
             return Err(err);
         }
 
+        // dependencies without a source live in the same source as the asset itself
+        if let Some(source) = asset_path.source() {
+            for loaded_asset in load_context.labeled_assets.values_mut() {
+                for dependency in loaded_asset.dependencies.iter_mut() {
+                    if dependency.source().is_none() {
+                        *dependency = dependency.clone().with_source(Some(source.to_string()));
+                    }
+                }
+            }
+        }
+
         // if version has changed since we loaded and grabbed a lock, return. theres is a newer
         // version being loaded
         let mut asset_sources = self.server.asset_sources.write();
@@ -349,10 +653,7 @@ impl AssetServer {
             }
         }
 
-        self.server
-            .asset_io
-            .watch_path_for_changes(asset_path.path())
-            .unwrap();
+        asset_io.watch_path_for_changes(asset_path.path()).unwrap();
         self.create_assets_in_load_context(&mut load_context);
         Ok(asset_path_id)
     }
@@ -363,6 +664,112 @@ impl AssetServer {
         self.get_handle_untyped(handle_id)
     }
 
+    /// Saves the asset of `handle` to `path`, using the [`AssetSaver`] registered for the
+    /// extension of `path` and the [`AssetIo`] of the source of `path`.
+    ///
+    /// The asset is saved in the background once its `Assets<T>` collection is next updated.
+    /// Saving an asset that was loaded from `path` does not hot-reload it.
+    pub fn save<'a, T: Asset, P: Into<AssetPath<'a>>>(
+        &self,
+        handle: &Handle<T>,
+        path: P,
+    ) -> Result<(), AssetServerError> {
+        let asset_path = path.into();
+        self.get_path_asset_saver::<T>(asset_path.path())?;
+        if let Some(source) = asset_path.source() {
+            if self.get_source(source).is_none() {
+                return Err(AssetServerError::MissingAssetSource(source.to_string()));
+            }
+        }
+
+        self.server
+            .pending_saves
+            .lock()
+            .entry(T::TYPE_UUID)
+            .or_insert_with(Vec::new)
+            .push((handle.id, asset_path.to_owned()));
+        Ok(())
+    }
+
+    pub(crate) fn save_assets<T: Asset>(&self, assets: &Assets<T>) {
+        let pending_saves = match self.server.pending_saves.lock().remove(&T::TYPE_UUID) {
+            Some(pending_saves) => pending_saves,
+            None => return,
+        };
+        for (handle_id, asset_path) in pending_saves {
+            if let Err(err) = self.save_asset(assets, handle_id, asset_path.clone()) {
+                warn!("failed to save asset to {:?}: {}", asset_path, err);
+            }
+        }
+    }
+
+    fn save_asset<T: Asset>(
+        &self,
+        assets: &Assets<T>,
+        handle_id: HandleId,
+        asset_path: AssetPath<'static>,
+    ) -> Result<(), AssetServerError> {
+        let asset = assets
+            .get(handle_id)
+            .ok_or(AssetServerError::MissingAsset)?;
+        let saver = self.get_path_asset_saver::<T>(asset_path.path())?;
+        let bytes = saver
+            .save(asset)
+            .map_err(AssetServerError::AssetSaverError)?;
+
+        // the file watcher skips the change of an asset that was saved over its own source file
+        let source_path_id = asset_path.get_id().source_path_id();
+        if handle_id == HandleId::from(asset_path.get_id()) {
+            self.server
+                .saved_content_hashes
+                .lock()
+                .insert(source_path_id, hash_bytes(&bytes));
+        }
+
+        let server = self.clone();
+        self.server
+            .task_pool
+            .spawn(async move {
+                let named_asset_io = asset_path
+                    .source()
+                    .and_then(|source| server.get_source(source));
+                let asset_io = named_asset_io
+                    .as_deref()
+                    .unwrap_or(&*server.server.asset_io);
+                if let Err(err) = asset_io.save_path(asset_path.path(), &bytes).await {
+                    server
+                        .server
+                        .saved_content_hashes
+                        .lock()
+                        .remove(&source_path_id);
+                    warn!("failed to save asset to {:?}: {}", asset_path, err);
+                }
+            })
+            .detach();
+        Ok(())
+    }
+
+    /// Returns true if the asset at `asset_path` was saved by this server and still has the
+    /// contents it was saved with, so a change notification for it does not need to reload it.
+    pub(crate) fn is_saved_content(
+        &self,
+        asset_path: &AssetPath,
+        read_bytes: impl FnOnce() -> Option<Vec<u8>>,
+    ) -> bool {
+        let source_path_id = asset_path.get_id().source_path_id();
+        let mut saved_content_hashes = self.server.saved_content_hashes.lock();
+        let saved_hash = match saved_content_hashes.get(&source_path_id) {
+            Some(saved_hash) => *saved_hash,
+            None => return false,
+        };
+        if read_bytes().map(|bytes| hash_bytes(&bytes)) == Some(saved_hash) {
+            true
+        } else {
+            saved_content_hashes.remove(&source_path_id);
+            false
+        }
+    }
+
     pub(crate) fn load_untracked(&self, asset_path: AssetPath<'_>, force: bool) -> HandleId {
         let server = self.clone();
         let owned_path = asset_path.to_owned();
@@ -421,6 +828,7 @@ impl AssetServer {
             let ref_counts = self.server.asset_ref_counter.ref_counts.read();
             let asset_sources = self.server.asset_sources.read();
             let asset_lifecycles = self.server.asset_lifecycles.read();
+            let memory_budgeted_types = self.server.memory_budgeted_types.read();
             for potential_free in potential_frees.drain(..) {
                 if let Some(&0) = ref_counts.get(&potential_free) {
                     let type_uuid = match potential_free {
@@ -431,6 +839,13 @@ impl AssetServer {
                     };
 
                     if let Some(type_uuid) = type_uuid {
+                        // unused assets with a memory budget are evicted once it is exceeded,
+                        // unless they can't be loaded again
+                        if matches!(potential_free, HandleId::AssetPathId(_))
+                            && memory_budgeted_types.contains(&type_uuid)
+                        {
+                            continue;
+                        }
                         if let Some(asset_lifecycle) = asset_lifecycles.get(&type_uuid) {
                             asset_lifecycle.free_asset(potential_free);
                         }
@@ -440,6 +855,33 @@ impl AssetServer {
         }
     }
 
+    /// Registers `T` as an asset type with a memory budget, whose unused assets are only freed
+    /// once they are evicted.
+    pub(crate) fn add_memory_budgeted_type<T: Asset>(&self) {
+        self.server
+            .memory_budgeted_types
+            .write()
+            .insert(T::TYPE_UUID);
+    }
+
+    /// Frees the least recently used assets without strong handles until the assets of type `T`
+    /// fit into their memory budget.
+    pub(crate) fn evict_unused_assets<T: Asset>(&self, memory_budget: &mut AssetMemoryBudget<T>) {
+        let evictions = {
+            let ref_counts = self.server.asset_ref_counter.ref_counts.read();
+            memory_budget.find_evictions(|id| ref_counts.get(&id).map_or(false, |count| *count > 0))
+        };
+        if evictions.is_empty() {
+            return;
+        }
+
+        let asset_lifecycles = self.server.asset_lifecycles.read();
+        let asset_lifecycle = asset_lifecycles.get(&T::TYPE_UUID).unwrap();
+        for id in evictions {
+            asset_lifecycle.free_asset(id);
+        }
+    }
+
     pub fn mark_unused_assets(&self) {
         let receiver = &self.server.asset_ref_counter.channel.receiver;
         let mut ref_counts = self.server.asset_ref_counter.ref_counts.write();
@@ -476,7 +918,8 @@ impl AssetServer {
                 .expect("Asset should exist at this point.");
             if let Some(asset_lifecycle) = asset_lifecycles.get(&asset_value.type_uuid()) {
                 let asset_path =
-                    AssetPath::new_ref(load_context.path, label.as_ref().map(|l| l.as_str()));
+                    AssetPath::new_ref(load_context.path, label.as_ref().map(|l| l.as_str()))
+                        .with_source(load_context.source);
                 asset_lifecycle.create_asset(asset_path.into(), asset_value, load_context.version);
             } else {
                 panic!(
@@ -515,6 +958,7 @@ impl AssetServer {
                                 }
                             }
                         }
+                        channel.pending_dependency_loads.lock().insert(result.id);
                     }
 
                     assets.set_untracked(result.id, *result.asset);
@@ -528,6 +972,7 @@ impl AssetServer {
                             source_info.load_state = LoadState::Unloaded;
                         }
                     }
+                    channel.pending_dependency_loads.lock().remove(&handle_id);
                     assets.remove(handle_id);
                 }
                 Err(TryRecvError::Empty) => {
@@ -536,6 +981,20 @@ impl AssetServer {
                 Err(TryRecvError::Disconnected) => panic!("AssetChannel disconnected."),
             }
         }
+
+        // the recursive load state takes its own lock on the asset sources
+        drop(asset_sources_guard);
+        let mut pending_dependency_loads = channel.pending_dependency_loads.lock();
+        pending_dependency_loads.retain(|handle_id| {
+            match self.get_recursive_load_state(*handle_id) {
+                LoadState::Loaded => {
+                    assets.send_loaded_with_dependencies(*handle_id);
+                    false
+                }
+                LoadState::Loading => true,
+                LoadState::NotLoaded | LoadState::Failed | LoadState::Unloaded => false,
+            }
+        });
     }
 }
 
@@ -548,10 +1007,22 @@ pub fn free_unused_assets_system(asset_server: Res<AssetServer>) {
     free_unused_assets_system_impl(&asset_server);
 }
 
+/// Sends an [`AssetLoadFailed`] event for every asset that failed to load since the last run.
+pub fn asset_load_failed_event_system(
+    asset_server: Res<AssetServer>,
+    mut events: EventWriter<AssetLoadFailed>,
+) {
+    let mut load_failures = asset_server.server.load_failures.lock();
+    if !load_failures.is_empty() {
+        events.send_batch(load_failures.drain(..));
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{loader::LoadedAsset, update_asset_storage_system};
+    use crate::{loader::LoadedAsset, update_asset_storage_system, AssetEvent};
+    use bevy_app::Events;
     use bevy_ecs::prelude::*;
     use bevy_reflect::TypeUuid;
     use bevy_utils::BoxedFuture;
@@ -606,6 +1077,67 @@ mod test {
         }
     }
 
+    struct FakeDependentLoader;
+    impl AssetLoader for FakeDependentLoader {
+        fn load<'a>(
+            &'a self,
+            _: &'a [u8],
+            ctx: &'a mut LoadContext,
+        ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+            ctx.set_default_asset(LoadedAsset::new(PngAsset).with_dependency("fake.png".into()));
+            Box::pin(async move { Ok(()) })
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["dependent"]
+        }
+    }
+
+    #[derive(Debug, TypeUuid)]
+    #[uuid = "0a1b8ba6-2dd5-4a57-9b57-4c3b3e2a8d0e"]
+    struct SettingsAsset(u32);
+
+    #[derive(serde::Deserialize)]
+    struct FakeSettings {
+        value: u32,
+    }
+
+    struct FakeSettingsLoader;
+    impl AssetLoader for FakeSettingsLoader {
+        fn load<'a>(
+            &'a self,
+            _: &'a [u8],
+            ctx: &'a mut LoadContext,
+        ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+            let value = ctx
+                .settings::<FakeSettings>()
+                .map_or(0, |settings| settings.value);
+            ctx.set_default_asset(LoadedAsset::new(SettingsAsset(value)));
+            Box::pin(async move { Ok(()) })
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["settings"]
+        }
+
+        fn has_settings(&self) -> bool {
+            true
+        }
+
+        fn deserialize_settings(
+            &self,
+            bytes: &[u8],
+        ) -> Result<Option<Box<dyn LoaderSettings>>, anyhow::Error> {
+            crate::deserialize_ron_settings::<FakeSettings>(bytes)
+        }
+    }
+
+    #[derive(Debug, PartialEq, TypeUuid, serde::Serialize, serde::Deserialize)]
+    #[uuid = "3b8bc7a4-94a5-4d67-a7d6-f7b0b6a1e5a0"]
+    struct RonAsset {
+        value: u32,
+    }
+
     fn setup(asset_path: impl AsRef<Path>) -> AssetServer {
         use crate::FileAssetIo;
 
@@ -617,8 +1149,15 @@ mod test {
                 asset_ref_counter: Default::default(),
                 handle_to_path: Default::default(),
                 asset_lifecycles: Default::default(),
+                loader_settings: Default::default(),
+                savers: Default::default(),
+                pending_saves: Default::default(),
+                saved_content_hashes: Default::default(),
+                load_failures: Default::default(),
+                memory_budgeted_types: Default::default(),
                 task_pool: Default::default(),
                 asset_io: Box::new(FileAssetIo::new(asset_path)),
+                named_asset_io: Default::default(),
             }),
         }
     }
@@ -755,9 +1294,367 @@ mod test {
             .unwrap_err();
         assert!(matches!(err, AssetServerError::AssetLoaderError(_)));
 
+        let failure = asset_server.get_recursive_load_failure(&handle).unwrap();
+        assert_eq!(failure.path.path(), Path::new("fake.fail"));
+        assert_eq!(failure.error, err.to_string());
+        assert_eq!(asset_server.get_load_error(&handle), Some(err.to_string()));
+        assert_eq!(asset_server.get_load_state(&handle), LoadState::Failed);
+
+        let mut world = World::new();
+        world.insert_resource(asset_server);
+        world.insert_resource(Events::<AssetLoadFailed>::default());
+        let mut asset_load_failed_event_system = asset_load_failed_event_system.system();
+        asset_load_failed_event_system.initialize(&mut world);
+        asset_load_failed_event_system.run((), &mut world);
+
+        let events = world.get_resource::<Events<AssetLoadFailed>>().unwrap();
+        let failures = events
+            .get_reader()
+            .iter(events)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].id, handle.id);
+        assert_eq!(failures[0].path.path(), Path::new("fake.fail"));
+        assert!(failures[0].error.contains("failed"));
+    }
+
+    #[test]
+    fn test_named_asset_source() {
+        let asset_server = setup(".");
+        asset_server.add_loader(FakePngLoader);
+        let _assets = asset_server.register_asset_type::<PngAsset>();
+        let memory_io = crate::MemoryAssetIo::default();
+        memory_io.insert_asset("fake.png", Vec::new());
+        asset_server.add_source("mem", memory_io);
+
+        let path: AssetPath = "mem://fake.png".into();
+        assert_eq!(path.source(), Some("mem"));
+        assert_eq!(path.path(), Path::new("fake.png"));
+        assert_ne!(path.get_id(), AssetPath::from("fake.png").get_id());
+
+        futures_lite::future::block_on(asset_server.load_async(path.clone(), true)).unwrap();
+
+        let path: AssetPath = "missing://fake.png".into();
+        let handle = asset_server.get_handle_untyped(path.get_id());
+        let err = futures_lite::future::block_on(asset_server.load_async(path.clone(), true))
+            .unwrap_err();
+        assert!(matches!(err, AssetServerError::MissingAssetSource(_)));
         assert_eq!(asset_server.get_load_state(handle), LoadState::Failed);
     }
 
+    #[test]
+    fn test_open_path() {
+        use std::io::Read;
+
+        let dir = create_dir_and_file("file.txt");
+        std::fs::write(dir.path().join("file.txt"), "on disk").unwrap();
+        let asset_server = setup(dir.path());
+        let memory_io = crate::MemoryAssetIo::default();
+        memory_io.insert_asset("file.txt", b"in memory".to_vec());
+        asset_server.add_source("mem", memory_io);
+
+        let read = |path: &str| {
+            let mut contents = String::new();
+            asset_server
+                .open_path(path)
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            contents
+        };
+        assert_eq!(read("file.txt"), "on disk");
+        assert_eq!(read("mem://file.txt"), "in memory");
+
+        assert!(matches!(
+            asset_server.open_path("missing.txt"),
+            Err(AssetServerError::AssetIoError(AssetIoError::NotFound(_)))
+        ));
+        assert!(matches!(
+            asset_server.open_path("missing://file.txt"),
+            Err(AssetServerError::MissingAssetSource(_))
+        ));
+    }
+
+    #[test]
+    fn test_loader_settings() {
+        let dir = create_dir_and_file("file.settings");
+        std::fs::write(dir.path().join("file.settings.meta"), "(value: 3)").unwrap();
+        let asset_server = setup(dir.path());
+        asset_server.add_loader(FakeSettingsLoader);
+        let assets = asset_server.register_asset_type::<SettingsAsset>();
+
+        let mut world = World::new();
+        world.insert_resource(assets);
+        world.insert_resource(asset_server);
+        let mut update_asset_storage_system = update_asset_storage_system::<SettingsAsset>.system();
+        update_asset_storage_system.initialize(&mut world);
+
+        // settings are read from the `.meta` file
+        let id = {
+            let asset_server = world.get_resource::<AssetServer>().unwrap();
+            futures_lite::future::block_on(asset_server.load_async("file.settings".into(), true))
+                .unwrap()
+        };
+        update_asset_storage_system.run((), &mut world);
+        let assets = world.get_resource::<Assets<SettingsAsset>>().unwrap();
+        assert_eq!(assets.get(id).unwrap().0, 3);
+
+        // settings passed from code take precedence, and reload the asset in the background
+        let handle: Handle<SettingsAsset> = world
+            .get_resource::<AssetServer>()
+            .unwrap()
+            .load_with_settings("file.settings", FakeSettings { value: 7 });
+        assert_eq!(handle.id, id.into());
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        loop {
+            update_asset_storage_system.run((), &mut world);
+            let assets = world.get_resource::<Assets<SettingsAsset>>().unwrap();
+            if assets.get(&handle).unwrap().0 == 7 {
+                break;
+            }
+            assert!(
+                std::time::Instant::now() < deadline,
+                "the asset was not reloaded"
+            );
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn test_invalid_loader_settings_are_ignored() {
+        let dir = create_dir_and_file("file.settings");
+        std::fs::write(dir.path().join("file.settings.meta"), "(value: \"three\")").unwrap();
+        let asset_server = setup(dir.path());
+        asset_server.add_loader(FakeSettingsLoader);
+        let assets = asset_server.register_asset_type::<SettingsAsset>();
+
+        let mut world = World::new();
+        world.insert_resource(assets);
+        world.insert_resource(asset_server);
+        let mut update_asset_storage_system = update_asset_storage_system::<SettingsAsset>.system();
+        update_asset_storage_system.initialize(&mut world);
+
+        let id = {
+            let asset_server = world.get_resource::<AssetServer>().unwrap();
+            futures_lite::future::block_on(asset_server.load_async("file.settings".into(), true))
+                .unwrap()
+        };
+        update_asset_storage_system.run((), &mut world);
+        let assets = world.get_resource::<Assets<SettingsAsset>>().unwrap();
+        assert_eq!(assets.get(id).unwrap().0, 0);
+    }
+
+    #[test]
+    fn test_meta_files_are_only_read_for_loaders_with_settings() {
+        let memory_io = crate::MemoryAssetIo::default();
+        for path in &["fake.png", "fake.png.meta", "file.settings"] {
+            memory_io.insert_asset(*path, b"()".to_vec());
+        }
+        let (saved, _) = crossbeam_channel::unbounded();
+        let asset_server = AssetServer::new(
+            RecordingAssetIo {
+                memory_io,
+                loaded: Default::default(),
+                saved,
+            },
+            TaskPool::default(),
+        );
+        asset_server.add_loader(FakePngLoader);
+        asset_server.add_loader(FakeSettingsLoader);
+        let _png_assets = asset_server.register_asset_type::<PngAsset>();
+        let _settings_assets = asset_server.register_asset_type::<SettingsAsset>();
+
+        for path in &["fake.png", "file.settings"] {
+            futures_lite::future::block_on(asset_server.load_async((*path).into(), true)).unwrap();
+        }
+
+        let loaded = asset_server
+            .server
+            .asset_io
+            .downcast_ref::<RecordingAssetIo>()
+            .unwrap()
+            .loaded
+            .lock()
+            .clone();
+        let loaded = loaded
+            .iter()
+            .map(|path| path.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(loaded, ["fake.png", "file.settings", "file.settings.meta"]);
+    }
+
+    /// Serves assets from memory, recording every load and reporting every completed save
+    struct RecordingAssetIo {
+        memory_io: crate::MemoryAssetIo,
+        loaded: Mutex<Vec<std::path::PathBuf>>,
+        saved: crossbeam_channel::Sender<std::path::PathBuf>,
+    }
+
+    impl AssetIo for RecordingAssetIo {
+        fn load_path<'a>(
+            &'a self,
+            path: &'a Path,
+        ) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+            self.loaded.lock().push(path.to_owned());
+            self.memory_io.load_path(path)
+        }
+
+        fn save_path<'a>(
+            &'a self,
+            path: &'a Path,
+            bytes: &'a [u8],
+        ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+            Box::pin(async move {
+                self.memory_io.save_path(path, bytes).await?;
+                self.saved.send(path.to_owned()).unwrap();
+                Ok(())
+            })
+        }
+
+        fn read_directory(
+            &self,
+            path: &Path,
+        ) -> Result<Box<dyn Iterator<Item = std::path::PathBuf>>, AssetIoError> {
+            self.memory_io.read_directory(path)
+        }
+
+        fn is_directory(&self, path: &Path) -> bool {
+            self.memory_io.is_directory(path)
+        }
+
+        fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+            self.memory_io.watch_path_for_changes(path)
+        }
+
+        fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+            self.memory_io.watch_for_changes()
+        }
+    }
+
+    #[test]
+    fn test_save_asset() {
+        let (sender, saved) = crossbeam_channel::unbounded();
+        let asset_server = AssetServer::new(
+            RecordingAssetIo {
+                memory_io: Default::default(),
+                loaded: Default::default(),
+                saved: sender,
+            },
+            TaskPool::default(),
+        );
+        asset_server.add_saver(crate::RonAssetSaver::<RonAsset>::default());
+        let mut assets = asset_server.register_asset_type::<RonAsset>();
+        let read = |path: &str| {
+            let bytes = futures_lite::future::block_on(
+                asset_server.server.asset_io.load_path(path.as_ref()),
+            )
+            .unwrap();
+            ron::de::from_bytes::<RonAsset>(&bytes).unwrap()
+        };
+        let wait_for_save = || {
+            saved
+                .recv_timeout(std::time::Duration::from_secs(10))
+                .expect("the asset was not saved")
+        };
+
+        let handle = assets.add(RonAsset { value: 7 });
+        let err = asset_server.save(&handle, "asset.txt").unwrap_err();
+        assert!(matches!(err, AssetServerError::MissingAssetSaver { .. }));
+
+        // the file is written in the background
+        asset_server.save(&handle, "saved/asset.ron").unwrap();
+        asset_server.save_assets(&assets);
+        assert_eq!(wait_for_save(), Path::new("saved/asset.ron"));
+        assert_eq!(read("saved/asset.ron"), RonAsset { value: 7 });
+
+        // saving an asset over the file it was loaded from is not a change to hot-reload
+        let asset_path = AssetPath::from("asset.ron");
+        let loaded = assets.set(asset_path.get_id(), RonAsset { value: 3 });
+        asset_server.save(&loaded, asset_path.clone()).unwrap();
+        asset_server.save_assets(&assets);
+        assert_eq!(wait_for_save(), Path::new("asset.ron"));
+        assert_eq!(read("asset.ron"), RonAsset { value: 3 });
+        let read_bytes = || {
+            futures_lite::future::block_on(
+                asset_server.server.asset_io.load_path(asset_path.path()),
+            )
+            .ok()
+        };
+        assert!(asset_server.is_saved_content(&asset_path, read_bytes));
+
+        // an outside change to the file is reloaded
+        futures_lite::future::block_on(
+            asset_server
+                .server
+                .asset_io
+                .save_path(asset_path.path(), b"(value: 4)"),
+        )
+        .unwrap();
+        wait_for_save();
+        assert!(!asset_server.is_saved_content(&asset_path, read_bytes));
+    }
+
+    #[test]
+    fn test_recursive_load_state() {
+        let dir = create_dir_and_file("fake.png");
+        std::fs::write(dir.path().join("fake.dependent"), &[]).unwrap();
+        let asset_server = setup(dir.path());
+        asset_server.add_loader(FakePngLoader);
+        asset_server.add_loader(FakeDependentLoader);
+        let assets = asset_server.register_asset_type::<PngAsset>();
+
+        let mut world = World::new();
+        world.insert_resource(assets);
+        world.insert_resource(asset_server);
+        world.insert_resource(Events::<AssetEvent<PngAsset>>::default());
+
+        let mut tick = {
+            let mut update_asset_storage_system = update_asset_storage_system::<PngAsset>.system();
+            update_asset_storage_system.initialize(&mut world);
+            let mut asset_event_system = Assets::<PngAsset>::asset_event_system.system();
+            asset_event_system.initialize(&mut world);
+
+            move |world: &mut World| {
+                update_asset_storage_system.run((), world);
+                asset_event_system.run((), world);
+            }
+        };
+
+        fn load_asset(path: &str, world: &World) -> AssetPathId {
+            let asset_server = world.get_resource::<AssetServer>().unwrap();
+            futures_lite::future::block_on(asset_server.load_async(path.into(), true)).unwrap()
+        }
+
+        fn get_recursive_load_state(id: AssetPathId, world: &World) -> LoadState {
+            world
+                .get_resource::<AssetServer>()
+                .unwrap()
+                .get_recursive_load_state(id)
+        }
+
+        // load the dependency up front, so it is not loaded again in the background
+        let dependency = load_asset("fake.png", &world);
+        tick(&mut world);
+        assert_eq!(
+            LoadState::Loaded,
+            get_recursive_load_state(dependency, &world)
+        );
+
+        let id = load_asset("fake.dependent", &world);
+        assert_eq!(LoadState::Loading, get_recursive_load_state(id, &world));
+        tick(&mut world);
+        assert_eq!(LoadState::Loaded, get_recursive_load_state(id, &world));
+
+        let events = world
+            .get_resource::<Events<AssetEvent<PngAsset>>>()
+            .unwrap();
+        assert!(events.get_reader().iter(events).any(|event| matches!(
+            event,
+            AssetEvent::LoadedWithDependencies { handle } if handle.id == id.into()
+        )));
+    }
+
     #[test]
     fn test_asset_lifecycle() {
         let dir = create_dir_and_file("fake.png");
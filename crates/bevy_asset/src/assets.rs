@@ -1,6 +1,7 @@
 use crate::{
-    update_asset_storage_system, Asset, AssetLoader, AssetServer, AssetStage, Handle, HandleId,
-    RefChange,
+    evict_unused_assets_system, save_assets_system, update_asset_storage_system, Asset,
+    AssetLoader, AssetMemoryBudget, AssetSaver, AssetServer, AssetSize, AssetStage, Handle,
+    HandleId, RefChange,
 };
 use bevy_app::{App, EventWriter, Events};
 use bevy_ecs::{system::ResMut, world::FromWorld};
@@ -9,10 +10,14 @@ use crossbeam_channel::Sender;
 use std::fmt::Debug;
 
 /// Events that happen on assets of type `T`
+///
+/// `LoadedWithDependencies` is sent once an asset loaded by the [`AssetServer`] and all of its
+/// dependencies, recursively, have finished loading.
 pub enum AssetEvent<T: Asset> {
     Created { handle: Handle<T> },
     Modified { handle: Handle<T> },
     Removed { handle: Handle<T> },
+    LoadedWithDependencies { handle: Handle<T> },
 }
 
 impl<T: Asset> Debug for AssetEvent<T> {
@@ -39,6 +44,13 @@ impl<T: Asset> Debug for AssetEvent<T> {
                 ))
                 .field("handle", &handle.id)
                 .finish(),
+            AssetEvent::LoadedWithDependencies { handle } => f
+                .debug_struct(&format!(
+                    "AssetEvent<{}>::LoadedWithDependencies",
+                    std::any::type_name::<T>()
+                ))
+                .field("handle", &handle.id)
+                .finish(),
         }
     }
 }
@@ -89,6 +101,12 @@ impl<T: Asset> Assets<T> {
         }
     }
 
+    pub(crate) fn send_loaded_with_dependencies(&mut self, id: HandleId) {
+        self.events.send(AssetEvent::LoadedWithDependencies {
+            handle: Handle::weak(id),
+        });
+    }
+
     pub fn get<H: Into<HandleId>>(&self, handle: H) -> Option<&T> {
         self.assets.get(&handle.into())
     }
@@ -201,6 +219,19 @@ pub trait AddAsset {
     fn add_asset_loader<T>(&mut self, loader: T) -> &mut Self
     where
         T: AssetLoader;
+    fn init_asset_saver<T>(&mut self) -> &mut Self
+    where
+        T: AssetSaver + FromWorld;
+    fn add_asset_saver<T>(&mut self, saver: T) -> &mut Self
+    where
+        T: AssetSaver;
+    fn set_asset_memory_budget<T>(&mut self, budget: usize) -> &mut Self
+    where
+        T: Asset + AssetSize;
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    fn add_asset_processor<T>(&mut self, processor: T) -> &mut Self
+    where
+        T: crate::AssetProcessor;
 }
 
 impl AddAsset for App {
@@ -216,6 +247,7 @@ impl AddAsset for App {
         self.insert_resource(assets)
             .add_system_to_stage(AssetStage::AssetEvents, Assets::<T>::asset_event_system)
             .add_system_to_stage(AssetStage::LoadAssets, update_asset_storage_system::<T>)
+            .add_system_to_stage(AssetStage::AssetEvents, save_assets_system::<T>)
             .register_type::<Handle<T>>()
             .add_event::<AssetEvent<T>>()
     }
@@ -238,4 +270,58 @@ impl AddAsset for App {
             .add_loader(loader);
         self
     }
+
+    fn init_asset_saver<T>(&mut self) -> &mut Self
+    where
+        T: AssetSaver + FromWorld,
+    {
+        let result = T::from_world(&mut self.world);
+        self.add_asset_saver(result)
+    }
+
+    fn add_asset_saver<T>(&mut self, saver: T) -> &mut Self
+    where
+        T: AssetSaver,
+    {
+        self.world
+            .get_resource_mut::<AssetServer>()
+            .expect("AssetServer does not exist. Consider adding it as a resource.")
+            .add_saver(saver);
+        self
+    }
+
+    fn set_asset_memory_budget<T>(&mut self, budget: usize) -> &mut Self
+    where
+        T: Asset + AssetSize,
+    {
+        if let Some(mut memory_budget) = self.world.get_resource_mut::<AssetMemoryBudget<T>>() {
+            memory_budget.set_budget(budget);
+            return self;
+        }
+
+        let memory_budget = {
+            let assets = self
+                .world
+                .get_resource::<Assets<T>>()
+                .expect("Assets do not exist. Consider adding them with `add_asset` first.");
+            AssetMemoryBudget::new(budget, assets)
+        };
+        self.world
+            .get_resource::<AssetServer>()
+            .expect("AssetServer does not exist. Consider adding it as a resource.")
+            .add_memory_budgeted_type::<T>();
+        self.insert_resource(memory_budget)
+            .add_system_to_stage(AssetStage::AssetEvents, evict_unused_assets_system::<T>)
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    fn add_asset_processor<T>(&mut self, processor: T) -> &mut Self
+    where
+        T: crate::AssetProcessor,
+    {
+        self.world
+            .get_resource_or_insert_with(crate::AssetProcessors::default)
+            .add_processor(processor);
+        self
+    }
 }
@@ -0,0 +1,341 @@
+use crate::{Asset, AssetEvent, AssetServer, Assets, HandleId};
+use bevy_app::EventReader;
+use bevy_ecs::system::{Res, ResMut};
+use bevy_utils::HashMap;
+use std::{fmt::Debug, marker::PhantomData};
+
+/// Reports the memory used by an asset, which is needed to give its [`Assets`] collection a
+/// memory budget with [`AddAsset::set_asset_memory_budget`](crate::AddAsset::set_asset_memory_budget)
+pub trait AssetSize {
+    /// Returns the number of bytes used by the asset, including its heap allocations.
+    fn asset_size(&self) -> usize;
+}
+
+/// Limits the memory used by the assets in the [`Assets`] collection of type `T`
+///
+/// This resource is added with
+/// [`AddAsset::set_asset_memory_budget`](crate::AddAsset::set_asset_memory_budget). Assets
+/// loaded from a path are kept when their last strong handle is dropped, and once the budget is
+/// exceeded the least recently used of them are evicted. An evicted asset is loaded again the
+/// next time it is loaded with the [`AssetServer`].
+///
+/// Memory usage is updated from the [`AssetEvent`]s of the collection. Assets that are borrowed
+/// with [`Assets::get`] do not count as used, only holding a strong handle or mutating an asset
+/// does.
+pub struct AssetMemoryBudget<T: Asset> {
+    memory_budget: MemoryBudget,
+    size_of: fn(&T) -> usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Asset> Debug for AssetMemoryBudget<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssetMemoryBudget")
+            .field("budget", &self.memory_budget.budget)
+            .field("usage", &self.memory_budget.usage)
+            .field("frame", &self.memory_budget.frame)
+            .finish()
+    }
+}
+
+impl<T: Asset> AssetMemoryBudget<T> {
+    pub(crate) fn new(budget: usize, assets: &Assets<T>) -> Self
+    where
+        T: AssetSize,
+    {
+        let mut memory_budget = MemoryBudget::new(budget);
+        for (id, asset) in assets.iter() {
+            memory_budget.track(id, asset.asset_size());
+        }
+        Self {
+            memory_budget,
+            size_of: T::asset_size,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the memory budget in bytes.
+    pub fn budget(&self) -> usize {
+        self.memory_budget.budget
+    }
+
+    pub fn set_budget(&mut self, budget: usize) {
+        self.memory_budget.budget = budget;
+    }
+
+    /// Returns the number of bytes used by the assets of the collection.
+    pub fn usage(&self) -> usize {
+        self.memory_budget.usage
+    }
+
+    /// Updates the memory usage of the assets that changed since the last update.
+    pub(crate) fn update<'a>(
+        &mut self,
+        assets: &Assets<T>,
+        events: impl Iterator<Item = &'a AssetEvent<T>>,
+    ) {
+        for event in events {
+            match event {
+                AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                    match assets.get(handle) {
+                        Some(asset) => self.memory_budget.track(handle.id, (self.size_of)(asset)),
+                        None => self.memory_budget.untrack(handle.id),
+                    }
+                }
+                AssetEvent::Removed { handle } => self.memory_budget.untrack(handle.id),
+                AssetEvent::LoadedWithDependencies { .. } => {}
+            }
+        }
+
+        // `Assets::clear` removes assets without sending events
+        if self.memory_budget.sizes.len() > assets.len() {
+            let removed = self
+                .memory_budget
+                .sizes
+                .keys()
+                .filter(|id| !assets.contains(**id))
+                .copied()
+                .collect::<Vec<_>>();
+            for id in removed {
+                self.memory_budget.untrack(id);
+            }
+        }
+    }
+
+    pub(crate) fn find_evictions(&mut self, is_used: impl Fn(HandleId) -> bool) -> Vec<HandleId> {
+        self.memory_budget.find_evictions(is_used)
+    }
+}
+
+/// Tracks the memory used by a set of assets and when they were last used
+#[derive(Debug)]
+struct MemoryBudget {
+    budget: usize,
+    usage: usize,
+    sizes: HashMap<HandleId, usize>,
+    last_used: HashMap<HandleId, u64>,
+    frame: u64,
+}
+
+impl MemoryBudget {
+    fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            usage: 0,
+            sizes: Default::default(),
+            last_used: Default::default(),
+            frame: 0,
+        }
+    }
+
+    fn track(&mut self, id: HandleId, size: usize) {
+        let previous_size = self.sizes.insert(id, size).unwrap_or(0);
+        self.usage = self.usage - previous_size + size;
+        self.last_used.insert(id, self.frame);
+    }
+
+    fn untrack(&mut self, id: HandleId) {
+        if let Some(size) = self.sizes.remove(&id) {
+            self.usage -= size;
+        }
+        self.last_used.remove(&id);
+    }
+
+    /// Starts a new frame and returns the least recently used assets that need to be evicted
+    /// to fit into the budget. `is_used` returns whether an asset is still held by strong handles.
+    ///
+    /// Only assets loaded from a path are evicted, as only those can be loaded again.
+    fn find_evictions(&mut self, is_used: impl Fn(HandleId) -> bool) -> Vec<HandleId> {
+        self.frame += 1;
+        let frame = self.frame;
+        for (id, last_used) in self.last_used.iter_mut() {
+            if is_used(*id) {
+                *last_used = frame;
+            }
+        }
+
+        if self.usage <= self.budget {
+            return Vec::new();
+        }
+
+        let mut unused = self
+            .last_used
+            .iter()
+            .filter(|(id, last_used)| {
+                **last_used != frame && matches!(id, HandleId::AssetPathId(_))
+            })
+            .map(|(id, last_used)| (*last_used, *id))
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(last_used, _)| *last_used);
+
+        let mut usage = self.usage;
+        let mut evictions = Vec::new();
+        for (_, id) in unused {
+            if usage <= self.budget {
+                break;
+            }
+            usage -= self.sizes.get(&id).copied().unwrap_or(0);
+            evictions.push(id);
+        }
+        evictions
+    }
+}
+
+/// Evicts the least recently used assets without strong handles from [`Assets`] collections
+/// that exceed their memory budget.
+///
+/// Only the [`AssetMemoryBudget`] is borrowed mutably, evicted assets are removed from the
+/// [`Assets`] collection when the [`AssetServer`] frees them.
+pub fn evict_unused_assets_system<T: Asset>(
+    asset_server: Res<AssetServer>,
+    assets: Res<Assets<T>>,
+    mut asset_events: EventReader<AssetEvent<T>>,
+    mut memory_budget: ResMut<AssetMemoryBudget<T>>,
+) {
+    memory_budget.update(&assets, asset_events.iter());
+    asset_server.evict_unused_assets(&mut memory_budget);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AddAsset, AssetPathId, AssetPlugin};
+    use bevy_app::{App, CoreStage};
+    use bevy_ecs::system::IntoSystem;
+    use bevy_reflect::TypeUuid;
+    use bevy_tasks::{IoTaskPool, TaskPool};
+
+    #[derive(Debug, TypeUuid)]
+    #[uuid = "9d6f1f8e-2c41-4a6b-8f0e-5b3c7e2a1d94"]
+    struct SizedAsset(usize);
+
+    impl AssetSize for SizedAsset {
+        fn asset_size(&self) -> usize {
+            self.0
+        }
+    }
+
+    #[derive(Default)]
+    struct AssetsChanged(usize);
+
+    fn count_changes(assets: Res<Assets<SizedAsset>>, mut changed: ResMut<AssetsChanged>) {
+        if assets.is_changed() {
+            changed.0 += 1;
+        }
+    }
+
+    fn path_id(path: &str) -> HandleId {
+        AssetPathId::from(path).into()
+    }
+
+    #[test]
+    fn evicts_least_recently_used_first() {
+        let mut memory_budget = MemoryBudget::new(10);
+        memory_budget.track(path_id("a"), 4);
+        memory_budget.find_evictions(|_| false);
+        memory_budget.track(path_id("b"), 4);
+        memory_budget.find_evictions(|_| false);
+        memory_budget.track(path_id("c"), 4);
+        assert_eq!(memory_budget.usage, 12);
+
+        // "a" is the least recently used asset, and evicting it is enough to fit the budget
+        assert_eq!(memory_budget.find_evictions(|_| false), vec![path_id("a")]);
+    }
+
+    #[test]
+    fn keeps_used_and_unreloadable_assets() {
+        let mut memory_budget = MemoryBudget::new(0);
+        let used = path_id("used");
+        let added = HandleId::Id(Default::default(), 1);
+        memory_budget.track(used, 1);
+        memory_budget.track(added, 1);
+        memory_budget.track(path_id("unused"), 1);
+        memory_budget.find_evictions(|_| false);
+
+        let evictions = memory_budget.find_evictions(|id| id == used);
+        assert_eq!(evictions, vec![path_id("unused")]);
+
+        memory_budget.untrack(path_id("unused"));
+        assert_eq!(memory_budget.usage, 2);
+    }
+
+    #[test]
+    fn tracks_usage_without_changing_assets() {
+        let mut app = App::new();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<SizedAsset>()
+            .set_asset_memory_budget::<SizedAsset>(100)
+            .init_resource::<AssetsChanged>()
+            .add_system_to_stage(CoreStage::Last, count_changes.system());
+        let usage = |app: &App| {
+            app.world
+                .get_resource::<AssetMemoryBudget<SizedAsset>>()
+                .unwrap()
+                .usage()
+        };
+        let changes = |app: &App| app.world.get_resource::<AssetsChanged>().unwrap().0;
+
+        let handle = app
+            .world
+            .get_resource_mut::<Assets<SizedAsset>>()
+            .unwrap()
+            .add(SizedAsset(10));
+        app.update();
+        app.update();
+        app.update();
+        assert_eq!(usage(&app), 10);
+        assert_eq!(changes(&app), 1);
+
+        app.world
+            .get_resource_mut::<Assets<SizedAsset>>()
+            .unwrap()
+            .get_mut(&handle)
+            .unwrap()
+            .0 = 30;
+        app.update();
+        app.update();
+        assert_eq!(usage(&app), 30);
+        assert_eq!(changes(&app), 2);
+
+        app.world
+            .get_resource_mut::<Assets<SizedAsset>>()
+            .unwrap()
+            .clear();
+        app.update();
+        assert_eq!(usage(&app), 0);
+    }
+
+    #[test]
+    fn frees_added_assets_without_strong_handles() {
+        let mut app = App::new();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<SizedAsset>()
+            .set_asset_memory_budget::<SizedAsset>(100);
+
+        let handle = app
+            .world
+            .get_resource_mut::<Assets<SizedAsset>>()
+            .unwrap()
+            .add(SizedAsset(10));
+        let id = handle.id;
+        app.update();
+        assert!(app
+            .world
+            .get_resource::<Assets<SizedAsset>>()
+            .unwrap()
+            .contains(id));
+
+        // added assets can't be loaded again, so they are freed even though they are in budget
+        drop(handle);
+        app.update();
+        app.update();
+        app.update();
+        assert!(!app
+            .world
+            .get_resource::<Assets<SizedAsset>>()
+            .unwrap()
+            .contains(id));
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{Asset, AssetMemoryBudget};
+use bevy_app::prelude::*;
+use bevy_diagnostic::{Diagnostic, DiagnosticId, Diagnostics, MAX_DIAGNOSTIC_NAME_WIDTH};
+use bevy_ecs::system::{Res, ResMut};
+
+/// Adds "asset_memory" and "asset_budget" diagnostics to an App, measuring the bytes used by
+/// the assets of an [`Assets`](crate::Assets) collection with an [`AssetMemoryBudget`], and the
+/// budget itself
+pub struct AssetMemoryDiagnosticsPlugin<T: Asset> {
+    marker: std::marker::PhantomData<T>,
+}
+
+impl<T: Asset> Default for AssetMemoryDiagnosticsPlugin<T> {
+    fn default() -> Self {
+        Self {
+            marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T: Asset> Plugin for AssetMemoryDiagnosticsPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(Self::setup_system)
+            .add_system(Self::diagnostic_system);
+    }
+}
+
+impl<T: Asset> AssetMemoryDiagnosticsPlugin<T> {
+    // mixed into the asset type uuid, so the ids differ from `AssetCountDiagnosticsPlugin`'s
+    const MEMORY_KEY: u128 = 0x7d3c_51e6_0b0e_4a8f_9c5e_3f1d_27a4_61b0;
+    const BUDGET_KEY: u128 = 0x2f8a_94c3_6d17_4e25_b3a9_c0e4_58d1_7f62;
+
+    pub fn memory_diagnostic_id() -> DiagnosticId {
+        DiagnosticId::from_u128(T::TYPE_UUID.as_u128() ^ Self::MEMORY_KEY)
+    }
+
+    pub fn budget_diagnostic_id() -> DiagnosticId {
+        DiagnosticId::from_u128(T::TYPE_UUID.as_u128() ^ Self::BUDGET_KEY)
+    }
+
+    pub fn setup_system(mut diagnostics: ResMut<Diagnostics>) {
+        diagnostics.add(
+            Diagnostic::new(
+                Self::memory_diagnostic_id(),
+                Self::diagnostic_name("asset_memory"),
+                20,
+            )
+            .with_suffix("B"),
+        );
+        diagnostics.add(
+            Diagnostic::new(
+                Self::budget_diagnostic_id(),
+                Self::diagnostic_name("asset_budget"),
+                20,
+            )
+            .with_suffix("B"),
+        );
+    }
+
+    fn diagnostic_name(prefix: &str) -> String {
+        let asset_type_name = std::any::type_name::<T>();
+        let max_length = MAX_DIAGNOSTIC_NAME_WIDTH - prefix.len() - 1;
+        format!(
+            "{} {}",
+            prefix,
+            if asset_type_name.len() > max_length {
+                asset_type_name
+                    .split_at(asset_type_name.len() - max_length + 1)
+                    .1
+            } else {
+                asset_type_name
+            }
+        )
+    }
+
+    pub fn diagnostic_system(
+        mut diagnostics: ResMut<Diagnostics>,
+        memory_budget: Option<Res<AssetMemoryBudget<T>>>,
+    ) {
+        if let Some(memory_budget) = memory_budget {
+            diagnostics.add_measurement(Self::memory_diagnostic_id(), memory_budget.usage() as f64);
+            diagnostics
+                .add_measurement(Self::budget_diagnostic_id(), memory_budget.budget() as f64);
+        }
+    }
+}
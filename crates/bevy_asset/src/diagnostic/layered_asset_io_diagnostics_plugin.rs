@@ -0,0 +1,52 @@
+use crate::{AssetServer, LayeredAssetIo};
+use bevy_app::prelude::*;
+use bevy_diagnostic::{Diagnostic, DiagnosticId, Diagnostics};
+use bevy_ecs::system::{Res, ResMut};
+
+/// Adds an "asset_layer" diagnostic for every layer of the [`AssetServer`]'s [`LayeredAssetIo`],
+/// measuring how many assets were served by that layer
+#[derive(Default)]
+pub struct LayeredAssetIoDiagnosticsPlugin;
+
+impl Plugin for LayeredAssetIoDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(Self::setup_system)
+            .add_system(Self::diagnostic_system);
+    }
+}
+
+impl LayeredAssetIoDiagnosticsPlugin {
+    const LAYER_BASE: u128 = 202170637839124186393838063128937216000;
+
+    pub fn diagnostic_id(layer_index: usize) -> DiagnosticId {
+        DiagnosticId::from_u128(Self::LAYER_BASE + layer_index as u128)
+    }
+
+    pub fn setup_system(mut diagnostics: ResMut<Diagnostics>, asset_server: Res<AssetServer>) {
+        if let Some(asset_io) = asset_server
+            .server
+            .asset_io
+            .downcast_ref::<LayeredAssetIo>()
+        {
+            for (index, layer) in asset_io.layers().iter().enumerate() {
+                diagnostics.add(Diagnostic::new(
+                    Self::diagnostic_id(index),
+                    format!("asset_layer {}", layer.name()),
+                    20,
+                ));
+            }
+        }
+    }
+
+    pub fn diagnostic_system(mut diagnostics: ResMut<Diagnostics>, asset_server: Res<AssetServer>) {
+        if let Some(asset_io) = asset_server
+            .server
+            .asset_io
+            .downcast_ref::<LayeredAssetIo>()
+        {
+            for (index, count) in asset_io.served_counts().into_iter().enumerate() {
+                diagnostics.add_measurement(Self::diagnostic_id(index), count as f64);
+            }
+        }
+    }
+}
@@ -1,2 +1,6 @@
 mod asset_count_diagnostics_plugin;
+mod asset_memory_diagnostics_plugin;
+mod layered_asset_io_diagnostics_plugin;
 pub use asset_count_diagnostics_plugin::AssetCountDiagnosticsPlugin;
+pub use asset_memory_diagnostics_plugin::AssetMemoryDiagnosticsPlugin;
+pub use layered_asset_io_diagnostics_plugin::LayeredAssetIoDiagnosticsPlugin;
@@ -1,4 +1,4 @@
-use crate::{path::AssetPath, LabelId};
+use crate::{path::AssetPath, HandleId, LabelId};
 use bevy_utils::{HashMap, HashSet, Uuid};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -19,11 +19,14 @@ pub struct AssetMeta {
 #[derive(Clone, Debug)]
 pub struct SourceInfo {
     pub meta: Option<SourceMeta>,
+    pub source: Option<String>,
     pub path: PathBuf,
     pub asset_types: HashMap<LabelId, Uuid>,
     pub load_state: LoadState,
     pub committed_assets: HashSet<LabelId>,
     pub version: usize,
+    /// The error of the last failed load of this source
+    pub load_error: Option<String>,
 }
 
 impl SourceInfo {
@@ -36,6 +39,29 @@ impl SourceInfo {
     pub fn get_asset_type(&self, label_id: LabelId) -> Option<Uuid> {
         self.asset_types.get(&label_id).cloned()
     }
+
+    pub fn asset_path(&self) -> AssetPath<'static> {
+        AssetPath::new(self.path.clone(), None).with_source(self.source.clone())
+    }
+}
+
+/// An asset that failed to load, and the error it failed with
+#[derive(Clone, Debug)]
+pub struct LoadFailure {
+    pub path: AssetPath<'static>,
+    pub error: String,
+}
+
+/// An event that is sent when an asset fails to load
+///
+/// The error is also kept until the asset is loaded again, see
+/// [`AssetServer::get_load_error`](crate::AssetServer::get_load_error).
+#[derive(Clone, Debug)]
+pub struct AssetLoadFailed {
+    pub id: HandleId,
+    pub path: AssetPath<'static>,
+    /// The error message, including the chain of errors that caused it
+    pub error: String,
 }
 
 /// The load state of an asset
@@ -1,4 +1,7 @@
-use crate::{filesystem_watcher::FilesystemWatcher, AssetIo, AssetIoError, AssetServer};
+use crate::{
+    filesystem_watcher::FilesystemWatcher, AssetIo, AssetIoError, AssetPath, AssetPathId,
+    AssetReader, AssetServer, LayeredAssetIo, LoadState, META_EXTENSION,
+};
 use anyhow::Result;
 use bevy_ecs::system::Res;
 use bevy_utils::{BoxedFuture, HashSet};
@@ -62,6 +65,30 @@ impl AssetIo for FileAssetIo {
         })
     }
 
+    fn open_path(&self, path: &Path) -> Result<Box<dyn AssetReader>, AssetIoError> {
+        let full_path = self.root_path.join(path);
+        match File::open(&full_path) {
+            Ok(file) => Ok(Box::new(io::BufReader::new(file))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(AssetIoError::NotFound(full_path)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save_path<'a>(
+        &'a self,
+        path: &'a Path,
+        bytes: &'a [u8],
+    ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+        Box::pin(async move {
+            let full_path = self.root_path.join(path);
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(full_path, bytes)?;
+            Ok(())
+        })
+    }
+
     fn read_directory(
         &self,
         path: &Path,
@@ -78,7 +105,13 @@ impl AssetIo for FileAssetIo {
     fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
         #[cfg(feature = "filesystem_watcher")]
         {
-            let path = self.root_path.join(path);
+            let mut path = self.root_path.join(path);
+            // a file that doesn't exist yet, like a `.meta` file, is watched through its folder
+            if !path.exists() {
+                if let Some(parent) = path.parent() {
+                    path = parent.to_owned();
+                }
+            }
             let mut watcher = self.filesystem_watcher.write();
             if let Some(ref mut watcher) = *watcher {
                 watcher
@@ -110,12 +143,59 @@ impl AssetIo for FileAssetIo {
 ))]
 pub fn filesystem_watcher_system(asset_server: Res<AssetServer>) {
     let mut changed = HashSet::default();
-    let asset_io =
-        if let Some(asset_io) = asset_server.server.asset_io.downcast_ref::<FileAssetIo>() {
-            asset_io
-        } else {
-            return;
-        };
+    reload_changed_sources(
+        &asset_server,
+        None,
+        &*asset_server.server.asset_io,
+        &mut changed,
+    );
+
+    let named_asset_io = asset_server
+        .server
+        .named_asset_io
+        .read()
+        .iter()
+        .map(|(name, asset_io)| (name.clone(), asset_io.clone()))
+        .collect::<Vec<_>>();
+    for (name, asset_io) in named_asset_io.iter() {
+        reload_changed_sources(
+            &asset_server,
+            Some(name.as_str()),
+            &**asset_io,
+            &mut changed,
+        );
+    }
+}
+
+#[cfg(all(
+    feature = "filesystem_watcher",
+    all(not(target_arch = "wasm32"), not(target_os = "android"))
+))]
+fn reload_changed_sources(
+    asset_server: &AssetServer,
+    source: Option<&str>,
+    asset_io: &dyn AssetIo,
+    changed: &mut HashSet<AssetPathId>,
+) {
+    if let Some(asset_io) = asset_io.downcast_ref::<FileAssetIo>() {
+        reload_changed_files(asset_server, source, asset_io, changed);
+    } else if let Some(asset_io) = asset_io.downcast_ref::<LayeredAssetIo>() {
+        for layer in asset_io.layers() {
+            reload_changed_sources(asset_server, source, layer.asset_io(), changed);
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "filesystem_watcher",
+    all(not(target_arch = "wasm32"), not(target_os = "android"))
+))]
+fn reload_changed_files(
+    asset_server: &AssetServer,
+    source: Option<&str>,
+    asset_io: &FileAssetIo,
+    changed: &mut HashSet<AssetPathId>,
+) {
     let watcher = asset_io.filesystem_watcher.read();
     if let Some(ref watcher) = *watcher {
         loop {
@@ -124,19 +204,41 @@ pub fn filesystem_watcher_system(asset_server: Res<AssetServer>) {
                 Err(TryRecvError::Empty) => break,
                 Err(TryRecvError::Disconnected) => panic!("FilesystemWatcher disconnected."),
             };
-            if let notify::event::Event {
-                kind: notify::event::EventKind::Modify(_),
-                paths,
-                ..
-            } = event
-            {
-                for path in paths.iter() {
-                    if !changed.contains(path) {
-                        let relative_path = path.strip_prefix(&asset_io.root_path).unwrap();
-                        let _ = asset_server.load_untracked(relative_path.into(), true);
-                    }
+            // `.meta` files are also watched while they don't exist, so they can be created
+            // and removed
+            let (modified, meta_modified) = match event.kind {
+                notify::event::EventKind::Modify(_) => (true, true),
+                notify::event::EventKind::Create(_) | notify::event::EventKind::Remove(_) => {
+                    (false, true)
+                }
+                _ => (false, false),
+            };
+            for path in event.paths.iter() {
+                let relative_path = path.strip_prefix(&asset_io.root_path).unwrap();
+                let is_meta = relative_path.extension() == Some(META_EXTENSION.as_ref());
+                if !(modified || (is_meta && meta_modified)) {
+                    continue;
+                }
+                // a changed `.meta` file reloads the asset it holds the settings of
+                let relative_path = if is_meta {
+                    relative_path.with_extension("")
+                } else {
+                    relative_path.to_owned()
+                };
+                let asset_path = AssetPath::from(relative_path).with_source(source);
+                // folders watched for `.meta` files also report changes to assets that aren't
+                // loaded
+                if asset_server.get_load_state(asset_path.get_id()) == LoadState::NotLoaded {
+                    continue;
+                }
+                // files the asset server saved itself are already up to date in memory
+                if !is_meta && asset_server.is_saved_content(&asset_path, || fs::read(path).ok()) {
+                    continue;
+                }
+                // the same relative path may change in several layers at once
+                if changed.insert(asset_path.get_id()) {
+                    let _ = asset_server.load_untracked(asset_path, true);
                 }
-                changed.extend(paths);
             }
         }
     }
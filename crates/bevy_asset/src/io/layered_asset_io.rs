@@ -0,0 +1,246 @@
+use crate::{AssetIo, AssetIoError, AssetReader};
+use anyhow::Result;
+use bevy_utils::{BoxedFuture, HashMap, HashSet};
+use parking_lot::RwLock;
+use std::path::{Path, PathBuf};
+
+/// A single named source inside a [`LayeredAssetIo`]
+pub struct AssetIoLayer {
+    name: String,
+    asset_io: Box<dyn AssetIo>,
+}
+
+impl AssetIoLayer {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn asset_io(&self) -> &dyn AssetIo {
+        &*self.asset_io
+    }
+}
+
+/// An [`AssetIo`] that stacks several sources on top of each other, e.g. a mod folder over a DLC
+/// folder over the base game.
+///
+/// Layers are queried in priority order: the layer that was added first is checked first, and a
+/// lookup falls through to the next layer if the path is not found. Directory listings are merged
+/// across all layers. Saved assets are written to the first layer, unless another layer is
+/// picked with [`LayeredAssetIo::with_save_layer`].
+#[derive(Default)]
+pub struct LayeredAssetIo {
+    layers: Vec<AssetIoLayer>,
+    save_layer: usize,
+    served_by: RwLock<HashMap<PathBuf, usize>>,
+}
+
+impl LayeredAssetIo {
+    /// Adds a layer with a lower priority than all previously added layers.
+    pub fn with_layer<T: AssetIo>(self, name: impl Into<String>, asset_io: T) -> Self {
+        self.with_boxed_layer(name, Box::new(asset_io))
+    }
+
+    /// Adds a boxed layer with a lower priority than all previously added layers.
+    pub fn with_boxed_layer(mut self, name: impl Into<String>, asset_io: Box<dyn AssetIo>) -> Self {
+        self.add_boxed_layer(name, asset_io);
+        self
+    }
+
+    /// Adds a boxed layer with a lower priority than all previously added layers.
+    pub fn add_boxed_layer(&mut self, name: impl Into<String>, asset_io: Box<dyn AssetIo>) {
+        self.layers.push(AssetIoLayer {
+            name: name.into(),
+            asset_io,
+        });
+    }
+
+    /// Writes saved assets to the layer called `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no layer called `name` has been added.
+    pub fn with_save_layer(mut self, name: &str) -> Self {
+        self.save_layer = self
+            .layers
+            .iter()
+            .position(|layer| layer.name == name)
+            .unwrap_or_else(|| panic!("no asset io layer called '{}'", name));
+        self
+    }
+
+    /// Returns the layers of this source, from highest to lowest priority.
+    pub fn layers(&self) -> &[AssetIoLayer] {
+        &self.layers
+    }
+
+    /// Returns the name of the layer that served the last successful load of `path`.
+    pub fn get_serving_layer(&self, path: &Path) -> Option<&str> {
+        self.served_by
+            .read()
+            .get(path)
+            .map(|index| self.layers[*index].name())
+    }
+
+    /// Returns how many distinct paths each layer has served, in layer priority order.
+    pub fn served_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.layers.len()];
+        for index in self.served_by.read().values() {
+            counts[*index] += 1;
+        }
+        counts
+    }
+}
+
+impl AssetIo for LayeredAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move {
+            for (index, layer) in self.layers.iter().enumerate() {
+                match layer.asset_io.load_path(path).await {
+                    Ok(bytes) => {
+                        self.served_by.write().insert(path.to_owned(), index);
+                        return Ok(bytes);
+                    }
+                    Err(AssetIoError::NotFound(_)) => continue,
+                    Err(err) => return Err(err),
+                }
+            }
+            Err(AssetIoError::NotFound(path.to_owned()))
+        })
+    }
+
+    fn open_path(&self, path: &Path) -> Result<Box<dyn AssetReader>, AssetIoError> {
+        for (index, layer) in self.layers.iter().enumerate() {
+            match layer.asset_io.open_path(path) {
+                Ok(reader) => {
+                    self.served_by.write().insert(path.to_owned(), index);
+                    return Ok(reader);
+                }
+                Err(AssetIoError::NotFound(_)) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Err(AssetIoError::NotFound(path.to_owned()))
+    }
+
+    fn save_path<'a>(
+        &'a self,
+        path: &'a Path,
+        bytes: &'a [u8],
+    ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+        Box::pin(async move {
+            match self.layers.get(self.save_layer) {
+                Some(layer) => layer.asset_io.save_path(path, bytes).await,
+                None => Err(AssetIoError::SaveUnsupported(path.to_owned())),
+            }
+        })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        if !self.is_directory(path) {
+            return Err(AssetIoError::NotFound(path.to_owned()));
+        }
+
+        let mut seen = HashSet::default();
+        let mut entries = Vec::new();
+        for layer in self.layers.iter() {
+            if !layer.asset_io.is_directory(path) {
+                continue;
+            }
+            for entry in layer.asset_io.read_directory(path)? {
+                if seen.insert(entry.clone()) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        self.layers
+            .iter()
+            .any(|layer| layer.asset_io.is_directory(path))
+    }
+
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        // only the layer that served the path can produce the bytes we loaded, but a higher
+        // priority layer may start shadowing it, so every layer watches the path if it can
+        for layer in self.layers.iter() {
+            match layer.asset_io.watch_path_for_changes(path) {
+                Ok(()) | Err(AssetIoError::PathWatchError(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        for layer in self.layers.iter() {
+            layer.asset_io.watch_for_changes()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32"), not(target_os = "android")))]
+mod test {
+    use super::*;
+    use crate::FileAssetIo;
+
+    fn layer_dir(files: &[(&str, &[u8])]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (file, bytes) in files {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, bytes).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn load_falls_through_layers() {
+        let mods = layer_dir(&[("a.txt", b"mod")]);
+        let base = layer_dir(&[("a.txt", b"base"), ("b.txt", b"base")]);
+        let io = LayeredAssetIo::default()
+            .with_layer("mods", FileAssetIo::new(mods.path()))
+            .with_layer("base", FileAssetIo::new(base.path()));
+
+        let a = futures_lite::future::block_on(io.load_path(Path::new("a.txt"))).unwrap();
+        let b = futures_lite::future::block_on(io.load_path(Path::new("b.txt"))).unwrap();
+        assert_eq!(a, b"mod");
+        assert_eq!(b, b"base");
+        assert_eq!(io.get_serving_layer(Path::new("a.txt")), Some("mods"));
+        assert_eq!(io.get_serving_layer(Path::new("b.txt")), Some("base"));
+        assert_eq!(io.served_counts(), vec![1, 1]);
+
+        let missing = futures_lite::future::block_on(io.load_path(Path::new("c.txt")));
+        assert!(matches!(missing, Err(AssetIoError::NotFound(_))));
+    }
+
+    #[test]
+    fn read_directory_merges_layers() {
+        let mods = layer_dir(&[("dir/a.txt", b""), ("dir/c.txt", b"")]);
+        let base = layer_dir(&[("dir/a.txt", b""), ("dir/b.txt", b"")]);
+        let io = LayeredAssetIo::default()
+            .with_layer("mods", FileAssetIo::new(mods.path()))
+            .with_layer("base", FileAssetIo::new(base.path()));
+
+        assert!(io.is_directory(Path::new("dir")));
+        let mut entries = io
+            .read_directory(Path::new("dir"))
+            .unwrap()
+            .collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(
+            entries,
+            vec![
+                PathBuf::from("dir/a.txt"),
+                PathBuf::from("dir/b.txt"),
+                PathBuf::from("dir/c.txt")
+            ]
+        );
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{AssetIo, AssetIoError};
+use anyhow::Result;
+use bevy_utils::{BoxedFuture, HashMap};
+use parking_lot::RwLock;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// An [`AssetIo`] that serves assets from memory, such as assets embedded in the binary with
+/// `include_bytes!` or generated at runtime
+#[derive(Default)]
+pub struct MemoryAssetIo {
+    assets: RwLock<HashMap<PathBuf, Arc<[u8]>>>,
+}
+
+impl MemoryAssetIo {
+    /// Inserts the bytes of an asset at `path`, replacing any previous asset at that path.
+    pub fn insert_asset<P: Into<PathBuf>, B: Into<Arc<[u8]>>>(&self, path: P, bytes: B) {
+        self.assets.write().insert(path.into(), bytes.into());
+    }
+
+    /// Removes the asset at `path`, returning whether it existed.
+    pub fn remove_asset(&self, path: &Path) -> bool {
+        self.assets.write().remove(path).is_some()
+    }
+
+    pub fn contains_asset(&self, path: &Path) -> bool {
+        self.assets.read().contains_key(path)
+    }
+}
+
+impl AssetIo for MemoryAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move {
+            self.assets
+                .read()
+                .get(path)
+                .map(|bytes| bytes.to_vec())
+                .ok_or_else(|| AssetIoError::NotFound(path.to_owned()))
+        })
+    }
+
+    fn save_path<'a>(
+        &'a self,
+        path: &'a Path,
+        bytes: &'a [u8],
+    ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+        Box::pin(async move {
+            self.insert_asset(path, bytes);
+            Ok(())
+        })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        let mut entries = Vec::new();
+        for asset_path in self.assets.read().keys() {
+            if let Ok(relative_path) = asset_path.strip_prefix(path) {
+                // direct children are returned as is, deeper assets as their top level directory
+                if let Some(child) = relative_path.components().next() {
+                    let child = path.join(child);
+                    if !entries.contains(&child) {
+                        entries.push(child);
+                    }
+                }
+            }
+        }
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        self.assets
+            .read()
+            .keys()
+            .any(|asset_path| asset_path != path && asset_path.starts_with(path))
+    }
+
+    fn watch_path_for_changes(&self, _path: &Path) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+}
@@ -2,6 +2,8 @@
 mod android_asset_io;
 #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
 mod file_asset_io;
+mod layered_asset_io;
+mod memory_asset_io;
 #[cfg(target_arch = "wasm32")]
 mod wasm_asset_io;
 
@@ -12,6 +14,9 @@ pub use file_asset_io::*;
 #[cfg(target_arch = "wasm32")]
 pub use wasm_asset_io::*;
 
+pub use layered_asset_io::*;
+pub use memory_asset_io::*;
+
 use anyhow::Result;
 use bevy_utils::BoxedFuture;
 use downcast_rs::{impl_downcast, Downcast};
@@ -30,11 +35,38 @@ pub enum AssetIoError {
     Io(#[from] io::Error),
     #[error("failed to watch path: {0}")]
     PathWatchError(PathBuf),
+    #[error("saving assets is not supported, failed to save: {0}")]
+    SaveUnsupported(PathBuf),
+    #[error("reading assets synchronously is not supported, failed to open: {0}")]
+    OpenUnsupported(PathBuf),
 }
 
+/// A readable and seekable handle to the contents of an asset, returned by [`AssetIo::open_path`]
+pub trait AssetReader: io::Read + io::Seek + Send + Sync {}
+
+impl<T: io::Read + io::Seek + Send + Sync> AssetReader for T {}
+
 /// Handles load requests from an AssetServer
 pub trait AssetIo: Downcast + Send + Sync + 'static {
     fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>>;
+    /// Opens `path` for reading on the calling thread, for consumers that read an asset
+    /// incrementally instead of loading it whole.
+    ///
+    /// The default implementation blocks on [`AssetIo::load_path`] and reads from memory.
+    fn open_path(&self, path: &Path) -> Result<Box<dyn AssetReader>, AssetIoError> {
+        let bytes = futures_lite::future::block_on(self.load_path(path))?;
+        Ok(Box::new(io::Cursor::new(bytes)))
+    }
+    /// Writes `bytes` to `path`, replacing the previous contents of the file if it exists.
+    ///
+    /// By default saving is not supported and [`AssetIoError::SaveUnsupported`] is returned.
+    fn save_path<'a>(
+        &'a self,
+        path: &'a Path,
+        _bytes: &'a [u8],
+    ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+        Box::pin(async move { Err(AssetIoError::SaveUnsupported(path.to_owned())) })
+    }
     fn read_directory(
         &self,
         path: &Path,
@@ -1,4 +1,4 @@
-use crate::{AssetIo, AssetIoError};
+use crate::{AssetIo, AssetIoError, AssetReader};
 use anyhow::Result;
 use bevy_utils::BoxedFuture;
 use js_sys::Uint8Array;
@@ -28,12 +28,20 @@ impl AssetIo for WasmAssetIo {
                 .await
                 .unwrap();
             let resp: Response = resp_value.dyn_into().unwrap();
+            if resp.status() == 404 {
+                return Err(AssetIoError::NotFound(path));
+            }
             let data = JsFuture::from(resp.array_buffer().unwrap()).await.unwrap();
             let bytes = Uint8Array::new(&data).to_vec();
             Ok(bytes)
         })
     }
 
+    fn open_path(&self, path: &Path) -> Result<Box<dyn AssetReader>, AssetIoError> {
+        // fetches can only complete once control returns to the browser
+        Err(AssetIoError::OpenUnsupported(self.root_path.join(path)))
+    }
+
     fn read_directory(
         &self,
         _path: &Path,
@@ -1,5 +1,6 @@
 mod asset_server;
 mod assets;
+mod budget;
 pub mod diagnostic;
 #[cfg(all(
     feature = "filesystem_watcher",
@@ -11,6 +12,9 @@ mod info;
 mod io;
 mod loader;
 mod path;
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+mod processor;
+mod saver;
 
 pub mod prelude {
     #[doc(hidden)]
@@ -20,11 +24,15 @@ pub mod prelude {
 pub use asset_server::*;
 pub use assets::*;
 pub use bevy_utils::BoxedFuture;
+pub use budget::*;
 pub use handle::*;
 pub use info::*;
 pub use io::*;
 pub use loader::*;
 pub use path::*;
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+pub use processor::*;
+pub use saver::*;
 
 use bevy_app::{prelude::Plugin, App};
 use bevy_ecs::schedule::{StageLabel, SystemStage};
@@ -42,6 +50,11 @@ pub enum AssetStage {
 #[derive(Default)]
 pub struct AssetPlugin;
 
+/// The name of the asset source that engine and plugin crates embed their default assets in.
+///
+/// It is backed by a [`MemoryAssetIo`] and addressed by paths like `"embedded://shaders/pbr.vert"`.
+pub const EMBEDDED_ASSET_SOURCE: &str = "embedded";
+
 pub struct AssetServerSettings {
     pub asset_folder: String,
 }
@@ -70,6 +83,20 @@ pub fn create_platform_default_asset_io(app: &mut App) -> Box<dyn AssetIo> {
     #[cfg(target_os = "android")]
     let source = AndroidAssetIo::new(&settings.asset_folder);
 
+    // processed assets are read in place of their sources
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    if let Some(processor_settings) = app.world.get_resource::<AssetProcessorSettings>() {
+        return Box::new(
+            LayeredAssetIo::default()
+                .with_layer(
+                    "processed",
+                    FileAssetIo::new(&processor_settings.processed_asset_folder),
+                )
+                .with_layer("source", source)
+                .with_save_layer("source"),
+        );
+    }
+
     Box::new(source)
 }
 
@@ -90,6 +117,26 @@ impl Plugin for AssetPlugin {
             app.insert_resource(asset_server);
         }
 
+        {
+            let asset_server = app.world.get_resource::<AssetServer>().unwrap();
+            if asset_server.get_source(EMBEDDED_ASSET_SOURCE).is_none() {
+                asset_server.add_source(EMBEDDED_ASSET_SOURCE, MemoryAssetIo::default());
+            }
+        }
+
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+        {
+            use bevy_ecs::system::IntoExclusiveSystem;
+
+            app.init_resource::<AssetProcessors>();
+            if app.world.contains_resource::<AssetProcessorSettings>() {
+                app.add_startup_system_to_stage(
+                    bevy_app::StartupStage::PreStartup,
+                    processor::process_assets_system.exclusive_system(),
+                );
+            }
+        }
+
         app.add_stage_before(
             bevy_app::CoreStage::PreUpdate,
             AssetStage::LoadAssets,
@@ -101,6 +148,11 @@ impl Plugin for AssetPlugin {
             SystemStage::parallel(),
         )
         .register_type::<HandleId>()
+        .add_event::<AssetLoadFailed>()
+        .add_system_to_stage(
+            AssetStage::LoadAssets,
+            asset_server::asset_load_failed_event_system,
+        )
         .add_system_to_stage(
             bevy_app::CoreStage::PreUpdate,
             asset_server::free_unused_assets_system,
@@ -9,10 +9,15 @@ use bevy_ecs::{
 };
 use bevy_reflect::{TypeUuid, TypeUuidDynamic};
 use bevy_tasks::TaskPool;
-use bevy_utils::{BoxedFuture, HashMap};
+use bevy_utils::{BoxedFuture, HashMap, HashSet};
 use crossbeam_channel::{Receiver, Sender};
 use downcast_rs::{impl_downcast, Downcast};
-use std::path::Path;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 /// A loader for an asset source
 pub trait AssetLoader: Send + Sync + 'static {
@@ -22,6 +27,51 @@ pub trait AssetLoader: Send + Sync + 'static {
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<(), anyhow::Error>>;
     fn extensions(&self) -> &[&str];
+
+    /// Returns true if the loader has [`LoaderSettings`], which are read from the `.meta` file
+    /// next to an asset with [`AssetLoader::deserialize_settings`]. `.meta` files are not read
+    /// for loaders without settings.
+    fn has_settings(&self) -> bool {
+        false
+    }
+
+    /// Deserializes the settings stored in the `.meta` file next to an asset, only called if
+    /// [`AssetLoader::has_settings`] returns true.
+    ///
+    /// See [`deserialize_ron_settings`] for loaders with RON settings.
+    fn deserialize_settings(
+        &self,
+        _bytes: &[u8],
+    ) -> Result<Option<Box<dyn LoaderSettings>>, anyhow::Error> {
+        Ok(None)
+    }
+}
+
+/// Settings of an [`AssetLoader`] for a specific asset, read from the asset's `.meta` file or
+/// passed to [`AssetServer::load_with_settings`]
+pub trait LoaderSettings: Downcast + Send + Sync + 'static {}
+impl_downcast!(LoaderSettings);
+
+impl<T> LoaderSettings for T where T: Send + Sync + 'static {}
+
+/// The extension of the files holding the [`LoaderSettings`] of an asset, e.g.
+/// `"texture.png.meta"` for `"texture.png"`
+pub const META_EXTENSION: &str = "meta";
+
+/// Returns the path of the `.meta` file of the asset at `path`.
+pub fn get_meta_path(path: &Path) -> PathBuf {
+    let mut meta_path = path.as_os_str().to_owned();
+    meta_path.push(".");
+    meta_path.push(META_EXTENSION);
+    PathBuf::from(meta_path)
+}
+
+/// Deserializes [`LoaderSettings`] of type `T` from RON, for use in
+/// [`AssetLoader::deserialize_settings`].
+pub fn deserialize_ron_settings<T: LoaderSettings + DeserializeOwned>(
+    bytes: &[u8],
+) -> Result<Option<Box<dyn LoaderSettings>>, anyhow::Error> {
+    Ok(Some(Box::new(ron::de::from_bytes::<T>(bytes)?)))
 }
 
 pub trait Asset: TypeUuid + AssetDynamic {}
@@ -77,33 +127,56 @@ pub struct LoadContext<'a> {
     pub(crate) ref_change_channel: &'a RefChangeChannel,
     pub(crate) asset_io: &'a dyn AssetIo,
     pub(crate) labeled_assets: HashMap<Option<String>, BoxedLoadedAsset>,
+    pub(crate) source: Option<&'a str>,
     pub(crate) path: &'a Path,
     pub(crate) version: usize,
     pub(crate) task_pool: &'a TaskPool,
+    pub(crate) settings: Option<Arc<dyn LoaderSettings>>,
 }
 
 impl<'a> LoadContext<'a> {
     pub(crate) fn new(
+        source: Option<&'a str>,
         path: &'a Path,
         ref_change_channel: &'a RefChangeChannel,
         asset_io: &'a dyn AssetIo,
         version: usize,
         task_pool: &'a TaskPool,
+        settings: Option<Arc<dyn LoaderSettings>>,
     ) -> Self {
         Self {
             ref_change_channel,
             asset_io,
             labeled_assets: Default::default(),
             version,
+            source,
             path,
             task_pool,
+            settings,
         }
     }
 
+    /// The named asset source the asset is being loaded from, if any.
+    pub fn source(&self) -> Option<&str> {
+        self.source
+    }
+
     pub fn path(&self) -> &Path {
         self.path
     }
 
+    /// Returns the [`AssetPath`] of the labeled asset `label` of the asset being loaded.
+    pub fn get_labeled_asset_path<'b>(&'b self, label: &'b str) -> AssetPath<'b> {
+        AssetPath::new_ref(self.path, Some(label)).with_source(self.source)
+    }
+
+    /// Returns the settings of the asset being loaded, if it has settings of type `T`.
+    pub fn settings<T: LoaderSettings>(&self) -> Option<&T> {
+        self.settings
+            .as_ref()
+            .and_then(|settings| settings.downcast_ref::<T>())
+    }
+
     pub fn has_labeled_asset(&self, label: &str) -> bool {
         self.labeled_assets.contains_key(&Some(label.to_string()))
     }
@@ -116,7 +189,7 @@ impl<'a> LoadContext<'a> {
         assert!(!label.is_empty());
         self.labeled_assets
             .insert(Some(label.to_string()), asset.into());
-        self.get_handle(AssetPath::new_ref(self.path(), Some(label)))
+        self.get_handle(self.get_labeled_asset_path(label))
     }
 
     pub fn get_handle<I: Into<HandleId>, T: Asset>(&self, id: I) -> Handle<T> {
@@ -157,6 +230,9 @@ pub struct AssetResult<T: Component> {
 pub struct AssetLifecycleChannel<T: Component> {
     pub sender: Sender<AssetLifecycleEvent<T>>,
     pub receiver: Receiver<AssetLifecycleEvent<T>>,
+    /// Loaded assets that are waiting on their dependencies before
+    /// [`AssetEvent::LoadedWithDependencies`](crate::AssetEvent::LoadedWithDependencies) is sent
+    pub(crate) pending_dependency_loads: Mutex<HashSet<HandleId>>,
 }
 
 pub enum AssetLifecycleEvent<T: Component> {
@@ -196,7 +272,11 @@ impl<T: AssetDynamic> AssetLifecycle for AssetLifecycleChannel<T> {
 impl<T: Component> Default for AssetLifecycleChannel<T> {
     fn default() -> Self {
         let (sender, receiver) = crossbeam_channel::unbounded();
-        AssetLifecycleChannel { sender, receiver }
+        AssetLifecycleChannel {
+            sender,
+            receiver,
+            pending_dependency_loads: Default::default(),
+        }
     }
 }
 
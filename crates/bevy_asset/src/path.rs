@@ -7,8 +7,16 @@ use std::{
     path::{Path, PathBuf},
 };
 
+/// The path of an asset, made of an optional named source, a path inside that source and an
+/// optional label.
+///
+/// Parsed from strings of the form `"source://path/to/asset.ext#label"`. Paths without a
+/// `source://` prefix are read from the default [`AssetIo`](crate::AssetIo) of the
+/// [`AssetServer`](crate::AssetServer).
 #[derive(Debug, Hash, Clone, Serialize, Deserialize)]
 pub struct AssetPath<'a> {
+    #[serde(default)]
+    source: Option<Cow<'a, str>>,
     path: Cow<'a, Path>,
     label: Option<Cow<'a, str>>,
 }
@@ -17,6 +25,7 @@ impl<'a> AssetPath<'a> {
     #[inline]
     pub fn new_ref(path: &'a Path, label: Option<&'a str>) -> AssetPath<'a> {
         AssetPath {
+            source: None,
             path: Cow::Borrowed(path),
             label: label.map(|val| Cow::Borrowed(val)),
         }
@@ -25,11 +34,24 @@ impl<'a> AssetPath<'a> {
     #[inline]
     pub fn new(path: PathBuf, label: Option<String>) -> AssetPath<'a> {
         AssetPath {
+            source: None,
             path: Cow::Owned(path),
             label: label.map(Cow::Owned),
         }
     }
 
+    /// Returns this path, read from the named asset source `source` instead.
+    #[inline]
+    pub fn with_source<S: Into<Cow<'a, str>>>(mut self, source: Option<S>) -> AssetPath<'a> {
+        self.source = source.map(Into::into);
+        self
+    }
+
+    #[inline]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_ref().map(|source| source.as_ref())
+    }
+
     #[inline]
     pub fn get_id(&self) -> AssetPathId {
         AssetPathId::from(self)
@@ -48,6 +70,10 @@ impl<'a> AssetPath<'a> {
     #[inline]
     pub fn to_owned(&self) -> AssetPath<'static> {
         AssetPath {
+            source: self
+                .source
+                .as_ref()
+                .map(|value| Cow::Owned(value.to_string())),
             path: Cow::Owned(self.path.to_path_buf()),
             label: self
                 .label
@@ -75,14 +101,24 @@ pub struct SourcePathId(u64);
 #[reflect_value(PartialEq, Hash, Serialize, Deserialize)]
 pub struct LabelId(u64);
 
-impl<'a> From<&'a Path> for SourcePathId {
-    fn from(value: &'a Path) -> Self {
+impl SourcePathId {
+    fn new(source: Option<&str>, path: &Path) -> Self {
         let mut hasher = get_hasher();
-        value.hash(&mut hasher);
+        // paths in the default source hash the same way they did before named sources existed
+        if let Some(source) = source {
+            source.hash(&mut hasher);
+        }
+        path.hash(&mut hasher);
         SourcePathId(hasher.finish())
     }
 }
 
+impl<'a> From<&'a Path> for SourcePathId {
+    fn from(value: &'a Path) -> Self {
+        SourcePathId::new(None, value)
+    }
+}
+
 impl From<AssetPathId> for SourcePathId {
     fn from(id: AssetPathId) -> Self {
         id.source_path_id()
@@ -118,23 +154,26 @@ pub(crate) fn get_hasher() -> AHasher {
     AHasher::new_with_keys(42, 23)
 }
 
+pub(crate) fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = get_hasher();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
 impl<'a, T> From<T> for AssetPathId
 where
     T: Into<AssetPath<'a>>,
 {
     fn from(value: T) -> Self {
         let asset_path: AssetPath = value.into();
-        AssetPathId(
-            SourcePathId::from(asset_path.path()),
-            LabelId::from(asset_path.label()),
-        )
+        AssetPathId::from(&asset_path)
     }
 }
 
 impl<'a, 'b> From<&'a AssetPath<'b>> for AssetPathId {
     fn from(asset_path: &'a AssetPath<'b>) -> Self {
         AssetPathId(
-            SourcePathId::from(asset_path.path()),
+            SourcePathId::new(asset_path.source(), asset_path.path()),
             LabelId::from(asset_path.label()),
         )
     }
@@ -142,10 +181,15 @@ impl<'a, 'b> From<&'a AssetPath<'b>> for AssetPathId {
 
 impl<'a> From<&'a str> for AssetPath<'a> {
     fn from(asset_path: &'a str) -> Self {
+        let (source, asset_path) = match asset_path.find("://") {
+            Some(index) => (Some(&asset_path[..index]), &asset_path[index + 3..]),
+            None => (None, asset_path),
+        };
         let mut parts = asset_path.split('#');
         let path = Path::new(parts.next().expect("Path must be set."));
         let label = parts.next();
         AssetPath {
+            source: source.map(Cow::Borrowed),
             path: Cow::Borrowed(path),
             label: label.map(|label| Cow::Borrowed(label)),
         }
@@ -155,6 +199,7 @@ impl<'a> From<&'a str> for AssetPath<'a> {
 impl<'a> From<&'a Path> for AssetPath<'a> {
     fn from(path: &'a Path) -> Self {
         AssetPath {
+            source: None,
             path: Cow::Borrowed(path),
             label: None,
         }
@@ -164,6 +209,7 @@ impl<'a> From<&'a Path> for AssetPath<'a> {
 impl<'a> From<PathBuf> for AssetPath<'a> {
     fn from(path: PathBuf) -> Self {
         AssetPath {
+            source: None,
             path: Cow::Owned(path),
             label: None,
         }
@@ -0,0 +1,472 @@
+use crate::{AssetIo, AssetIoError, AssetServerSettings};
+use anyhow::Result;
+use bevy_ecs::world::World;
+use bevy_log::{info, warn};
+use bevy_tasks::{IoTaskPool, TaskPool};
+use bevy_utils::{BoxedFuture, HashMap};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
+
+/// Turns a source asset into an optimized form, such as textures with compressed data or meshes
+/// with precomputed tangents.
+///
+/// The output is cached and read instead of the source asset at runtime, so it must be
+/// understood by the [`AssetLoader`](crate::AssetLoader) registered for the same extension.
+///
+/// Bevy ships processors that compress images into DDS textures (with the `dds` feature) and
+/// that embed the buffers and precomputed tangents of glTF scenes. Audio isn't processed yet.
+/// Processors are registered with [`AddAsset::add_asset_processor`](crate::AddAsset).
+pub trait AssetProcessor: Send + Sync + 'static {
+    fn process<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        process_context: &'a mut ProcessContext,
+    ) -> BoxedFuture<'a, Result<Vec<u8>, anyhow::Error>>;
+    fn extensions(&self) -> &[&str];
+
+    /// The version of the processed output. Changing it invalidates all cached outputs of this
+    /// processor.
+    fn version(&self) -> u32 {
+        0
+    }
+}
+
+/// Errors that occur while processing assets
+#[derive(Error, Debug)]
+pub enum AssetProcessorError {
+    #[error("encountered an error while reading an asset: {0}")]
+    AssetIoError(#[from] AssetIoError),
+    #[error("encountered an error while writing a processed asset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("encountered an error while processing asset {0}: {1}")]
+    ProcessorError(PathBuf, anyhow::Error),
+    #[error("failed to write the processor manifest: {0}")]
+    InvalidManifest(#[from] ron::Error),
+}
+
+/// The context of a single [`AssetProcessor::process`] call
+pub struct ProcessContext<'a> {
+    asset_io: &'a dyn AssetIo,
+    path: &'a Path,
+    dependencies: BTreeMap<PathBuf, String>,
+}
+
+impl<'a> ProcessContext<'a> {
+    pub fn path(&self) -> &Path {
+        self.path
+    }
+
+    /// Reads the bytes of another source asset. The asset is tracked as a dependency, so the
+    /// asset being processed is reprocessed whenever it changes.
+    pub async fn read_asset_bytes<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<Vec<u8>, AssetIoError> {
+        let path = path.as_ref();
+        let bytes = self.asset_io.load_path(path).await?;
+        self.dependencies
+            .insert(path.to_owned(), content_hash(&bytes));
+        Ok(bytes)
+    }
+}
+
+/// What a processed asset was produced from, used to decide whether it is up to date.
+///
+/// Hashes are hex encoded BLAKE3 hashes, which are stable across Bevy versions and platforms.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessedAssetInfo {
+    pub source_hash: String,
+    pub processor_version: u32,
+    pub dependencies: BTreeMap<PathBuf, String>,
+}
+
+/// The record of every processed asset in a cache folder
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProcessorManifest {
+    pub assets: BTreeMap<PathBuf, ProcessedAssetInfo>,
+}
+
+impl ProcessorManifest {
+    /// The name of the manifest file inside the cache folder.
+    pub const FILE_NAME: &'static str = ".processor_manifest.ron";
+}
+
+/// The outcome of [`AssetProcessors::process_folder`]
+#[derive(Debug, Default)]
+pub struct ProcessReport {
+    pub processed: Vec<PathBuf>,
+    pub up_to_date: Vec<PathBuf>,
+    /// Sources that were deleted or no longer have a processor, whose outputs were removed
+    pub removed: Vec<PathBuf>,
+    pub failed: Vec<AssetProcessorError>,
+}
+
+/// Settings for processing assets into a cache folder. Insert this resource before adding the
+/// [`AssetPlugin`](crate::AssetPlugin) to enable asset processing.
+///
+/// Processing runs once at startup. Sources that change while the app runs are reprocessed on
+/// the next start.
+pub struct AssetProcessorSettings {
+    pub processed_asset_folder: String,
+}
+
+impl Default for AssetProcessorSettings {
+    fn default() -> Self {
+        Self {
+            processed_asset_folder: "processed_assets".to_string(),
+        }
+    }
+}
+
+/// The registered [`AssetProcessor`]s, keyed by extension
+#[derive(Default)]
+pub struct AssetProcessors {
+    processors: Vec<Arc<dyn AssetProcessor>>,
+    extension_to_processor_index: HashMap<String, usize>,
+}
+
+impl AssetProcessors {
+    pub fn add_processor<T: AssetProcessor>(&mut self, processor: T) {
+        let processor_index = self.processors.len();
+        for extension in processor.extensions().iter() {
+            self.extension_to_processor_index
+                .insert(extension.to_string(), processor_index);
+        }
+        self.processors.push(Arc::new(processor));
+    }
+
+    fn get_path_processor(&self, path: &Path) -> Option<&Arc<dyn AssetProcessor>> {
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+        let mut ext = file_name.as_str();
+        while let Some(idx) = ext.find('.') {
+            ext = &ext[idx + 1..];
+            if let Some(index) = self.extension_to_processor_index.get(ext) {
+                return Some(&self.processors[*index]);
+            }
+        }
+        None
+    }
+
+    /// Processes every asset in `source_io` that has a registered processor into `cache_folder`.
+    ///
+    /// Assets whose source, dependencies and processor version are unchanged since the last run
+    /// are skipped. The outputs of sources that failed to process, were deleted or no longer have
+    /// a processor are removed, so their sources are read instead.
+    pub fn process_folder(
+        &self,
+        source_io: &dyn AssetIo,
+        cache_folder: &Path,
+        task_pool: &TaskPool,
+    ) -> Result<ProcessReport, AssetProcessorError> {
+        let manifest_path = cache_folder.join(ProcessorManifest::FILE_NAME);
+        let mut manifest = match fs::read_to_string(&manifest_path) {
+            // a manifest that can't be read (e.g. written by an older version) reprocesses everything
+            Ok(manifest) => ron::from_str::<ProcessorManifest>(&manifest).unwrap_or_else(|err| {
+                warn!("ignoring invalid processor manifest: {}", err);
+                Default::default()
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Default::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut paths = Vec::new();
+        self.collect_processable_paths(source_io, Path::new(""), &mut paths)?;
+
+        let removed = manifest
+            .assets
+            .keys()
+            .filter(|path| !paths.contains(*path))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let previous_manifest = &manifest;
+        let results = task_pool.scope(|scope| {
+            for path in paths {
+                scope.spawn(async move {
+                    let result = self
+                        .process_asset(source_io, cache_folder, &path, previous_manifest)
+                        .await;
+                    (path, result)
+                });
+            }
+        });
+
+        let mut report = ProcessReport::default();
+        for path in removed {
+            manifest.assets.remove(&path);
+            match remove_output(cache_folder, &path) {
+                Ok(()) => report.removed.push(path),
+                Err(err) => report.failed.push(err.into()),
+            }
+        }
+        for (path, result) in results {
+            match result {
+                Ok(Some(info)) => {
+                    manifest.assets.insert(path.clone(), info);
+                    report.processed.push(path);
+                }
+                Ok(None) => report.up_to_date.push(path),
+                Err(err) => {
+                    manifest.assets.remove(&path);
+                    report.failed.push(err);
+                    if let Err(err) = remove_output(cache_folder, &path) {
+                        report.failed.push(err.into());
+                    }
+                }
+            }
+        }
+
+        fs::create_dir_all(cache_folder)?;
+        let manifest = ron::ser::to_string_pretty(&manifest, Default::default())?;
+        fs::write(manifest_path, manifest)?;
+        Ok(report)
+    }
+
+    fn collect_processable_paths(
+        &self,
+        source_io: &dyn AssetIo,
+        path: &Path,
+        paths: &mut Vec<PathBuf>,
+    ) -> Result<(), AssetIoError> {
+        for child_path in source_io.read_directory(path)? {
+            if source_io.is_directory(&child_path) {
+                self.collect_processable_paths(source_io, &child_path, paths)?;
+            } else if self.get_path_processor(&child_path).is_some() {
+                paths.push(child_path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes a single asset, returning `None` if the cached output is still up to date.
+    async fn process_asset(
+        &self,
+        source_io: &dyn AssetIo,
+        cache_folder: &Path,
+        path: &Path,
+        manifest: &ProcessorManifest,
+    ) -> Result<Option<ProcessedAssetInfo>, AssetProcessorError> {
+        let processor = self
+            .get_path_processor(path)
+            .expect("Only paths with a processor are processed.");
+        let bytes = source_io.load_path(path).await?;
+        let source_hash = content_hash(&bytes);
+        let output_path = cache_folder.join(path);
+
+        if let Some(info) = manifest.assets.get(path) {
+            if info.source_hash == source_hash
+                && info.processor_version == processor.version()
+                && output_path.exists()
+                && dependencies_unchanged(source_io, info).await
+            {
+                return Ok(None);
+            }
+        }
+
+        let mut process_context = ProcessContext {
+            asset_io: source_io,
+            path,
+            dependencies: Default::default(),
+        };
+        let output = processor
+            .process(&bytes, &mut process_context)
+            .await
+            .map_err(|err| AssetProcessorError::ProcessorError(path.to_owned(), err))?;
+
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&output_path, output)?;
+
+        Ok(Some(ProcessedAssetInfo {
+            source_hash,
+            processor_version: processor.version(),
+            dependencies: process_context.dependencies,
+        }))
+    }
+}
+
+/// Removes the processed output of `path`, if there is one.
+fn remove_output(cache_folder: &Path, path: &Path) -> Result<(), std::io::Error> {
+    match fs::remove_file(cache_folder.join(path)) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+fn content_hash(bytes: &[u8]) -> String {
+    blake3::hash(bytes).to_hex().to_string()
+}
+
+async fn dependencies_unchanged(source_io: &dyn AssetIo, info: &ProcessedAssetInfo) -> bool {
+    for (dependency, hash) in info.dependencies.iter() {
+        match source_io.load_path(dependency).await {
+            Ok(bytes) if content_hash(&bytes) == *hash => continue,
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Processes all assets of the asset folder into the folder set in [`AssetProcessorSettings`].
+pub fn process_assets_system(world: &mut World) {
+    let world = &*world;
+    let processors = world.get_resource::<AssetProcessors>().unwrap();
+    let settings = world.get_resource::<AssetProcessorSettings>().unwrap();
+    let server_settings = world.get_resource::<AssetServerSettings>().unwrap();
+    let task_pool = world.get_resource::<IoTaskPool>().unwrap();
+
+    let source_io = crate::FileAssetIo::new(&server_settings.asset_folder);
+    let cache_folder = crate::FileAssetIo::get_root_path().join(&settings.processed_asset_folder);
+    match processors.process_folder(&source_io, &cache_folder, task_pool) {
+        Ok(report) => {
+            info!(
+                "processed {} assets, {} were up to date",
+                report.processed.len(),
+                report.up_to_date.len()
+            );
+            for err in report.failed {
+                warn!("{}", err);
+            }
+        }
+        Err(err) => warn!("failed to process assets: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::FileAssetIo;
+
+    struct UppercaseProcessor;
+    impl AssetProcessor for UppercaseProcessor {
+        fn process<'a>(
+            &'a self,
+            bytes: &'a [u8],
+            process_context: &'a mut ProcessContext,
+        ) -> BoxedFuture<'a, Result<Vec<u8>, anyhow::Error>> {
+            Box::pin(async move {
+                if bytes == b"fail" {
+                    return Err(anyhow::anyhow!("failed to process"));
+                }
+                let mut output = bytes.to_ascii_uppercase();
+                if process_context.path() == Path::new("with_dep.txt") {
+                    output.extend(process_context.read_asset_bytes("dep.dat").await?);
+                }
+                Ok(output)
+            })
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+    }
+
+    #[test]
+    fn content_hashes_are_stable() {
+        assert_eq!(
+            content_hash(b""),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+
+    #[test]
+    fn invalid_manifests_reprocess_everything() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        fs::write(source_dir.path().join("a.txt"), b"a").unwrap();
+        fs::write(
+            cache_dir.path().join(ProcessorManifest::FILE_NAME),
+            "(assets: {\"a.txt\": (source_hash: 42)})",
+        )
+        .unwrap();
+
+        let mut processors = AssetProcessors::default();
+        processors.add_processor(UppercaseProcessor);
+        let report = processors
+            .process_folder(
+                &FileAssetIo::new(source_dir.path()),
+                cache_dir.path(),
+                &TaskPool::default(),
+            )
+            .unwrap();
+        assert_eq!(report.processed, vec![PathBuf::from("a.txt")]);
+    }
+
+    #[test]
+    fn processes_changed_assets_only() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        fs::write(source_dir.path().join("a.txt"), b"a").unwrap();
+        fs::write(source_dir.path().join("with_dep.txt"), b"b").unwrap();
+        fs::write(source_dir.path().join("dep.dat"), b"1").unwrap();
+
+        let mut processors = AssetProcessors::default();
+        processors.add_processor(UppercaseProcessor);
+        let source_io = FileAssetIo::new(source_dir.path());
+        let task_pool = TaskPool::default();
+        let process = || {
+            processors
+                .process_folder(&source_io, cache_dir.path(), &task_pool)
+                .unwrap()
+        };
+
+        let report = process();
+        assert_eq!(report.processed.len(), 2);
+        assert_eq!(fs::read(cache_dir.path().join("a.txt")).unwrap(), b"A");
+        assert_eq!(
+            fs::read(cache_dir.path().join("with_dep.txt")).unwrap(),
+            b"B1"
+        );
+        assert!(!cache_dir.path().join("dep.dat").exists());
+
+        let report = process();
+        assert!(report.processed.is_empty());
+        assert_eq!(report.up_to_date.len(), 2);
+
+        fs::write(source_dir.path().join("dep.dat"), b"2").unwrap();
+        let report = process();
+        assert_eq!(report.processed, vec![PathBuf::from("with_dep.txt")]);
+        assert_eq!(
+            fs::read(cache_dir.path().join("with_dep.txt")).unwrap(),
+            b"B2"
+        );
+    }
+
+    #[test]
+    fn removes_outputs_of_failed_and_deleted_sources() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        fs::write(source_dir.path().join("a.txt"), b"a").unwrap();
+        fs::write(source_dir.path().join("b.txt"), b"b").unwrap();
+
+        let mut processors = AssetProcessors::default();
+        processors.add_processor(UppercaseProcessor);
+        let source_io = FileAssetIo::new(source_dir.path());
+        let task_pool = TaskPool::default();
+        let report = processors
+            .process_folder(&source_io, cache_dir.path(), &task_pool)
+            .unwrap();
+        assert_eq!(report.processed.len(), 2);
+
+        fs::remove_file(source_dir.path().join("a.txt")).unwrap();
+        fs::write(source_dir.path().join("b.txt"), b"fail").unwrap();
+        let report = processors
+            .process_folder(&source_io, cache_dir.path(), &task_pool)
+            .unwrap();
+        assert_eq!(report.removed, vec![PathBuf::from("a.txt")]);
+        assert_eq!(report.failed.len(), 1);
+        assert!(!cache_dir.path().join("a.txt").exists());
+        assert!(!cache_dir.path().join("b.txt").exists());
+
+        let manifest = fs::read_to_string(cache_dir.path().join(ProcessorManifest::FILE_NAME));
+        let manifest = ron::from_str::<ProcessorManifest>(&manifest.unwrap()).unwrap();
+        assert!(manifest.assets.is_empty());
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{Asset, AssetServer, Assets};
+use anyhow::Result;
+use bevy_ecs::system::Res;
+use serde::Serialize;
+use std::marker::PhantomData;
+
+/// A saver for an asset type, the counterpart of an [`AssetLoader`](crate::AssetLoader)
+///
+/// Savers are used by [`AssetServer::save`], which picks the saver
+/// by the extension of the path the asset is saved to.
+pub trait AssetSaver: Send + Sync + 'static {
+    type Asset: Asset;
+
+    fn save(&self, asset: &Self::Asset) -> Result<Vec<u8>, anyhow::Error>;
+    fn extensions(&self) -> &[&str];
+}
+
+/// Saves the assets queued up by [`AssetServer::save`].
+pub fn save_assets_system<T: Asset>(asset_server: Res<AssetServer>, assets: Res<Assets<T>>) {
+    asset_server.save_assets(&assets);
+}
+
+/// Saves assets that implement [`Serialize`] as pretty printed RON
+pub struct RonAssetSaver<T> {
+    extensions: &'static [&'static str],
+    marker: PhantomData<fn(&T)>,
+}
+
+impl<T> RonAssetSaver<T> {
+    /// Creates a saver for paths with the given extensions, e.g. `&["level.ron"]`.
+    pub fn new(extensions: &'static [&'static str]) -> Self {
+        Self {
+            extensions,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for RonAssetSaver<T> {
+    fn default() -> Self {
+        Self::new(&["ron"])
+    }
+}
+
+impl<T: Asset + Serialize> AssetSaver for RonAssetSaver<T> {
+    type Asset = T;
+
+    fn save(&self, asset: &T) -> Result<Vec<u8>, anyhow::Error> {
+        let ron = ron::ser::to_string_pretty(asset, ron::ser::PrettyConfig::default())?;
+        Ok(ron.into_bytes())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+}
@@ -1,5 +1,5 @@
-use crate::{AudioSource, Decodable};
-use bevy_asset::{Asset, Handle};
+use crate::{AudioBus, AudioSink, AudioSource, Decodable, EffectChain};
+use bevy_asset::{Asset, Handle, HandleId};
 use parking_lot::RwLock;
 use std::{collections::VecDeque, fmt};
 
@@ -8,7 +8,8 @@ pub struct Audio<P = AudioSource>
 where
     P: Asset + Decodable,
 {
-    pub queue: RwLock<VecDeque<Handle<P>>>,
+    /// Queue for playing audio from asset handles
+    pub queue: RwLock<VecDeque<AudioToPlay<P>>>,
 }
 
 impl<P: Asset> fmt::Debug for Audio<P>
@@ -37,7 +38,150 @@ where
     <P as Decodable>::Decoder: rodio::Source + Send + Sync,
     <<P as Decodable>::Decoder as Iterator>::Item: rodio::Sample + Send + Sync,
 {
-    pub fn play(&self, audio_source: Handle<P>) {
-        self.queue.write().push_front(audio_source);
+    /// Plays audio from a [`Handle`] to the audio source once.
+    ///
+    /// Returns a weak [`Handle`] to the [`AudioSink`] that controls the playback. The sink is
+    /// added to the `Assets<AudioSink>` collection once the sound starts playing, which may be
+    /// a few frames later if the audio source is still loading. The sink is freed once the sound
+    /// is done, unless the handle was upgraded with
+    /// [`Assets::get_handle`](bevy_asset::Assets::get_handle).
+    ///
+    /// ```
+    /// # use bevy_ecs::system::Res;
+    /// # use bevy_asset::AssetServer;
+    /// # use bevy_audio::Audio;
+    /// fn play_audio_system(asset_server: Res<AssetServer>, audio: Res<Audio>) {
+    ///     audio.play(asset_server.load("my_sound.ogg"));
+    /// }
+    /// ```
+    pub fn play(&self, audio_source: Handle<P>) -> Handle<AudioSink> {
+        self.play_with_settings(audio_source, PlaybackSettings::ONCE)
+    }
+
+    /// Plays audio from a [`Handle`] to the audio source with the given [`PlaybackSettings`].
+    ///
+    /// See [`Audio::play`] for the returned handle.
+    pub fn play_with_settings(
+        &self,
+        audio_source: Handle<P>,
+        settings: PlaybackSettings,
+    ) -> Handle<AudioSink> {
+        let sink_handle_id = HandleId::random::<AudioSink>();
+        self.queue.write().push_front(AudioToPlay {
+            sink_handle_id,
+            source_handle: audio_source,
+            settings,
+        });
+        Handle::<AudioSink>::weak(sink_handle_id)
+    }
+}
+
+/// Settings to control playback from the start
+#[derive(Clone, Debug)]
+pub struct PlaybackSettings {
+    /// Play in repeat
+    pub repeat: bool,
+    /// Volume to play at, where `1.0` is the volume of the source
+    pub volume: f32,
+    /// Speed to play at, which also changes the pitch
+    pub speed: f32,
+    /// Start the sound paused
+    pub paused: bool,
+    /// The bus of the [`AudioMixer`](crate::AudioMixer) to play on
+    pub bus: AudioBus,
+    /// Play the sound from the position of an [`AudioEmitter`](crate::AudioEmitter)
+    pub spatial: bool,
+    /// Effects applied to the sound, before it is played on its bus
+    pub effects: Option<EffectChain>,
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        Self::ONCE
+    }
+}
+
+impl PlaybackSettings {
+    /// Play the sound once
+    pub const ONCE: PlaybackSettings = PlaybackSettings {
+        repeat: false,
+        volume: 1.0,
+        speed: 1.0,
+        paused: false,
+        bus: AudioBus::Master,
+        spatial: false,
+        effects: None,
+    };
+
+    /// Repeat the sound until it is stopped
+    pub const LOOP: PlaybackSettings = PlaybackSettings {
+        repeat: true,
+        volume: 1.0,
+        speed: 1.0,
+        paused: false,
+        bus: AudioBus::Master,
+        spatial: false,
+        effects: None,
+    };
+
+    /// Helper to set the volume from start of playback
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Helper to set the speed from start of playback
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Helper to play on the given bus of the [`AudioMixer`](crate::AudioMixer)
+    pub fn with_bus(mut self, bus: AudioBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    /// Helper to play the sound from the position of an [`AudioEmitter`](crate::AudioEmitter),
+    /// which is given the returned sink handle
+    pub fn spatial(mut self) -> Self {
+        self.spatial = true;
+        self
+    }
+
+    /// Helper to apply effects to the sound, which can be changed while it plays through the
+    /// shared [`EffectChain`]
+    pub fn with_effects(mut self, effects: EffectChain) -> Self {
+        self.effects = Some(effects);
+        self
+    }
+
+    /// Helper to start playback paused
+    pub fn paused(mut self) -> Self {
+        self.paused = true;
+        self
+    }
+}
+
+/// A sound waiting in the [`Audio`] queue to be played
+pub struct AudioToPlay<P>
+where
+    P: Asset + Decodable,
+{
+    pub(crate) sink_handle_id: HandleId,
+    pub(crate) source_handle: Handle<P>,
+    pub(crate) settings: PlaybackSettings,
+}
+
+impl<P> fmt::Debug for AudioToPlay<P>
+where
+    P: Asset + Decodable,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AudioToPlay")
+            .field("sink_handle_id", &self.sink_handle_id)
+            .field("source_handle", &self.source_handle)
+            .field("settings", &self.settings)
+            .finish()
     }
 }
@@ -1,38 +1,87 @@
-use crate::{Audio, AudioSource, Decodable};
-use bevy_asset::{Asset, Assets};
-use bevy_ecs::world::World;
+use crate::{
+    mixer::{TrackPlaying, UntilDone},
+    spatial::{Spatial, SpatialControls},
+    Audio, AudioMixer, AudioSource, Decodable, PlaybackSettings,
+};
+use bevy_asset::{Asset, AssetServer, Assets};
+use bevy_ecs::{
+    system::{Res, ResMut},
+    world::{FromWorld, World},
+};
+use bevy_reflect::TypeUuid;
 use bevy_utils::tracing::warn;
-use rodio::{OutputStream, OutputStreamHandle, Sink};
-use std::marker::PhantomData;
+use rodio::{OutputStream, OutputStreamHandle, Sample, Sink, Source};
+use std::{
+    marker::PhantomData,
+    sync::{atomic::AtomicBool, Arc},
+};
 
-/// Used internally to play audio on the current "audio device"
+/// The current "audio device", which plays the [`AudioMixer`] resource
+pub struct AudioDevice {
+    // the stream stops playing once it is dropped
+    stream: Option<(OutputStream, OutputStreamHandle)>,
+}
+
+impl AudioDevice {
+    /// Opens the default audio device, if there is one.
+    pub fn open() -> Self {
+        match OutputStream::try_default() {
+            Ok(stream) => Self {
+                stream: Some(stream),
+            },
+            Err(_) => {
+                warn!("No audio device found.");
+                Self { stream: None }
+            }
+        }
+    }
+
+    /// Returns true if an audio device was found.
+    pub fn is_available(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Plays the output of `mixer` on the device. Does nothing without a device.
+    pub fn attach(&self, mixer: &AudioMixer) {
+        if let Some((_, stream_handle)) = &self.stream {
+            if let Err(err) = stream_handle.play_raw(mixer.output()) {
+                warn!("Failed to play audio: {}", err);
+            }
+        }
+    }
+}
+
+impl FromWorld for AudioDevice {
+    fn from_world(world: &mut World) -> Self {
+        let device = Self::open();
+        device.attach(&world.get_resource_or_insert_with(AudioMixer::default));
+        device
+    }
+}
+
+/// Used internally to play audio through the [`AudioMixer`]
+///
+/// Sounds are played on the mixer whether or not an [`AudioDevice`] is available. Without a
+/// device, nothing consumes the mix, so sounds only advance when the mix is rendered with
+/// [`AudioMixer::render`].
 pub struct AudioOutput<P = AudioSource>
 where
     P: Decodable,
 {
-    _stream: Option<OutputStream>,
-    stream_handle: Option<OutputStreamHandle>,
+    mixer: AudioMixer,
     phantom: PhantomData<P>,
 }
 
-impl<P> Default for AudioOutput<P>
+impl<P> FromWorld for AudioOutput<P>
 where
     P: Decodable,
 {
-    fn default() -> Self {
-        if let Ok((stream, stream_handle)) = OutputStream::try_default() {
-            Self {
-                _stream: Some(stream),
-                stream_handle: Some(stream_handle),
-                phantom: PhantomData,
-            }
-        } else {
-            warn!("No audio device found.");
-            Self {
-                _stream: None,
-                stream_handle: None,
-                phantom: PhantomData,
-            }
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            mixer: world
+                .get_resource_or_insert_with(AudioMixer::default)
+                .clone(),
+            phantom: PhantomData,
         }
     }
 }
@@ -43,31 +92,99 @@ where
     <P as Decodable>::Decoder: rodio::Source + Send + Sync,
     <<P as Decodable>::Decoder as Iterator>::Item: rodio::Sample + Send + Sync,
 {
-    fn play_source(&self, audio_source: &P) {
-        if let Some(stream_handle) = &self.stream_handle {
-            let sink = Sink::try_new(stream_handle).unwrap();
-            sink.append(audio_source.decoder());
-            sink.detach();
+    fn play_source(&self, audio_source: &P, settings: &PlaybackSettings) -> AudioSink {
+        let (sink, output) = Sink::new_idle();
+        // the output of an idle sink never ends, so the mixer drops it once the sound is done
+        let playing = Arc::new(AtomicBool::new(true));
+        let spatial = if settings.repeat {
+            append_source(
+                &sink,
+                audio_source.decoder().repeat_infinite(),
+                settings,
+                playing.clone(),
+            )
+        } else {
+            append_source(&sink, audio_source.decoder(), settings, playing.clone())
+        };
+        sink.set_volume(settings.volume);
+        sink.set_speed(settings.speed);
+        if settings.paused {
+            sink.pause();
+        }
+        self.mixer
+            .play(settings.bus, UntilDone::new(output, playing));
+        AudioSink {
+            sink: Some(sink),
+            spatial,
         }
     }
 
-    fn try_play_queued(&self, audio_sources: &Assets<P>, audio: &mut Audio<P>) {
+    fn try_play_queued(
+        &self,
+        audio_sources: &Assets<P>,
+        audio: &mut Audio<P>,
+        sinks: &mut Assets<AudioSink>,
+    ) {
         let mut queue = audio.queue.write();
         let len = queue.len();
         let mut i = 0;
         while i < len {
-            let audio_source_handle = queue.pop_back().unwrap();
-            if let Some(audio_source) = audio_sources.get(&audio_source_handle) {
-                self.play_source(audio_source);
+            let config = queue.pop_back().unwrap();
+            if let Some(audio_source) = audio_sources.get(&config.source_handle) {
+                let sink = self.play_source(audio_source, &config.settings);
+                // no strong handle is kept here, the sink is freed by
+                // `remove_finished_audio_sinks_system` once the sound is done
+                sinks.set_untracked(config.sink_handle_id, sink);
             } else {
                 // audio source hasn't loaded yet. add it back to the queue
-                queue.push_front(audio_source_handle);
+                queue.push_front(config);
             }
             i += 1;
         }
     }
 }
 
+/// Appends `source` with its effects to `sink`, returning the controls of the sound if it is
+/// spatial
+fn append_source<S>(
+    sink: &Sink,
+    source: S,
+    settings: &PlaybackSettings,
+    playing: Arc<AtomicBool>,
+) -> Option<Arc<SpatialControls>>
+where
+    S: Source + Send + 'static,
+    S::Item: Sample + Send,
+{
+    match &settings.effects {
+        Some(effects) => append_spatial(sink, effects.apply(source), settings.spatial, playing),
+        None => append_spatial(sink, source, settings.spatial, playing),
+    }
+}
+
+fn append_spatial<S>(
+    sink: &Sink,
+    source: S,
+    spatial: bool,
+    playing: Arc<AtomicBool>,
+) -> Option<Arc<SpatialControls>>
+where
+    S: Source + Send + 'static,
+    S::Item: Sample + Send,
+{
+    if spatial {
+        let controls = Arc::new(SpatialControls::default());
+        sink.append(TrackPlaying::new(
+            Spatial::new(source, controls.clone()),
+            playing,
+        ));
+        Some(controls)
+    } else {
+        sink.append(TrackPlaying::new(source, playing));
+        None
+    }
+}
+
 /// Plays audio currently queued in the [Audio] resource through the [AudioOutput] resource
 pub fn play_queued_audio_system<P: Asset>(world: &mut World)
 where
@@ -78,8 +195,243 @@ where
     let world = world.cell();
     let audio_output = world.get_non_send::<AudioOutput<P>>().unwrap();
     let mut audio = world.get_resource_mut::<Audio<P>>().unwrap();
+    let mut sinks = world.get_resource_mut::<Assets<AudioSink>>().unwrap();
 
     if let Some(audio_sources) = world.get_resource::<Assets<P>>() {
-        audio_output.try_play_queued(&*audio_sources, &mut *audio);
+        audio_output.try_play_queued(&*audio_sources, &mut *audio, &mut *sinks);
     };
 }
+
+/// Frees the [`AudioSink`]s of sounds that are done playing, unless strong handles to them are
+/// left.
+pub fn remove_finished_audio_sinks_system(
+    asset_server: Res<AssetServer>,
+    mut sinks: ResMut<Assets<AudioSink>>,
+) {
+    let finished = sinks
+        .iter()
+        .filter(|(id, sink)| sink.empty() && !asset_server.has_strong_handles(*id))
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+    for id in finished {
+        sinks.remove(id);
+    }
+}
+
+/// Asset controlling the playback of a sound
+///
+/// ```
+/// # use bevy_ecs::system::{Local, Res};
+/// # use bevy_asset::{Assets, Handle};
+/// # use bevy_audio::AudioSink;
+/// // Execution of this system should be controlled by a state or input,
+/// // otherwise it would just toggle between play and pause every frame.
+/// fn pause(
+///     audio_sinks: Res<Assets<AudioSink>>,
+///     music_controller: Local<Handle<AudioSink>>,
+/// ) {
+///     if let Some(sink) = audio_sinks.get(&*music_controller) {
+///         if sink.is_paused() {
+///             sink.play()
+///         } else {
+///             sink.pause()
+///         }
+///     }
+/// }
+/// ```
+#[derive(TypeUuid)]
+#[uuid = "8bec2ff2-9cbd-4c2c-bd21-43f4b4d5a1f4"]
+pub struct AudioSink {
+    // This field is an Option in order to allow us to have a safe drop that will detach the sink.
+    // It will never be None during its life
+    sink: Option<Sink>,
+    spatial: Option<Arc<SpatialControls>>,
+}
+
+impl Drop for AudioSink {
+    fn drop(&mut self) {
+        // dropping a rodio sink stops its sound, detaching lets it play to the end
+        self.sink.take().unwrap().detach();
+    }
+}
+
+impl AudioSink {
+    fn sink(&self) -> &Sink {
+        self.sink.as_ref().unwrap()
+    }
+
+    pub(crate) fn spatial(&self) -> Option<&SpatialControls> {
+        self.spatial.as_deref()
+    }
+
+    /// Gets the volume of the sound.
+    ///
+    /// The value `1.0` is the "normal" volume (unfiltered input). Any value other than `1.0`
+    /// will multiply each sample by this value.
+    pub fn volume(&self) -> f32 {
+        self.sink().volume()
+    }
+
+    /// Changes the volume of the sound.
+    ///
+    /// The value `1.0` is the "normal" volume (unfiltered input). Any value other than `1.0`
+    /// will multiply each sample by this value.
+    pub fn set_volume(&self, volume: f32) {
+        self.sink().set_volume(volume);
+    }
+
+    /// Gets the speed of the sound.
+    ///
+    /// The value `1.0` is the "normal" speed (unfiltered input). Any value other than `1.0`
+    /// will change the play speed of the sound, and its pitch with it.
+    pub fn speed(&self) -> f32 {
+        self.sink().speed()
+    }
+
+    /// Changes the speed of the sound.
+    ///
+    /// The value `1.0` is the "normal" speed (unfiltered input). Any value other than `1.0`
+    /// will change the play speed of the sound, and its pitch with it.
+    pub fn set_speed(&self, speed: f32) {
+        self.sink().set_speed(speed);
+    }
+
+    /// Resumes playback of a paused sink.
+    ///
+    /// No effect if not paused.
+    pub fn play(&self) {
+        self.sink().play();
+    }
+
+    /// Pauses playback of this sink.
+    ///
+    /// No effect if already paused. A paused sink can be resumed with [`play`](Self::play).
+    pub fn pause(&self) {
+        self.sink().pause();
+    }
+
+    /// Is this sink paused?
+    ///
+    /// Sinks can be paused and resumed using [`pause`](Self::pause) and [`play`](Self::play).
+    pub fn is_paused(&self) -> bool {
+        self.sink().is_paused()
+    }
+
+    /// Stops the sound for good, e.g. to end a looping sound. A stopped sink can not be
+    /// resumed.
+    pub fn stop(&self) {
+        self.sink().stop();
+    }
+
+    /// Returns true if the sound has finished or was stopped.
+    pub fn empty(&self) -> bool {
+        self.sink().empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AudioBus, ProceduralAudio};
+    use bevy_app::{App, CoreStage};
+    use bevy_asset::{AddAsset, AssetPlugin, HandleId};
+    use bevy_core::CorePlugin;
+    use bevy_ecs::system::{IntoExclusiveSystem, IntoSystem};
+    use rodio::{buffer::SamplesBuffer, source::Zero};
+
+    fn insert_sink(app: &mut App, playing: bool) -> HandleId {
+        let (sink, _output) = Sink::new_idle();
+        if playing {
+            // nothing consumes the output of the idle sink, so the sound never ends
+            sink.append(Zero::<f32>::new(1, 44100));
+        }
+        let id = HandleId::random::<AudioSink>();
+        app.world
+            .get_resource_mut::<Assets<AudioSink>>()
+            .unwrap()
+            .set_untracked(
+                id,
+                AudioSink {
+                    sink: Some(sink),
+                    spatial: None,
+                },
+            );
+        id
+    }
+
+    #[test]
+    fn finished_sinks_are_freed() {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<AudioSink>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                remove_finished_audio_sinks_system.system(),
+            );
+
+        let finished = insert_sink(&mut app, false);
+        let playing = insert_sink(&mut app, true);
+        let kept = insert_sink(&mut app, false);
+        let _kept_handle = app
+            .world
+            .get_resource::<Assets<AudioSink>>()
+            .unwrap()
+            .get_handle(kept);
+        app.update();
+
+        let sinks = app.world.get_resource::<Assets<AudioSink>>().unwrap();
+        assert!(sinks.get(finished).is_none());
+        assert!(sinks.get(playing).is_some());
+        assert!(sinks.get(kept).is_some());
+    }
+
+    #[test]
+    fn queued_audio_plays_on_its_bus_without_a_device() {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<ProceduralAudio>()
+            .add_asset::<AudioSink>()
+            .init_resource::<AudioMixer>()
+            .init_resource::<Audio<ProceduralAudio>>()
+            .init_non_send_resource::<AudioOutput<ProceduralAudio>>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                play_queued_audio_system::<ProceduralAudio>.exclusive_system(),
+            );
+
+        let mixer = app.world.get_resource::<AudioMixer>().unwrap().clone();
+        mixer.set_bus_volume(AudioBus::Music, 0.5);
+        let sound = app
+            .world
+            .get_resource_mut::<Assets<ProceduralAudio>>()
+            .unwrap()
+            .add(ProceduralAudio::new(|| {
+                SamplesBuffer::new(1, 44_100, vec![0.5; 1000])
+            }));
+        let sink = app
+            .world
+            .get_resource::<Audio<ProceduralAudio>>()
+            .unwrap()
+            .play_with_settings(
+                sound,
+                PlaybackSettings {
+                    bus: AudioBus::Music,
+                    ..PlaybackSettings::ONCE
+                },
+            );
+        app.update();
+
+        assert!(app
+            .world
+            .get_resource::<Assets<AudioSink>>()
+            .unwrap()
+            .get(sink)
+            .is_some());
+        assert_eq!(mixer.playing_count(AudioBus::Music), 1);
+        let mut buffer = vec![0.0; 200];
+        mixer.render(&mut buffer);
+        assert!(buffer.iter().all(|sample| (sample - 0.25).abs() < 1e-6));
+    }
+}
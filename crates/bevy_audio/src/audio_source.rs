@@ -1,8 +1,17 @@
 use anyhow::Result;
-use bevy_asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy_asset::{
+    AssetLoader, AssetPath, AssetReader, AssetServer, AssetSize, LoadContext, LoadedAsset,
+};
+use bevy_ecs::world::{FromWorld, World};
 use bevy_reflect::TypeUuid;
-use bevy_utils::BoxedFuture;
-use std::{io::Cursor, sync::Arc};
+use bevy_utils::{tracing::error, BoxedFuture};
+use rodio::Source;
+use std::{
+    fmt,
+    io::{BufReader, Cursor, Read, Seek},
+    sync::Arc,
+    time::Duration,
+};
 
 /// A source of audio data
 #[derive(Debug, Clone, TypeUuid)]
@@ -17,29 +26,105 @@ impl AsRef<[u8]> for AudioSource {
     }
 }
 
+impl AssetSize for AudioSource {
+    fn asset_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.bytes.len()
+    }
+}
+
+#[cfg(any(feature = "mp3", feature = "flac", feature = "wav", feature = "vorbis"))]
+fn load_audio_source<'a>(
+    bytes: &'a [u8],
+    load_context: &'a mut LoadContext,
+) -> BoxedFuture<'a, Result<()>> {
+    Box::pin(async move {
+        let audio_source = AudioSource {
+            bytes: bytes.into(),
+        };
+        // a file that can't be decoded fails to load instead of failing to play
+        rodio::Decoder::new(Cursor::new(audio_source.clone()))?;
+        load_context.set_default_asset(LoadedAsset::new(audio_source));
+        Ok(())
+    })
+}
+
 /// Loads mp3 files as [AudioSource] [Assets](bevy_asset::Assets)
+#[cfg(feature = "mp3")]
 #[derive(Default)]
 pub struct Mp3Loader;
 
+#[cfg(feature = "mp3")]
 impl AssetLoader for Mp3Loader {
-    fn load(&self, bytes: &[u8], load_context: &mut LoadContext) -> BoxedFuture<Result<()>> {
-        load_context.set_default_asset(LoadedAsset::new(AudioSource {
-            bytes: bytes.into(),
-        }));
-        Box::pin(async move { Ok(()) })
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<()>> {
+        load_audio_source(bytes, load_context)
     }
 
     fn extensions(&self) -> &[&str] {
-        &[
-            #[cfg(feature = "mp3")]
-            "mp3",
-            #[cfg(feature = "flac")]
-            "flac",
-            #[cfg(feature = "wav")]
-            "wav",
-            #[cfg(feature = "vorbis")]
-            "ogg",
-        ]
+        &["mp3"]
+    }
+}
+
+/// Loads Ogg Vorbis files as [AudioSource] [Assets](bevy_asset::Assets)
+#[cfg(feature = "vorbis")]
+#[derive(Default)]
+pub struct OggLoader;
+
+#[cfg(feature = "vorbis")]
+impl AssetLoader for OggLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<()>> {
+        load_audio_source(bytes, load_context)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ogg", "oga"]
+    }
+}
+
+/// Loads wav files as [AudioSource] [Assets](bevy_asset::Assets)
+#[cfg(feature = "wav")]
+#[derive(Default)]
+pub struct WavLoader;
+
+#[cfg(feature = "wav")]
+impl AssetLoader for WavLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<()>> {
+        load_audio_source(bytes, load_context)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["wav"]
+    }
+}
+
+/// Loads flac files as [AudioSource] [Assets](bevy_asset::Assets)
+#[cfg(feature = "flac")]
+#[derive(Default)]
+pub struct FlacLoader;
+
+#[cfg(feature = "flac")]
+impl AssetLoader for FlacLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<()>> {
+        load_audio_source(bytes, load_context)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["flac"]
     }
 }
 
@@ -50,9 +135,355 @@ pub trait Decodable: Send + Sync + 'static {
 }
 
 impl Decodable for AudioSource {
-    type Decoder = rodio::Decoder<Cursor<AudioSource>>;
+    type Decoder = AudioDecoder<Cursor<AudioSource>>;
 
     fn decoder(&self) -> Self::Decoder {
-        rodio::Decoder::new(Cursor::new(self.clone())).unwrap()
+        match rodio::Decoder::new(Cursor::new(self.clone())) {
+            Ok(decoder) => AudioDecoder {
+                decoder: Some(decoder),
+            },
+            Err(err) => {
+                error!("Failed to decode audio: {}", err);
+                AudioDecoder { decoder: None }
+            }
+        }
+    }
+}
+
+/// A long audio track, such as music, that is decoded from its file while it plays
+///
+/// Unlike an [`AudioSource`], the encoded track is not kept in memory. It is read through the
+/// [`AssetIo`](bevy_asset::AssetIo) of the [`AssetServer`] each time it starts playing, so named
+/// asset sources work and changes to the file are picked up by the next playback. Streams are
+/// loaded from files with a `.stream` extension before the audio extension, such as
+/// `music.stream.ogg`, see [`AudioStreamLoader`], or opened with [`AudioStream::open`]. They are
+/// played with the `Audio<AudioStream>` resource.
+///
+/// ```no_run
+/// # use bevy_asset::{AssetServer, Assets};
+/// # use bevy_audio::{Audio, AudioStream};
+/// # use bevy_ecs::system::{Res, ResMut};
+/// fn play_music(
+///     asset_server: Res<AssetServer>,
+///     mut streams: ResMut<Assets<AudioStream>>,
+///     audio: Res<Audio<AudioStream>>,
+/// ) {
+///     match AudioStream::open(&asset_server, "sounds/music.ogg") {
+///         Ok(stream) => audio.play(streams.add(stream)),
+///         Err(err) => eprintln!("can not play music: {}", err),
+///     }
+/// }
+/// ```
+#[derive(Clone, TypeUuid)]
+#[uuid = "3f3a2ad4-21c0-4e6b-9d0d-bb1b0f6c1d8e"]
+pub struct AudioStream {
+    asset_server: AssetServer,
+    path: AssetPath<'static>,
+}
+
+impl AudioStream {
+    /// Opens the audio file at `path` through `asset_server`, checking that it can be decoded.
+    pub fn open<'a, P: Into<AssetPath<'a>>>(asset_server: &AssetServer, path: P) -> Result<Self> {
+        let path = path.into().to_owned();
+        rodio::Decoder::new(asset_server.open_path(path.clone())?)?;
+        Ok(Self {
+            asset_server: asset_server.clone(),
+            path,
+        })
+    }
+
+    pub fn path(&self) -> &AssetPath<'static> {
+        &self.path
+    }
+
+    fn open_decoder(&self) -> Result<rodio::Decoder<BufReader<Box<dyn AssetReader>>>> {
+        // the decoder reads on the audio thread, so the file is read ahead in larger chunks
+        let reader = self.asset_server.open_path(self.path.clone())?;
+        Ok(rodio::Decoder::new(BufReader::with_capacity(
+            STREAM_BUFFER_SIZE,
+            reader,
+        ))?)
+    }
+}
+
+impl fmt::Debug for AudioStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioStream")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl AssetSize for AudioStream {
+    fn asset_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.path.path().as_os_str().len()
+    }
+}
+
+impl Decodable for AudioStream {
+    type Decoder = StreamDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        match self.open_decoder() {
+            Ok(decoder) => AudioDecoder {
+                decoder: Some(decoder),
+            },
+            Err(err) => {
+                error!("Failed to stream audio from {:?}: {:#}", self.path, err);
+                AudioDecoder { decoder: None }
+            }
+        }
+    }
+}
+
+/// Size of the read-ahead buffer of an [`AudioStream`]
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Loads files such as `music.stream.ogg` as [`AudioStream`]s
+///
+/// The file is decoded once while loading to check that it can be played, and is read again
+/// each time the stream plays.
+pub struct AudioStreamLoader {
+    asset_server: AssetServer,
+}
+
+impl FromWorld for AudioStreamLoader {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            asset_server: world.get_resource::<AssetServer>().unwrap().clone(),
+        }
+    }
+}
+
+impl AssetLoader for AudioStreamLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<()>> {
+        Box::pin(async move {
+            rodio::Decoder::new(Cursor::new(bytes.to_vec()))?;
+            let path = AssetPath::new(load_context.path().to_owned(), None)
+                .with_source(load_context.source().map(str::to_string));
+            load_context.set_default_asset(LoadedAsset::new(AudioStream {
+                asset_server: self.asset_server.clone(),
+                path,
+            }));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[
+            "stream.mp3",
+            "stream.ogg",
+            "stream.oga",
+            "stream.wav",
+            "stream.flac",
+        ]
+    }
+}
+
+/// Decodes an [`AudioStream`] while it plays
+pub type StreamDecoder = AudioDecoder<BufReader<Box<dyn AssetReader>>>;
+
+/// Decodes audio while it plays
+///
+/// If the audio could not be opened or decoded, the decoder ends immediately.
+pub struct AudioDecoder<R>
+where
+    R: Read + Seek,
+{
+    decoder: Option<rodio::Decoder<R>>,
+}
+
+impl<R> Iterator for AudioDecoder<R>
+where
+    R: Read + Seek,
+{
+    type Item = i16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.decoder.as_mut()?.next()
+    }
+}
+
+impl<R> Source for AudioDecoder<R>
+where
+    R: Read + Seek,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        match &self.decoder {
+            Some(decoder) => decoder.current_frame_len(),
+            None => Some(0),
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.decoder
+            .as_ref()
+            .map_or(1, |decoder| decoder.channels())
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.decoder
+            .as_ref()
+            .map_or(44_100, |decoder| decoder.sample_rate())
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        match &self.decoder {
+            Some(decoder) => decoder.total_duration(),
+            None => Some(Duration::from_secs(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin, MemoryAssetIo};
+    #[cfg(feature = "wav")]
+    use bevy_asset::{Asset, Assets, Handle, LoadState};
+    use bevy_core::CorePlugin;
+
+    fn setup_app(memory_io: MemoryAssetIo) -> App {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<AudioSource>()
+            .add_asset::<AudioStream>()
+            .init_asset_loader::<AudioStreamLoader>();
+        #[cfg(feature = "wav")]
+        app.init_asset_loader::<WavLoader>();
+        let asset_server = app.world.get_resource::<AssetServer>().unwrap();
+        asset_server.add_source("mem", memory_io);
+        app
+    }
+
+    fn setup(memory_io: MemoryAssetIo) -> AssetServer {
+        let app = setup_app(memory_io);
+        app.world.get_resource::<AssetServer>().unwrap().clone()
+    }
+
+    /// Loads `path` and runs `app` until the load is done.
+    #[cfg(feature = "wav")]
+    fn load<T: Asset>(app: &mut App, path: &str) -> (Handle<T>, LoadState) {
+        let asset_server = app.world.get_resource::<AssetServer>().unwrap().clone();
+        let handle = asset_server.load(path);
+        for _ in 0..500 {
+            app.update();
+            match asset_server.get_load_state(&handle) {
+                LoadState::Loading | LoadState::NotLoaded => {
+                    std::thread::sleep(Duration::from_millis(10))
+                }
+                state => return (handle, state),
+            }
+        }
+        panic!("{} did not load", path);
+    }
+
+    #[cfg(feature = "wav")]
+    fn wav_bytes(samples: &[i16]) -> Vec<u8> {
+        let data_len = samples.len() as u32 * 2;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        // PCM, mono, 8000 Hz, 16000 bytes per second, 2 byte frames, 16 bit samples
+        for field in [1u16, 1].iter() {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [8000u32, 16000].iter() {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [2u16, 16].iter() {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn open_reports_errors() {
+        let memory_io = MemoryAssetIo::default();
+        memory_io.insert_asset("corrupt.ogg", b"not audio".to_vec());
+        let asset_server = setup(memory_io);
+
+        assert!(AudioStream::open(&asset_server, "mem://missing.ogg").is_err());
+        assert!(AudioStream::open(&asset_server, "mem://corrupt.ogg").is_err());
+        assert!(AudioStream::open(&asset_server, "missing://music.ogg").is_err());
+    }
+
+    #[cfg(feature = "wav")]
+    #[test]
+    fn streams_through_asset_io() {
+        let memory_io = MemoryAssetIo::default();
+        memory_io.insert_asset("music.wav", wav_bytes(&[1, -2, 3, -4]));
+        let asset_server = setup(memory_io);
+
+        let stream = AudioStream::open(&asset_server, "mem://music.wav").unwrap();
+        let decoder = stream.decoder();
+        assert_eq!(decoder.channels(), 1);
+        assert_eq!(decoder.sample_rate(), 8000);
+        assert_eq!(decoder.collect::<Vec<_>>(), vec![1, -2, 3, -4]);
+    }
+
+    #[cfg(feature = "wav")]
+    #[test]
+    fn corrupt_files_fail_to_load() {
+        let memory_io = MemoryAssetIo::default();
+        memory_io.insert_asset("sound.wav", wav_bytes(&[1, 2]));
+        memory_io.insert_asset("corrupt.wav", b"not audio".to_vec());
+        let mut app = setup_app(memory_io);
+
+        let (_, state) = load::<AudioSource>(&mut app, "mem://sound.wav");
+        assert_eq!(state, LoadState::Loaded);
+        let (_, state) = load::<AudioSource>(&mut app, "mem://corrupt.wav");
+        assert_eq!(state, LoadState::Failed);
+    }
+
+    #[test]
+    fn decoder_of_corrupt_source_ends_immediately() {
+        let audio_source = AudioSource {
+            bytes: b"not audio".to_vec().into(),
+        };
+        assert_eq!(audio_source.decoder().next(), None);
+    }
+
+    #[cfg(feature = "wav")]
+    #[test]
+    fn loads_streams() {
+        let memory_io = MemoryAssetIo::default();
+        memory_io.insert_asset("music.stream.wav", wav_bytes(&[1, -2, 3, -4]));
+        let mut app = setup_app(memory_io);
+
+        let (handle, state) = load::<AudioStream>(&mut app, "mem://music.stream.wav");
+        assert_eq!(state, LoadState::Loaded);
+        let streams = app.world.get_resource::<Assets<AudioStream>>().unwrap();
+        let stream = streams.get(&handle).unwrap();
+        assert_eq!(stream.path().source(), Some("mem"));
+        assert_eq!(stream.decoder().collect::<Vec<_>>(), vec![1, -2, 3, -4]);
+    }
+
+    #[test]
+    fn decoder_of_unreadable_stream_ends_immediately() {
+        let memory_io = MemoryAssetIo::default();
+        let asset_server = setup(memory_io);
+        // the stream was opened earlier, but its file is gone by the time it plays
+        let stream = AudioStream {
+            asset_server,
+            path: AssetPath::from("mem://music.ogg").to_owned(),
+        };
+
+        let mut decoder = stream.decoder();
+        assert_eq!(decoder.next(), None);
+        assert_eq!(decoder.total_duration(), Some(Duration::from_secs(0)));
     }
 }
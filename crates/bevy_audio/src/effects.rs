@@ -0,0 +1,678 @@
+use parking_lot::Mutex;
+use rodio::{Sample, Source};
+use std::{
+    f32::consts::{FRAC_1_SQRT_2, PI},
+    fmt,
+    mem::{discriminant, Discriminant},
+    sync::Arc,
+    time::Duration,
+};
+
+/// Number of samples an [`Effected`] source processes at once
+const BLOCK_LEN: usize = 512;
+
+/// A DSP effect of an [`EffectChain`]
+///
+/// Frequencies are in Hz, times in seconds, and `mix` is the share of the processed signal in
+/// the output, from `0.0` (dry) to `1.0` (wet).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect {
+    /// Removes frequencies above the cutoff, e.g. to muffle sounds underwater.
+    LowPass { cutoff: f32 },
+    /// Removes frequencies below the cutoff, e.g. for radio voices.
+    HighPass { cutoff: f32 },
+    /// Repeats the sound after `time`, with each echo scaled by `feedback`.
+    Delay { time: f32, feedback: f32, mix: f32 },
+    /// Simulates the reflections of a room, `room_size` and `damping` range from `0.0` to `1.0`.
+    Reverb {
+        room_size: f32,
+        damping: f32,
+        mix: f32,
+    },
+    /// Lowers the volume of the parts of the sound that are louder than `threshold`, which is an
+    /// amplitude from `0.0` to `1.0`, dividing the excess by `ratio`.
+    Compressor {
+        threshold: f32,
+        ratio: f32,
+        attack: f32,
+        release: f32,
+    },
+}
+
+impl Effect {
+    pub fn low_pass(cutoff: f32) -> Self {
+        Effect::LowPass { cutoff }
+    }
+
+    pub fn high_pass(cutoff: f32) -> Self {
+        Effect::HighPass { cutoff }
+    }
+
+    pub fn delay(time: f32, feedback: f32, mix: f32) -> Self {
+        Effect::Delay {
+            time,
+            feedback,
+            mix,
+        }
+    }
+
+    pub fn reverb(room_size: f32, damping: f32, mix: f32) -> Self {
+        Effect::Reverb {
+            room_size,
+            damping,
+            mix,
+        }
+    }
+
+    pub fn compressor(threshold: f32, ratio: f32) -> Self {
+        Effect::Compressor {
+            threshold,
+            ratio,
+            attack: 0.005,
+            release: 0.1,
+        }
+    }
+}
+
+/// A list of [`Effect`]s applied one after the other
+///
+/// The effects are shared with the sounds or [`AudioBus`](crate::AudioBus)es the chain is applied
+/// to, so they can be changed every frame from systems, e.g. to lower the cutoff of a low-pass
+/// filter while the player dives. The state of the effects, such as the echoes of a delay, is
+/// kept per sound or bus, so sounds with the same chain don't affect each other. Apply a chain to
+/// a sound with [`PlaybackSettings::with_effects`](crate::PlaybackSettings::with_effects) and to
+/// a bus with [`AudioMixer::bus_effects`](crate::AudioMixer::bus_effects).
+///
+/// The adaptors of [`rodio::Source`] such as `low_pass` and `reverb` are not used, as their
+/// parameters are fixed once the sound starts playing.
+///
+/// ```
+/// # use bevy_audio::{Effect, EffectChain};
+/// let underwater = EffectChain::new(vec![
+///     Effect::low_pass(800.0),
+///     Effect::reverb(0.8, 0.5, 0.3),
+/// ]);
+/// // surfacing opens up the filter again
+/// underwater.set(0, Effect::low_pass(20_000.0));
+/// ```
+#[derive(Clone, Default)]
+pub struct EffectChain {
+    effects: Arc<Mutex<Vec<Effect>>>,
+}
+
+impl fmt::Debug for EffectChain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EffectChain")
+            .field("effects", &self.effects())
+            .finish()
+    }
+}
+
+impl EffectChain {
+    pub fn new(effects: Vec<Effect>) -> Self {
+        let chain = Self::default();
+        chain.set_effects(effects);
+        chain
+    }
+
+    pub fn effects(&self) -> Vec<Effect> {
+        self.effects.lock().clone()
+    }
+
+    /// Replaces all effects. Effects of the same kind as the effect previously at their index
+    /// keep their state.
+    pub fn set_effects(&self, effects: Vec<Effect>) {
+        *self.effects.lock() = effects;
+    }
+
+    pub fn push(&self, effect: Effect) {
+        self.effects.lock().push(effect);
+    }
+
+    /// Changes the effect at `index`.
+    ///
+    /// Changing the parameters of an effect keeps its state, so sounds continue smoothly, e.g.
+    /// the echoes of a delay keep playing. Changing the kind of effect resets its state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&self, index: usize, effect: Effect) {
+        self.effects.lock()[index] = effect;
+    }
+
+    pub fn remove(&self, index: usize) -> Effect {
+        self.effects.lock().remove(index)
+    }
+
+    pub fn clear(&self) {
+        self.effects.lock().clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.lock().is_empty()
+    }
+
+    /// Applies the chain to `source`, e.g. to render a sound with effects offline.
+    pub fn apply<S>(&self, source: S) -> Effected<S>
+    where
+        S: Source,
+        S::Item: Sample,
+    {
+        Effected {
+            source,
+            effects: EffectProcessor::new(self.clone()),
+            buffer: Vec::with_capacity(BLOCK_LEN),
+            position: 0,
+            channels: 1,
+            sample_rate: 1,
+        }
+    }
+}
+
+/// Applies the effects of an [`EffectChain`] to a single sound or bus, with its own state
+#[derive(Default)]
+pub(crate) struct EffectProcessor {
+    chain: EffectChain,
+    // the state of the effect at each index, reset when the kind of the effect changes
+    processors: Vec<(Discriminant<Effect>, Processor)>,
+}
+
+impl EffectProcessor {
+    pub(crate) fn new(chain: EffectChain) -> Self {
+        Self {
+            chain,
+            processors: Vec::new(),
+        }
+    }
+
+    pub(crate) fn chain(&self) -> &EffectChain {
+        &self.chain
+    }
+
+    /// Processes interleaved `samples` in place.
+    pub(crate) fn process(&mut self, samples: &mut [f32], channels: u16, sample_rate: u32) {
+        let channels = channels.max(1) as usize;
+        let sample_rate = sample_rate.max(1) as f32;
+        let effects = self.chain.effects.lock();
+        self.processors.truncate(effects.len());
+        for (index, effect) in effects.iter().enumerate() {
+            let kind = discriminant(effect);
+            match self.processors.get_mut(index) {
+                Some((processor_kind, processor)) if *processor_kind != kind => {
+                    *processor_kind = kind;
+                    *processor = Processor::default();
+                }
+                Some(_) => {}
+                None => self.processors.push((kind, Processor::default())),
+            }
+            self.processors[index]
+                .1
+                .process(effect, samples, channels, sample_rate);
+        }
+    }
+}
+
+/// The state of an effect, such as the samples of a delay line
+#[derive(Default)]
+struct Processor {
+    biquads: Vec<Biquad>,
+    lines: Vec<DelayLine>,
+    combs: Vec<Comb>,
+    allpasses: Vec<DelayLine>,
+    envelope: f32,
+}
+
+impl Processor {
+    fn process(&mut self, effect: &Effect, samples: &mut [f32], channels: usize, sample_rate: f32) {
+        match *effect {
+            Effect::LowPass { cutoff } => {
+                let coefficients = BiquadCoefficients::low_pass(cutoff, sample_rate);
+                self.process_biquads(coefficients, samples, channels);
+            }
+            Effect::HighPass { cutoff } => {
+                let coefficients = BiquadCoefficients::high_pass(cutoff, sample_rate);
+                self.process_biquads(coefficients, samples, channels);
+            }
+            Effect::Delay {
+                time,
+                feedback,
+                mix,
+            } => {
+                let len = ((time * sample_rate).round() as usize).max(1);
+                self.lines.resize_with(channels, DelayLine::default);
+                for line in self.lines.iter_mut() {
+                    line.resize(len);
+                }
+                for frame in samples.chunks_mut(channels) {
+                    for (sample, line) in frame.iter_mut().zip(self.lines.iter_mut()) {
+                        let delayed = line.read();
+                        line.write(*sample + delayed * feedback);
+                        *sample = *sample * (1.0 - mix) + delayed * mix;
+                    }
+                }
+            }
+            Effect::Reverb {
+                room_size,
+                damping,
+                mix,
+            } => self.process_reverb(room_size, damping, mix, samples, channels, sample_rate),
+            Effect::Compressor {
+                threshold,
+                ratio,
+                attack,
+                release,
+            } => {
+                let attack = smoothing(attack, sample_rate);
+                let release = smoothing(release, sample_rate);
+                let ratio = ratio.max(1.0);
+                for frame in samples.chunks_mut(channels) {
+                    let peak = frame.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+                    let coefficient = if peak > self.envelope {
+                        attack
+                    } else {
+                        release
+                    };
+                    self.envelope = peak + (self.envelope - peak) * coefficient;
+                    if self.envelope > threshold && self.envelope > 0.0 {
+                        let gain =
+                            (threshold + (self.envelope - threshold) / ratio) / self.envelope;
+                        for sample in frame.iter_mut() {
+                            *sample *= gain;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn process_biquads(
+        &mut self,
+        coefficients: BiquadCoefficients,
+        samples: &mut [f32],
+        channels: usize,
+    ) {
+        self.biquads.resize_with(channels, Biquad::default);
+        for frame in samples.chunks_mut(channels) {
+            for (sample, biquad) in frame.iter_mut().zip(self.biquads.iter_mut()) {
+                *sample = biquad.process(&coefficients, *sample);
+            }
+        }
+    }
+
+    fn process_reverb(
+        &mut self,
+        room_size: f32,
+        damping: f32,
+        mix: f32,
+        samples: &mut [f32],
+        channels: usize,
+        sample_rate: f32,
+    ) {
+        // the comb and allpass filters of Freeverb, with the lengths tuned for 44.1kHz
+        const COMB_LENGTHS: [usize; 4] = [1116, 1188, 1277, 1356];
+        const ALLPASS_LENGTHS: [usize; 2] = [556, 441];
+        const STEREO_SPREAD: usize = 23;
+
+        let scale = sample_rate / 44_100.0;
+        let length = |length: usize, channel: usize| {
+            (((length + channel % 2 * STEREO_SPREAD) as f32 * scale) as usize).max(1)
+        };
+        if self.combs.len() != channels * COMB_LENGTHS.len() {
+            self.combs = (0..channels)
+                .flat_map(|channel| {
+                    COMB_LENGTHS.iter().map(move |len| Comb {
+                        line: DelayLine::new(length(*len, channel)),
+                        filter: 0.0,
+                    })
+                })
+                .collect();
+            self.allpasses = (0..channels)
+                .flat_map(|channel| {
+                    ALLPASS_LENGTHS
+                        .iter()
+                        .map(move |len| DelayLine::new(length(*len, channel)))
+                })
+                .collect();
+        }
+
+        let feedback = 0.7 + 0.28 * room_size.max(0.0).min(1.0);
+        let damping = damping.max(0.0).min(1.0);
+        for frame in samples.chunks_mut(channels) {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                let input = *sample;
+                let combs = &mut self.combs[channel * COMB_LENGTHS.len()..][..COMB_LENGTHS.len()];
+                let mut wet = 0.0;
+                for comb in combs {
+                    wet += comb.process(input, feedback, damping);
+                }
+                wet /= COMB_LENGTHS.len() as f32;
+                let allpasses =
+                    &mut self.allpasses[channel * ALLPASS_LENGTHS.len()..][..ALLPASS_LENGTHS.len()];
+                for allpass in allpasses {
+                    let delayed = allpass.read();
+                    allpass.write(wet + delayed * 0.5);
+                    wet = delayed - wet;
+                }
+                *sample = input * (1.0 - mix) + wet * mix;
+            }
+        }
+    }
+}
+
+/// Returns the per sample smoothing coefficient of an envelope that settles in `time`.
+fn smoothing(time: f32, sample_rate: f32) -> f32 {
+    if time > 0.0 {
+        (-1.0 / (time * sample_rate)).exp()
+    } else {
+        0.0
+    }
+}
+
+#[derive(Clone, Copy)]
+struct BiquadCoefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl BiquadCoefficients {
+    // filters from the Audio EQ Cookbook, with a Butterworth response
+    fn low_pass(cutoff: f32, sample_rate: f32) -> Self {
+        let (cos, alpha) = Self::angle(cutoff, sample_rate);
+        Self::normalized(
+            (1.0 - cos) / 2.0,
+            1.0 - cos,
+            (1.0 - cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    fn high_pass(cutoff: f32, sample_rate: f32) -> Self {
+        let (cos, alpha) = Self::angle(cutoff, sample_rate);
+        Self::normalized(
+            (1.0 + cos) / 2.0,
+            -(1.0 + cos),
+            (1.0 + cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    fn angle(cutoff: f32, sample_rate: f32) -> (f32, f32) {
+        let cutoff = cutoff.max(10.0).min(sample_rate * 0.49);
+        let omega = 2.0 * PI * cutoff / sample_rate;
+        (omega.cos(), omega.sin() / (2.0 * FRAC_1_SQRT_2))
+    }
+
+    fn normalized(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Biquad {
+    x1: f32,
+    x2: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Biquad {
+    fn process(&mut self, c: &BiquadCoefficients, x: f32) -> f32 {
+        let y = c.b0 * x + c.b1 * self.x1 + c.b2 * self.x2 - c.a1 * self.y1 - c.a2 * self.y2;
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+#[derive(Default)]
+struct DelayLine {
+    samples: Vec<f32>,
+    position: usize,
+}
+
+impl DelayLine {
+    fn new(len: usize) -> Self {
+        Self {
+            samples: vec![0.0; len],
+            position: 0,
+        }
+    }
+
+    fn resize(&mut self, len: usize) {
+        if self.samples.len() != len {
+            *self = Self::new(len);
+        }
+    }
+
+    /// Returns the sample written `len` samples ago.
+    fn read(&self) -> f32 {
+        self.samples[self.position]
+    }
+
+    fn write(&mut self, sample: f32) {
+        self.samples[self.position] = sample;
+        self.position = (self.position + 1) % self.samples.len();
+    }
+}
+
+struct Comb {
+    line: DelayLine,
+    filter: f32,
+}
+
+impl Comb {
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.line.read();
+        self.filter = output * (1.0 - damping) + self.filter * damping;
+        self.line.write(input + self.filter * feedback);
+        output
+    }
+}
+
+/// A [`Source`] with the effects of an [`EffectChain`], see [`EffectChain::apply`]
+pub struct Effected<S> {
+    source: S,
+    effects: EffectProcessor,
+    buffer: Vec<f32>,
+    position: usize,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl<S> Iterator for Effected<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position == self.buffer.len() {
+            // blocks end at frame boundaries, where the channels and sample rate may change
+            self.channels = self.source.channels();
+            self.sample_rate = self.source.sample_rate();
+            let channels = self.channels.max(1) as usize;
+            let len = self
+                .source
+                .current_frame_len()
+                .map_or(BLOCK_LEN, |len| len.min(BLOCK_LEN));
+            let len = (len / channels * channels).max(channels);
+            self.buffer.clear();
+            self.buffer
+                .extend(self.source.by_ref().take(len).map(|sample| sample.to_f32()));
+            if self.buffer.is_empty() {
+                return None;
+            }
+            self.effects
+                .process(&mut self.buffer, self.channels, self.sample_rate);
+            self.position = 0;
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl<S> Source for Effected<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        let buffered = self.buffer.len() - self.position;
+        if buffered > 0 {
+            Some(buffered)
+        } else {
+            self.source
+                .current_frame_len()
+                .map(|len| len.min(BLOCK_LEN))
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        if self.position < self.buffer.len() {
+            self.channels
+        } else {
+            self.source.channels()
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        if self.position < self.buffer.len() {
+            self.sample_rate
+        } else {
+            self.source.sample_rate()
+        }
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        // delays and reverbs are cut off at the end of the source
+        self.source.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    const SAMPLE_RATE: u32 = 44_100;
+
+    fn sine(frequency: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    fn render(chain: &EffectChain, samples: Vec<f32>) -> Vec<f32> {
+        chain
+            .apply(SamplesBuffer::new(1, SAMPLE_RATE, samples))
+            .collect()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0, |peak, s| peak.max(s.abs()))
+    }
+
+    #[test]
+    fn low_pass_removes_high_frequencies() {
+        let chain = EffectChain::new(vec![Effect::low_pass(500.0)]);
+        let high = render(&chain, sine(10_000.0, 4096));
+        assert!(peak(&high[2048..]) < 0.01);
+
+        let chain = EffectChain::new(vec![Effect::low_pass(500.0)]);
+        let low = render(&chain, sine(50.0, 4096));
+        assert!(peak(&low[2048..]) > 0.95);
+    }
+
+    #[test]
+    fn high_pass_removes_constant_offset() {
+        let chain = EffectChain::new(vec![Effect::high_pass(200.0)]);
+        let output = render(&chain, vec![1.0; 4096]);
+        assert!(output[0] > 0.9);
+        assert!(peak(&output[2048..]) < 0.001);
+    }
+
+    #[test]
+    fn delay_repeats_impulse() {
+        let chain = EffectChain::new(vec![Effect::delay(10.0 / SAMPLE_RATE as f32, 0.5, 0.5)]);
+        let mut impulse = vec![0.0; 32];
+        impulse[0] = 1.0;
+        let output = render(&chain, impulse);
+        assert_eq!(output[0], 0.5);
+        assert_eq!(output[10], 0.5);
+        assert_eq!(output[20], 0.25);
+        assert_eq!(output[5], 0.0);
+    }
+
+    #[test]
+    fn compressor_lowers_loud_sounds() {
+        let chain = EffectChain::new(vec![Effect::compressor(0.5, 4.0)]);
+        let output = render(&chain, vec![1.0; 4096]);
+        assert!((output[4095] - 0.625).abs() < 0.01);
+
+        let chain = EffectChain::new(vec![Effect::compressor(0.5, 4.0)]);
+        let quiet = render(&chain, vec![0.25; 4096]);
+        assert!((quiet[4095] - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn reverb_keeps_ringing() {
+        let chain = EffectChain::new(vec![Effect::reverb(0.8, 0.2, 1.0)]);
+        let mut impulse = vec![0.0; 8192];
+        impulse[0] = 1.0;
+        let output = render(&chain, impulse);
+        assert_eq!(output[0], 0.0);
+        assert!(peak(&output[4096..]) > 0.001);
+    }
+
+    #[test]
+    fn sounds_with_the_same_chain_are_processed_independently() {
+        let chain = EffectChain::new(vec![Effect::delay(10.0 / SAMPLE_RATE as f32, 0.5, 0.5)]);
+        let mut impulse = vec![0.0; 32];
+        impulse[0] = 1.0;
+        let expected = render(&chain, impulse.clone());
+
+        let mut first = chain.apply(SamplesBuffer::new(1, SAMPLE_RATE, impulse.clone()));
+        let mut second = chain
+            .clone()
+            .apply(SamplesBuffer::new(1, SAMPLE_RATE, impulse));
+        let (mut first_output, mut second_output) = (Vec::new(), Vec::new());
+        for _ in 0..expected.len() {
+            first_output.push(first.next().unwrap());
+            second_output.push(second.next().unwrap());
+        }
+        assert_eq!(first_output, expected);
+        assert_eq!(second_output, expected);
+    }
+
+    #[test]
+    fn effects_change_between_blocks() {
+        let chain = EffectChain::new(vec![Effect::delay(0.01, 0.0, 0.0)]);
+        let mut samples = vec![1.0; BLOCK_LEN];
+        samples.extend(vec![0.0; BLOCK_LEN]);
+        let mut source = chain.apply(SamplesBuffer::new(1, SAMPLE_RATE, samples));
+        assert_eq!(source.by_ref().take(BLOCK_LEN).last(), Some(1.0));
+
+        // only the delayed signal of the first block is heard now
+        chain.set(0, Effect::delay(0.01, 0.0, 1.0));
+        assert_eq!(source.next(), Some(1.0));
+        assert_eq!(chain.effects(), vec![Effect::delay(0.01, 0.0, 1.0)]);
+    }
+}
@@ -1,19 +1,38 @@
 mod audio;
 mod audio_output;
 mod audio_source;
+mod effects;
+mod mixer;
+mod procedural;
+mod spatial;
 
 pub mod prelude {
     #[doc(hidden)]
-    pub use crate::{Audio, AudioOutput, AudioSource, Decodable};
+    pub use crate::{
+        Audio, AudioBus, AudioEmitter, AudioListener, AudioMixer, AudioOutput, AudioSink,
+        AudioSource, Decodable, Effect, EffectChain, PlaybackSettings, ProceduralAudio, ReverbZone,
+        Rolloff, Waveform,
+    };
 }
 
 pub use audio::*;
 pub use audio_output::*;
 pub use audio_source::*;
+pub use effects::{Effect, EffectChain, Effected};
+pub use mixer::{AudioBus, AudioMixer, BusSettings, Ducking, MixerOutput};
+pub use procedural::*;
+pub use spatial::{
+    update_reverb_zones_system, update_spatial_audio_system, AudioEmitter, AudioListener,
+    PreviousSpatialPositions, ReverbZone, Rolloff,
+};
 
 use bevy_app::prelude::*;
 use bevy_asset::AddAsset;
-use bevy_ecs::system::IntoExclusiveSystem;
+use bevy_ecs::{
+    schedule::ParallelSystemDescriptorCoercion,
+    system::{IntoExclusiveSystem, IntoSystem},
+};
+use bevy_transform::TransformSystem;
 
 /// Adds support for audio playback to an App
 #[derive(Default)]
@@ -21,15 +40,55 @@ pub struct AudioPlugin;
 
 impl Plugin for AudioPlugin {
     fn build(&self, app: &mut App) {
-        app.init_non_send_resource::<AudioOutput<AudioSource>>()
+        app.init_resource::<AudioMixer>()
+            .init_non_send_resource::<AudioDevice>()
+            .init_non_send_resource::<AudioOutput<AudioSource>>()
             .add_asset::<AudioSource>()
+            .add_asset::<AudioSink>()
             .init_resource::<Audio<AudioSource>>()
             .add_system_to_stage(
                 CoreStage::PostUpdate,
                 play_queued_audio_system::<AudioSource>.exclusive_system(),
+            )
+            .init_non_send_resource::<AudioOutput<ProceduralAudio>>()
+            .add_asset::<ProceduralAudio>()
+            .init_resource::<Audio<ProceduralAudio>>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                play_queued_audio_system::<ProceduralAudio>.exclusive_system(),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                remove_finished_audio_sinks_system.system(),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_spatial_audio_system
+                    .system()
+                    .after(TransformSystem::TransformPropagate),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                update_reverb_zones_system
+                    .system()
+                    .after(TransformSystem::TransformPropagate),
+            )
+            .init_non_send_resource::<AudioOutput<AudioStream>>()
+            .add_asset::<AudioStream>()
+            .init_resource::<Audio<AudioStream>>()
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                play_queued_audio_system::<AudioStream>.exclusive_system(),
             );
 
-        #[cfg(any(feature = "mp3", feature = "flac", feature = "wav", feature = "vorbis"))]
+        app.init_asset_loader::<AudioStreamLoader>();
+        #[cfg(feature = "mp3")]
         app.init_asset_loader::<Mp3Loader>();
+        #[cfg(feature = "vorbis")]
+        app.init_asset_loader::<OggLoader>();
+        #[cfg(feature = "wav")]
+        app.init_asset_loader::<WavLoader>();
+        #[cfg(feature = "flac")]
+        app.init_asset_loader::<FlacLoader>();
     }
 }
@@ -0,0 +1,718 @@
+use crate::{effects::EffectProcessor, Effect, EffectChain};
+use bevy_utils::tracing::warn;
+use parking_lot::Mutex;
+use rodio::{source::UniformSourceIterator, Sample, Source};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// Number of frames that are mixed with the same bus gains
+const BLOCK_FRAMES: usize = 256;
+
+const BUS_COUNT: usize = 5;
+
+/// A bus of the [`AudioMixer`], which sounds are played on
+///
+/// Every bus is mixed into a parent bus, up to [`AudioBus::Master`], so the volume, effects and
+/// duckings of a bus also apply to its sub-buses. The built-in buses are mixed into the master
+/// bus, custom buses are created with [`AudioMixer::add_bus`] under any other bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AudioBus {
+    Master,
+    Music,
+    Sfx,
+    Voice,
+    Ui,
+    /// A bus created with [`AudioMixer::add_bus`], with an id that is unique to its mixer
+    Custom(u32),
+}
+
+impl AudioBus {
+    /// The built-in buses, which every mixer has
+    pub const ALL: [AudioBus; BUS_COUNT] = [
+        AudioBus::Master,
+        AudioBus::Music,
+        AudioBus::Sfx,
+        AudioBus::Voice,
+        AudioBus::Ui,
+    ];
+}
+
+impl Default for AudioBus {
+    fn default() -> Self {
+        AudioBus::Master
+    }
+}
+
+/// The volume settings of an [`AudioBus`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BusSettings {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl BusSettings {
+    fn gain(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume
+        }
+    }
+}
+
+/// Lowers the volume of the `target` bus while sounds are playing on the `trigger` bus, e.g. to
+/// duck music while voice lines play
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ducking {
+    pub trigger: AudioBus,
+    pub target: AudioBus,
+    /// The gain applied to the target bus while it is ducked
+    pub gain: f32,
+    /// How long it takes to duck the target bus, in seconds
+    pub attack: f32,
+    /// How long it takes to restore the volume of the target bus, in seconds
+    pub release: f32,
+}
+
+impl Ducking {
+    pub fn new(trigger: AudioBus, target: AudioBus, gain: f32) -> Self {
+        Self {
+            trigger,
+            target,
+            gain,
+            attack: 0.1,
+            release: 0.5,
+        }
+    }
+}
+
+struct Voice {
+    // the index of the bus in `MixerState::buses`
+    bus: usize,
+    source: Box<dyn Iterator<Item = f32> + Send>,
+}
+
+struct Bus {
+    bus: AudioBus,
+    // the index of the bus it is mixed into, `None` for the master bus
+    parent: Option<usize>,
+    settings: BusSettings,
+    effects: EffectProcessor,
+    // the reverb of the reverb zone the listener is in, applied before the effects of the bus
+    zone_effects: EffectProcessor,
+    // scratch buffer the voices and sub-buses of the bus are mixed into
+    buffer: Vec<f32>,
+    // the gain of the current block, with duckings applied
+    gain: f32,
+}
+
+impl Bus {
+    fn new(bus: AudioBus, parent: Option<usize>) -> Self {
+        Self {
+            bus,
+            parent,
+            settings: Default::default(),
+            effects: Default::default(),
+            zone_effects: Default::default(),
+            buffer: Vec::new(),
+            gain: 1.0,
+        }
+    }
+}
+
+struct MixerState {
+    // buses are never removed and always come after their parent
+    buses: Vec<Bus>,
+    next_custom_bus: u32,
+    // every ducking together with its current gain
+    duckings: Vec<(Ducking, f32)>,
+    voices: Vec<Voice>,
+}
+
+impl MixerState {
+    fn new() -> Self {
+        let buses = AudioBus::ALL
+            .iter()
+            .map(|bus| match bus {
+                AudioBus::Master => Bus::new(*bus, None),
+                _ => Bus::new(*bus, Some(0)),
+            })
+            .collect();
+        Self {
+            buses,
+            next_custom_bus: 0,
+            duckings: Vec::new(),
+            voices: Vec::new(),
+        }
+    }
+
+    fn bus_index(&self, bus: AudioBus) -> Option<usize> {
+        self.buses.iter().position(|state| state.bus == bus)
+    }
+
+    /// Whether the bus at `index` is the bus at `ancestor` or one of its sub-buses.
+    fn is_within(&self, mut index: usize, ancestor: usize) -> bool {
+        loop {
+            if index == ancestor {
+                return true;
+            }
+            match self.buses[index].parent {
+                Some(parent) => index = parent,
+                None => return false,
+            }
+        }
+    }
+
+    fn render_block(&mut self, block: &mut [f32], channels: u16, sample_rate: u32) {
+        let duration = (block.len() / channels as usize) as f32 / sample_rate as f32;
+        for bus in self.buses.iter_mut() {
+            bus.gain = bus.settings.gain();
+            bus.buffer.clear();
+            bus.buffer.resize(block.len(), 0.0);
+        }
+
+        for i in 0..self.duckings.len() {
+            let (ducking, _) = self.duckings[i];
+            let (trigger, target) = match (
+                self.bus_index(ducking.trigger),
+                self.bus_index(ducking.target),
+            ) {
+                (Some(trigger), Some(target)) => (trigger, target),
+                _ => continue,
+            };
+            let triggered = self
+                .voices
+                .iter()
+                .any(|voice| self.is_within(voice.bus, trigger));
+            let (target_gain, time) = if triggered {
+                (ducking.gain, ducking.attack)
+            } else {
+                (1.0, ducking.release)
+            };
+            let smoothing = if time > 0.0 {
+                1.0 - (-duration / time).exp()
+            } else {
+                1.0
+            };
+            let gain = &mut self.duckings[i].1;
+            *gain += (target_gain - *gain) * smoothing;
+            self.buses[target].gain *= *gain;
+        }
+
+        let mut i = 0;
+        while i < self.voices.len() {
+            let voice = &mut self.voices[i];
+            let mut finished = false;
+            for sample in self.buses[voice.bus].buffer.iter_mut() {
+                match voice.source.next() {
+                    Some(value) => *sample += value,
+                    None => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+
+            if finished {
+                self.voices.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        for sample in block.iter_mut() {
+            *sample = 0.0;
+        }
+        // sub-buses come after their parent, so they are mixed into it before it is processed
+        for i in (0..self.buses.len()).rev() {
+            let bus = &mut self.buses[i];
+            let mut buffer = std::mem::take(&mut bus.buffer);
+            bus.zone_effects.process(&mut buffer, channels, sample_rate);
+            bus.effects.process(&mut buffer, channels, sample_rate);
+            let (gain, parent) = (bus.gain, bus.parent);
+            let output = match parent {
+                Some(parent) => &mut self.buses[parent].buffer[..],
+                None => &mut *block,
+            };
+            for (sample, bus_sample) in output.iter_mut().zip(buffer.iter()) {
+                *sample += *bus_sample * gain;
+            }
+            self.buses[i].buffer = buffer;
+        }
+    }
+}
+
+/// Mixes the sounds played on its [`AudioBus`]es into a single output
+///
+/// The [`AudioDevice`](crate::AudioDevice) plays the [`AudioMixer::output`] of the mixer
+/// resource. Without a device, the mix can be rendered into a buffer with
+/// [`AudioMixer::render`], e.g. to check mix levels in tests.
+///
+/// Each bus has an [`EffectChain`], see [`AudioMixer::bus_effects`]. The effects and volume of a
+/// bus are applied to the mix of its sounds and sub-buses, before it is mixed into its parent.
+///
+/// ```
+/// # use bevy_audio::{AudioBus, AudioMixer, Ducking};
+/// let mixer = AudioMixer::default();
+/// mixer.set_bus_volume(AudioBus::Music, 0.8);
+/// // voice lines lower the music volume to 30%
+/// mixer.add_ducking(Ducking::new(AudioBus::Voice, AudioBus::Music, 0.3));
+/// // footsteps are sound effects with a volume of their own
+/// let footsteps = mixer.add_bus(AudioBus::Sfx);
+/// mixer.set_bus_volume(footsteps, 0.5);
+/// ```
+#[derive(Clone)]
+pub struct AudioMixer {
+    state: Arc<Mutex<MixerState>>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl Default for AudioMixer {
+    fn default() -> Self {
+        Self::new(2, 44_100)
+    }
+}
+
+impl AudioMixer {
+    /// Creates a mixer that outputs `channels` interleaved channels at `sample_rate`.
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MixerState::new())),
+            channels,
+            sample_rate,
+        }
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Creates a custom bus that is mixed into `parent`. Custom buses exist as long as the mixer.
+    ///
+    /// A `parent` that isn't a bus of this mixer is replaced by [`AudioBus::Master`].
+    pub fn add_bus(&self, parent: AudioBus) -> AudioBus {
+        let mut state = self.state.lock();
+        let parent = state.bus_index(parent).unwrap_or_else(|| {
+            warn!(
+                "{:?} is not a bus of this mixer, using the master bus",
+                parent
+            );
+            0
+        });
+        let bus = AudioBus::Custom(state.next_custom_bus);
+        state.next_custom_bus += 1;
+        state.buses.push(Bus::new(bus, Some(parent)));
+        bus
+    }
+
+    /// Returns the bus `bus` is mixed into, or `None` for the master bus and buses that aren't
+    /// part of this mixer.
+    pub fn parent_bus(&self, bus: AudioBus) -> Option<AudioBus> {
+        let state = self.state.lock();
+        let parent = state.buses[state.bus_index(bus)?].parent?;
+        Some(state.buses[parent].bus)
+    }
+
+    pub fn bus(&self, bus: AudioBus) -> BusSettings {
+        let state = self.state.lock();
+        state
+            .bus_index(bus)
+            .map_or_else(Default::default, |index| state.buses[index].settings)
+    }
+
+    pub fn set_bus(&self, bus: AudioBus, settings: BusSettings) {
+        self.update_bus(bus, |bus| bus.settings = settings);
+    }
+
+    pub fn set_bus_volume(&self, bus: AudioBus, volume: f32) {
+        self.update_bus(bus, |bus| bus.settings.volume = volume);
+    }
+
+    pub fn set_bus_muted(&self, bus: AudioBus, muted: bool) {
+        self.update_bus(bus, |bus| bus.settings.muted = muted);
+    }
+
+    fn update_bus(&self, bus: AudioBus, update: impl FnOnce(&mut Bus)) {
+        let mut state = self.state.lock();
+        match state.bus_index(bus) {
+            Some(index) => update(&mut state.buses[index]),
+            None => warn!("{:?} is not a bus of this mixer", bus),
+        }
+    }
+
+    pub fn add_ducking(&self, ducking: Ducking) {
+        self.state.lock().duckings.push((ducking, 1.0));
+    }
+
+    /// Removes all duckings that lower the volume of the `target` bus.
+    pub fn remove_duckings(&self, target: AudioBus) {
+        self.state
+            .lock()
+            .duckings
+            .retain(|(ducking, _)| ducking.target != target);
+    }
+
+    /// Returns the [`EffectChain`] applied to the mix of `bus`, before its volume is applied.
+    ///
+    /// Buses that aren't part of this mixer get a chain that isn't applied to anything.
+    pub fn bus_effects(&self, bus: AudioBus) -> EffectChain {
+        let state = self.state.lock();
+        state.bus_index(bus).map_or_else(Default::default, |index| {
+            state.buses[index].effects.chain().clone()
+        })
+    }
+
+    /// Applies the reverb of a [`ReverbZone`](crate::ReverbZone) to its bus, or removes the
+    /// zone reverb if `None`.
+    pub(crate) fn set_zone_reverb(&self, zone_reverb: Option<(AudioBus, Effect)>) {
+        let state = self.state.lock();
+        for bus in state.buses.iter() {
+            let chain = bus.zone_effects.chain();
+            match &zone_reverb {
+                Some((zone_bus, reverb)) if *zone_bus == bus.bus => {
+                    chain.set_effects(vec![reverb.clone()])
+                }
+                _ => chain.clear(),
+            }
+        }
+    }
+
+    /// Returns the number of sounds currently playing on `bus` and its sub-buses.
+    pub fn playing_count(&self, bus: AudioBus) -> usize {
+        let state = self.state.lock();
+        let bus = match state.bus_index(bus) {
+            Some(bus) => bus,
+            None => return 0,
+        };
+        state
+            .voices
+            .iter()
+            .filter(|voice| state.is_within(voice.bus, bus))
+            .count()
+    }
+
+    /// Plays `source` on `bus` until the source ends. Sounds played on a bus that isn't part of
+    /// this mixer are played on [`AudioBus::Master`].
+    pub fn play<S>(&self, bus: AudioBus, source: S)
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample + Send,
+    {
+        let source = UniformSourceIterator::<S, f32>::new(source, self.channels, self.sample_rate);
+        let mut state = self.state.lock();
+        let bus = state.bus_index(bus).unwrap_or_else(|| {
+            warn!("{:?} is not a bus of this mixer, using the master bus", bus);
+            0
+        });
+        state.voices.push(Voice {
+            bus,
+            source: Box::new(source),
+        });
+    }
+
+    /// Renders the next samples of the mix into `buffer`, as interleaved channels.
+    pub fn render(&self, buffer: &mut [f32]) {
+        let block_len = BLOCK_FRAMES * self.channels as usize;
+        let mut state = self.state.lock();
+        for block in buffer.chunks_mut(block_len) {
+            state.render_block(block, self.channels, self.sample_rate);
+        }
+    }
+
+    /// Returns a never ending [`Source`] of the mix, to be played on an audio device.
+    pub fn output(&self) -> MixerOutput {
+        MixerOutput {
+            mixer: self.clone(),
+            buffer: vec![0.0; BLOCK_FRAMES * self.channels as usize],
+            position: BLOCK_FRAMES * self.channels as usize,
+        }
+    }
+}
+
+/// The output of an [`AudioMixer`], see [`AudioMixer::output`]
+pub struct MixerOutput {
+    mixer: AudioMixer,
+    buffer: Vec<f32>,
+    position: usize,
+}
+
+impl Iterator for MixerOutput {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position == self.buffer.len() {
+            self.mixer.render(&mut self.buffer);
+            self.position = 0;
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for MixerOutput {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.mixer.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.mixer.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Clears a flag once the wrapped source is finished or dropped, e.g. when its sink is stopped
+pub(crate) struct TrackPlaying<S> {
+    source: S,
+    playing: Arc<AtomicBool>,
+}
+
+impl<S> TrackPlaying<S> {
+    pub(crate) fn new(source: S, playing: Arc<AtomicBool>) -> Self {
+        Self { source, playing }
+    }
+}
+
+impl<S: Source> Iterator for TrackPlaying<S>
+where
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let next = self.source.next();
+        if next.is_none() {
+            self.playing.store(false, Ordering::Release);
+        }
+        next
+    }
+}
+
+impl<S: Source> Source for TrackPlaying<S>
+where
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+impl<S> Drop for TrackPlaying<S> {
+    fn drop(&mut self) {
+        self.playing.store(false, Ordering::Release);
+    }
+}
+
+/// Ends the wrapped source once the flag of a [`TrackPlaying`] source is cleared
+pub(crate) struct UntilDone<S> {
+    source: S,
+    playing: Arc<AtomicBool>,
+}
+
+impl<S> UntilDone<S> {
+    pub(crate) fn new(source: S, playing: Arc<AtomicBool>) -> Self {
+        Self { source, playing }
+    }
+}
+
+impl<S: Source> Iterator for UntilDone<S>
+where
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        if self.playing.load(Ordering::Acquire) {
+            self.source.next()
+        } else {
+            None
+        }
+    }
+}
+
+impl<S: Source> Source for UntilDone<S>
+where
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Effect;
+    use rodio::buffer::SamplesBuffer;
+
+    fn constant(value: f32, frames: usize) -> SamplesBuffer<f32> {
+        SamplesBuffer::new(1, 44_100, vec![value; frames])
+    }
+
+    fn render(mixer: &AudioMixer, frames: usize) -> Vec<f32> {
+        let mut buffer = vec![0.0; frames * mixer.channels() as usize];
+        mixer.render(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn bus_volumes_multiply_with_master() {
+        let mixer = AudioMixer::default();
+        mixer.set_bus_volume(AudioBus::Master, 0.5);
+        mixer.set_bus_volume(AudioBus::Music, 0.5);
+        mixer.play(AudioBus::Music, constant(1.0, 1000));
+        mixer.play(AudioBus::Ui, constant(1.0, 1000));
+
+        let buffer = render(&mixer, 100);
+        assert!(buffer.iter().all(|sample| (sample - 0.75).abs() < 1e-6));
+
+        mixer.set_bus_muted(AudioBus::Ui, true);
+        let buffer = render(&mixer, 100);
+        assert!(buffer.iter().all(|sample| (sample - 0.25).abs() < 1e-6));
+    }
+
+    #[test]
+    fn finished_sounds_are_removed() {
+        let mixer = AudioMixer::default();
+        mixer.play(AudioBus::Sfx, constant(1.0, 10));
+        assert_eq!(mixer.playing_count(AudioBus::Sfx), 1);
+
+        let buffer = render(&mixer, 20);
+        assert!(buffer[..20].iter().all(|sample| *sample == 1.0));
+        assert!(buffer[20..].iter().all(|sample| *sample == 0.0));
+        assert_eq!(mixer.playing_count(AudioBus::Sfx), 0);
+    }
+
+    #[test]
+    fn voice_ducks_music() {
+        let mixer = AudioMixer::default();
+        let mut ducking = Ducking::new(AudioBus::Voice, AudioBus::Music, 0.25);
+        ducking.attack = 0.0;
+        ducking.release = 0.0;
+        mixer.add_ducking(ducking);
+        mixer.play(AudioBus::Music, constant(1.0, 44_100));
+        mixer.play(AudioBus::Voice, constant(0.0, BLOCK_FRAMES));
+
+        let buffer = render(&mixer, BLOCK_FRAMES);
+        assert!(buffer.iter().all(|sample| (sample - 0.25).abs() < 1e-6));
+
+        // the voice line ended during the last block, so the music is back to full volume
+        render(&mixer, BLOCK_FRAMES);
+        let buffer = render(&mixer, BLOCK_FRAMES);
+        assert!(buffer.iter().all(|sample| (sample - 1.0).abs() < 1e-6));
+    }
+
+    #[test]
+    fn bus_effects_apply_to_their_bus() {
+        let mixer = AudioMixer::default();
+        // a fully wet delay silences the first second of the sound effects
+        mixer
+            .bus_effects(AudioBus::Sfx)
+            .push(Effect::delay(1.0, 0.0, 1.0));
+        mixer.play(AudioBus::Sfx, constant(1.0, 1000));
+        mixer.play(AudioBus::Music, constant(0.5, 1000));
+
+        let buffer = render(&mixer, 100);
+        assert!(buffer.iter().all(|sample| (sample - 0.5).abs() < 1e-6));
+    }
+
+    #[test]
+    fn zone_reverb_applies_to_its_bus() {
+        let mixer = AudioMixer::default();
+        // the fully wet reverb is silent until its first reflection
+        mixer.set_zone_reverb(Some((AudioBus::Sfx, Effect::reverb(0.5, 0.5, 1.0))));
+        mixer.play(AudioBus::Sfx, constant(1.0, 1000));
+        mixer.play(AudioBus::Music, constant(0.5, 1000));
+
+        let buffer = render(&mixer, 100);
+        assert!(buffer.iter().all(|sample| (sample - 0.5).abs() < 1e-6));
+
+        mixer.set_zone_reverb(None);
+        let buffer = render(&mixer, 100);
+        assert!(buffer.iter().all(|sample| (sample - 1.5).abs() < 1e-6));
+    }
+
+    #[test]
+    fn sub_buses_are_mixed_into_their_parent() {
+        let mixer = AudioMixer::default();
+        let footsteps = mixer.add_bus(AudioBus::Sfx);
+        let left_foot = mixer.add_bus(footsteps);
+        assert_eq!(mixer.parent_bus(left_foot), Some(footsteps));
+        assert_eq!(mixer.parent_bus(footsteps), Some(AudioBus::Sfx));
+        assert_eq!(mixer.parent_bus(AudioBus::Master), None);
+
+        mixer.set_bus_volume(AudioBus::Sfx, 0.5);
+        mixer.set_bus_volume(footsteps, 0.5);
+        mixer.play(left_foot, constant(1.0, 1000));
+        mixer.play(AudioBus::Sfx, constant(1.0, 1000));
+        assert_eq!(mixer.playing_count(AudioBus::Sfx), 2);
+        assert_eq!(mixer.playing_count(footsteps), 1);
+
+        let buffer = render(&mixer, 100);
+        assert!(buffer.iter().all(|sample| (sample - 0.75).abs() < 1e-6));
+    }
+
+    #[test]
+    fn sub_buses_trigger_the_duckings_of_their_parent() {
+        let mixer = AudioMixer::default();
+        let narrator = mixer.add_bus(AudioBus::Voice);
+        let mut ducking = Ducking::new(AudioBus::Voice, AudioBus::Music, 0.25);
+        ducking.attack = 0.0;
+        mixer.add_ducking(ducking);
+        mixer.play(AudioBus::Music, constant(1.0, 44_100));
+        mixer.play(narrator, constant(0.0, 44_100));
+
+        let buffer = render(&mixer, BLOCK_FRAMES);
+        assert!(buffer.iter().all(|sample| (sample - 0.25).abs() < 1e-6));
+    }
+}
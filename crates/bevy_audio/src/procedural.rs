@@ -0,0 +1,437 @@
+use crate::Decodable;
+use bevy_asset::AssetSize;
+use bevy_reflect::TypeUuid;
+use parking_lot::Mutex;
+use rodio::Source;
+use std::{collections::VecDeque, f32::consts::PI, fmt, sync::Arc, time::Duration};
+
+/// Sample rate of the generated [`ProceduralAudio`] sources
+const PROCEDURAL_SAMPLE_RATE: u32 = 44_100;
+
+/// Number of samples a generated source requests at once
+const CHUNK_LEN: usize = 256;
+
+/// A source of samples that is generated at runtime, see [`ProceduralAudio`]
+pub type ProceduralSource = Box<dyn Source<Item = f32> + Send + Sync>;
+
+/// Audio synthesized at runtime instead of decoded from a file
+///
+/// Every time the asset is played, a new source is created, so a tone that is played twice
+/// starts from the beginning both times. Procedural audio is added to the
+/// `Assets<ProceduralAudio>` collection and played with the `Audio<ProceduralAudio>` resource.
+///
+/// ```
+/// # use bevy_asset::Assets;
+/// # use bevy_audio::{Audio, ProceduralAudio, Waveform};
+/// # use bevy_ecs::system::{Res, ResMut};
+/// fn beep(mut sounds: ResMut<Assets<ProceduralAudio>>, audio: Res<Audio<ProceduralAudio>>) {
+///     let tone = sounds.add(ProceduralAudio::tone(Waveform::Sine, 440.0));
+///     audio.play(tone);
+/// }
+/// ```
+#[derive(Clone, TypeUuid)]
+#[uuid = "b7e8a4a8-3f7c-4f53-9d5a-1c2e0a9d6f41"]
+pub struct ProceduralAudio {
+    factory: Arc<dyn Fn() -> ProceduralSource + Send + Sync>,
+}
+
+impl fmt::Debug for ProceduralAudio {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProceduralAudio").finish()
+    }
+}
+
+impl ProceduralAudio {
+    /// Creates procedural audio that plays the sources created by `factory`.
+    pub fn new<F, S>(factory: F) -> Self
+    where
+        F: Fn() -> S + Send + Sync + 'static,
+        S: Source<Item = f32> + Send + Sync + 'static,
+    {
+        Self {
+            factory: Arc::new(move || Box::new(factory()) as ProceduralSource),
+        }
+    }
+
+    /// A never ending tone of the given frequency in Hz.
+    pub fn tone(waveform: Waveform, frequency: f32) -> Self {
+        Self::new(move || Tone {
+            waveform,
+            frequency,
+            phase: 0.0,
+        })
+    }
+
+    /// Never ending white noise.
+    pub fn noise() -> Self {
+        Self::new(|| Noise { state: 0x2545_f491 })
+    }
+
+    /// Audio generated by callbacks that fill buffers of interleaved samples.
+    ///
+    /// `factory` creates a callback every time the audio is played. The callback fills a buffer
+    /// of silence whenever more samples are needed, and the sound ends once it returns
+    /// `false`.
+    pub fn from_callback<F, C>(channels: u16, sample_rate: u32, factory: F) -> Self
+    where
+        F: Fn() -> C + Send + Sync + 'static,
+        C: FnMut(&mut [f32]) -> bool + Send + Sync + 'static,
+    {
+        Self::new(move || CallbackSource {
+            callback: factory(),
+            buffer: vec![0.0; CHUNK_LEN * channels as usize],
+            position: CHUNK_LEN * channels as usize,
+            finished: false,
+            channels,
+            sample_rate,
+        })
+    }
+
+    /// Audio that plays the samples pushed into `ring_buffer`, e.g. by a system receiving
+    /// voice chat. The sound ends once the ring buffer is closed and all samples are played.
+    pub fn from_ring_buffer(ring_buffer: &AudioRingBuffer) -> Self {
+        let ring_buffer = ring_buffer.clone();
+        Self::new(move || RingBufferSource {
+            ring_buffer: ring_buffer.clone(),
+            buffer: VecDeque::new(),
+        })
+    }
+}
+
+impl AssetSize for ProceduralAudio {
+    fn asset_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
+}
+
+impl Decodable for ProceduralAudio {
+    type Decoder = ProceduralSource;
+
+    fn decoder(&self) -> Self::Decoder {
+        (self.factory)()
+    }
+}
+
+/// The shape of a [`ProceduralAudio::tone`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Sawtooth,
+    Triangle,
+}
+
+impl Waveform {
+    /// Returns the value of the waveform at `phase`, which goes from `0.0` to `1.0` over a period.
+    pub fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (phase * 2.0 * PI).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Sawtooth => 2.0 * phase - 1.0,
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        }
+    }
+}
+
+struct Tone {
+    waveform: Waveform,
+    frequency: f32,
+    phase: f32,
+}
+
+impl Iterator for Tone {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.waveform.sample(self.phase);
+        self.phase = (self.phase + self.frequency / PROCEDURAL_SAMPLE_RATE as f32).fract();
+        Some(sample)
+    }
+}
+
+impl Source for Tone {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        PROCEDURAL_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+struct Noise {
+    // xorshift state, never zero
+    state: u32,
+}
+
+impl Iterator for Noise {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        Some(self.state as f32 / u32::MAX as f32 * 2.0 - 1.0)
+    }
+}
+
+impl Source for Noise {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        PROCEDURAL_SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+struct CallbackSource<C> {
+    callback: C,
+    buffer: Vec<f32>,
+    position: usize,
+    finished: bool,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl<C: FnMut(&mut [f32]) -> bool> Iterator for CallbackSource<C> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position == self.buffer.len() {
+            if self.finished {
+                return None;
+            }
+            for sample in self.buffer.iter_mut() {
+                *sample = 0.0;
+            }
+            self.finished = !(self.callback)(&mut self.buffer);
+            self.position = 0;
+        }
+        let sample = self.buffer[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl<C: FnMut(&mut [f32]) -> bool> Source for CallbackSource<C> {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+struct RingBufferState {
+    samples: VecDeque<f32>,
+    capacity: usize,
+    closed: bool,
+}
+
+/// A queue of interleaved samples that systems push into while it is played, created for
+/// [`ProceduralAudio::from_ring_buffer`]
+///
+/// When the buffer is full, the oldest samples are dropped to keep the latency bounded. When it
+/// runs empty, silence is played until more samples are pushed.
+#[derive(Clone)]
+pub struct AudioRingBuffer {
+    state: Arc<Mutex<RingBufferState>>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl fmt::Debug for AudioRingBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AudioRingBuffer")
+            .field("channels", &self.channels)
+            .field("sample_rate", &self.sample_rate)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl AudioRingBuffer {
+    /// Creates a ring buffer holding up to `capacity` samples.
+    pub fn new(channels: u16, sample_rate: u32, capacity: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(RingBufferState {
+                samples: VecDeque::with_capacity(capacity),
+                capacity,
+                closed: false,
+            })),
+            channels,
+            sample_rate,
+        }
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Pushes interleaved samples, returning how many of the oldest samples were dropped to
+    /// make room for them.
+    pub fn push(&self, samples: &[f32]) -> usize {
+        let mut state = self.state.lock();
+        state.samples.extend(samples);
+        let overflow = state.samples.len().saturating_sub(state.capacity);
+        // drop whole frames to keep the channels in order
+        let channels = self.channels.max(1) as usize;
+        let dropped = (overflow + channels - 1) / channels * channels;
+        let dropped = dropped.min(state.samples.len());
+        state.samples.drain(..dropped);
+        dropped
+    }
+
+    /// Returns the number of samples waiting to be played.
+    pub fn len(&self) -> usize {
+        self.state.lock().samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().samples.clear();
+    }
+
+    /// Ends the sounds playing this ring buffer once the remaining samples are played.
+    pub fn close(&self) {
+        self.state.lock().closed = true;
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().closed
+    }
+}
+
+struct RingBufferSource {
+    ring_buffer: AudioRingBuffer,
+    buffer: VecDeque<f32>,
+}
+
+impl Iterator for RingBufferSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.buffer.is_empty() {
+            let channels = self.ring_buffer.channels.max(1) as usize;
+            let mut state = self.ring_buffer.state.lock();
+            let len = state.samples.len().min(CHUNK_LEN) / channels * channels;
+            if len > 0 {
+                self.buffer.extend(state.samples.drain(..len));
+            } else if state.closed {
+                return None;
+            } else {
+                // play a frame of silence until more samples are pushed
+                self.buffer.extend(std::iter::repeat(0.0).take(channels));
+            }
+        }
+        self.buffer.pop_front()
+    }
+}
+
+impl Source for RingBufferSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.ring_buffer.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.ring_buffer.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn waveforms() {
+        assert!(Waveform::Sine.sample(0.25) > 0.999);
+        assert_eq!(Waveform::Square.sample(0.75), -1.0);
+        assert_eq!(Waveform::Sawtooth.sample(0.5), 0.0);
+        assert_eq!(Waveform::Triangle.sample(0.5), 1.0);
+        assert_eq!(Waveform::Triangle.sample(0.0), -1.0);
+    }
+
+    #[test]
+    fn callback_ends_when_it_returns_false() {
+        let audio = ProceduralAudio::from_callback(1, 8_000, || {
+            let mut calls = 0;
+            move |buffer: &mut [f32]| {
+                calls += 1;
+                for sample in buffer.iter_mut() {
+                    *sample = calls as f32;
+                }
+                calls < 2
+            }
+        });
+        let samples = audio.decoder().collect::<Vec<_>>();
+        assert_eq!(samples.len(), 2 * CHUNK_LEN);
+        assert_eq!(samples[CHUNK_LEN], 2.0);
+    }
+
+    #[test]
+    fn ring_buffer_plays_pushed_samples() {
+        let ring_buffer = AudioRingBuffer::new(2, 8_000, 4);
+        let mut source = ProceduralAudio::from_ring_buffer(&ring_buffer).decoder();
+
+        // the oldest frame is dropped once the buffer is full
+        assert_eq!(ring_buffer.push(&[1.0, 1.0, 2.0, 2.0, 3.0, 3.0]), 2);
+        assert_eq!(
+            source.by_ref().take(4).collect::<Vec<_>>(),
+            [2.0, 2.0, 3.0, 3.0]
+        );
+
+        // silence is played while the buffer is empty
+        assert_eq!(source.by_ref().take(2).collect::<Vec<_>>(), [0.0, 0.0]);
+
+        ring_buffer.push(&[4.0, 4.0]);
+        ring_buffer.close();
+        assert_eq!(source.collect::<Vec<_>>(), [4.0, 4.0]);
+    }
+}
@@ -0,0 +1,509 @@
+use crate::{AudioBus, AudioMixer, AudioSink, Effect};
+use bevy_asset::{Assets, Handle};
+use bevy_core::Time;
+use bevy_ecs::{
+    entity::Entity,
+    query::With,
+    system::{Local, Query, Res},
+};
+use bevy_math::Vec3;
+use bevy_transform::components::GlobalTransform;
+use bevy_utils::HashMap;
+use parking_lot::Mutex;
+use rodio::{source::UniformSourceIterator, Sample, Source};
+use std::{f32::consts::FRAC_PI_4, sync::Arc, time::Duration};
+
+/// Number of samples that are played with the same spatial parameters, must be even
+const SPATIAL_BLOCK_LEN: usize = 512;
+
+/// The point from which [`AudioEmitter`]s are heard, usually the camera or the player
+///
+/// The listener is read from the [`GlobalTransform`] of its entity, hearing sounds to its right
+/// on the right channel. If there are several listeners, only the first one is used.
+#[derive(Clone, Debug)]
+pub struct AudioListener {
+    /// The speed of sound in world units per second, used for the Doppler effect
+    pub speed_of_sound: f32,
+}
+
+impl Default for AudioListener {
+    fn default() -> Self {
+        Self {
+            speed_of_sound: 343.0,
+        }
+    }
+}
+
+/// How the volume of an [`AudioEmitter`] decreases with its distance to the [`AudioListener`]
+#[derive(Clone, Copy, Debug)]
+pub enum Rolloff {
+    /// The volume does not change with distance.
+    None,
+    /// The volume falls linearly from full volume at the minimum distance to silence at the
+    /// maximum distance.
+    Linear,
+    /// The volume is inversely proportional to the distance, like the sound of a point source.
+    Inverse,
+    /// The volume falls with the distance raised to the given exponent, which is steeper than
+    /// [`Rolloff::Inverse`] for exponents above `1.0`.
+    Exponential(f32),
+    /// The volume is given by a function of the distance.
+    Custom(fn(f32) -> f32),
+}
+
+/// Plays a spatial sound from the position of its entity
+///
+/// The sound has to be started with [`PlaybackSettings::spatial`](crate::PlaybackSettings::spatial),
+/// and follows the [`GlobalTransform`] of the entity every frame.
+///
+/// ```
+/// # use bevy_ecs::system::{Commands, Res};
+/// # use bevy_asset::AssetServer;
+/// # use bevy_audio::{Audio, AudioEmitter, PlaybackSettings};
+/// # use bevy_transform::components::{GlobalTransform, Transform};
+/// fn spawn_engine_sound(
+///     mut commands: Commands,
+///     asset_server: Res<AssetServer>,
+///     audio: Res<Audio>,
+/// ) {
+///     let sink = audio.play_with_settings(
+///         asset_server.load("sounds/engine.ogg"),
+///         PlaybackSettings::LOOP.spatial(),
+///     );
+///     commands
+///         .spawn()
+///         .insert(Transform::default())
+///         .insert(GlobalTransform::default())
+///         .insert(AudioEmitter::new(sink).with_doppler());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct AudioEmitter {
+    /// The sound played by this emitter
+    pub sink: Handle<AudioSink>,
+    pub rolloff: Rolloff,
+    /// The distance up to which the sound plays at full volume
+    pub min_distance: f32,
+    /// The distance after which the volume no longer decreases
+    pub max_distance: f32,
+    /// Shift the pitch of the sound with the velocities of the emitter and the listener
+    pub doppler: bool,
+}
+
+impl AudioEmitter {
+    pub fn new(sink: Handle<AudioSink>) -> Self {
+        Self {
+            sink,
+            rolloff: Rolloff::Inverse,
+            min_distance: 1.0,
+            max_distance: 100.0,
+            doppler: false,
+        }
+    }
+
+    /// Helper to set the rolloff curve
+    pub fn with_rolloff(mut self, rolloff: Rolloff) -> Self {
+        self.rolloff = rolloff;
+        self
+    }
+
+    /// Helper to set the distances between which the volume decreases
+    pub fn with_distances(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Helper to enable the Doppler effect
+    pub fn with_doppler(mut self) -> Self {
+        self.doppler = true;
+        self
+    }
+
+    /// Returns the gain of the sound at the given distance from the listener.
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        let min_distance = self.min_distance.max(f32::EPSILON);
+        let distance = distance
+            .max(min_distance)
+            .min(self.max_distance.max(min_distance));
+        let gain = match self.rolloff {
+            Rolloff::None => 1.0,
+            Rolloff::Linear => {
+                let range = self.max_distance - min_distance;
+                if range > 0.0 {
+                    1.0 - (distance - min_distance) / range
+                } else {
+                    1.0
+                }
+            }
+            Rolloff::Inverse => min_distance / distance,
+            Rolloff::Exponential(exponent) => (distance / min_distance).powf(-exponent),
+            Rolloff::Custom(rolloff) => rolloff(distance),
+        };
+        gain.max(0.0).min(1.0)
+    }
+}
+
+/// A sphere around its entity in which a bus gets reverb, e.g. a cave or a hall
+///
+/// While the [`AudioListener`] is inside the zone, the sounds of its [`AudioBus`] play with an
+/// [`Effect::Reverb`], applied before the effects of the bus. If the listener is inside several
+/// zones, the smallest one is used.
+#[derive(Clone, Debug)]
+pub struct ReverbZone {
+    pub radius: f32,
+    pub room_size: f32,
+    pub damping: f32,
+    pub mix: f32,
+    pub bus: AudioBus,
+}
+
+impl ReverbZone {
+    pub fn new(radius: f32, room_size: f32, damping: f32, mix: f32) -> Self {
+        Self {
+            radius,
+            room_size,
+            damping,
+            mix,
+            bus: AudioBus::Sfx,
+        }
+    }
+
+    /// Helper to set the bus that gets the reverb
+    pub fn with_bus(mut self, bus: AudioBus) -> Self {
+        self.bus = bus;
+        self
+    }
+
+    pub fn reverb(&self) -> Effect {
+        Effect::reverb(self.room_size, self.damping, self.mix)
+    }
+}
+
+/// Returns the smallest of the `zones` that contains `position`.
+fn reverb_zone_at<'a>(
+    position: Vec3,
+    zones: impl Iterator<Item = (&'a ReverbZone, &'a GlobalTransform)>,
+) -> Option<&'a ReverbZone> {
+    zones
+        .filter(|(zone, transform)| transform.translation.distance(position) <= zone.radius)
+        .map(|(zone, _)| zone)
+        .min_by(|a, b| a.radius.partial_cmp(&b.radius).unwrap())
+}
+
+/// Applies the reverb of the [`ReverbZone`] the [`AudioListener`] is in to the [`AudioMixer`]
+pub fn update_reverb_zones_system(
+    mixer: Res<AudioMixer>,
+    listeners: Query<&GlobalTransform, With<AudioListener>>,
+    zones: Query<(&ReverbZone, &GlobalTransform)>,
+) {
+    let zone = listeners
+        .iter()
+        .next()
+        .and_then(|listener| reverb_zone_at(listener.translation, zones.iter()));
+    mixer.set_zone_reverb(zone.map(|zone| (zone.bus, zone.reverb())));
+}
+
+/// Returns the gains of the left and right channels for a sound at `position`.
+///
+/// Uses an equal power pan law, scaled so that sounds in front of or behind the listener play
+/// at full volume on both channels.
+fn stereo_gains(listener: &GlobalTransform, position: Vec3) -> [f32; 2] {
+    let local = listener.rotation.inverse() * (position - listener.translation);
+    let length = local.length();
+    let pan = if length > f32::EPSILON {
+        local.x / length
+    } else {
+        0.0
+    };
+    let angle = (pan + 1.0) * FRAC_PI_4;
+    [
+        (angle.cos() * std::f32::consts::SQRT_2).min(1.0),
+        (angle.sin() * std::f32::consts::SQRT_2).min(1.0),
+    ]
+}
+
+/// Returns the factor the pitch of a sound is shifted by the Doppler effect.
+fn doppler_factor(
+    speed_of_sound: f32,
+    listener_position: Vec3,
+    listener_velocity: Vec3,
+    emitter_position: Vec3,
+    emitter_velocity: Vec3,
+) -> f32 {
+    let direction = (emitter_position - listener_position).normalize_or_zero();
+    // speeds towards each other, kept below the speed of sound
+    let max_speed = speed_of_sound * 0.9;
+    let listener_speed = listener_velocity
+        .dot(direction)
+        .max(-max_speed)
+        .min(max_speed);
+    let emitter_speed = (-emitter_velocity.dot(direction))
+        .max(-max_speed)
+        .min(max_speed);
+    (speed_of_sound + listener_speed) / (speed_of_sound - emitter_speed)
+}
+
+/// Spatial parameters of a playing sound, updated by [`update_spatial_audio_system`]
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SpatialParams {
+    pub(crate) gains: [f32; 2],
+    pub(crate) doppler: f32,
+}
+
+impl Default for SpatialParams {
+    fn default() -> Self {
+        Self {
+            gains: [1.0, 1.0],
+            doppler: 1.0,
+        }
+    }
+}
+
+/// Shares the [`SpatialParams`] between an [`AudioSink`] and its [`Spatial`] source
+#[derive(Debug, Default)]
+pub(crate) struct SpatialControls {
+    params: Mutex<SpatialParams>,
+}
+
+impl SpatialControls {
+    pub(crate) fn get(&self) -> SpatialParams {
+        *self.params.lock()
+    }
+
+    pub(crate) fn set(&self, params: SpatialParams) {
+        *self.params.lock() = params;
+    }
+}
+
+/// Plays a source in stereo with the gains and the Doppler shift of its [`SpatialControls`]
+pub(crate) struct Spatial<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    input: UniformSourceIterator<S, f32>,
+    controls: Arc<SpatialControls>,
+    params: SpatialParams,
+    sample_rate: u32,
+    // samples left until the parameters are read again
+    remaining: usize,
+}
+
+impl<S> Spatial<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    pub(crate) fn new(input: S, controls: Arc<SpatialControls>) -> Self {
+        let sample_rate = input.sample_rate();
+        Self {
+            input: UniformSourceIterator::new(input, 2, sample_rate),
+            params: controls.get(),
+            controls,
+            sample_rate,
+            remaining: SPATIAL_BLOCK_LEN,
+        }
+    }
+}
+
+impl<S> Iterator for Spatial<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.input.next()?;
+        let channel = (SPATIAL_BLOCK_LEN - self.remaining) % 2;
+        let sample = sample * self.params.gains[channel];
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            // parameters only change between frames, so the sample rate is read again
+            self.remaining = SPATIAL_BLOCK_LEN;
+            self.params = self.controls.get();
+        }
+        Some(sample)
+    }
+}
+
+impl<S> Source for Spatial<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        ((self.sample_rate as f32 * self.params.doppler) as u32).max(1)
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// The positions of the listener and the emitters in the previous frame, used to get their
+/// velocities
+#[derive(Default)]
+pub struct PreviousSpatialPositions {
+    listener: Option<Vec3>,
+    emitters: HashMap<Entity, Vec3>,
+}
+
+fn velocity(previous: Option<Vec3>, current: Vec3, delta_seconds: f32) -> Vec3 {
+    match previous {
+        Some(previous) if delta_seconds > 0.0 => (current - previous) / delta_seconds,
+        _ => Vec3::ZERO,
+    }
+}
+
+/// Updates the spatial sounds of [`AudioEmitter`]s from their position relative to the
+/// [`AudioListener`]
+pub fn update_spatial_audio_system(
+    time: Res<Time>,
+    sinks: Res<Assets<AudioSink>>,
+    mut previous_positions: Local<PreviousSpatialPositions>,
+    listeners: Query<(&AudioListener, &GlobalTransform)>,
+    emitters: Query<(Entity, &AudioEmitter, &GlobalTransform)>,
+) {
+    let delta_seconds = time.delta_seconds();
+    let (listener, listener_transform) = match listeners.iter().next() {
+        Some(listener) => listener,
+        None => {
+            previous_positions.listener = None;
+            return;
+        }
+    };
+    let listener_position = listener_transform.translation;
+    let listener_velocity = velocity(
+        previous_positions.listener,
+        listener_position,
+        delta_seconds,
+    );
+    previous_positions.listener = Some(listener_position);
+
+    let mut emitter_positions = HashMap::default();
+    for (entity, emitter, transform) in emitters.iter() {
+        let position = transform.translation;
+        emitter_positions.insert(entity, position);
+
+        let controls = match sinks.get(&emitter.sink).and_then(|sink| sink.spatial()) {
+            Some(controls) => controls,
+            None => continue,
+        };
+        let attenuation = emitter.attenuation(position.distance(listener_position));
+        let gains = stereo_gains(listener_transform, position);
+        let doppler = if emitter.doppler {
+            let emitter_velocity = velocity(
+                previous_positions.emitters.get(&entity).copied(),
+                position,
+                delta_seconds,
+            );
+            doppler_factor(
+                listener.speed_of_sound,
+                listener_position,
+                listener_velocity,
+                position,
+                emitter_velocity,
+            )
+        } else {
+            1.0
+        };
+        controls.set(SpatialParams {
+            gains: [gains[0] * attenuation, gains[1] * attenuation],
+            doppler,
+        });
+    }
+    previous_positions.emitters = emitter_positions;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy_math::Quat;
+
+    fn emitter(rolloff: Rolloff) -> AudioEmitter {
+        AudioEmitter::new(Handle::default())
+            .with_rolloff(rolloff)
+            .with_distances(1.0, 11.0)
+    }
+
+    #[test]
+    fn rolloff_curves() {
+        let linear = emitter(Rolloff::Linear);
+        assert_eq!(linear.attenuation(0.5), 1.0);
+        assert!((linear.attenuation(6.0) - 0.5).abs() < 1e-6);
+        assert_eq!(linear.attenuation(20.0), 0.0);
+
+        let inverse = emitter(Rolloff::Inverse);
+        assert!((inverse.attenuation(4.0) - 0.25).abs() < 1e-6);
+        // the volume stays constant after the maximum distance
+        assert_eq!(inverse.attenuation(11.0), inverse.attenuation(50.0));
+
+        let exponential = emitter(Rolloff::Exponential(2.0));
+        assert!((exponential.attenuation(4.0) - 1.0 / 16.0).abs() < 1e-6);
+
+        let custom = emitter(Rolloff::Custom(|distance| 1.0 / distance.sqrt()));
+        assert!((custom.attenuation(4.0) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn pans_with_listener_rotation() {
+        let listener = GlobalTransform::identity();
+        let [left, right] = stereo_gains(&listener, Vec3::X);
+        assert!(left.abs() < 1e-6);
+        assert!((right - 1.0).abs() < 1e-6);
+
+        let [left, right] = stereo_gains(&listener, -Vec3::Z);
+        assert!((left - 1.0).abs() < 1e-6);
+        assert!((right - 1.0).abs() < 1e-6);
+
+        // turned around, the sound comes from the left
+        let listener = GlobalTransform::from_rotation(Quat::from_rotation_y(std::f32::consts::PI));
+        let [left, right] = stereo_gains(&listener, Vec3::X);
+        assert!((left - 1.0).abs() < 1e-6);
+        assert!(right.abs() < 1e-6);
+    }
+
+    #[test]
+    fn listener_uses_the_smallest_reverb_zone() {
+        let hall = (
+            ReverbZone::new(10.0, 0.8, 0.5, 0.3),
+            GlobalTransform::identity(),
+        );
+        let closet = (
+            ReverbZone::new(1.0, 0.1, 0.5, 0.3),
+            GlobalTransform::from_translation(Vec3::X * 5.0),
+        );
+        let zones = || vec![(&hall.0, &hall.1), (&closet.0, &closet.1)].into_iter();
+
+        let zone = reverb_zone_at(Vec3::X * 5.0, zones()).unwrap();
+        assert_eq!(zone.radius, 1.0);
+        let zone = reverb_zone_at(Vec3::ZERO, zones()).unwrap();
+        assert_eq!(zone.radius, 10.0);
+        assert!(reverb_zone_at(Vec3::X * 20.0, zones()).is_none());
+    }
+
+    #[test]
+    fn doppler_raises_pitch_of_approaching_sounds() {
+        let approaching = doppler_factor(
+            343.0,
+            Vec3::ZERO,
+            Vec3::ZERO,
+            Vec3::X * 10.0,
+            -Vec3::X * 34.3,
+        );
+        assert!((approaching - 1.0 / 0.9).abs() < 1e-4);
+
+        let receding = doppler_factor(343.0, Vec3::ZERO, -Vec3::X * 34.3, Vec3::X, Vec3::ZERO);
+        assert!((receding - 0.9).abs() < 1e-4);
+    }
+}
@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 
 mod loader;
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+mod processor;
 pub use loader::*;
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+pub use processor::*;
 
 use bevy_app::prelude::*;
 use bevy_asset::{AddAsset, Handle};
@@ -21,6 +25,8 @@ impl Plugin for GltfPlugin {
             .add_asset::<GltfNode>()
             .add_asset::<GltfPrimitive>()
             .add_asset::<GltfMesh>();
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+        app.add_asset_processor(GltfProcessor);
     }
 }
 
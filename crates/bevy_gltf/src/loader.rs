@@ -1,7 +1,5 @@
 use anyhow::Result;
-use bevy_asset::{
-    AssetIoError, AssetLoader, AssetPath, BoxedFuture, Handle, LoadContext, LoadedAsset,
-};
+use bevy_asset::{AssetIoError, AssetLoader, BoxedFuture, Handle, LoadContext, LoadedAsset};
 use bevy_core::Name;
 use bevy_ecs::world::World;
 use bevy_log::warn;
@@ -54,6 +52,8 @@ pub enum GltfError {
     ImageError(#[from] TextureError),
     #[error("failed to load an asset path: {0}")]
     AssetIoError(#[from] AssetIoError),
+    #[error("invalid GLTF JSON: {0}")]
+    Json(#[from] serde_json::Error),
 }
 
 /// Loads meshes from GLTF files into Mesh assets
@@ -174,6 +174,21 @@ async fn load_gltf<'a, 'b>(
                 }
             }
 
+            if mesh.attribute(Mesh::ATTRIBUTE_TANGENT).is_none()
+                && primitive.material().normal_texture().is_some()
+            {
+                bevy_log::debug!(
+                    "Missing vertex tangents for {}, computing them using the MikkTSpace algorithm",
+                    primitive_label
+                );
+                if let Err(err) = mesh.generate_tangents() {
+                    warn!(
+                        "Failed to generate vertex tangents using the MikkTSpace algorithm: {}",
+                        err
+                    );
+                }
+            }
+
             let mesh = load_context.set_labeled_asset(&primitive_label, LoadedAsset::new(mesh));
             primitives.push(super::GltfPrimitive {
                 mesh,
@@ -377,7 +392,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
     let base_color_texture = if let Some(info) = pbr.base_color_texture() {
         // TODO: handle info.tex_coord() (the *set* index for the right texcoords)
         let label = texture_label(&info.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.get_labeled_asset_path(&label);
         Some(load_context.get_handle(path))
     } else {
         None
@@ -387,7 +402,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
         // TODO: handle normal_texture.scale
         // TODO: handle normal_texture.tex_coord() (the *set* index for the right texcoords)
         let label = texture_label(&normal_texture.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.get_labeled_asset_path(&label);
         Some(load_context.get_handle(path))
     } else {
         None
@@ -396,7 +411,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
     let metallic_roughness_texture = if let Some(info) = pbr.metallic_roughness_texture() {
         // TODO: handle info.tex_coord() (the *set* index for the right texcoords)
         let label = texture_label(&info.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.get_labeled_asset_path(&label);
         Some(load_context.get_handle(path))
     } else {
         None
@@ -406,7 +421,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
         // TODO: handle occlusion_texture.tex_coord() (the *set* index for the right texcoords)
         // TODO: handle occlusion_texture.strength() (a scalar multiplier for occlusion strength)
         let label = texture_label(&occlusion_texture.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.get_labeled_asset_path(&label);
         Some(load_context.get_handle(path))
     } else {
         None
@@ -417,7 +432,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
         // TODO: handle occlusion_texture.tex_coord() (the *set* index for the right texcoords)
         // TODO: handle occlusion_texture.strength() (a scalar multiplier for occlusion strength)
         let label = texture_label(&info.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.get_labeled_asset_path(&label);
         Some(load_context.get_handle(path))
     } else {
         None
@@ -523,10 +538,8 @@ fn load_node(
                 }
 
                 let primitive_label = primitive_label(&mesh, &primitive);
-                let mesh_asset_path =
-                    AssetPath::new_ref(load_context.path(), Some(&primitive_label));
-                let material_asset_path =
-                    AssetPath::new_ref(load_context.path(), Some(&material_label));
+                let mesh_asset_path = load_context.get_labeled_asset_path(&primitive_label);
+                let material_asset_path = load_context.get_labeled_asset_path(&material_label);
 
                 parent.spawn_bundle(PbrBundle {
                     mesh: load_context.get_handle(mesh_asset_path),
@@ -647,8 +660,6 @@ async fn load_buffers(
     load_context: &LoadContext<'_>,
     asset_path: &Path,
 ) -> Result<Vec<Vec<u8>>, GltfError> {
-    const OCTET_STREAM_URI: &str = "application/octet-stream";
-
     let mut buffer_data = Vec::new();
     for buffer in gltf.buffers() {
         match buffer.source() {
@@ -728,8 +739,10 @@ fn resolve_node_hierarchy(
         .collect()
 }
 
-struct DataUri<'a> {
-    mime_type: &'a str,
+pub(crate) const OCTET_STREAM_URI: &str = "application/octet-stream";
+
+pub(crate) struct DataUri<'a> {
+    pub(crate) mime_type: &'a str,
     base64: bool,
     data: &'a str,
 }
@@ -740,7 +753,7 @@ fn split_once(input: &str, delimiter: char) -> Option<(&str, &str)> {
 }
 
 impl<'a> DataUri<'a> {
-    fn parse(uri: &'a str) -> Result<DataUri<'a>, ()> {
+    pub(crate) fn parse(uri: &'a str) -> Result<DataUri<'a>, ()> {
         let uri = uri.strip_prefix("data:").ok_or(())?;
         let (mime_type, data) = split_once(uri, ',').ok_or(())?;
 
@@ -756,7 +769,7 @@ impl<'a> DataUri<'a> {
         })
     }
 
-    fn decode(&self) -> Result<Vec<u8>, base64::DecodeError> {
+    pub(crate) fn decode(&self) -> Result<Vec<u8>, base64::DecodeError> {
         if self.base64 {
             base64::decode(self.data)
         } else {
@@ -0,0 +1,321 @@
+use crate::loader::{DataUri, GltfError, OCTET_STREAM_URI};
+use anyhow::Result;
+use bevy_asset::{AssetProcessor, BoxedFuture, ProcessContext};
+use bevy_log::warn;
+use bevy_render::{
+    mesh::{Indices, Mesh, VertexAttributeValues},
+    pipeline::PrimitiveTopology,
+};
+use gltf::{buffer::Source, mesh::Mode, Primitive, Semantic};
+use serde_json::{json, Value};
+
+/// Turns glTF scenes into GLB files with a single embedded buffer, and computes the vertex
+/// tangents that the [`GltfLoader`](crate::GltfLoader) would otherwise generate on load.
+///
+/// External and base64 encoded buffers are embedded, images are kept as they are.
+#[derive(Clone, Default)]
+pub struct GltfProcessor;
+
+impl AssetProcessor for GltfProcessor {
+    fn process<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        process_context: &'a mut ProcessContext,
+    ) -> BoxedFuture<'a, Result<Vec<u8>>> {
+        Box::pin(async move {
+            let gltf = gltf::Gltf::from_slice(bytes)?;
+
+            let mut buffer_data = Vec::new();
+            for buffer in gltf.buffers() {
+                let buffer_bytes = match buffer.source() {
+                    Source::Uri(uri) => {
+                        let uri = percent_encoding::percent_decode_str(uri).decode_utf8()?;
+                        match DataUri::parse(&uri) {
+                            Ok(data_uri) if data_uri.mime_type == OCTET_STREAM_URI => {
+                                data_uri.decode()?
+                            }
+                            Ok(_) => return Err(GltfError::BufferFormatUnsupported.into()),
+                            Err(()) => {
+                                let buffer_path =
+                                    process_context.path().parent().unwrap().join(&*uri);
+                                process_context.read_asset_bytes(buffer_path).await?
+                            }
+                        }
+                    }
+                    Source::Bin => gltf.blob.clone().ok_or(GltfError::MissingBlob)?,
+                };
+                buffer_data.push(buffer_bytes);
+            }
+
+            Ok(pack_glb(&gltf, json_chunk(bytes), &buffer_data)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["gltf", "glb"]
+    }
+
+    fn version(&self) -> u32 {
+        1
+    }
+}
+
+/// Returns the JSON of a glTF or GLB file that was already parsed successfully.
+fn json_chunk(bytes: &[u8]) -> &[u8] {
+    if bytes.starts_with(b"glTF") {
+        let mut length = [0; 4];
+        length.copy_from_slice(&bytes[12..16]);
+        &bytes[20..20 + u32::from_le_bytes(length) as usize]
+    } else {
+        bytes
+    }
+}
+
+/// Writes a GLB file holding all of `buffer_data` in its binary chunk, with tangents added to the
+/// primitives that need them.
+fn pack_glb(gltf: &gltf::Gltf, json: &[u8], buffer_data: &[Vec<u8>]) -> Result<Vec<u8>, GltfError> {
+    let mut root: Value = serde_json::from_slice(json)?;
+
+    let mut bin = Vec::new();
+    let mut buffer_offsets = Vec::new();
+    for data in buffer_data {
+        buffer_offsets.push(bin.len());
+        bin.extend_from_slice(data);
+        pad(&mut bin, 0);
+    }
+    if let Some(views) = root
+        .get_mut("bufferViews")
+        .and_then(|views| views.as_array_mut())
+    {
+        for view in views {
+            let buffer = view["buffer"].as_u64().unwrap_or(0) as usize;
+            let offset = view["byteOffset"].as_u64().unwrap_or(0) as usize;
+            view["buffer"] = 0.into();
+            view["byteOffset"] = (buffer_offsets[buffer] + offset).into();
+        }
+    }
+
+    for mesh in gltf.meshes() {
+        for (index, primitive) in mesh.primitives().enumerate() {
+            let tangents = match primitive_tangents(&primitive, buffer_data) {
+                Some(tangents) => tangents,
+                None => continue,
+            };
+            let view = push(
+                &mut root,
+                "bufferViews",
+                json!({
+                    "buffer": 0,
+                    "byteOffset": bin.len(),
+                    "byteLength": tangents.len() * 16,
+                }),
+            );
+            for component in tangents.iter().flatten() {
+                bin.extend_from_slice(&component.to_le_bytes());
+            }
+            let accessor = push(
+                &mut root,
+                "accessors",
+                json!({
+                    "bufferView": view,
+                    "componentType": 5126,
+                    "count": tangents.len(),
+                    "type": "VEC4",
+                }),
+            );
+            root["meshes"][mesh.index()]["primitives"][index]["attributes"]["TANGENT"] =
+                accessor.into();
+        }
+    }
+
+    if !bin.is_empty() {
+        root["buffers"] = json!([{ "byteLength": bin.len() }]);
+    }
+    Ok(write_glb(serde_json::to_vec(&root)?, bin))
+}
+
+/// Generates the tangents of a primitive like the loader would, if it has a normal map and no
+/// tangents.
+fn primitive_tangents(primitive: &Primitive, buffer_data: &[Vec<u8>]) -> Option<Vec<[f32; 4]>> {
+    if primitive.mode() != Mode::Triangles
+        || primitive.get(&Semantic::Tangents).is_some()
+        || primitive.material().normal_texture().is_none()
+    {
+        return None;
+    }
+
+    // primitives without normals or texture coordinates are left to the loader, which
+    // duplicates their vertices or fills in zeroed texture coordinates
+    let reader = primitive.reader(|buffer| Some(&buffer_data[buffer.index()]));
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        reader.read_positions()?.collect::<Vec<_>>(),
+    );
+    mesh.set_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        reader.read_normals()?.collect::<Vec<_>>(),
+    );
+    mesh.set_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        reader.read_tex_coords(0)?.into_f32().collect::<Vec<_>>(),
+    );
+    if let Some(indices) = reader.read_indices() {
+        mesh.set_indices(Some(Indices::U32(indices.into_u32().collect())));
+    }
+
+    if let Err(err) = mesh.generate_tangents() {
+        warn!(
+            "Failed to generate vertex tangents using the MikkTSpace algorithm: {}",
+            err
+        );
+        return None;
+    }
+    match mesh.attribute(Mesh::ATTRIBUTE_TANGENT) {
+        Some(VertexAttributeValues::Float32x4(tangents)) => Some(tangents.clone()),
+        _ => None,
+    }
+}
+
+/// Appends `value` to the array at `key` of the glTF JSON, returning its index.
+fn push(root: &mut Value, key: &str, value: Value) -> usize {
+    let array = &mut root[key];
+    if !array.is_array() {
+        *array = Value::Array(Vec::new());
+    }
+    let array = array.as_array_mut().unwrap();
+    array.push(value);
+    array.len() - 1
+}
+
+/// Pads `bytes` to a multiple of 4 bytes, as GLB chunks and buffer views require.
+fn pad(bytes: &mut Vec<u8>, padding: u8) {
+    while bytes.len() % 4 != 0 {
+        bytes.push(padding);
+    }
+}
+
+fn write_glb(mut json: Vec<u8>, bin: Vec<u8>) -> Vec<u8> {
+    pad(&mut json, b' ');
+    let mut length = 12 + 8 + json.len();
+    if !bin.is_empty() {
+        length += 8 + bin.len();
+    }
+
+    let mut glb = Vec::with_capacity(length);
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&(length as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    if !bin.is_empty() {
+        glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"BIN\0");
+        glb.extend_from_slice(&bin);
+    }
+    glb
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A triangle with a normal map, whose data is stored in a base64 encoded buffer.
+    fn triangle_gltf() -> (Vec<u8>, Vec<u8>) {
+        let mut buffer = Vec::new();
+        let values: [f32; 24] = [
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, // positions
+            0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, // normals
+            0.0, 1.0, 1.0, 1.0, 0.0, 0.0, // texture coordinates
+        ];
+        for value in values.iter() {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+        let json = json!({
+            "asset": { "version": "2.0" },
+            "buffers": [{
+                "byteLength": buffer.len(),
+                "uri": format!("data:application/octet-stream;base64,{}", base64::encode(&buffer)),
+            }],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 72, "byteLength": 24 },
+            ],
+            "accessors": [
+                {
+                    "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0],
+                },
+                { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3" },
+                { "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC2" },
+            ],
+            "images": [{ "uri": "normal.png" }],
+            "textures": [{ "source": 0 }],
+            "materials": [{ "normalTexture": { "index": 0 } }],
+            "meshes": [{
+                "primitives": [{
+                    "attributes": { "POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2 },
+                    "material": 0,
+                }],
+            }],
+        });
+        (serde_json::to_vec(&json).unwrap(), buffer)
+    }
+
+    #[test]
+    fn embeds_buffers_and_tangents() {
+        let (json, buffer) = triangle_gltf();
+        let gltf = gltf::Gltf::from_slice(&json).unwrap();
+        let glb = pack_glb(&gltf, json_chunk(&json), &[buffer]).unwrap();
+
+        let processed = gltf::Gltf::from_slice(&glb).unwrap();
+        assert_eq!(processed.buffers().len(), 1);
+        assert!(matches!(
+            processed.buffers().next().unwrap().source(),
+            Source::Bin
+        ));
+        let blob = processed.blob.as_deref().unwrap();
+        let primitive = processed
+            .meshes()
+            .next()
+            .unwrap()
+            .primitives()
+            .next()
+            .unwrap();
+        let reader = primitive.reader(|_| Some(blob));
+        assert_eq!(
+            reader.read_positions().unwrap().nth(1),
+            Some([1.0, 0.0, 0.0])
+        );
+
+        let tangents = reader.read_tangents().unwrap().collect::<Vec<_>>();
+        assert_eq!(tangents.len(), 3);
+        for tangent in tangents {
+            assert!((tangent[0] - 1.0).abs() < 1e-5);
+            assert!(tangent[1].abs() < 1e-5 && tangent[2].abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn keeps_primitives_without_normal_maps() {
+        let (json, buffer) = triangle_gltf();
+        let mut root: Value = serde_json::from_slice(&json).unwrap();
+        root["materials"][0] = json!({});
+        let json = serde_json::to_vec(&root).unwrap();
+        let gltf = gltf::Gltf::from_slice(&json).unwrap();
+        let glb = pack_glb(&gltf, json_chunk(&json), &[buffer]).unwrap();
+
+        let processed = gltf::Gltf::from_slice(&glb).unwrap();
+        let primitive = processed
+            .meshes()
+            .next()
+            .unwrap()
+            .primitives()
+            .next()
+            .unwrap();
+        assert!(primitive.get(&Semantic::Tangents).is_none());
+        assert_eq!(json_chunk(&glb).len() % 4, 0);
+    }
+}
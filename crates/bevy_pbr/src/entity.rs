@@ -1,4 +1,8 @@
-use crate::{light::PointLight, material::StandardMaterial, render_graph::PBR_PIPELINE_HANDLE};
+use crate::{
+    light::{PointLight, SpotLight},
+    material::StandardMaterial,
+    render_graph::PBR_PIPELINE_HANDLE,
+};
 use bevy_asset::Handle;
 use bevy_ecs::bundle::Bundle;
 use bevy_render::{
@@ -47,3 +51,12 @@ pub struct PointLightBundle {
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 }
+
+/// A component bundle for spot light entities, which shine towards the forward direction of
+/// their transform
+#[derive(Debug, Bundle, Default)]
+pub struct SpotLightBundle {
+    pub spot_light: SpotLight,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+}
@@ -0,0 +1,554 @@
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::reflect::ReflectComponent;
+use bevy_math::Vec3;
+use bevy_reflect::Reflect;
+use bevy_render::{
+    color::Color,
+    texture::{Extent3d, FilterMode, SamplerDescriptor, Texture, TextureDimension, TextureFormat},
+};
+use std::{convert::TryInto, f32::consts::PI};
+use thiserror::Error;
+
+/// Image based lighting, which lights [`StandardMaterial`](crate::StandardMaterial) surfaces with
+/// the surroundings of the scene.
+///
+/// Both maps are cube maps, textures with six layers in the order +X, -X, +Y, -Y, +Z, -Z. The
+/// environment map of the active 3D camera is used if it has one, otherwise the one of any other
+/// entity. Maps can be generated from an equirectangular image, like an `.hdr` file, with
+/// [`EnvironmentMapLight::from_equirectangular`].
+#[derive(Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct EnvironmentMapLight {
+    /// The irradiance reaching surfaces facing each direction, divided by pi
+    pub diffuse_map: Handle<Texture>,
+    /// The radiance coming from each direction, prefiltered for increasing roughness in each of
+    /// its mip levels
+    pub specular_map: Handle<Texture>,
+    /// Scale applied to both maps
+    pub intensity: f32,
+}
+
+impl Default for EnvironmentMapLight {
+    fn default() -> Self {
+        EnvironmentMapLight {
+            diffuse_map: Default::default(),
+            specular_map: Default::default(),
+            intensity: 1.0,
+        }
+    }
+}
+
+impl EnvironmentMapLight {
+    /// The size of the faces of the diffuse map generated by [`Self::from_equirectangular`]
+    pub const DIFFUSE_MAP_SIZE: u32 = 32;
+    /// The size of the faces of the first mip level of the specular map generated by
+    /// [`Self::from_equirectangular`]
+    pub const SPECULAR_MAP_SIZE: u32 = 256;
+    /// The number of mip levels of the specular map generated by [`Self::from_equirectangular`],
+    /// the last one being used for fully rough surfaces
+    pub const SPECULAR_MAP_MIP_LEVELS: u32 = 6;
+
+    /// Generates the diffuse and specular maps of an equirectangular image of the surroundings
+    /// and adds them to the texture assets.
+    pub fn from_equirectangular(
+        texture: &Texture,
+        textures: &mut Assets<Texture>,
+    ) -> Result<Self, EnvironmentMapError> {
+        let diffuse_map = diffuse_irradiance_map(texture, Self::DIFFUSE_MAP_SIZE)?;
+        let specular_map = specular_prefiltered_map(
+            texture,
+            Self::SPECULAR_MAP_SIZE,
+            Self::SPECULAR_MAP_MIP_LEVELS,
+        )?;
+        Ok(EnvironmentMapLight {
+            diffuse_map: textures.add(diffuse_map),
+            specular_map: textures.add(specular_map),
+            ..Default::default()
+        })
+    }
+}
+
+/// An error that occurs when generating environment maps
+#[derive(Error, Debug)]
+pub enum EnvironmentMapError {
+    #[error(
+        "unsupported texture format {0:?}, expected Rgba32Float, Rgba8Unorm or Rgba8UnormSrgb"
+    )]
+    UnsupportedFormat(TextureFormat),
+    #[error("expected a 2D texture with a single layer")]
+    UnsupportedDimension,
+}
+
+/// Projects an equirectangular image onto the faces of a cube map with the given size.
+pub fn equirectangular_to_cubemap(
+    texture: &Texture,
+    size: u32,
+) -> Result<Texture, EnvironmentMapError> {
+    let image = EquirectangularImage::from_texture(texture)?;
+    Ok(cubemap_texture(size, 1, |_level, direction| {
+        image.sample(direction)
+    }))
+}
+
+/// Generates the diffuse map of an [`EnvironmentMapLight`] from an equirectangular image, by
+/// integrating the light reaching surfaces facing each texel of a cube map with the given size.
+pub fn diffuse_irradiance_map(
+    texture: &Texture,
+    size: u32,
+) -> Result<Texture, EnvironmentMapError> {
+    // irradiance varies slowly, so a small image of the surroundings is enough
+    let mut image = EquirectangularImage::from_texture(texture)?;
+    while image.height > 32 {
+        image = image.downsample();
+    }
+
+    let mut samples = Vec::with_capacity(image.pixels.len());
+    for y in 0..image.height {
+        let polar = (y as f32 + 0.5) / image.height as f32 * PI;
+        let solid_angle =
+            (2.0 * PI / image.width as f32) * (PI / image.height as f32) * polar.sin();
+        for x in 0..image.width {
+            let direction = image.texel_direction(x, y);
+            samples.push((direction, image.pixel(x, y) * solid_angle));
+        }
+    }
+
+    Ok(cubemap_texture(size, 1, |_level, normal| {
+        let irradiance = samples
+            .iter()
+            .fold(Vec3::ZERO, |sum, (direction, radiance)| {
+                sum + *radiance * normal.dot(*direction).max(0.0)
+            });
+        irradiance / PI
+    }))
+}
+
+/// Generates the specular map of an [`EnvironmentMapLight`] from an equirectangular image.
+///
+/// The first mip level holds the radiance of the surroundings, and the following levels are
+/// prefiltered with the GGX distribution for a perceptual roughness increasing up to 1.0 in the
+/// last level.
+pub fn specular_prefiltered_map(
+    texture: &Texture,
+    size: u32,
+    mip_level_count: u32,
+) -> Result<Texture, EnvironmentMapError> {
+    const SAMPLE_COUNT: u32 = 64;
+    let mip_level_count = mip_level_count.clamp(1, 32 - size.max(1).leading_zeros());
+
+    // each level samples a downsampled image close to its resolution, to avoid aliasing
+    let mut images = vec![EquirectangularImage::from_texture(texture)?];
+    while images.last().unwrap().height > 1 {
+        let next = images.last().unwrap().downsample();
+        images.push(next);
+    }
+
+    Ok(cubemap_texture(size, mip_level_count, |level, normal| {
+        let level_size = (size >> level).max(1);
+        let image = images
+            .iter()
+            .rev()
+            .find(|image| image.height >= 2 * level_size)
+            .unwrap_or(&images[0]);
+        if level == 0 {
+            return image.sample(normal);
+        }
+        let perceptual_roughness = level as f32 / (mip_level_count - 1) as f32;
+        let roughness = perceptual_roughness * perceptual_roughness;
+        let (tangent, bitangent) = orthonormal_basis(normal);
+
+        let mut radiance = Vec3::ZERO;
+        let mut total_weight = 0.0;
+        for i in 0..SAMPLE_COUNT {
+            let (u, v) = hammersley(i, SAMPLE_COUNT);
+            let half_vector = ggx_half_vector(u, v, roughness);
+            let half_vector =
+                tangent * half_vector.x + bitangent * half_vector.y + normal * half_vector.z;
+            // the view direction is assumed to be the normal
+            let light = half_vector * 2.0 * normal.dot(half_vector) - normal;
+            let n_dot_l = normal.dot(light);
+            if n_dot_l > 0.0 {
+                radiance += image.sample(light) * n_dot_l;
+                total_weight += n_dot_l;
+            }
+        }
+        radiance / total_weight.max(f32::EPSILON)
+    }))
+}
+
+/// An equirectangular image in linear colors. The center of the image is in the -Z direction,
+/// and the top row is in the +Y direction.
+struct EquirectangularImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vec3>,
+}
+
+impl EquirectangularImage {
+    fn from_texture(texture: &Texture) -> Result<Self, EnvironmentMapError> {
+        if texture.dimension != TextureDimension::D2 || texture.size.depth_or_array_layers != 1 {
+            return Err(EnvironmentMapError::UnsupportedDimension);
+        }
+        let data = texture.mip_level_data(0);
+        let pixels = match texture.format {
+            TextureFormat::Rgba32Float => data
+                .chunks_exact(16)
+                .map(|pixel| {
+                    let channel =
+                        |i: usize| f32::from_ne_bytes(pixel[i * 4..i * 4 + 4].try_into().unwrap());
+                    Vec3::new(channel(0), channel(1), channel(2))
+                })
+                .collect(),
+            TextureFormat::Rgba8UnormSrgb => data
+                .chunks_exact(4)
+                .map(|pixel| {
+                    let [r, g, b, _] =
+                        Color::rgb_u8(pixel[0], pixel[1], pixel[2]).as_linear_rgba_f32();
+                    Vec3::new(r, g, b)
+                })
+                .collect(),
+            TextureFormat::Rgba8Unorm => data
+                .chunks_exact(4)
+                .map(|pixel| Vec3::new(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32) / 255.0)
+                .collect(),
+            format => return Err(EnvironmentMapError::UnsupportedFormat(format)),
+        };
+        Ok(EquirectangularImage {
+            width: texture.size.width,
+            height: texture.size.height,
+            pixels,
+        })
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> Vec3 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Averages each 2x2 block of pixels.
+    fn downsample(&self) -> Self {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let x0 = (2 * x).min(self.width - 1);
+                let x1 = (2 * x + 1).min(self.width - 1);
+                let y0 = (2 * y).min(self.height - 1);
+                let y1 = (2 * y + 1).min(self.height - 1);
+                pixels.push(
+                    (self.pixel(x0, y0)
+                        + self.pixel(x1, y0)
+                        + self.pixel(x0, y1)
+                        + self.pixel(x1, y1))
+                        / 4.0,
+                );
+            }
+        }
+        EquirectangularImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Returns the direction towards the center of a texel.
+    fn texel_direction(&self, x: u32, y: u32) -> Vec3 {
+        let azimuth = ((x as f32 + 0.5) / self.width as f32 - 0.5) * 2.0 * PI;
+        let polar = (y as f32 + 0.5) / self.height as f32 * PI;
+        Vec3::new(
+            polar.sin() * azimuth.sin(),
+            polar.cos(),
+            -polar.sin() * azimuth.cos(),
+        )
+    }
+
+    /// Samples the image in a direction with bilinear filtering.
+    fn sample(&self, direction: Vec3) -> Vec3 {
+        let direction = direction.normalize();
+        let azimuth = direction.x.atan2(-direction.z);
+        let polar = direction.y.clamp(-1.0, 1.0).acos();
+        let x = (azimuth / (2.0 * PI) + 0.5) * self.width as f32 - 0.5;
+        let y = (polar / PI * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        // wrap around horizontally, clamp vertically
+        let column = |x: f32| (x as i64).rem_euclid(self.width as i64) as u32;
+        let row = |y: f32| (y as u32).min(self.height - 1);
+        let top = self
+            .pixel(column(x0), row(y0))
+            .lerp(self.pixel(column(x0 + 1.0), row(y0)), tx);
+        let bottom = self
+            .pixel(column(x0), row(y0 + 1.0))
+            .lerp(self.pixel(column(x0 + 1.0), row(y0 + 1.0)), tx);
+        top.lerp(bottom, ty)
+    }
+}
+
+/// Returns the direction towards a point on a face of a cube map, with `u` and `v` going from
+/// 0.0 to 1.0 across the face.
+pub(crate) fn cube_face_direction(face: u32, u: f32, v: f32) -> Vec3 {
+    let a = 2.0 * u - 1.0;
+    let b = 2.0 * v - 1.0;
+    let direction = match face {
+        0 => Vec3::new(1.0, -b, -a),
+        1 => Vec3::new(-1.0, -b, a),
+        2 => Vec3::new(a, 1.0, b),
+        3 => Vec3::new(a, -1.0, -b),
+        4 => Vec3::new(a, -b, 1.0),
+        _ => Vec3::new(-a, -b, -1.0),
+    };
+    direction.normalize()
+}
+
+/// Creates an Rgba16Float cube map texture, evaluating the color of each texel of each mip level
+/// in the direction of its center.
+fn cubemap_texture(size: u32, mip_level_count: u32, color: impl Fn(u32, Vec3) -> Vec3) -> Texture {
+    let mut data = Vec::new();
+    for level in 0..mip_level_count {
+        let level_size = (size >> level).max(1);
+        for face in 0..6 {
+            for y in 0..level_size {
+                for x in 0..level_size {
+                    let direction = cube_face_direction(
+                        face,
+                        (x as f32 + 0.5) / level_size as f32,
+                        (y as f32 + 0.5) / level_size as f32,
+                    );
+                    let color = color(level, direction);
+                    for channel in [color.x, color.y, color.z, 1.0].iter() {
+                        data.extend_from_slice(&f32_to_f16(*channel).to_le_bytes());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut texture = Texture::new_with_mips(
+        Extent3d::new(size, size, 6),
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba16Float,
+        mip_level_count,
+    );
+    texture.sampler = SamplerDescriptor {
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        mipmap_filter: FilterMode::Linear,
+        ..Default::default()
+    };
+    texture
+}
+
+/// Converts a float to the bits of a half precision float, rounding to the nearest value.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    if exponent == 0xff {
+        // infinity or NaN
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        // too large, rounded to infinity
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        // too small for a normal half float
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let round = (mantissa >> (shift - 1)) & 1;
+        sign | ((mantissa >> shift) + round) as u16
+    } else {
+        // rounding up can carry into the exponent, which is still the nearest value
+        let round = (mantissa >> 12) & 1;
+        sign | ((((exponent as u32) << 10) | (mantissa >> 13)) + round) as u16
+    }
+}
+
+/// Returns the `i`th point of a Hammersley sequence of `count` points.
+fn hammersley(i: u32, count: u32) -> (f32, f32) {
+    (
+        i as f32 / count as f32,
+        i.reverse_bits() as f32 / 4_294_967_296.0,
+    )
+}
+
+/// Returns a half vector around +Z distributed with the GGX distribution, from two uniformly
+/// distributed numbers.
+fn ggx_half_vector(u: f32, v: f32, roughness: f32) -> Vec3 {
+    let a2 = roughness * roughness;
+    let phi = 2.0 * PI * u;
+    let cos_theta = ((1.0 - v) / (1.0 + (a2 - 1.0) * v)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+fn orthonormal_basis(normal: Vec3) -> (Vec3, Vec3) {
+    let up = if normal.y.abs() < 0.999 {
+        Vec3::Y
+    } else {
+        Vec3::X
+    };
+    let tangent = up.cross(normal).normalize();
+    (tangent, normal.cross(tangent))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// An equirectangular image that is bright in the directions with a positive `x`.
+    fn half_lit_texture(width: u32, height: u32) -> Texture {
+        let image = EquirectangularImage {
+            width,
+            height,
+            pixels: Vec::new(),
+        };
+        let mut data = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let value: f32 = if image.texel_direction(x, y).x > 0.0 {
+                    4.0
+                } else {
+                    0.0
+                };
+                for channel in [value, value, value, 1.0].iter() {
+                    data.extend_from_slice(&channel.to_ne_bytes());
+                }
+            }
+        }
+        Texture::new(
+            Extent3d::new(width, height, 1),
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba32Float,
+        )
+    }
+
+    fn uniform_texture(value: f32) -> Texture {
+        let mut pixel = Vec::new();
+        for channel in [value, value, value, 1.0].iter() {
+            pixel.extend_from_slice(&channel.to_ne_bytes());
+        }
+        Texture::new_fill(
+            Extent3d::new(64, 32, 1),
+            TextureDimension::D2,
+            &pixel,
+            TextureFormat::Rgba32Float,
+        )
+    }
+
+    fn texel(texture: &Texture, level: u32, face: u32, x: u32, y: u32) -> f32 {
+        let size = texture.mip_level_size(level).width;
+        let offset = ((face * size + y) * size + x) as usize * 8;
+        let bits = &texture.mip_level_data(level)[offset..offset + 2];
+        half_to_f32(u16::from_le_bytes([bits[0], bits[1]]))
+    }
+
+    fn half_to_f32(half: u16) -> f32 {
+        let exponent = ((half >> 10) & 0x1f) as i32;
+        let mantissa = (half & 0x3ff) as f32 / 1024.0;
+        let value = if exponent == 0 {
+            mantissa * 2f32.powi(-14)
+        } else {
+            (1.0 + mantissa) * 2f32.powi(exponent - 15)
+        };
+        if half & 0x8000 != 0 {
+            -value
+        } else {
+            value
+        }
+    }
+
+    #[test]
+    fn converts_to_half_floats() {
+        assert_eq!(f32_to_f16(0.0), 0);
+        assert_eq!(f32_to_f16(1.0), 0x3c00);
+        assert_eq!(f32_to_f16(-2.0), 0xc000);
+        assert_eq!(f32_to_f16(0.5), 0x3800);
+        assert_eq!(f32_to_f16(65504.0), 0x7bff);
+        assert_eq!(f32_to_f16(1e6), 0x7c00);
+        assert_eq!(f32_to_f16(f32::INFINITY), 0x7c00);
+        // the smallest subnormal half float
+        assert_eq!(f32_to_f16(2f32.powi(-24)), 1);
+        assert!((half_to_f32(f32_to_f16(0.1)) - 0.1).abs() < 1e-4);
+    }
+
+    #[test]
+    fn cube_faces_point_along_axes() {
+        let directions = [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        for (face, direction) in directions.iter().enumerate() {
+            assert!((cube_face_direction(face as u32, 0.5, 0.5) - *direction).length() < 1e-5);
+        }
+        // the top of the side faces is towards +Y
+        assert!(cube_face_direction(0, 0.5, 0.0).y > 0.0);
+        assert!(cube_face_direction(5, 0.5, 0.0).y > 0.0);
+    }
+
+    #[test]
+    fn equirectangular_directions_round_trip() {
+        let texture = half_lit_texture(16, 8);
+        let image = EquirectangularImage::from_texture(&texture).unwrap();
+        for (x, y) in [(0, 0), (3, 2), (8, 4), (15, 7)].iter() {
+            let direction = image.texel_direction(*x, *y);
+            assert!((image.sample(direction) - image.pixel(*x, *y)).length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn cubemap_faces_follow_the_environment() {
+        let cubemap = equirectangular_to_cubemap(&half_lit_texture(64, 32), 8).unwrap();
+        assert_eq!(cubemap.size, Extent3d::new(8, 8, 6));
+        assert_eq!(cubemap.format, TextureFormat::Rgba16Float);
+        assert!((texel(&cubemap, 0, 0, 4, 4) - 4.0).abs() < 1e-3);
+        assert!(texel(&cubemap, 0, 1, 4, 4).abs() < 1e-3);
+    }
+
+    #[test]
+    fn uniform_environment_gives_uniform_maps() {
+        let texture = uniform_texture(2.0);
+        let diffuse = diffuse_irradiance_map(&texture, 4).unwrap();
+        let specular = specular_prefiltered_map(&texture, 8, 4).unwrap();
+        assert_eq!(specular.mip_level_count, 4);
+        for face in 0..6 {
+            assert!((texel(&diffuse, 0, face, 1, 2) - 2.0).abs() < 0.05);
+            for level in 0..4 {
+                assert!((texel(&specular, level, face, 0, 0) - 2.0).abs() < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn diffuse_map_blurs_the_environment() {
+        // an odd size has a texel at the center of each face
+        let diffuse = diffuse_irradiance_map(&half_lit_texture(64, 32), 5).unwrap();
+        let lit = texel(&diffuse, 0, 0, 2, 2);
+        let unlit = texel(&diffuse, 0, 1, 2, 2);
+        let side = texel(&diffuse, 0, 4, 2, 2);
+        assert!(lit > side && side > unlit);
+        // surfaces facing +Z see half of the lit hemisphere
+        assert!((side - 2.0).abs() < 0.3);
+    }
+
+    #[test]
+    fn rejects_unsupported_textures() {
+        let texture = Texture::new_fill(
+            Extent3d::new(4, 2, 1),
+            TextureDimension::D2,
+            &[0, 0],
+            TextureFormat::Rg8Unorm,
+        );
+        assert!(matches!(
+            diffuse_irradiance_map(&texture, 4),
+            Err(EnvironmentMapError::UnsupportedFormat(
+                TextureFormat::Rg8Unorm
+            ))
+        ));
+    }
+}
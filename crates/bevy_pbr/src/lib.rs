@@ -1,10 +1,12 @@
 pub mod render_graph;
 
 mod entity;
+mod environment_map;
 mod light;
 mod material;
 
 pub use entity::*;
+pub use environment_map::*;
 pub use light::*;
 pub use material::*;
 
@@ -12,16 +14,17 @@ pub mod prelude {
     #[doc(hidden)]
     pub use crate::{
         entity::*,
-        light::{DirectionalLight, PointLight},
+        environment_map::EnvironmentMapLight,
+        light::{DirectionalLight, NotShadowCaster, NotShadowReceiver, PointLight, SpotLight},
         material::StandardMaterial,
     };
 }
 
 use bevy_app::prelude::*;
 use bevy_asset::{AddAsset, Assets, Handle};
-use bevy_render::{prelude::Color, shader};
+use bevy_render::{pipeline::InstancedPipelines, prelude::Color, shader};
 use material::StandardMaterial;
-use render_graph::add_pbr_graph;
+use render_graph::{add_pbr_graph, PBR_PIPELINE_HANDLE};
 
 /// NOTE: this isn't PBR yet. consider this name "aspirational" :)
 #[derive(Default)]
@@ -31,12 +34,25 @@ impl Plugin for PbrPlugin {
     fn build(&self, app: &mut App) {
         app.add_asset::<StandardMaterial>()
             .register_type::<PointLight>()
+            .register_type::<DirectionalLight>()
+            .register_type::<SpotLight>()
+            .register_type::<EnvironmentMapLight>()
+            .register_type::<NotShadowCaster>()
+            .register_type::<NotShadowReceiver>()
             .add_system_to_stage(
                 CoreStage::PostUpdate,
                 shader::asset_shader_defs_system::<StandardMaterial>,
             )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                shader::shader_defs_system::<NotShadowReceiver>,
+            )
             .init_resource::<AmbientLight>();
         add_pbr_graph(&mut app.world);
+        app.world
+            .get_resource_mut::<InstancedPipelines>()
+            .unwrap()
+            .add(PBR_PIPELINE_HANDLE.typed());
 
         // add default StandardMaterial
         let mut materials = app
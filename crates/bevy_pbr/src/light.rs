@@ -1,11 +1,20 @@
+use crate::render_graph::{MAX_SHADOW_CASCADES, SHADOW_ATLAS_SIZE, SHADOW_ATLAS_TILES_PER_ROW};
 use bevy_core::{Pod, Zeroable};
 use bevy_ecs::reflect::ReflectComponent;
-use bevy_math::Vec3;
+use bevy_math::{Mat4, Vec3};
 use bevy_reflect::Reflect;
-use bevy_render::color::Color;
+use bevy_render::{
+    color::Color,
+    shader::{ShaderDefIterator, ShaderDefs},
+};
 use bevy_transform::components::GlobalTransform;
 
 /// A point light
+///
+/// When `shadows_enabled` is set, the scene is rendered into six tiles of the shadow atlas, one
+/// for each face of a cube around the light. The faces are 2D atlas tiles rather than a cube map,
+/// so all shadow casting lights share a single depth texture and sampler binding without needing
+/// cube map arrays, which not every backend supports.
 #[derive(Debug, Clone, Copy, Reflect)]
 #[reflect(Component)]
 pub struct PointLight {
@@ -13,6 +22,18 @@ pub struct PointLight {
     pub intensity: f32,
     pub range: f32,
     pub radius: f32,
+    pub shadows_enabled: bool,
+    /// Distance in world units that surfaces are moved towards the light before being tested
+    /// against the shadow map, to avoid shadow acne
+    pub shadow_depth_bias: f32,
+    /// Distance in world units that surfaces are moved along their normal before being tested
+    /// against the shadow map, to avoid shadow acne on surfaces at grazing angles
+    pub shadow_normal_bias: f32,
+}
+
+impl PointLight {
+    pub const DEFAULT_SHADOW_DEPTH_BIAS: f32 = 0.02;
+    pub const DEFAULT_SHADOW_NORMAL_BIAS: f32 = 0.05;
 }
 
 impl Default for PointLight {
@@ -22,6 +43,9 @@ impl Default for PointLight {
             intensity: 200.0,
             range: 20.0,
             radius: 0.0,
+            shadows_enabled: false,
+            shadow_depth_bias: Self::DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_normal_bias: Self::DEFAULT_SHADOW_NORMAL_BIAS,
         }
     }
 }
@@ -33,24 +57,211 @@ pub(crate) struct PointLightUniform {
     pub color: [f32; 4],
     // storing as a `[f32; 4]` for memory alignement
     pub light_params: [f32; 4],
+    // x = first shadow atlas tile, or -1 without shadows
+    // y = depth bias, z = normal bias
+    pub shadow_params: [f32; 4],
+    // one per cube face, in the order +X, -X, +Y, -Y, +Z, -Z
+    pub shadow_view_projections: [Mat4; 6],
 }
 
 impl PointLightUniform {
-    pub fn new(light: &PointLight, global_transform: &GlobalTransform) -> PointLightUniform {
+    pub fn new(
+        light: &PointLight,
+        global_transform: &GlobalTransform,
+        shadow_tile: Option<u32>,
+    ) -> PointLightUniform {
         let (x, y, z) = global_transform.translation.into();
 
         // premultiply color by intensity
         // we don't use the alpha at all, so no reason to multiply only [0..3]
         let color: [f32; 4] = (light.color * light.intensity).into();
 
+        let (shadow_tile, shadow_view_projections) = match shadow_tile {
+            Some(tile) => (
+                tile as f32,
+                point_light_view_projections(global_transform.translation, light.range),
+            ),
+            None => (-1.0, [Mat4::IDENTITY; 6]),
+        };
+
         PointLightUniform {
             pos: [x, y, z, 1.0],
             color,
             light_params: [1.0 / (light.range * light.range), light.radius, 0.0, 0.0],
+            shadow_params: [
+                shadow_tile,
+                light.shadow_depth_bias,
+                light.shadow_normal_bias,
+                0.0,
+            ],
+            shadow_view_projections,
+        }
+    }
+}
+
+/// Returns the view projections of the six cube faces a point light renders its shadows to, in
+/// the order +X, -X, +Y, -Y, +Z, -Z.
+pub(crate) fn point_light_view_projections(position: Vec3, range: f32) -> [Mat4; 6] {
+    const NEAR: f32 = 0.1;
+    let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, NEAR, range.max(NEAR));
+    let face = |direction: Vec3, up: Vec3| {
+        projection * Mat4::look_at_rh(position, position + direction, up)
+    };
+    [
+        face(Vec3::X, -Vec3::Y),
+        face(-Vec3::X, -Vec3::Y),
+        face(Vec3::Y, Vec3::Z),
+        face(-Vec3::Y, -Vec3::Z),
+        face(Vec3::Z, -Vec3::Y),
+        face(-Vec3::Z, -Vec3::Y),
+    ]
+}
+
+/// A spot light, which emits light in a cone towards the forward direction (-Z) of its
+/// transform.
+///
+/// `intensity` is the luminous power in lumens, as for a [`PointLight`]. The cone only masks the
+/// light, so narrowing it doesn't make the light brighter. The light is at full intensity inside
+/// of `inner_angle` and fades out until `outer_angle`, both measured from the center of the cone
+/// in radians.
+///
+/// When `shadows_enabled` is set, the scene is rendered into a shadow map covering the cone.
+#[derive(Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct SpotLight {
+    pub color: Color,
+    pub intensity: f32,
+    pub range: f32,
+    pub radius: f32,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub shadows_enabled: bool,
+    /// Distance in world units that surfaces are moved towards the light before being tested
+    /// against the shadow map, to avoid shadow acne
+    pub shadow_depth_bias: f32,
+    /// Distance in world units that surfaces are moved along their normal before being tested
+    /// against the shadow map, to avoid shadow acne on surfaces at grazing angles
+    pub shadow_normal_bias: f32,
+}
+
+impl SpotLight {
+    pub const DEFAULT_SHADOW_DEPTH_BIAS: f32 = 0.02;
+    pub const DEFAULT_SHADOW_NORMAL_BIAS: f32 = 0.05;
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        SpotLight {
+            color: Color::rgb(1.0, 1.0, 1.0),
+            intensity: 200.0,
+            range: 20.0,
+            radius: 0.0,
+            inner_angle: 0.0,
+            outer_angle: std::f32::consts::FRAC_PI_4,
+            shadows_enabled: false,
+            shadow_depth_bias: Self::DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_normal_bias: Self::DEFAULT_SHADOW_NORMAL_BIAS,
         }
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub(crate) struct SpotLightUniform {
+    pub pos: [f32; 4],
+    pub color: [f32; 4],
+    // the direction of the cone, towards the lit surfaces
+    pub dir: [f32; 4],
+    // x = 1 / range^2, y = radius, z = angular attenuation scale, w = angular attenuation offset
+    pub light_params: [f32; 4],
+    // x = shadow atlas tile, or -1 without shadows
+    // y = depth bias, z = normal bias
+    pub shadow_params: [f32; 4],
+    pub shadow_view_projection: Mat4,
+}
+
+impl SpotLightUniform {
+    pub fn new(
+        light: &SpotLight,
+        global_transform: &GlobalTransform,
+        shadow_tile: Option<u32>,
+    ) -> SpotLightUniform {
+        let (x, y, z) = global_transform.translation.into();
+        let direction = global_transform.forward();
+
+        // premultiply color by intensity
+        // we don't use the alpha at all, so no reason to multiply only [0..3]
+        let color: [f32; 4] = (light.color * light.intensity).into();
+
+        // the angular attenuation is `saturate(cos(angle) * scale + offset)`, which goes from 0 at
+        // the outer angle to 1 at the inner angle
+        // see https://google.github.io/filament/Filament.html#listing_glslpunctuallight
+        let outer_angle = light.outer_angle.clamp(0.0, SPOT_LIGHT_MAX_ANGLE);
+        let inner_angle = light.inner_angle.clamp(0.0, outer_angle);
+        let cos_outer = outer_angle.cos();
+        let scale = 1.0 / (inner_angle.cos() - cos_outer).max(1e-4);
+        let offset = -cos_outer * scale;
+
+        let (shadow_tile, shadow_view_projection) = match shadow_tile {
+            Some(tile) => (
+                tile as f32,
+                spot_light_view_projection(
+                    global_transform.translation,
+                    direction,
+                    outer_angle,
+                    light.range,
+                ),
+            ),
+            None => (-1.0, Mat4::IDENTITY),
+        };
+
+        SpotLightUniform {
+            pos: [x, y, z, 1.0],
+            color,
+            dir: [direction.x, direction.y, direction.z, 0.0],
+            light_params: [
+                1.0 / (light.range * light.range),
+                light.radius,
+                scale,
+                offset,
+            ],
+            shadow_params: [
+                shadow_tile,
+                light.shadow_depth_bias,
+                light.shadow_normal_bias,
+                0.0,
+            ],
+            shadow_view_projection,
+        }
+    }
+}
+
+/// The widest cone of a spot light, just under a half sphere so that its shadow map can still
+/// be rendered with a perspective projection
+const SPOT_LIGHT_MAX_ANGLE: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+/// Returns the view projection of the shadow map of a spot light, which covers its cone.
+pub(crate) fn spot_light_view_projection(
+    position: Vec3,
+    direction: Vec3,
+    outer_angle: f32,
+    range: f32,
+) -> Mat4 {
+    const NEAR: f32 = 0.1;
+    let projection = Mat4::perspective_rh(
+        2.0 * outer_angle.clamp(0.01, SPOT_LIGHT_MAX_ANGLE),
+        1.0,
+        NEAR,
+        range.max(NEAR),
+    );
+    let up = if direction.y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    };
+    projection * Mat4::look_at_rh(position, position + direction, up)
+}
+
 /// A Directional light.
 ///
 /// Directional lights don't exist in reality but they are a good
@@ -77,21 +288,39 @@ impl PointLightUniform {
 /// | 32,000–100,000    | Direct sunlight                                |
 ///
 /// Source: [Wikipedia](https://en.wikipedia.org/wiki/Lux)
+///
+/// When `shadows_enabled` is set, the view frustum of the 3D camera is split into
+/// `shadow_cascade_count` cascades up to `shadow_maximum_distance`, each with its own shadow map.
 #[derive(Debug, Clone, Copy, Reflect)]
 #[reflect(Component)]
 pub struct DirectionalLight {
     pub color: Color,
     pub illuminance: f32,
     direction: Vec3,
+    pub shadows_enabled: bool,
+    /// Distance in world units that surfaces are moved towards the light before being tested
+    /// against the shadow map, to avoid shadow acne
+    pub shadow_depth_bias: f32,
+    /// Distance in world units that surfaces are moved along their normal before being tested
+    /// against the shadow map, to avoid shadow acne on surfaces at grazing angles
+    pub shadow_normal_bias: f32,
+    /// The number of shadow cascades, at most [`MAX_SHADOW_CASCADES`]
+    pub shadow_cascade_count: usize,
+    /// The distance from the camera after which surfaces don't receive shadows anymore
+    pub shadow_maximum_distance: f32,
 }
 
 impl DirectionalLight {
+    pub const DEFAULT_SHADOW_DEPTH_BIAS: f32 = 0.02;
+    pub const DEFAULT_SHADOW_NORMAL_BIAS: f32 = 0.05;
+
     /// Create a new directional light component.
     pub fn new(color: Color, illuminance: f32, direction: Vec3) -> Self {
         DirectionalLight {
             color,
             illuminance,
             direction: direction.normalize(),
+            ..Default::default()
         }
     }
 
@@ -111,6 +340,11 @@ impl Default for DirectionalLight {
             color: Color::rgb(1.0, 1.0, 1.0),
             illuminance: 100000.0,
             direction: Vec3::new(0.0, -1.0, 0.0),
+            shadows_enabled: false,
+            shadow_depth_bias: Self::DEFAULT_SHADOW_DEPTH_BIAS,
+            shadow_normal_bias: Self::DEFAULT_SHADOW_NORMAL_BIAS,
+            shadow_cascade_count: MAX_SHADOW_CASCADES,
+            shadow_maximum_distance: 100.0,
         }
     }
 }
@@ -120,6 +354,12 @@ impl Default for DirectionalLight {
 pub(crate) struct DirectionalLightUniform {
     pub dir: [f32; 4],
     pub color: [f32; 4],
+    // x = first shadow atlas tile, or -1 without shadows
+    // y = depth bias, z = normal bias, w = cascade count
+    pub shadow_params: [f32; 4],
+    // the view depth at which each cascade ends
+    pub cascade_splits: [f32; MAX_SHADOW_CASCADES],
+    pub cascade_view_projections: [Mat4; MAX_SHADOW_CASCADES],
 }
 
 impl DirectionalLightUniform {
@@ -148,7 +388,167 @@ impl DirectionalLightUniform {
         // we don't use the alpha at all, so no reason to multiply only [0..3]
         let color: [f32; 4] = (light.color * intensity).into();
 
-        DirectionalLightUniform { dir, color }
+        DirectionalLightUniform {
+            dir,
+            color,
+            shadow_params: [-1.0, light.shadow_depth_bias, light.shadow_normal_bias, 0.0],
+            cascade_splits: [0.0; MAX_SHADOW_CASCADES],
+            cascade_view_projections: [Mat4::IDENTITY; MAX_SHADOW_CASCADES],
+        }
+    }
+
+    /// Enables the shadows of the light, rendered into the shadow atlas tiles starting at
+    /// `first_tile`.
+    pub fn set_cascades(&mut self, first_tile: u32, cascades: &[(f32, Mat4)]) {
+        self.shadow_params[0] = first_tile as f32;
+        self.shadow_params[3] = cascades.len() as f32;
+        for (i, (split, view_projection)) in cascades.iter().enumerate() {
+            self.cascade_splits[i] = *split;
+            self.cascade_view_projections[i] = *view_projection;
+        }
+    }
+}
+
+/// Returns the view depth at which each of the `count` cascades between `near` and `far` ends.
+///
+/// Splits are a blend of a logarithmic and a uniform distribution, so that close cascades get
+/// more shadow map resolution.
+pub(crate) fn cascade_splits(near: f32, far: f32, count: usize) -> Vec<f32> {
+    const LOGARITHMIC_WEIGHT: f32 = 0.75;
+    (1..=count)
+        .map(|i| {
+            let t = i as f32 / count as f32;
+            let logarithmic = near * (far / near).powf(t);
+            let uniform = near + (far - near) * t;
+            LOGARITHMIC_WEIGHT * logarithmic + (1.0 - LOGARITHMIC_WEIGHT) * uniform
+        })
+        .collect()
+}
+
+/// Returns the world space corners of the slice of a camera's view frustum between two view
+/// depths.
+pub(crate) fn frustum_slice_corners(
+    projection: &Mat4,
+    camera_transform: &Mat4,
+    near: f32,
+    far: f32,
+) -> [Vec3; 8] {
+    let inverse_projection = projection.inverse();
+    let mut corners = [Vec3::ZERO; 8];
+    for (i, (x, y)) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+        .iter()
+        .enumerate()
+    {
+        let near_corner = inverse_projection.project_point3(Vec3::new(*x, *y, 0.0));
+        let far_corner = inverse_projection.project_point3(Vec3::new(*x, *y, 1.0));
+        // both corners are on the same ray from the camera, so points at any depth are on the
+        // line between them
+        let at_depth = |depth: f32| {
+            let t = (depth + near_corner.z) / (near_corner.z - far_corner.z);
+            camera_transform.transform_point3(near_corner + (far_corner - near_corner) * t)
+        };
+        corners[i] = at_depth(near);
+        corners[i + 4] = at_depth(far);
+    }
+    corners
+}
+
+/// Returns the orthographic view projection of a directional light that covers the given
+/// corners of a cascade, with room for shadow casters up to `caster_distance` towards the light.
+pub(crate) fn cascade_view_projection(
+    direction: Vec3,
+    corners: &[Vec3; 8],
+    caster_distance: f32,
+    tile_size: u32,
+) -> Mat4 {
+    let center = corners.iter().fold(Vec3::ZERO, |sum, corner| sum + *corner) / 8.0;
+    // a bounding sphere keeps the size of the projection constant when the camera rotates
+    let radius = corners
+        .iter()
+        .map(|corner| corner.distance(center))
+        .fold(0.0, f32::max);
+    let up = if direction.y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    };
+    let view = Mat4::look_at_rh(Vec3::ZERO, direction, up);
+
+    // move the projection in whole texels, so that shadow edges don't shimmer when the camera
+    // moves. the projection is one texel larger on each side to still cover the corners
+    let texel_size = 2.0 * radius / (tile_size - 2) as f32;
+    let extent = radius + texel_size;
+    let mut center = view.transform_point3(center);
+    center.x = (center.x / texel_size).floor() * texel_size;
+    center.y = (center.y / texel_size).floor() * texel_size;
+
+    let projection = Mat4::orthographic_rh(
+        center.x - extent,
+        center.x + extent,
+        center.y - extent,
+        center.y + extent,
+        -center.z - radius - caster_distance,
+        -center.z + radius,
+    );
+    projection * view
+}
+
+/// Returns the view depth at which each shadow cascade of a directional light ends, and the view
+/// projection of the cascade, for a camera with the given projection and transform.
+pub(crate) fn directional_light_cascades(
+    light: &DirectionalLight,
+    projection: &Mat4,
+    camera_transform: &Mat4,
+) -> Vec<(f32, Mat4)> {
+    let inverse_projection = projection.inverse();
+    // orthographic projections can start at the camera, where logarithmic splits are undefined
+    let near = (-inverse_projection.project_point3(Vec3::ZERO).z).max(0.1);
+    let far = (-inverse_projection.project_point3(Vec3::Z).z).min(light.shadow_maximum_distance);
+    let count = light.shadow_cascade_count.clamp(1, MAX_SHADOW_CASCADES);
+    let tile_size = SHADOW_ATLAS_SIZE / SHADOW_ATLAS_TILES_PER_ROW;
+
+    let mut cascade_near = near;
+    cascade_splits(near, far, count)
+        .into_iter()
+        .map(|cascade_far| {
+            let corners =
+                frustum_slice_corners(projection, camera_transform, cascade_near, cascade_far);
+            cascade_near = cascade_far;
+            let view_projection = cascade_view_projection(
+                light.direction,
+                &corners,
+                light.shadow_maximum_distance,
+                tile_size,
+            );
+            (cascade_far, view_projection)
+        })
+        .collect()
+}
+
+/// Entities with this component don't cast shadows.
+#[derive(Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct NotShadowCaster;
+
+/// Entities with this component don't receive shadows.
+#[derive(Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct NotShadowReceiver;
+
+impl ShaderDefs for NotShadowReceiver {
+    fn shader_defs_len(&self) -> usize {
+        1
+    }
+
+    fn get_shader_def(&self, index: usize) -> Option<&str> {
+        match index {
+            0 => Some("NOT_SHADOW_RECEIVER"),
+            _ => None,
+        }
+    }
+
+    fn iter_shader_defs(&self) -> ShaderDefIterator {
+        ShaderDefIterator::new(self)
     }
 }
 
@@ -168,3 +568,115 @@ impl Default for AmbientLight {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy_math::Quat;
+
+    fn assert_in_clip_space(view_projection: &Mat4, point: Vec3) {
+        let ndc = view_projection.project_point3(point);
+        assert!(
+            ndc.x.abs() <= 1.0 + 1e-4 && ndc.y.abs() <= 1.0 + 1e-4,
+            "{:?} is outside of the shadow map",
+            point
+        );
+        assert!(
+            ndc.z >= -1e-4 && ndc.z <= 1.0 + 1e-4,
+            "{:?} is clipped",
+            point
+        );
+    }
+
+    #[test]
+    fn cascade_splits_end_at_far() {
+        let splits = cascade_splits(0.1, 100.0, 4);
+        assert_eq!(splits.len(), 4);
+        assert!((splits[3] - 100.0).abs() < 1e-3);
+        assert!(splits.windows(2).all(|pair| pair[0] < pair[1]));
+        // close cascades are smaller than with uniform splits
+        assert!(splits[0] < 25.0);
+    }
+
+    #[test]
+    fn cascades_cover_view_frustum_slices() {
+        let light = DirectionalLight {
+            shadows_enabled: true,
+            ..DirectionalLight::new(Color::WHITE, 1000.0, Vec3::new(-1.0, -2.0, -0.5))
+        };
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_4, 1.5, 0.1, 1000.0);
+        let camera_transform = Mat4::from_translation(Vec3::new(3.0, 5.0, 10.0));
+        let cascades = directional_light_cascades(&light, &projection, &camera_transform);
+        assert_eq!(cascades.len(), MAX_SHADOW_CASCADES);
+        assert!((cascades[MAX_SHADOW_CASCADES - 1].0 - light.shadow_maximum_distance).abs() < 1e-3);
+
+        let mut near = 0.1;
+        for (far, view_projection) in cascades.iter() {
+            let corners = frustum_slice_corners(&projection, &camera_transform, near, *far);
+            for corner in corners.iter() {
+                assert_in_clip_space(view_projection, *corner);
+            }
+            near = *far;
+        }
+    }
+
+    #[test]
+    fn frustum_slice_corners_are_at_view_depth() {
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 100.0);
+        let corners = frustum_slice_corners(&projection, &Mat4::IDENTITY, 2.0, 10.0);
+        for corner in corners[..4].iter() {
+            assert!((corner.z + 2.0).abs() < 1e-4);
+            assert!((corner.x.abs() - 2.0).abs() < 1e-4);
+        }
+        for corner in corners[4..].iter() {
+            assert!((corner.z + 10.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn point_light_faces_cover_all_directions() {
+        let position = Vec3::new(1.0, 2.0, 3.0);
+        let view_projections = point_light_view_projections(position, 20.0);
+        let directions = [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        for (view_projection, direction) in view_projections.iter().zip(directions.iter()) {
+            assert_in_clip_space(view_projection, position + *direction * 5.0);
+            // the opposite direction is behind the face
+            let behind = view_projection.project_point3(position - *direction * 5.0);
+            assert!(behind.z < 0.0 || behind.z > 1.0);
+        }
+    }
+
+    #[test]
+    fn spot_light_shadow_covers_cone() {
+        let position = Vec3::new(1.0, 2.0, 3.0);
+        let direction = Vec3::new(1.0, -1.0, 0.0).normalize();
+        let outer_angle = 0.5;
+        let view_projection = spot_light_view_projection(position, direction, outer_angle, 20.0);
+        assert_in_clip_space(&view_projection, position + direction * 10.0);
+        // a point on the edge of the cone
+        let side = direction.cross(Vec3::Z).normalize();
+        let edge = Quat::from_axis_angle(side, outer_angle * 0.99) * direction;
+        assert_in_clip_space(&view_projection, position + edge * 10.0);
+        let behind = view_projection.project_point3(position - direction * 5.0);
+        assert!(behind.z < 0.0 || behind.z > 1.0);
+    }
+
+    #[test]
+    fn spot_light_angular_attenuation() {
+        let light = SpotLight {
+            inner_angle: 0.2,
+            outer_angle: 0.6,
+            ..Default::default()
+        };
+        let transform = GlobalTransform::from_translation(Vec3::ZERO).looking_at(-Vec3::Y, Vec3::Z);
+        let uniform = SpotLightUniform::new(&light, &transform, None);
+        let direction = Vec3::new(uniform.dir[0], uniform.dir[1], uniform.dir[2]);
+        assert!((direction + Vec3::Y).length() < 1e-5);
+        let attenuation =
+            |angle: f32| angle.cos() * uniform.light_params[2] + uniform.light_params[3];
+        assert!((attenuation(0.2) - 1.0).abs() < 1e-4);
+        assert!(attenuation(0.6).abs() < 1e-4);
+        assert!(attenuation(0.8) < 0.0);
+        assert!(uniform.shadow_params[0] < 0.0);
+    }
+}
@@ -1,36 +1,69 @@
 use crate::{
+    environment_map::EnvironmentMapLight,
     light::{
-        AmbientLight, DirectionalLight, DirectionalLightUniform, PointLight, PointLightUniform,
+        directional_light_cascades, AmbientLight, DirectionalLight, DirectionalLightUniform,
+        PointLight, PointLightUniform, SpotLight, SpotLightUniform,
+    },
+    render_graph::{
+        uniform, ShadowView, ShadowViews, MAX_SHADOW_CASCADES, MAX_SPOT_LIGHTS, SHADOW_ATLAS_SIZE,
+        SHADOW_ATLAS_TILES_PER_ROW,
     },
-    render_graph::uniform,
 };
+use bevy_asset::{Assets, Handle};
 use bevy_core::{bytes_of, Pod, Zeroable};
 use bevy_ecs::{
     system::{BoxedSystem, ConfigurableSystem, Local, Query, Res, ResMut},
     world::World,
 };
+use bevy_math::Mat4;
 use bevy_render::{
-    render_graph::{CommandQueue, Node, ResourceSlots, SystemNode},
+    camera::{ActiveCameras, Camera},
+    pipeline::CompareFunction,
+    render_graph::{base, CommandQueue, Node, ResourceSlots, SystemNode},
     renderer::{
         BufferId, BufferInfo, BufferMapMode, BufferUsage, RenderContext, RenderResourceBinding,
-        RenderResourceBindings, RenderResourceContext,
+        RenderResourceBindings, RenderResourceContext, TextureId,
+    },
+    texture::{
+        Extent3d, FilterMode, SamplerDescriptor, Texture, TextureDescriptor, TextureDimension,
+        TextureFormat, TextureUsage, TEXTURE_ASSET_INDEX,
     },
 };
 use bevy_transform::prelude::*;
+use bevy_utils::tracing::warn;
 
 /// A Render Graph [Node] that write light data from the ECS to GPU buffers
+///
+/// It also creates the shadow atlas once a light casts shadows and assigns its tiles to the
+/// shadow views of lights, which
+/// are rendered by the [`ShadowPassNode`](super::ShadowPassNode), and binds the maps of the
+/// [`EnvironmentMapLight`].
+///
+/// The cascades of directional lights are assigned their tiles first, then point lights and
+/// spot lights share the remaining tiles. Lights that don't get tiles cast no shadows.
 #[derive(Debug, Default)]
 pub struct LightsNode {
     command_queue: CommandQueue,
     max_point_lights: usize,
     max_dir_lights: usize,
+    max_spot_lights: usize,
 }
 
 impl LightsNode {
+    /// Creates a node that supports up to [`MAX_SPOT_LIGHTS`] spot lights.
     pub fn new(max_point_lights: usize, max_dir_lights: usize) -> Self {
+        Self::with_spot_lights(max_point_lights, max_dir_lights, MAX_SPOT_LIGHTS)
+    }
+
+    pub fn with_spot_lights(
+        max_point_lights: usize,
+        max_dir_lights: usize,
+        max_spot_lights: usize,
+    ) -> Self {
         LightsNode {
             max_point_lights,
             max_dir_lights,
+            max_spot_lights,
             command_queue: CommandQueue::default(),
         }
     }
@@ -54,6 +87,7 @@ struct LightCount {
     // storing as a `[u32; 4]` for memory alignement
     // Index 0 is for point lights,
     // Index 1 is for directional lights
+    // Index 2 is for spot lights
     pub num_lights: [u32; 4],
 }
 
@@ -64,8 +98,13 @@ impl SystemNode for LightsNode {
                 command_queue: self.command_queue.clone(),
                 max_point_lights: self.max_point_lights,
                 max_dir_lights: self.max_dir_lights,
+                max_spot_lights: self.max_spot_lights,
                 light_buffer: None,
+                shadow_view_buffer: None,
                 staging_buffer: None,
+                shadow_atlas: None,
+                empty_environment_map: None,
+                warned_shadow_atlas_full: false,
             })
         });
         Box::new(system)
@@ -76,24 +115,40 @@ impl SystemNode for LightsNode {
 #[derive(Debug, Default)]
 pub struct LightsNodeSystemState {
     light_buffer: Option<BufferId>,
+    shadow_view_buffer: Option<BufferId>,
     staging_buffer: Option<BufferId>,
+    /// created once the first light casts shadows, until then a 1x1 placeholder is bound
+    shadow_atlas: Option<TextureId>,
+    /// bound instead of the maps of the environment map light when there is none
+    empty_environment_map: Option<TextureId>,
+    /// the warning about lights without shadows is only logged once
+    warned_shadow_atlas_full: bool,
     command_queue: CommandQueue,
     max_point_lights: usize,
     max_dir_lights: usize,
+    max_spot_lights: usize,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn lights_node_system(
     mut state: Local<LightsNodeSystemState>,
     render_resource_context: Res<Box<dyn RenderResourceContext>>,
     ambient_light_resource: Res<AmbientLight>,
+    active_cameras: Res<ActiveCameras>,
     // TODO: this write on RenderResourceBindings will prevent this system from running in parallel
     // with other systems that do the same
     mut render_resource_bindings: ResMut<RenderResourceBindings>,
+    mut shadow_views: ResMut<ShadowViews>,
     point_lights: Query<(&PointLight, &GlobalTransform)>,
     dir_lights: Query<&DirectionalLight>,
+    spot_lights: Query<(&SpotLight, &GlobalTransform)>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    environment_maps: Query<&EnvironmentMapLight>,
+    textures: Res<Assets<Texture>>,
 ) {
     let state = &mut state;
     let render_resource_context = &**render_resource_context;
+    shadow_views.views.clear();
 
     // premultiply ambient brightness
     let ambient_light: [f32; 4] =
@@ -105,25 +160,59 @@ pub fn lights_node_system(
     let point_light_array_size = point_light_size * point_light_count;
     let point_light_array_max_size = point_light_size * state.max_point_lights;
 
+    let camera_entity = active_cameras
+        .get(base::camera::CAMERA_3D)
+        .and_then(|active_camera| active_camera.entity);
+    // the environment map of the camera takes precedence over the one of the scene
+    let environment_map = camera_entity
+        .and_then(|entity| environment_maps.get(entity).ok())
+        .or_else(|| environment_maps.iter().next());
+
     let dir_light_count = dir_lights.iter().len().min(state.max_dir_lights);
     let dir_light_size = std::mem::size_of::<DirectionalLightUniform>();
     let dir_light_array_size = dir_light_size * dir_light_count;
     let dir_light_array_max_size = dir_light_size * state.max_dir_lights;
 
+    let spot_light_count = spot_lights.iter().len().min(state.max_spot_lights);
+    let spot_light_size = std::mem::size_of::<SpotLightUniform>();
+    let spot_light_array_size = spot_light_size * spot_light_count;
+    let spot_light_array_max_size = spot_light_size * state.max_spot_lights;
+
     let light_count_size = ambient_light_size + std::mem::size_of::<LightCount>();
+    let environment_map_params_size = std::mem::size_of::<[f32; 4]>();
+    let light_header_size = light_count_size + environment_map_params_size;
 
-    let point_light_uniform_start = light_count_size;
-    let point_light_uniform_end = light_count_size + point_light_array_size;
+    let point_light_uniform_start = light_header_size;
+    let point_light_uniform_end = light_header_size + point_light_array_size;
 
-    let dir_light_uniform_start = light_count_size + point_light_array_max_size;
+    let dir_light_uniform_start = light_header_size + point_light_array_max_size;
     let dir_light_uniform_end =
-        light_count_size + point_light_array_max_size + dir_light_array_size;
+        light_header_size + point_light_array_max_size + dir_light_array_size;
+
+    let spot_light_uniform_start =
+        light_header_size + point_light_array_max_size + dir_light_array_max_size;
+    let spot_light_uniform_end = spot_light_uniform_start + spot_light_array_size;
+
+    let max_light_uniform_size = light_header_size
+        + point_light_array_max_size
+        + dir_light_array_max_size
+        + spot_light_array_max_size;
 
-    let max_light_uniform_size =
-        light_count_size + point_light_array_max_size + dir_light_array_max_size;
+    // every shadow view is bound separately, so each view projection is aligned for binding
+    let shadow_view_size = std::mem::size_of::<Mat4>();
+    let shadow_view_stride =
+        render_resource_context.get_aligned_uniform_size(shadow_view_size, true);
+    let max_shadow_views = state.max_dir_lights * MAX_SHADOW_CASCADES
+        + state.max_point_lights * 6
+        + state.max_spot_lights;
+    let max_shadow_view_uniform_size = shadow_view_stride * max_shadow_views;
 
     if let Some(staging_buffer) = state.staging_buffer {
-        if point_light_count == 0 && dir_light_count == 0 {
+        if point_light_count == 0
+            && dir_light_count == 0
+            && spot_light_count == 0
+            && environment_map.is_none()
+        {
             return;
         }
 
@@ -144,18 +233,219 @@ pub fn lights_node_system(
         );
         state.light_buffer = Some(buffer);
 
+        let shadow_view_buffer = render_resource_context.create_buffer(BufferInfo {
+            size: max_shadow_view_uniform_size,
+            buffer_usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+            ..Default::default()
+        });
+        state.shadow_view_buffer = Some(shadow_view_buffer);
+        shadow_views.buffer = Some(shadow_view_buffer);
+
+        let empty_shadow_atlas = render_resource_context.create_texture(TextureDescriptor {
+            size: Extent3d::new(1, 1, 1),
+            format: TextureFormat::Depth32Float,
+            usage: TextureUsage::SAMPLED,
+            ..Default::default()
+        });
+        render_resource_bindings.set(
+            uniform::SHADOW_ATLAS,
+            RenderResourceBinding::Texture(empty_shadow_atlas),
+        );
+        // a linear comparison sampler filters each sample of the shadow map over 2x2 texels
+        let shadow_sampler = render_resource_context.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            compare_function: Some(CompareFunction::LessEqual),
+            ..Default::default()
+        });
+        render_resource_bindings.set(
+            uniform::SHADOW_ATLAS_SAMPLER,
+            RenderResourceBinding::Sampler(shadow_sampler),
+        );
+
+        let empty_environment_map = render_resource_context.create_texture(TextureDescriptor {
+            size: Extent3d::new(1, 1, 6),
+            format: TextureFormat::Rgba8Unorm,
+            usage: TextureUsage::SAMPLED,
+            ..Default::default()
+        });
+        state.empty_environment_map = Some(empty_environment_map);
+        let environment_map_sampler = render_resource_context.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        render_resource_bindings.set(
+            uniform::ENVIRONMENT_MAP_SAMPLER,
+            RenderResourceBinding::Sampler(environment_map_sampler),
+        );
+
         let staging_buffer = render_resource_context.create_buffer(BufferInfo {
-            size: max_light_uniform_size,
+            size: max_light_uniform_size + max_shadow_view_uniform_size,
             buffer_usage: BufferUsage::COPY_SRC | BufferUsage::MAP_WRITE,
             mapped_at_creation: true,
         });
         state.staging_buffer = Some(staging_buffer);
     }
 
+    // the maps of the environment map are used once they are both loaded
+    let texture_id = |handle: &Handle<Texture>| {
+        render_resource_context
+            .get_asset_resource(handle, TEXTURE_ASSET_INDEX)
+            .and_then(|resource| resource.get_texture())
+    };
+    let environment_map_textures = environment_map.and_then(|environment_map| {
+        let specular_mip_level_count = textures.get(&environment_map.specular_map)?.mip_level_count;
+        Some((
+            texture_id(&environment_map.diffuse_map)?,
+            texture_id(&environment_map.specular_map)?,
+            [
+                environment_map.intensity,
+                specular_mip_level_count as f32,
+                0.0,
+                0.0,
+            ],
+        ))
+    });
+    let (diffuse_map, specular_map, environment_map_params) = environment_map_textures
+        .unwrap_or_else(|| {
+            let empty = state.empty_environment_map.unwrap();
+            (empty, empty, [0.0, 1.0, 0.0, 0.0])
+        });
+    render_resource_bindings.set(
+        uniform::ENVIRONMENT_MAP_DIFFUSE,
+        RenderResourceBinding::Texture(diffuse_map),
+    );
+    render_resource_bindings.set(
+        uniform::ENVIRONMENT_MAP_SPECULAR,
+        RenderResourceBinding::Texture(specular_map),
+    );
+
+    let shadows_enabled = point_lights
+        .iter()
+        .take(point_light_count)
+        .any(|(point_light, _)| point_light.shadows_enabled)
+        || spot_lights
+            .iter()
+            .take(spot_light_count)
+            .any(|(spot_light, _)| spot_light.shadows_enabled)
+        || dir_lights
+            .iter()
+            .take(dir_light_count)
+            .any(|dir_light| dir_light.shadows_enabled);
+    if shadows_enabled && state.shadow_atlas.is_none() {
+        let shadow_atlas = render_resource_context.create_texture(TextureDescriptor {
+            size: Extent3d::new(SHADOW_ATLAS_SIZE, SHADOW_ATLAS_SIZE, 1),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Depth32Float,
+            usage: TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::SAMPLED,
+        });
+        render_resource_bindings.set(
+            uniform::SHADOW_ATLAS,
+            RenderResourceBinding::Texture(shadow_atlas),
+        );
+        state.shadow_atlas = Some(shadow_atlas);
+    }
+
+    // assign the tiles of the shadow atlas to the lights that cast shadows
+    let tile_count = SHADOW_ATLAS_TILES_PER_ROW * SHADOW_ATLAS_TILES_PER_ROW;
+    let mut next_tile = 0;
+    let mut shadow_atlas_full = false;
+    let mut allocate_tiles = |count: u32| {
+        if next_tile + count > tile_count {
+            shadow_atlas_full = true;
+            return None;
+        }
+        next_tile += count;
+        Some(next_tile - count)
+    };
+
+    // directional light shadows are fitted to the view frustum of the 3d camera. They are
+    // assigned their tiles first, so point and spot lights can't take all tiles of the atlas
+    let camera = camera_entity.and_then(|entity| cameras.get(entity).ok());
+    let dir_light_uniforms = dir_lights
+        .iter()
+        .take(dir_light_count)
+        .map(|dir_light| {
+            let mut uniform = DirectionalLightUniform::new(dir_light);
+            if let Some((camera, camera_transform)) = camera.filter(|_| dir_light.shadows_enabled) {
+                let cascades = directional_light_cascades(
+                    dir_light,
+                    &camera.projection_matrix,
+                    &camera_transform.compute_matrix(),
+                );
+                if let Some(tile) = allocate_tiles(cascades.len() as u32) {
+                    uniform.set_cascades(tile, &cascades);
+                    for (cascade, (_, view_projection)) in cascades.iter().enumerate() {
+                        shadow_views.views.push(ShadowView {
+                            tile: tile + cascade as u32,
+                            view_projection: *view_projection,
+                        });
+                    }
+                }
+            }
+            uniform
+        })
+        .collect::<Vec<_>>();
+
+    let point_light_uniforms = point_lights
+        .iter()
+        .take(point_light_count)
+        .map(|(point_light, global_transform)| {
+            let shadow_tile = if point_light.shadows_enabled {
+                allocate_tiles(6)
+            } else {
+                None
+            };
+            let uniform = PointLightUniform::new(point_light, global_transform, shadow_tile);
+            if let Some(tile) = shadow_tile {
+                for (face, view_projection) in uniform.shadow_view_projections.iter().enumerate() {
+                    shadow_views.views.push(ShadowView {
+                        tile: tile + face as u32,
+                        view_projection: *view_projection,
+                    });
+                }
+            }
+            uniform
+        })
+        .collect::<Vec<_>>();
+
+    let spot_light_uniforms = spot_lights
+        .iter()
+        .take(spot_light_count)
+        .map(|(spot_light, global_transform)| {
+            let shadow_tile = if spot_light.shadows_enabled {
+                allocate_tiles(1)
+            } else {
+                None
+            };
+            let uniform = SpotLightUniform::new(spot_light, global_transform, shadow_tile);
+            if let Some(tile) = shadow_tile {
+                shadow_views.views.push(ShadowView {
+                    tile,
+                    view_projection: uniform.shadow_view_projection,
+                });
+            }
+            uniform
+        })
+        .collect::<Vec<_>>();
+
+    if shadow_atlas_full && !state.warned_shadow_atlas_full {
+        warn!(
+            "The shadow atlas has no tiles left, some lights cast no shadows. Enable shadows on \
+            fewer point and spot lights."
+        );
+        state.warned_shadow_atlas_full = true;
+    }
+
     let staging_buffer = state.staging_buffer.unwrap();
+    let shadow_view_uniform_size = shadow_view_stride * shadow_views.views.len();
     render_resource_context.write_mapped_buffer(
         staging_buffer,
-        0..max_light_uniform_size as u64,
+        0..(max_light_uniform_size + shadow_view_uniform_size) as u64,
         &mut |data, _renderer| {
             // ambient light
             data[0..ambient_light_size].copy_from_slice(bytes_of(&ambient_light));
@@ -164,27 +454,44 @@ pub fn lights_node_system(
             data[ambient_light_size..light_count_size].copy_from_slice(bytes_of(&[
                 point_light_count as u32,
                 dir_light_count as u32,
-                0,
+                spot_light_count as u32,
                 0,
             ]));
 
+            // environment map intensity and specular mip level count
+            data[light_count_size..light_header_size]
+                .copy_from_slice(bytes_of(&environment_map_params));
+
             // point light array
-            for ((point_light, global_transform), slot) in point_lights.iter().zip(
+            for (point_light, slot) in point_light_uniforms.iter().zip(
                 data[point_light_uniform_start..point_light_uniform_end]
                     .chunks_exact_mut(point_light_size),
             ) {
-                slot.copy_from_slice(bytes_of(&PointLightUniform::new(
-                    point_light,
-                    global_transform,
-                )));
+                slot.copy_from_slice(bytes_of(point_light));
             }
 
             // directional light array
-            for (dir_light, slot) in dir_lights.iter().zip(
+            for (dir_light, slot) in dir_light_uniforms.iter().zip(
                 data[dir_light_uniform_start..dir_light_uniform_end]
                     .chunks_exact_mut(dir_light_size),
             ) {
-                slot.copy_from_slice(bytes_of(&DirectionalLightUniform::new(dir_light)));
+                slot.copy_from_slice(bytes_of(dir_light));
+            }
+
+            // spot light array
+            for (spot_light, slot) in spot_light_uniforms.iter().zip(
+                data[spot_light_uniform_start..spot_light_uniform_end]
+                    .chunks_exact_mut(spot_light_size),
+            ) {
+                slot.copy_from_slice(bytes_of(spot_light));
+            }
+
+            // shadow view projections
+            for (shadow_view, slot) in shadow_views.views.iter().zip(
+                data[max_light_uniform_size..max_light_uniform_size + shadow_view_uniform_size]
+                    .chunks_exact_mut(shadow_view_stride),
+            ) {
+                slot[..shadow_view_size].copy_from_slice(bytes_of(&shadow_view.view_projection));
             }
         },
     );
@@ -197,4 +504,309 @@ pub fn lights_node_system(
         0,
         max_light_uniform_size as u64,
     );
+    if shadow_view_uniform_size > 0 {
+        state.command_queue.copy_buffer_to_buffer(
+            staging_buffer,
+            max_light_uniform_size as u64,
+            state.shadow_view_buffer.unwrap(),
+            0,
+            shadow_view_uniform_size as u64,
+        );
+    }
+    shadow_views.stride = shadow_view_stride as u64;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::render_graph::{MAX_DIRECTIONAL_LIGHTS, MAX_POINT_LIGHTS};
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin};
+    use bevy_core::CorePlugin;
+    use bevy_ecs::{
+        entity::Entity,
+        schedule::{Schedule, SystemStage},
+    };
+    use bevy_math::Vec3;
+    use bevy_render::renderer::{HeadlessRenderResourceContext, RenderResourceId};
+
+    fn lights_node_schedule() -> Schedule {
+        let node = LightsNode::new(MAX_POINT_LIGHTS, MAX_DIRECTIONAL_LIGHTS);
+        let mut schedule = Schedule::default();
+        let mut stage = SystemStage::parallel();
+        stage.add_system(node.get_system());
+        schedule.add_stage("lights", stage);
+        schedule
+    }
+
+    fn run_lights_node(world: &mut World) {
+        lights_node_schedule().run(world);
+    }
+
+    fn lights_world() -> World {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Texture>();
+        let mut world = app.world;
+        world.insert_resource::<Box<dyn RenderResourceContext>>(Box::new(
+            HeadlessRenderResourceContext::default(),
+        ));
+        world.insert_resource(AmbientLight::default());
+        world.insert_resource(ActiveCameras::default());
+        world.insert_resource(RenderResourceBindings::default());
+        world.insert_resource(ShadowViews::default());
+        world
+    }
+
+    fn add_3d_camera(world: &mut World) -> Entity {
+        let camera = world
+            .spawn()
+            .insert_bundle((
+                Camera {
+                    projection_matrix: Mat4::perspective_rh(1.0, 1.0, 0.1, 1000.0),
+                    ..Default::default()
+                },
+                GlobalTransform::from_translation(Vec3::new(0.0, 5.0, 10.0)),
+            ))
+            .id();
+        let mut active_cameras = world.get_resource_mut::<ActiveCameras>().unwrap();
+        active_cameras.add(base::camera::CAMERA_3D);
+        active_cameras
+            .get_mut(base::camera::CAMERA_3D)
+            .unwrap()
+            .entity = Some(camera);
+        camera
+    }
+
+    /// Adds a texture asset along with its texture resource.
+    fn add_texture(world: &mut World) -> (Handle<Texture>, TextureId) {
+        let handle = world
+            .get_resource_mut::<Assets<Texture>>()
+            .unwrap()
+            .add(Texture::default());
+        let render_resource_context = world
+            .get_resource::<Box<dyn RenderResourceContext>>()
+            .unwrap();
+        let texture = render_resource_context.create_texture(TextureDescriptor::default());
+        render_resource_context.set_asset_resource(
+            &handle,
+            RenderResourceId::Texture(texture),
+            TEXTURE_ASSET_INDEX,
+        );
+        (handle, texture)
+    }
+
+    fn bound_texture(world: &World, name: &str) -> Option<TextureId> {
+        world
+            .get_resource::<RenderResourceBindings>()
+            .unwrap()
+            .get(name)
+            .and_then(|binding| binding.get_texture())
+    }
+
+    #[test]
+    fn assigns_atlas_tiles_to_shadow_casting_lights() {
+        let mut world = lights_world();
+        world.spawn().insert_bundle((
+            PointLight {
+                shadows_enabled: true,
+                ..Default::default()
+            },
+            GlobalTransform::default(),
+        ));
+        world
+            .spawn()
+            .insert_bundle((PointLight::default(), GlobalTransform::default()));
+        world.spawn().insert_bundle((
+            SpotLight {
+                shadows_enabled: true,
+                ..Default::default()
+            },
+            GlobalTransform::default(),
+        ));
+        world.spawn().insert(DirectionalLight {
+            shadows_enabled: true,
+            ..Default::default()
+        });
+        add_3d_camera(&mut world);
+
+        run_lights_node(&mut world);
+
+        let bindings = world.get_resource::<RenderResourceBindings>().unwrap();
+        assert!(matches!(
+            bindings.get(uniform::SHADOW_ATLAS),
+            Some(RenderResourceBinding::Texture(_))
+        ));
+        assert!(matches!(
+            bindings.get(uniform::SHADOW_ATLAS_SAMPLER),
+            Some(RenderResourceBinding::Sampler(_))
+        ));
+
+        let shadow_views = world.get_resource::<ShadowViews>().unwrap();
+        let tiles = shadow_views
+            .views
+            .iter()
+            .map(|view| view.tile)
+            .collect::<Vec<_>>();
+        // the cascades of the directional light, then six cube faces for the point light and one
+        // tile for the spot light
+        let expected = (0..7 + MAX_SHADOW_CASCADES as u32).collect::<Vec<_>>();
+        assert_eq!(tiles, expected);
+
+        let render_resource_context = world
+            .get_resource::<Box<dyn RenderResourceContext>>()
+            .unwrap();
+        let buffer_info = render_resource_context
+            .get_buffer_info(shadow_views.buffer.unwrap())
+            .unwrap();
+        assert!(buffer_info.size as u64 >= shadow_views.stride * tiles.len() as u64);
+    }
+
+    #[test]
+    fn reserves_atlas_tiles_for_directional_cascades() {
+        let mut world = lights_world();
+        for _ in 0..MAX_POINT_LIGHTS {
+            world.spawn().insert_bundle((
+                PointLight {
+                    shadows_enabled: true,
+                    ..Default::default()
+                },
+                GlobalTransform::default(),
+            ));
+        }
+        for _ in 0..MAX_SPOT_LIGHTS {
+            world.spawn().insert_bundle((
+                SpotLight {
+                    shadows_enabled: true,
+                    ..Default::default()
+                },
+                GlobalTransform::default(),
+            ));
+        }
+        let dir_light = DirectionalLight {
+            shadows_enabled: true,
+            ..Default::default()
+        };
+        world.spawn().insert(dir_light);
+        let camera = add_3d_camera(&mut world);
+
+        run_lights_node(&mut world);
+
+        let (camera, camera_transform) = world
+            .query::<(&Camera, &GlobalTransform)>()
+            .get(&world, camera)
+            .unwrap();
+        let cascades = directional_light_cascades(
+            &dir_light,
+            &camera.projection_matrix,
+            &camera_transform.compute_matrix(),
+        );
+        let shadow_views = world.get_resource::<ShadowViews>().unwrap();
+        assert_eq!(
+            shadow_views.views.len() as u32,
+            SHADOW_ATLAS_TILES_PER_ROW * SHADOW_ATLAS_TILES_PER_ROW
+        );
+        for (cascade, (_, view_projection)) in cascades.iter().enumerate() {
+            let view = &shadow_views.views[cascade];
+            assert_eq!(view.tile, cascade as u32);
+            assert_eq!(view.view_projection, *view_projection);
+        }
+    }
+
+    #[test]
+    fn creates_shadow_atlas_once_a_light_casts_shadows() {
+        let mut world = lights_world();
+        let light = world
+            .spawn()
+            .insert_bundle((PointLight::default(), GlobalTransform::default()))
+            .id();
+        let atlas_size = |world: &World| {
+            let texture = bound_texture(world, uniform::SHADOW_ATLAS).unwrap();
+            world
+                .get_resource::<Box<dyn RenderResourceContext>>()
+                .unwrap()
+                .downcast_ref::<HeadlessRenderResourceContext>()
+                .unwrap()
+                .get_texture_descriptor(texture)
+                .unwrap()
+                .size
+        };
+
+        let mut schedule = lights_node_schedule();
+        schedule.run(&mut world);
+        assert_eq!(atlas_size(&world), Extent3d::new(1, 1, 1));
+
+        world.get_mut::<PointLight>(light).unwrap().shadows_enabled = true;
+        schedule.run(&mut world);
+        assert_eq!(
+            atlas_size(&world),
+            Extent3d::new(SHADOW_ATLAS_SIZE, SHADOW_ATLAS_SIZE, 1)
+        );
+    }
+
+    #[test]
+    fn directional_shadows_need_a_3d_camera() {
+        let mut world = lights_world();
+        world.spawn().insert(DirectionalLight {
+            shadows_enabled: true,
+            ..Default::default()
+        });
+
+        run_lights_node(&mut world);
+
+        let shadow_views = world.get_resource::<ShadowViews>().unwrap();
+        assert!(shadow_views.views.is_empty());
+    }
+
+    #[test]
+    fn binds_environment_map_of_the_camera() {
+        let mut world = lights_world();
+        let (scene_diffuse, _) = add_texture(&mut world);
+        let (scene_specular, _) = add_texture(&mut world);
+        world.spawn().insert(EnvironmentMapLight {
+            diffuse_map: scene_diffuse,
+            specular_map: scene_specular,
+            ..Default::default()
+        });
+        let (camera_diffuse, camera_diffuse_texture) = add_texture(&mut world);
+        let (camera_specular, camera_specular_texture) = add_texture(&mut world);
+        let camera = add_3d_camera(&mut world);
+        world.entity_mut(camera).insert(EnvironmentMapLight {
+            diffuse_map: camera_diffuse,
+            specular_map: camera_specular,
+            ..Default::default()
+        });
+
+        run_lights_node(&mut world);
+
+        assert_eq!(
+            bound_texture(&world, uniform::ENVIRONMENT_MAP_DIFFUSE),
+            Some(camera_diffuse_texture)
+        );
+        assert_eq!(
+            bound_texture(&world, uniform::ENVIRONMENT_MAP_SPECULAR),
+            Some(camera_specular_texture)
+        );
+    }
+
+    #[test]
+    fn binds_empty_environment_map_until_loaded() {
+        let mut world = lights_world();
+        world.spawn().insert(EnvironmentMapLight::default());
+
+        run_lights_node(&mut world);
+
+        let diffuse = bound_texture(&world, uniform::ENVIRONMENT_MAP_DIFFUSE);
+        assert!(diffuse.is_some());
+        assert_eq!(
+            diffuse,
+            bound_texture(&world, uniform::ENVIRONMENT_MAP_SPECULAR)
+        );
+        assert!(world
+            .get_resource::<RenderResourceBindings>()
+            .unwrap()
+            .get(uniform::ENVIRONMENT_MAP_SAMPLER)
+            .is_some());
+    }
 }
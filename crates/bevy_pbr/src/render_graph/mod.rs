@@ -1,20 +1,30 @@
 mod lights_node;
 mod pbr_pipeline;
+mod shadow_pass_node;
+mod shadow_pipeline;
 
 use bevy_ecs::world::World;
 pub use lights_node::*;
 pub use pbr_pipeline::*;
+pub use shadow_pass_node::*;
+pub use shadow_pipeline::*;
 
 /// the names of pbr graph nodes
 pub mod node {
     pub const TRANSFORM: &str = "transform";
     pub const STANDARD_MATERIAL: &str = "standard_material";
     pub const LIGHTS: &str = "lights";
+    pub const SHADOW_PASS: &str = "shadow_pass";
 }
 
 /// the names of pbr uniforms
 pub mod uniform {
     pub const LIGHTS: &str = "Lights";
+    pub const SHADOW_ATLAS: &str = "ShadowAtlas";
+    pub const SHADOW_ATLAS_SAMPLER: &str = "ShadowAtlas_sampler";
+    pub const ENVIRONMENT_MAP_DIFFUSE: &str = "EnvironmentMap_diffuse";
+    pub const ENVIRONMENT_MAP_SPECULAR: &str = "EnvironmentMap_specular";
+    pub const ENVIRONMENT_MAP_SAMPLER: &str = "EnvironmentMap_sampler";
 }
 
 use crate::prelude::StandardMaterial;
@@ -28,6 +38,13 @@ use bevy_transform::prelude::GlobalTransform;
 
 pub const MAX_POINT_LIGHTS: usize = 10;
 pub const MAX_DIRECTIONAL_LIGHTS: usize = 1;
+pub const MAX_SPOT_LIGHTS: usize = 10;
+pub const MAX_SHADOW_CASCADES: usize = 4;
+/// The width and height of the shadow atlas, which holds the shadow maps of all lights
+pub const SHADOW_ATLAS_SIZE: u32 = 4096;
+/// The shadow atlas is divided into this many tiles per row and per column, one for each cascade
+/// of a directional light, each cube face of a point light and each spot light
+pub const SHADOW_ATLAS_TILES_PER_ROW: u32 = 8;
 pub(crate) fn add_pbr_graph(world: &mut World) {
     {
         let mut graph = world.get_resource_mut::<RenderGraph>().unwrap();
@@ -44,6 +61,7 @@ pub(crate) fn add_pbr_graph(world: &mut World) {
             node::LIGHTS,
             LightsNode::new(MAX_POINT_LIGHTS, MAX_DIRECTIONAL_LIGHTS),
         );
+        graph.add_node(node::SHADOW_PASS, ShadowPassNode::default());
 
         // TODO: replace these with "autowire" groups
         graph
@@ -55,10 +73,148 @@ pub(crate) fn add_pbr_graph(world: &mut World) {
         graph
             .add_node_edge(node::LIGHTS, base::node::MAIN_PASS)
             .unwrap();
+        graph
+            .add_node_edge(node::TRANSFORM, node::SHADOW_PASS)
+            .unwrap();
+        graph
+            .add_node_edge(node::LIGHTS, node::SHADOW_PASS)
+            .unwrap();
+        graph
+            .add_node_edge(node::SHADOW_PASS, base::node::MAIN_PASS)
+            .unwrap();
     }
-    let pipeline = build_pbr_pipeline(&mut world.get_resource_mut::<Assets<Shader>>().unwrap());
+    world.insert_resource(ShadowViews::default());
+    let mut shaders = world.get_resource_mut::<Assets<Shader>>().unwrap();
+    add_pbr_shader_imports(&mut shaders);
+    let pipeline = build_pbr_pipeline(&mut shaders);
+    let shadow_pipeline = build_shadow_pipeline(&mut shaders);
     let mut pipelines = world
         .get_resource_mut::<Assets<PipelineDescriptor>>()
         .unwrap();
     pipelines.set_untracked(PBR_PIPELINE_HANDLE, pipeline);
+    pipelines.set_untracked(SHADOW_PIPELINE_HANDLE, shadow_pipeline);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin};
+    use bevy_core::CorePlugin;
+    use bevy_render::{
+        pipeline::PipelineSpecialization,
+        render_graph::{Node, ResourceSlots},
+        renderer::RenderContext,
+        shader::{resolve_imports, ShaderSource},
+    };
+
+    struct MainPassNode;
+
+    impl Node for MainPassNode {
+        fn update(
+            &mut self,
+            _world: &World,
+            _render_context: &mut dyn RenderContext,
+            _input: &ResourceSlots,
+            _output: &mut ResourceSlots,
+        ) {
+        }
+    }
+
+    fn pbr_graph() -> World {
+        let mut graph = RenderGraph::default();
+        graph.add_node(base::node::MAIN_PASS, MainPassNode);
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Shader>()
+            .add_asset::<PipelineDescriptor>()
+            .insert_resource(graph);
+        add_pbr_graph(&mut app.world);
+        app.world
+    }
+
+    #[test]
+    fn shadow_pass_runs_between_lights_and_main_pass() {
+        let world = pbr_graph();
+        let graph = world.get_resource::<RenderGraph>().unwrap();
+        let shadow_pass = graph.get_node_id(node::SHADOW_PASS).unwrap();
+
+        let shadow_pass_inputs = graph
+            .iter_node_inputs(node::SHADOW_PASS)
+            .unwrap()
+            .map(|(_edge, node)| node.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert!(shadow_pass_inputs.contains(&node::LIGHTS));
+        assert!(shadow_pass_inputs.contains(&node::TRANSFORM));
+        assert!(graph
+            .iter_node_inputs(base::node::MAIN_PASS)
+            .unwrap()
+            .any(|(_edge, node)| node.id == shadow_pass));
+    }
+
+    #[test]
+    fn adds_shadow_pipeline() {
+        let world = pbr_graph();
+        let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
+        let shadow_pipeline = pipelines.get(SHADOW_PIPELINE_HANDLE).unwrap();
+        assert!(shadow_pipeline.shader_stages.fragment.is_none());
+        assert!(shadow_pipeline.color_target_states.is_empty());
+        assert!(world.get_resource::<ShadowViews>().is_some());
+    }
+
+    #[test]
+    fn pbr_shader_imports_resolve() {
+        let world = pbr_graph();
+        let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
+        let shaders = world.get_resource::<Assets<Shader>>().unwrap();
+        let pipeline = pipelines.get(PBR_PIPELINE_HANDLE).unwrap();
+        let fragment = shaders
+            .get(pipeline.shader_stages.fragment.as_ref().unwrap())
+            .unwrap();
+
+        let resolved = resolve_imports(fragment, shaders).unwrap();
+        assert!(resolved.imports.is_empty());
+        match resolved.source {
+            ShaderSource::Glsl(ref source) => {
+                assert!(!source.contains("#import"));
+                assert!(source.contains("vec3 direct_lighting("));
+                assert_eq!(source.matches("uniform Lights").count(), 1);
+            }
+            _ => panic!("the pbr shader is a GLSL shader"),
+        }
+    }
+
+    #[test]
+    fn hdr_pbr_pipeline_skips_tonemapping() {
+        let world = pbr_graph();
+        let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
+        let shaders = world.get_resource::<Assets<Shader>>().unwrap();
+        let pipeline = pipelines.get(PBR_PIPELINE_HANDLE).unwrap();
+        let fragment = shaders
+            .get(pipeline.shader_stages.fragment.as_ref().unwrap())
+            .unwrap();
+        let fragment = resolve_imports(fragment, shaders).unwrap();
+
+        let tonemaps = |hdr: bool| {
+            let specialization = PipelineSpecialization {
+                hdr,
+                ..Default::default()
+            };
+            let shader_defs = specialization
+                .get_shader_specialization()
+                .shader_defs
+                .into_iter()
+                .collect::<Vec<_>>();
+            let spirv = fragment.get_spirv(Some(&shader_defs)).unwrap();
+            let bytes = spirv
+                .iter()
+                .flat_map(|word| word.to_le_bytes().to_vec())
+                .collect::<Vec<u8>>();
+            let name = b"reinhard_luminance";
+            bytes.windows(name.len()).any(|window| window == name)
+        };
+        assert!(tonemaps(false));
+        assert!(!tonemaps(true));
+    }
 }
@@ -0,0 +1,397 @@
+#define_import_path bevy_pbr::lighting
+
+// The PBR lighting functions of bevy_pbr, for custom materials to `#import bevy_pbr::lighting`.
+
+// From the Filament design doc
+// https://google.github.io/filament/Filament.html#table_symbols
+// Symbol Definition
+// v    View unit vector
+// l    Incident light unit vector
+// n    Surface normal unit vector
+// h    Half unit vector between l and v
+// f    BRDF
+// f_d    Diffuse component of a BRDF
+// f_r    Specular component of a BRDF
+// α    Roughness, remapped from using input perceptualRoughness
+// σ    Diffuse reflectance
+// Ω    Spherical domain
+// f0    Reflectance at normal incidence
+// f90    Reflectance at grazing angle
+// χ+(a)    Heaviside function (1 if a>0 and 0 otherwise)
+// nior    Index of refraction (IOR) of an interface
+// ⟨n⋅l⟩    Dot product clamped to [0..1]
+// ⟨a⟩    Saturated value (clamped to [0..1])
+
+// The Bidirectional Reflectance Distribution Function (BRDF) describes the surface response of a standard material
+// and consists of two components, the diffuse component (f_d) and the specular component (f_r):
+// f(v,l) = f_d(v,l) + f_r(v,l)
+//
+// The form of the microfacet model is the same for diffuse and specular
+// f_r(v,l) = f_d(v,l) = 1 / { |n⋅v||n⋅l| } ∫_Ω D(m,α) G(v,l,m) f_m(v,l,m) (v⋅m) (l⋅m) dm
+//
+// In which:
+// D, also called the Normal Distribution Function (NDF) models the distribution of the microfacets
+// G models the visibility (or occlusion or shadow-masking) of the microfacets
+// f_m is the microfacet BRDF and differs between specular and diffuse components
+//
+// The above integration needs to be approximated.
+
+#import bevy_pbr::lights
+
+#ifndef NOT_SHADOW_RECEIVER
+layout(set = 0, binding = 2) uniform CameraView {
+    mat4 View;
+};
+
+layout(set = 1, binding = 1) uniform texture2D ShadowAtlas;
+layout(set = 1, binding = 2) uniform samplerShadow ShadowAtlas_sampler;
+#endif
+
+layout(set = 1, binding = 3) uniform textureCube EnvironmentMap_diffuse;
+layout(set = 1, binding = 4) uniform textureCube EnvironmentMap_specular;
+layout(set = 1, binding = 5) uniform sampler EnvironmentMap_sampler;
+
+#define saturate(x) clamp(x, 0.0, 1.0)
+const float PI = 3.141592653589793;
+
+float pow5(float x) {
+    float x2 = x * x;
+    return x2 * x2 * x;
+}
+
+// distanceAttenuation is simply the square falloff of light intensity
+// combined with a smooth attenuation at the edge of the light radius
+//
+// light radius is a non-physical construct for efficiency purposes,
+// because otherwise every light affects every fragment in the scene
+float getDistanceAttenuation(float distanceSquare, float inverseRangeSquared) {
+    float factor = distanceSquare * inverseRangeSquared;
+    float smoothFactor = saturate(1.0 - factor * factor);
+    float attenuation = smoothFactor * smoothFactor;
+    return attenuation * 1.0 / max(distanceSquare, 1e-4);
+}
+
+// Normal distribution function (specular D)
+// Based on https://google.github.io/filament/Filament.html#citation-walter07
+
+// D_GGX(h,α) = α^2 / { π ((n⋅h)^2 (α2−1) + 1)^2 }
+
+// Simple implementation, has precision problems when using fp16 instead of fp32
+// see https://google.github.io/filament/Filament.html#listing_speculardfp16
+float D_GGX(float roughness, float NoH, const vec3 h) {
+    float oneMinusNoHSquared = 1.0 - NoH * NoH;
+    float a = NoH * roughness;
+    float k = roughness / (oneMinusNoHSquared + a * a);
+    float d = k * k * (1.0 / PI);
+    return d;
+}
+
+// Visibility function (Specular G)
+// V(v,l,a) = G(v,l,α) / { 4 (n⋅v) (n⋅l) }
+// such that f_r becomes
+// f_r(v,l) = D(h,α) V(v,l,α) F(v,h,f0)
+// where
+// V(v,l,α) = 0.5 / { n⋅l sqrt((n⋅v)^2 (1−α2) + α2) + n⋅v sqrt((n⋅l)^2 (1−α2) + α2) }
+// Note the two sqrt's, that may be slow on mobile, see https://google.github.io/filament/Filament.html#listing_approximatedspecularv
+float V_SmithGGXCorrelated(float roughness, float NoV, float NoL) {
+    float a2 = roughness * roughness;
+    float lambdaV = NoL * sqrt((NoV - a2 * NoV) * NoV + a2);
+    float lambdaL = NoV * sqrt((NoL - a2 * NoL) * NoL + a2);
+    float v = 0.5 / (lambdaV + lambdaL);
+    return v;
+}
+
+// Fresnel function
+// see https://google.github.io/filament/Filament.html#citation-schlick94
+// F_Schlick(v,h,f_0,f_90) = f_0 + (f_90 − f_0) (1 − v⋅h)^5
+vec3 F_Schlick(const vec3 f0, float f90, float VoH) {
+    // not using mix to keep the vec3 and float versions identical
+    return f0 + (f90 - f0) * pow5(1.0 - VoH);
+}
+
+float F_Schlick(float f0, float f90, float VoH) {
+    // not using mix to keep the vec3 and float versions identical
+    return f0 + (f90 - f0) * pow5(1.0 - VoH);
+}
+
+vec3 fresnel(vec3 f0, float LoH) {
+    // f_90 suitable for ambient occlusion
+    // see https://google.github.io/filament/Filament.html#lighting/occlusion
+    float f90 = saturate(dot(f0, vec3(50.0 * 0.33)));
+    return F_Schlick(f0, f90, LoH);
+}
+
+// Specular BRDF
+// https://google.github.io/filament/Filament.html#materialsystem/specularbrdf
+
+// Cook-Torrance approximation of the microfacet model integration using Fresnel law F to model f_m
+// f_r(v,l) = { D(h,α) G(v,l,α) F(v,h,f0) } / { 4 (n⋅v) (n⋅l) }
+vec3 specular(vec3 f0, float roughness, const vec3 h, float NoV, float NoL,
+              float NoH, float LoH, float specularIntensity) {
+    float D = D_GGX(roughness, NoH, h);
+    float V = V_SmithGGXCorrelated(roughness, NoV, NoL);
+    vec3 F = fresnel(f0, LoH);
+
+    return (specularIntensity * D * V) * F;
+}
+
+// Diffuse BRDF
+// https://google.github.io/filament/Filament.html#materialsystem/diffusebrdf
+// fd(v,l) = σ/π * 1 / { |n⋅v||n⋅l| } ∫Ω D(m,α) G(v,l,m) (v⋅m) (l⋅m) dm
+
+// simplest approximation
+// float Fd_Lambert() {
+//     return 1.0 / PI;
+// }
+//
+// vec3 Fd = diffuseColor * Fd_Lambert();
+
+// Disney approximation
+// See https://google.github.io/filament/Filament.html#citation-burley12
+// minimal quality difference
+float Fd_Burley(float roughness, float NoV, float NoL, float LoH) {
+    float f90 = 0.5 + 2.0 * roughness * LoH * LoH;
+    float lightScatter = F_Schlick(1.0, f90, NoL);
+    float viewScatter = F_Schlick(1.0, f90, NoV);
+    return lightScatter * viewScatter * (1.0 / PI);
+}
+
+// From https://www.unrealengine.com/en-US/blog/physically-based-shading-on-mobile
+vec3 EnvBRDFApprox(vec3 f0, float perceptual_roughness, float NoV) {
+    const vec4 c0 = { -1, -0.0275, -0.572, 0.022 };
+    const vec4 c1 = { 1, 0.0425, 1.04, -0.04 };
+    vec4 r = perceptual_roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * NoV)) * r.x + r.y;
+    vec2 AB = vec2(-1.04, 1.04) * a004 + r.zw;
+    return f0 * AB.x + AB.y;
+}
+
+float perceptualRoughnessToRoughness(float perceptualRoughness) {
+    // clamp perceptual roughness to prevent precision problems
+    // According to Filament design 0.089 is recommended for mobile
+    // Filament uses 0.045 for non-mobile
+    float clampedPerceptualRoughness = clamp(perceptualRoughness, 0.089, 1.0);
+    return clampedPerceptualRoughness * clampedPerceptualRoughness;
+}
+
+// from https://64.github.io/tonemapping/
+// reinhard on RGB oversaturates colors
+vec3 reinhard(vec3 color) {
+    return color / (1.0 + color);
+}
+
+vec3 reinhard_extended(vec3 color, float max_white) {
+    vec3 numerator = color * (1.0f + (color / vec3(max_white * max_white)));
+    return numerator / (1.0 + color);
+}
+
+// luminance coefficients from Rec. 709.
+// https://en.wikipedia.org/wiki/Rec._709
+float luminance(vec3 v) {
+    return dot(v, vec3(0.2126, 0.7152, 0.0722));
+}
+
+vec3 change_luminance(vec3 c_in, float l_out) {
+    float l_in = luminance(c_in);
+    return c_in * (l_out / l_in);
+}
+
+vec3 reinhard_luminance(vec3 color) {
+    float l_old = luminance(color);
+    float l_new = l_old / (1.0f + l_old);
+    return change_luminance(color, l_new);
+}
+
+vec3 reinhard_extended_luminance(vec3 color, float max_white_l) {
+    float l_old = luminance(color);
+    float numerator = l_old * (1.0f + (l_old / (max_white_l * max_white_l)));
+    float l_new = numerator / (1.0f + l_old);
+    return change_luminance(color, l_new);
+}
+
+// shading of a light at a position, shared by point and spot lights
+// light_params.x = 1 / range^2, light_params.y = radius
+vec3 punctual_light(vec3 world_position, vec3 light_pos, vec3 light_color, vec4 light_params, float roughness, float NdotV, vec3 N, vec3 V, vec3 R, vec3 F0, vec3 diffuseColor) {
+    vec3 light_to_frag = light_pos - world_position;
+    float distance_square = dot(light_to_frag, light_to_frag);
+    float rangeAttenuation =
+        getDistanceAttenuation(distance_square, light_params.r);
+
+    // Specular.
+    // Representative Point Area Lights.
+    // see http://blog.selfshadow.com/publications/s2013-shading-course/karis/s2013_pbs_epic_notes_v2.pdf p14-16
+    float a = roughness;
+    float radius = light_params.g;
+    vec3 centerToRay = dot(light_to_frag, R) * R - light_to_frag;
+    vec3 closestPoint = light_to_frag + centerToRay * saturate(radius * inversesqrt(dot(centerToRay, centerToRay)));
+    float LspecLengthInverse = inversesqrt(dot(closestPoint, closestPoint));
+    float normalizationFactor = a / saturate(a + (radius * 0.5 * LspecLengthInverse));
+    float specularIntensity = normalizationFactor * normalizationFactor;
+
+    vec3 L = closestPoint * LspecLengthInverse; // normalize() equivalent?
+    vec3 H = normalize(L + V);
+    float NoL = saturate(dot(N, L));
+    float NoH = saturate(dot(N, H));
+    float LoH = saturate(dot(L, H));
+
+    vec3 specular = specular(F0, roughness, H, NdotV, NoL, NoH, LoH, specularIntensity);
+
+    // Diffuse.
+    // Comes after specular since its NoL is used in the lighting equation.
+    L = normalize(light_to_frag);
+    H = normalize(L + V);
+    NoL = saturate(dot(N, L));
+    NoH = saturate(dot(N, H));
+    LoH = saturate(dot(L, H));
+
+    vec3 diffuse = diffuseColor * Fd_Burley(roughness, NdotV, NoL, LoH);
+
+    // Lout = f(v,l) Φ / { 4 π d^2 }⟨n⋅l⟩
+    // where
+    // f(v,l) = (f_d(v,l) + f_r(v,l)) * light_color
+    // Φ is light intensity
+
+    // our rangeAttentuation = 1 / d^2 multiplied with an attenuation factor for smoothing at the edge of the non-physical maximum light radius
+    // It's not 100% clear where the 1/4π goes in the derivation, but we follow the filament shader and leave it out
+
+    // See https://google.github.io/filament/Filament.html#mjx-eqn-pointLightLuminanceEquation
+    // TODO compensate for energy loss https://google.github.io/filament/Filament.html#materialsystem/improvingthebrdfs/energylossinspecularreflectance
+    // light_color is premultiplied with the intensity of the light on the CPU
+    return ((diffuse + specular) * light_color) * (rangeAttenuation * NoL);
+}
+
+vec3 point_light(vec3 world_position, PointLight light, float roughness, float NdotV, vec3 N, vec3 V, vec3 R, vec3 F0, vec3 diffuseColor) {
+    return punctual_light(world_position, light.pos.xyz, light.color.rgb, light.lightParams, roughness, NdotV, N, V, R, F0, diffuseColor);
+}
+
+// a point light masked by a cone, which fades out between its inner and outer angles
+// see https://google.github.io/filament/Filament.html#listing_glslpunctuallight
+vec3 spot_light(vec3 world_position, SpotLight light, float roughness, float NdotV, vec3 N, vec3 V, vec3 R, vec3 F0, vec3 diffuseColor) {
+    vec3 light_to_frag = normalize(world_position - light.pos.xyz);
+    float attenuation = saturate(dot(light.direction.xyz, light_to_frag) * light.lightParams.z + light.lightParams.w);
+    attenuation *= attenuation;
+    return punctual_light(world_position, light.pos.xyz, light.color.rgb, light.lightParams, roughness, NdotV, N, V, R, F0, diffuseColor) * attenuation;
+}
+
+vec3 dir_light(DirectionalLight light, float roughness, float NdotV, vec3 normal, vec3 view, vec3 R, vec3 F0, vec3 diffuseColor) {
+    vec3 incident_light = light.direction.xyz;
+
+    vec3 half_vector = normalize(incident_light + view);
+    float NoL = saturate(dot(normal, incident_light));
+    float NoH = saturate(dot(normal, half_vector));
+    float LoH = saturate(dot(incident_light, half_vector));
+
+    vec3 diffuse = diffuseColor * Fd_Burley(roughness, NdotV, NoL, LoH);
+    float specularIntensity = 1.0;
+    vec3 specular = specular(F0, roughness, half_vector, NdotV, NoL, NoH, LoH, specularIntensity);
+
+    return (specular + diffuse) * light.color.rgb * NoL;
+}
+
+#ifndef NOT_SHADOW_RECEIVER
+// Returns how much of the light reaches the position, from 0.0 when it is fully in shadow to 1.0
+// when it is fully lit, using a 3x3 percentage closer filter over a tile of the shadow atlas.
+float sample_shadow_tile(float tile, mat4 view_proj, vec3 world_position) {
+    vec4 clip_position = view_proj * vec4(world_position, 1.0);
+    if (clip_position.w <= 0.0) {
+        return 1.0;
+    }
+    vec3 ndc = clip_position.xyz / clip_position.w;
+    if (any(greaterThan(abs(ndc.xy), vec2(1.0))) || ndc.z < 0.0 || ndc.z > 1.0) {
+        return 1.0;
+    }
+
+    vec2 tile_uv = ndc.xy * vec2(0.5, -0.5) + 0.5;
+    vec2 tile_origin = vec2(mod(tile, SHADOW_ATLAS_TILES_PER_ROW), floor(tile / SHADOW_ATLAS_TILES_PER_ROW));
+    // the size of a texel in the uv space of a tile
+    float texel_size = SHADOW_ATLAS_TILES_PER_ROW / SHADOW_ATLAS_SIZE;
+
+    float lit = 0.0;
+    for (int x = -1; x <= 1; ++x) {
+        for (int y = -1; y <= 1; ++y) {
+            // stay inside of the tile, so that neighboring shadow maps aren't sampled
+            vec2 uv = clamp(tile_uv + vec2(x, y) * texel_size, vec2(0.5 * texel_size), vec2(1.0 - 0.5 * texel_size));
+            vec2 atlas_uv = (tile_origin + uv) / SHADOW_ATLAS_TILES_PER_ROW;
+            lit += texture(sampler2DShadow(ShadowAtlas, ShadowAtlas_sampler), vec3(atlas_uv, ndc.z));
+        }
+    }
+    return lit / 9.0;
+}
+
+float point_light_shadow(vec3 world_position, PointLight light, vec3 normal) {
+    if (light.shadowParams.x < 0.0) {
+        return 1.0;
+    }
+    vec3 frag_to_light = normalize(light.pos.xyz - world_position);
+    vec3 position = world_position + frag_to_light * light.shadowParams.y + normal * light.shadowParams.z;
+
+    // pick the cube face that the position is projected on
+    vec3 light_to_position = position - light.pos.xyz;
+    vec3 axis_distance = abs(light_to_position);
+    int face;
+    if (axis_distance.x >= axis_distance.y && axis_distance.x >= axis_distance.z) {
+        face = light_to_position.x > 0.0 ? 0 : 1;
+    } else if (axis_distance.y >= axis_distance.z) {
+        face = light_to_position.y > 0.0 ? 2 : 3;
+    } else {
+        face = light_to_position.z > 0.0 ? 4 : 5;
+    }
+    return sample_shadow_tile(light.shadowParams.x + float(face), light.shadowViewProjs[face], position);
+}
+
+float spot_light_shadow(vec3 world_position, SpotLight light, vec3 normal) {
+    if (light.shadowParams.x < 0.0) {
+        return 1.0;
+    }
+    vec3 frag_to_light = normalize(light.pos.xyz - world_position);
+    vec3 position = world_position + frag_to_light * light.shadowParams.y + normal * light.shadowParams.z;
+    return sample_shadow_tile(light.shadowParams.x, light.shadowViewProj, position);
+}
+
+float dir_light_shadow(vec3 world_position, DirectionalLight light, vec3 normal, float view_depth) {
+    if (light.shadowParams.x < 0.0) {
+        return 1.0;
+    }
+    vec3 position = world_position + light.direction.xyz * light.shadowParams.y + normal * light.shadowParams.z;
+    for (int i = 0; i < int(light.shadowParams.w) && i < MAX_SHADOW_CASCADES; ++i) {
+        if (view_depth < light.cascadeSplits[i]) {
+            return sample_shadow_tile(light.shadowParams.x + float(i), light.cascadeViewProjs[i], position);
+        }
+    }
+    // beyond the last cascade
+    return 1.0;
+}
+#endif
+
+// Returns the light that reaches a surface from all point, directional and spot lights, including
+// their shadows unless NOT_SHADOW_RECEIVER is defined. The shadows use the surface normal without
+// normal mapping.
+vec3 direct_lighting(vec3 world_position, vec3 surface_normal, float roughness, float NdotV, vec3 N, vec3 V, vec3 R, vec3 F0, vec3 diffuseColor) {
+#ifndef NOT_SHADOW_RECEIVER
+    float view_depth = dot(world_position - View[3].xyz, -normalize(View[2].xyz));
+#endif
+
+    vec3 light_accum = vec3(0.0);
+    for (int i = 0; i < int(NumLights.x) && i < MAX_POINT_LIGHTS; ++i) {
+        vec3 light_contrib = point_light(world_position, PointLights[i], roughness, NdotV, N, V, R, F0, diffuseColor);
+#ifndef NOT_SHADOW_RECEIVER
+        light_contrib *= point_light_shadow(world_position, PointLights[i], surface_normal);
+#endif
+        light_accum += light_contrib;
+    }
+    for (int i = 0; i < int(NumLights.y) && i < MAX_DIRECTIONAL_LIGHTS; ++i) {
+        vec3 light_contrib = dir_light(DirectionalLights[i], roughness, NdotV, N, V, R, F0, diffuseColor);
+#ifndef NOT_SHADOW_RECEIVER
+        light_contrib *= dir_light_shadow(world_position, DirectionalLights[i], surface_normal, view_depth);
+#endif
+        light_accum += light_contrib;
+    }
+    for (int i = 0; i < int(NumLights.z) && i < MAX_SPOT_LIGHTS; ++i) {
+        vec3 light_contrib = spot_light(world_position, SpotLights[i], roughness, NdotV, N, V, R, F0, diffuseColor);
+#ifndef NOT_SHADOW_RECEIVER
+        light_contrib *= spot_light_shadow(world_position, SpotLights[i], surface_normal);
+#endif
+        light_accum += light_contrib;
+    }
+    return light_accum;
+}
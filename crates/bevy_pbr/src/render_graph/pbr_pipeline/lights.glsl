@@ -0,0 +1,51 @@
+#define_import_path bevy_pbr::lights
+
+// The lights of bevy_pbr, for shaders to `#import bevy_pbr::lights`.
+
+// reflects the constants defined bevy_pbr/src/render_graph/mod.rs
+const int MAX_POINT_LIGHTS = 10;
+const int MAX_DIRECTIONAL_LIGHTS = 1;
+const int MAX_SPOT_LIGHTS = 10;
+const int MAX_SHADOW_CASCADES = 4;
+const float SHADOW_ATLAS_SIZE = 4096.0;
+const float SHADOW_ATLAS_TILES_PER_ROW = 8.0;
+
+struct PointLight {
+    vec4 pos;
+    vec4 color;
+    vec4 lightParams;
+    // x = first shadow atlas tile, or -1 without shadows, y = depth bias, z = normal bias
+    vec4 shadowParams;
+    // +X, -X, +Y, -Y, +Z, -Z
+    mat4 shadowViewProjs[6];
+};
+ 
+struct DirectionalLight {
+    vec4 direction;
+    vec4 color;
+    // x = first shadow atlas tile, or -1 without shadows, y = depth bias, z = normal bias,
+    // w = cascade count
+    vec4 shadowParams;
+    vec4 cascadeSplits;
+    mat4 cascadeViewProjs[MAX_SHADOW_CASCADES];
+};
+
+struct SpotLight {
+    vec4 pos;
+    vec4 color;
+    vec4 direction;
+    // x = 1 / range^2, y = radius, z = angular attenuation scale, w = angular attenuation offset
+    vec4 lightParams;
+    // x = shadow atlas tile, or -1 without shadows, y = depth bias, z = normal bias
+    vec4 shadowParams;
+    mat4 shadowViewProj;
+};
+
+layout(std140, set = 1, binding = 0) uniform Lights {
+    vec4 AmbientColor;
+    uvec4 NumLights; // x = point lights, y = directional lights, z = spot lights
+    vec4 EnvironmentMapParams; // x = intensity, y = specular mip level count
+    PointLight PointLights[MAX_POINT_LIGHTS];
+    DirectionalLight DirectionalLights[MAX_DIRECTIONAL_LIGHTS];
+    SpotLight SpotLights[MAX_SPOT_LIGHTS];
+};
@@ -13,6 +13,28 @@ use bevy_render::{
 pub const PBR_PIPELINE_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 13148362314012771389);
 
+/// The shader of `bevy_pbr::lights`, with the light structs and the `Lights` uniform.
+pub const PBR_LIGHTS_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 6029185417383725710);
+
+/// The shader of `bevy_pbr::lighting`, with the PBR lighting functions of [`StandardMaterial`].
+///
+/// [`StandardMaterial`]: crate::prelude::StandardMaterial
+pub const PBR_LIGHTING_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 3370813498140126538);
+
+/// Adds the shaders that the PBR shaders and custom materials import.
+pub(crate) fn add_pbr_shader_imports(shaders: &mut Assets<Shader>) {
+    shaders.set_untracked(
+        PBR_LIGHTS_SHADER_HANDLE,
+        Shader::from_glsl(ShaderStage::Fragment, include_str!("lights.glsl")),
+    );
+    shaders.set_untracked(
+        PBR_LIGHTING_SHADER_HANDLE,
+        Shader::from_glsl(ShaderStage::Fragment, include_str!("lighting.glsl")),
+    );
+}
+
 pub(crate) fn build_pbr_pipeline(shaders: &mut Assets<Shader>) -> PipelineDescriptor {
     PipelineDescriptor {
         depth_stencil: Some(DepthStencilState {
@@ -20,11 +20,21 @@ layout(set = 0, binding = 0) uniform CameraViewProj {
 layout(location = 3) out vec4 v_WorldTangent;
 #endif
 
+#ifdef INSTANCING
+layout(location = 4) in vec4 I_Model_0;
+layout(location = 5) in vec4 I_Model_1;
+layout(location = 6) in vec4 I_Model_2;
+layout(location = 7) in vec4 I_Model_3;
+#else
 layout(set = 2, binding = 0) uniform Transform {
     mat4 Model;
 };
+#endif
 
 void main() {
+#ifdef INSTANCING
+    mat4 Model = mat4(I_Model_0, I_Model_1, I_Model_2, I_Model_3);
+#endif
     vec4 world_position = Model * vec4(Vertex_Position, 1.0);
     v_WorldPosition = world_position.xyz;
     v_WorldNormal = mat3(Model) * Vertex_Normal;
@@ -0,0 +1,391 @@
+use crate::{
+    light::NotShadowCaster,
+    render_graph::{
+        uniform, SHADOW_ATLAS_SIZE, SHADOW_ATLAS_TILES_PER_ROW, SHADOW_PIPELINE_HANDLE,
+    },
+};
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::{
+    query::{QueryState, Without},
+    world::{Mut, World},
+};
+use bevy_math::Mat4;
+use bevy_render::{
+    draw::{RenderCommand, Visible},
+    mesh::{Indices, Mesh},
+    pass::{
+        LoadOp, Operations, PassDescriptor, RenderPassDepthStencilAttachment, TextureAttachment,
+    },
+    pipeline::{
+        BindGroupDescriptorId, PipelineCompiler, PipelineDescriptor, PipelineSpecialization,
+        RenderPipelines,
+    },
+    primitives::{Aabb, Frustum},
+    render_graph::{Node, ResourceSlots},
+    renderer::{
+        BindGroup, BufferId, RenderContext, RenderResourceBinding, RenderResourceBindings,
+        RenderResourceContext,
+    },
+    shader::Shader,
+};
+use bevy_transform::components::GlobalTransform;
+use std::fmt;
+
+/// The views rendered into the shadow atlas, written by the [`LightsNode`](super::LightsNode)
+/// every frame
+#[derive(Debug, Default)]
+pub struct ShadowViews {
+    /// The uniform buffer that holds the view projection of each view, `stride` bytes apart
+    pub buffer: Option<BufferId>,
+    pub stride: u64,
+    pub views: Vec<ShadowView>,
+}
+
+/// A view of a light, such as a cascade of a directional light or a cube face of a point light
+#[derive(Debug, Clone)]
+pub struct ShadowView {
+    /// The tile of the shadow atlas that the view is rendered into
+    pub tile: u32,
+    pub view_projection: Mat4,
+}
+
+/// Returns the x and y position and the size in texels of a tile of the shadow atlas
+pub fn shadow_atlas_tile_rect(tile: u32) -> (u32, u32, u32) {
+    let size = SHADOW_ATLAS_SIZE / SHADOW_ATLAS_TILES_PER_ROW;
+    (
+        (tile % SHADOW_ATLAS_TILES_PER_ROW) * size,
+        (tile / SHADOW_ATLAS_TILES_PER_ROW) * size,
+        size,
+    )
+}
+
+type ShadowCasterQuery = (
+    &'static Handle<Mesh>,
+    &'static RenderPipelines,
+    &'static GlobalTransform,
+    &'static Visible,
+    Option<&'static Aabb>,
+);
+
+struct ShadowCaster {
+    pipeline: Handle<PipelineDescriptor>,
+    view_bind_group_descriptor: BindGroupDescriptorId,
+    /// The commands that draw the caster once its pipeline and view are set
+    commands: Vec<RenderCommand>,
+    model: Mat4,
+    aabb: Option<Aabb>,
+}
+
+struct ShadowViewCommands {
+    tile: u32,
+    commands: Vec<RenderCommand>,
+}
+
+/// A Render Graph [Node] that renders the depth of shadow casters into the tile of the shadow
+/// atlas of each [`ShadowView`].
+///
+/// Meshes with the [`NotShadowCaster`] component are skipped, as well as meshes whose [`Aabb`]
+/// is outside of a view.
+pub struct ShadowPassNode {
+    descriptor: PassDescriptor,
+    query_state: Option<QueryState<ShadowCasterQuery, Without<NotShadowCaster>>>,
+    views: Vec<ShadowViewCommands>,
+}
+
+impl fmt::Debug for ShadowPassNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShadowPassNode")
+            .field("descriptor", &self.descriptor)
+            .field("views", &self.views.len())
+            .finish()
+    }
+}
+
+impl Default for ShadowPassNode {
+    fn default() -> Self {
+        ShadowPassNode {
+            descriptor: PassDescriptor {
+                color_attachments: Vec::new(),
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    attachment: TextureAttachment::Name(uniform::SHADOW_ATLAS.to_string()),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+                sample_count: 1,
+            },
+            query_state: None,
+            views: Vec::new(),
+        }
+    }
+}
+
+impl Node for ShadowPassNode {
+    fn prepare(&mut self, world: &mut World) {
+        self.views.clear();
+        let query_state = self
+            .query_state
+            .get_or_insert_with(|| world.query_filtered());
+        let views = &mut self.views;
+        world.resource_scope(|world, mut pipeline_compiler: Mut<PipelineCompiler>| {
+            world.resource_scope(|world, mut pipelines: Mut<Assets<PipelineDescriptor>>| {
+                world.resource_scope(|world, mut shaders: Mut<Assets<Shader>>| {
+                    let render_resource_context = &**world
+                        .get_resource::<Box<dyn RenderResourceContext>>()
+                        .unwrap();
+                    let shadow_views = world.get_resource::<ShadowViews>().unwrap();
+                    let shadow_view_buffer = match shadow_views.buffer {
+                        Some(buffer) if !shadow_views.views.is_empty() => buffer,
+                        _ => return,
+                    };
+                    let meshes = world.get_resource::<Assets<Mesh>>().unwrap();
+                    let shadow_pipeline: Handle<PipelineDescriptor> =
+                        SHADOW_PIPELINE_HANDLE.typed();
+
+                    let mut casters = Vec::new();
+                    for (mesh_handle, render_pipelines, global_transform, visible, aabb) in
+                        query_state.iter(world)
+                    {
+                        if !visible.is_visible {
+                            continue;
+                        }
+                        let mesh = if let Some(mesh) = meshes.get(mesh_handle) {
+                            mesh
+                        } else {
+                            continue;
+                        };
+                        let bindings = &render_pipelines.bindings;
+                        let (vertex_buffer, transform_binding, specialization) = match (
+                            bindings.vertex_attribute_buffer,
+                            bindings.get("Transform"),
+                            render_pipelines.pipelines.first(),
+                        ) {
+                            (Some(vertex_buffer), Some(transform), Some(render_pipeline)) => {
+                                (vertex_buffer, transform, &render_pipeline.specialization)
+                            }
+                            // the mesh or its transform haven't been uploaded yet
+                            _ => continue,
+                        };
+
+                        let shadow_specialization = PipelineSpecialization {
+                            primitive_topology: specialization.primitive_topology,
+                            strip_index_format: specialization.strip_index_format,
+                            vertex_buffer_layout: specialization.vertex_buffer_layout.clone(),
+                            dynamic_bindings: bindings
+                                .iter_dynamic_bindings()
+                                .map(|name| name.to_string())
+                                .collect(),
+                            ..Default::default()
+                        };
+                        let pipeline = pipeline_compiler
+                            .get_specialized_pipeline(&shadow_pipeline, &shadow_specialization)
+                            .unwrap_or_else(|| {
+                                pipeline_compiler.compile_pipeline(
+                                    render_resource_context,
+                                    &mut pipelines,
+                                    &mut shaders,
+                                    &shadow_pipeline,
+                                    &shadow_specialization,
+                                )
+                            });
+                        let layout = pipelines.get(&pipeline).unwrap().get_layout().unwrap();
+
+                        let transform_bind_group = BindGroup::build()
+                            .add_binding(0, transform_binding.clone())
+                            .finish();
+                        render_resource_context.create_bind_group(
+                            layout.get_bind_group(1).unwrap().id,
+                            &transform_bind_group,
+                        );
+                        let mut commands = vec![
+                            RenderCommand::SetBindGroup {
+                                index: 1,
+                                bind_group: transform_bind_group.id,
+                                dynamic_uniform_indices: transform_bind_group
+                                    .dynamic_uniform_indices
+                                    .clone(),
+                            },
+                            RenderCommand::SetVertexBuffer {
+                                slot: 0,
+                                buffer: vertex_buffer,
+                                offset: 0,
+                            },
+                        ];
+                        match (bindings.index_buffer, mesh.indices()) {
+                            (Some((buffer, index_format)), Some(indices)) => {
+                                let index_count = match indices {
+                                    Indices::U16(indices) => indices.len(),
+                                    Indices::U32(indices) => indices.len(),
+                                };
+                                commands.push(RenderCommand::SetIndexBuffer {
+                                    buffer,
+                                    offset: 0,
+                                    index_format,
+                                });
+                                commands.push(RenderCommand::DrawIndexed {
+                                    indices: 0..index_count as u32,
+                                    base_vertex: 0,
+                                    instances: 0..1,
+                                });
+                            }
+                            _ => commands.push(RenderCommand::Draw {
+                                vertices: 0..mesh.count_vertices() as u32,
+                                instances: 0..1,
+                            }),
+                        }
+
+                        casters.push(ShadowCaster {
+                            view_bind_group_descriptor: layout.get_bind_group(0).unwrap().id,
+                            pipeline,
+                            commands,
+                            model: global_transform.compute_matrix(),
+                            aabb: aabb.copied(),
+                        });
+                    }
+
+                    for (i, shadow_view) in shadow_views.views.iter().enumerate() {
+                        let offset = i as u64 * shadow_views.stride;
+                        let view_bind_group = BindGroup::build()
+                            .add_binding(
+                                0,
+                                RenderResourceBinding::Buffer {
+                                    buffer: shadow_view_buffer,
+                                    range: offset..offset + std::mem::size_of::<Mat4>() as u64,
+                                    dynamic_index: None,
+                                },
+                            )
+                            .finish();
+                        let frustum = Frustum::from_view_projection(&shadow_view.view_projection);
+
+                        let mut commands = Vec::new();
+                        for caster in casters.iter() {
+                            if let Some(aabb) = caster.aabb {
+                                if !frustum.intersects_obb(&aabb, &caster.model) {
+                                    continue;
+                                }
+                            }
+                            render_resource_context.create_bind_group(
+                                caster.view_bind_group_descriptor,
+                                &view_bind_group,
+                            );
+                            commands.push(RenderCommand::SetPipeline {
+                                pipeline: caster.pipeline.clone_weak(),
+                            });
+                            commands.push(RenderCommand::SetBindGroup {
+                                index: 0,
+                                bind_group: view_bind_group.id,
+                                dynamic_uniform_indices: None,
+                            });
+                            commands.extend(caster.commands.iter().cloned());
+                        }
+                        views.push(ShadowViewCommands {
+                            tile: shadow_view.tile,
+                            commands,
+                        });
+                    }
+                });
+            });
+        });
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        _input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        if self.views.is_empty() {
+            return;
+        }
+
+        let render_resource_bindings = world.get_resource::<RenderResourceBindings>().unwrap();
+        let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
+        let views = &mut self.views;
+        render_context.begin_pass(
+            &self.descriptor,
+            render_resource_bindings,
+            &mut |render_pass| {
+                let mut layout = None;
+                for view in views.drain(..) {
+                    let (x, y, size) = shadow_atlas_tile_rect(view.tile);
+                    render_pass.set_viewport(
+                        x as f32,
+                        y as f32,
+                        size as f32,
+                        size as f32,
+                        0.0,
+                        1.0,
+                    );
+                    render_pass.set_scissor_rect(x, y, size, size);
+                    for render_command in view.commands {
+                        match render_command {
+                            RenderCommand::SetPipeline { pipeline } => {
+                                render_pass.set_pipeline(&pipeline);
+                                layout = pipelines
+                                    .get(&pipeline)
+                                    .and_then(|descriptor| descriptor.get_layout());
+                            }
+                            RenderCommand::SetBindGroup {
+                                index,
+                                bind_group,
+                                dynamic_uniform_indices,
+                            } => {
+                                let bind_group_descriptor =
+                                    layout.and_then(|layout| layout.get_bind_group(index));
+                                if let Some(bind_group_descriptor) = bind_group_descriptor {
+                                    render_pass.set_bind_group(
+                                        index,
+                                        bind_group_descriptor.id,
+                                        bind_group,
+                                        dynamic_uniform_indices.as_deref(),
+                                    );
+                                }
+                            }
+                            RenderCommand::SetVertexBuffer {
+                                slot,
+                                buffer,
+                                offset,
+                            } => render_pass.set_vertex_buffer(slot, buffer, offset),
+                            RenderCommand::SetIndexBuffer {
+                                buffer,
+                                offset,
+                                index_format,
+                            } => render_pass.set_index_buffer(buffer, offset, index_format),
+                            RenderCommand::DrawIndexed {
+                                indices,
+                                base_vertex,
+                                instances,
+                            } => render_pass.draw_indexed(indices, base_vertex, instances),
+                            RenderCommand::Draw {
+                                vertices,
+                                instances,
+                            } => render_pass.draw(vertices, instances),
+                        }
+                    }
+                }
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tiles_cover_the_atlas() {
+        let tile_size = SHADOW_ATLAS_SIZE / SHADOW_ATLAS_TILES_PER_ROW;
+        assert_eq!(shadow_atlas_tile_rect(0), (0, 0, tile_size));
+        assert_eq!(shadow_atlas_tile_rect(1), (tile_size, 0, tile_size));
+        assert_eq!(
+            shadow_atlas_tile_rect(SHADOW_ATLAS_TILES_PER_ROW),
+            (0, tile_size, tile_size)
+        );
+        let last = SHADOW_ATLAS_TILES_PER_ROW * SHADOW_ATLAS_TILES_PER_ROW - 1;
+        let (x, y, size) = shadow_atlas_tile_rect(last);
+        assert_eq!((x + size, y + size), (SHADOW_ATLAS_SIZE, SHADOW_ATLAS_SIZE));
+    }
+}
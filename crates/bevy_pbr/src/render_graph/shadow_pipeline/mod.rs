@@ -0,0 +1,42 @@
+use bevy_asset::{Assets, HandleUntyped};
+use bevy_reflect::TypeUuid;
+use bevy_render::{
+    pipeline::{
+        CompareFunction, DepthBiasState, DepthStencilState, PipelineDescriptor, StencilFaceState,
+        StencilState,
+    },
+    shader::{Shader, ShaderStage, ShaderStages},
+    texture::TextureFormat,
+};
+
+pub const SHADOW_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 9138716259176140733);
+
+/// A depth only pipeline that renders shadow casters into the shadow atlas
+pub(crate) fn build_shadow_pipeline(shaders: &mut Assets<Shader>) -> PipelineDescriptor {
+    PipelineDescriptor {
+        depth_stencil: Some(DepthStencilState {
+            format: TextureFormat::Depth32Float,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::LessEqual,
+            stencil: StencilState {
+                front: StencilFaceState::IGNORE,
+                back: StencilFaceState::IGNORE,
+                read_mask: 0,
+                write_mask: 0,
+            },
+            bias: DepthBiasState {
+                constant: 2,
+                slope_scale: 2.0,
+                clamp: 0.0,
+            },
+        }),
+        ..PipelineDescriptor::new(ShaderStages {
+            vertex: shaders.add(Shader::from_glsl(
+                ShaderStage::Vertex,
+                include_str!("shadow.vert"),
+            )),
+            fragment: None,
+        })
+    }
+}
@@ -0,0 +1,199 @@
+use super::{ActiveCameras, CameraProjection, RenderLayers};
+use crate::{
+    draw::{NoFrustumCulling, OutsideFrustum},
+    mesh::Mesh,
+    primitives::{Aabb, Frustum},
+};
+use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    event::EventReader,
+    query::{ChangeTrackers, With, Without},
+    system::{Commands, Query, Res},
+};
+use bevy_transform::components::GlobalTransform;
+use bevy_utils::HashSet;
+
+/// Adds an [`Aabb`] to mesh entities once their mesh is loaded, and recomputes it when the mesh
+/// handle or the mesh asset changes.
+#[allow(clippy::type_complexity)]
+pub fn calculate_bounds_system(
+    mut commands: Commands,
+    meshes: Res<Assets<Mesh>>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    without_aabb_query: Query<(Entity, &Handle<Mesh>), (Without<Aabb>, Without<NoFrustumCulling>)>,
+    mut with_aabb_query: Query<
+        (&Handle<Mesh>, ChangeTrackers<Handle<Mesh>>, &mut Aabb),
+        Without<NoFrustumCulling>,
+    >,
+) {
+    let mut modified_meshes = HashSet::default();
+    for event in mesh_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            modified_meshes.insert(handle.clone_weak());
+        }
+    }
+
+    for (entity, mesh_handle) in without_aabb_query.iter() {
+        if let Some(aabb) = meshes.get(mesh_handle).and_then(|mesh| mesh.compute_aabb()) {
+            commands.entity(entity).insert(aabb);
+        }
+    }
+
+    for (mesh_handle, mesh_tracker, mut aabb) in with_aabb_query.iter_mut() {
+        if !mesh_tracker.is_changed() && !modified_meshes.contains(mesh_handle) {
+            continue;
+        }
+        if let Some(new_aabb) = meshes.get(mesh_handle).and_then(|mesh| mesh.compute_aabb()) {
+            *aabb = new_aabb;
+        }
+    }
+}
+
+/// Updates the [`Frustum`] of cameras from their projection and [`GlobalTransform`].
+pub fn update_frusta_system<T: CameraProjection + Component>(
+    mut query: Query<(&GlobalTransform, &T, &mut Frustum)>,
+) {
+    for (transform, projection, mut frustum) in query.iter_mut() {
+        let view_projection =
+            projection.get_projection_matrix() * transform.compute_matrix().inverse();
+        *frustum = Frustum::from_view_projection(&view_projection);
+    }
+}
+
+/// Marks mesh entities with an [`Aabb`] as [`OutsideFrustum`] when they are outside of the
+/// frustum of every active camera that renders their [`RenderLayers`].
+///
+/// Active cameras without a [`Frustum`] are ignored. Without any camera to cull against, no entity
+/// is marked. Entities with [`NoFrustumCulling`] are culled by other systems, which own their
+/// [`OutsideFrustum`] marker.
+#[allow(clippy::type_complexity)]
+pub fn mesh_frustum_culling_system(
+    mut commands: Commands,
+    active_cameras: Res<ActiveCameras>,
+    camera_query: Query<(&Frustum, Option<&RenderLayers>)>,
+    culled_query: Query<Entity, (With<OutsideFrustum>, Without<NoFrustumCulling>)>,
+    mesh_query: Query<
+        (Entity, &Aabb, &GlobalTransform, Option<&RenderLayers>),
+        Without<NoFrustumCulling>,
+    >,
+) {
+    let cameras = active_cameras
+        .iter()
+        .filter_map(|active_camera| active_camera.entity)
+        .filter_map(|entity| camera_query.get(entity).ok())
+        .map(|(frustum, layers)| (frustum, layers.copied().unwrap_or_default()))
+        .collect::<Vec<_>>();
+    if cameras.is_empty() {
+        for entity in culled_query.iter() {
+            commands.entity(entity).remove::<OutsideFrustum>();
+        }
+        return;
+    }
+
+    for (entity, aabb, transform, layers) in mesh_query.iter() {
+        let layers = layers.copied().unwrap_or_default();
+        let model = transform.compute_matrix();
+        let inside = cameras.iter().any(|(frustum, camera_layers)| {
+            camera_layers.intersects(&layers) && frustum.intersects_obb(aabb, &model)
+        });
+
+        let culled = culled_query.get(entity).is_ok();
+        if inside && culled {
+            commands.entity(entity).remove::<OutsideFrustum>();
+        } else if !inside && !culled {
+            commands.entity(entity).insert(OutsideFrustum);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pipeline::PrimitiveTopology;
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin};
+    use bevy_core::CorePlugin;
+    use bevy_ecs::{
+        schedule::{Stage, SystemStage},
+        world::World,
+    };
+    use bevy_math::Vec3;
+
+    fn triangle(half_size: f32) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [-half_size, -half_size, 0.0],
+                [half_size, -half_size, 0.0],
+                [half_size, half_size, 0.0],
+            ],
+        );
+        mesh
+    }
+
+    #[test]
+    fn culling_is_cleared_without_cameras() {
+        let mut world = World::default();
+        world.insert_resource(ActiveCameras::default());
+        let entity = world
+            .spawn()
+            .insert_bundle((Aabb::default(), GlobalTransform::default(), OutsideFrustum))
+            .id();
+
+        SystemStage::single(mesh_frustum_culling_system).run(&mut world);
+        assert!(world.get::<OutsideFrustum>(entity).is_none());
+    }
+
+    #[test]
+    fn entities_without_frustum_culling_keep_their_marker() {
+        let mut world = World::default();
+        world.insert_resource(ActiveCameras::default());
+        // like sprites, which are culled by their own system
+        let entity = world
+            .spawn()
+            .insert_bundle((
+                Aabb::default(),
+                GlobalTransform::default(),
+                NoFrustumCulling,
+                OutsideFrustum,
+            ))
+            .id();
+
+        SystemStage::single(mesh_frustum_culling_system).run(&mut world);
+        assert!(world.get::<OutsideFrustum>(entity).is_some());
+    }
+
+    #[test]
+    fn bounds_are_recomputed_when_the_mesh_changes() {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Mesh>()
+            .add_system(calculate_bounds_system);
+        let half_extents =
+            |app: &App, entity| app.world.get::<Aabb>(entity).map(|aabb| aabb.half_extents);
+
+        let mut meshes = app.world.get_resource_mut::<Assets<Mesh>>().unwrap();
+        let mesh = meshes.add(triangle(1.0));
+        let entity = app.world.spawn().insert(mesh.clone()).id();
+        app.update();
+        assert_eq!(half_extents(&app, entity), Some(Vec3::new(1.0, 1.0, 0.0)));
+
+        // the mesh asset is modified
+        let mut meshes = app.world.get_resource_mut::<Assets<Mesh>>().unwrap();
+        *meshes.get_mut(&mesh).unwrap() = triangle(2.0);
+        app.update();
+        app.update();
+        assert_eq!(half_extents(&app, entity), Some(Vec3::new(2.0, 2.0, 0.0)));
+
+        // the entity is given another mesh
+        let mut meshes = app.world.get_resource_mut::<Assets<Mesh>>().unwrap();
+        let other_mesh = meshes.add(triangle(3.0));
+        app.world.entity_mut(entity).insert(other_mesh);
+        app.update();
+        assert_eq!(half_extents(&app, entity), Some(Vec3::new(3.0, 3.0, 0.0)));
+    }
+}
@@ -13,12 +13,13 @@ use bevy_ecs::{
     reflect::ReflectComponent,
     system::{Query, Res, ResMut, SystemParam},
 };
+use bevy_math::Mat4;
 use bevy_reflect::Reflect;
 use std::{marker::PhantomData, ops::Range, sync::Arc};
 use thiserror::Error;
 
 /// A queued command for the renderer
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum RenderCommand {
     SetPipeline {
         pipeline: Handle<PipelineDescriptor>,
@@ -77,18 +78,30 @@ impl Default for Visible {
 #[reflect(Component)]
 pub struct OutsideFrustum;
 
+/// A component that excludes a mesh entity from frustum culling, for meshes that are drawn at a
+/// different size than their vertex positions, or that should always be drawn. Their
+/// [`OutsideFrustum`] marker is left to other systems, like the culling of sprites.
+#[derive(Debug, Default, Clone, Reflect)]
+#[reflect(Component)]
+pub struct NoFrustumCulling;
+
 /// A component that indicates how to draw an entity.
 #[derive(Debug, Clone, Reflect)]
 #[reflect(Component)]
 pub struct Draw {
     #[reflect(ignore)]
     pub render_commands: Vec<RenderCommand>,
+    /// The model matrix of the entity when its pipelines are drawn with GPU instancing, see
+    /// [`InstancedPipelines`](crate::pipeline::InstancedPipelines). Entities with the same render commands are then drawn together.
+    #[reflect(ignore)]
+    pub instance: Option<Mat4>,
 }
 
 impl Default for Draw {
     fn default() -> Self {
         Self {
             render_commands: Default::default(),
+            instance: None,
         }
     }
 }
@@ -96,6 +109,7 @@ impl Default for Draw {
 impl Draw {
     pub fn clear_render_commands(&mut self) {
         self.render_commands.clear();
+        self.instance = None;
     }
 
     pub fn set_pipeline(&mut self, pipeline: &Handle<PipelineDescriptor>) {
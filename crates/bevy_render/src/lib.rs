@@ -6,6 +6,8 @@ pub mod entity;
 pub mod mesh;
 pub mod pass;
 pub mod pipeline;
+pub mod post_process;
+pub mod primitives;
 pub mod render_graph;
 pub mod renderer;
 pub mod shader;
@@ -18,27 +20,28 @@ use bevy_ecs::{
 };
 use bevy_transform::TransformSystem;
 use bevy_utils::tracing::warn;
-use draw::{OutsideFrustum, Visible};
+use draw::{NoFrustumCulling, OutsideFrustum, Visible};
 
 pub use once_cell;
 
 pub mod prelude {
     #[doc(hidden)]
     pub use crate::{
-        base::Msaa,
+        base::{Hdr, Msaa},
         color::Color,
         draw::{Draw, Visible},
         entity::*,
         mesh::{shape, Mesh},
         pass::ClearColor,
         pipeline::RenderPipelines,
+        post_process::{Bloom, ColorGrading, Tonemapping, TonemappingOperator},
         shader::Shader,
         texture::Texture,
     };
 }
 
 use crate::prelude::*;
-use base::Msaa;
+use base::{Hdr, Msaa};
 use bevy_app::prelude::*;
 use bevy_asset::{AddAsset, AssetStage};
 use bevy_ecs::schedule::{StageLabel, SystemLabel};
@@ -47,29 +50,41 @@ use camera::{
     RenderLayers, ScalingMode, VisibleEntities, WindowOrigin,
 };
 use pipeline::{
-    IndexFormat, PipelineCompiler, PipelineDescriptor, PipelineSpecialization, PrimitiveTopology,
-    ShaderSpecialization, VertexBufferLayout,
+    ComputePipelineDescriptor, IndexFormat, InstancedPipelines, PipelineCompiler,
+    PipelineDescriptor, PipelineSpecialization, PrimitiveTopology, ShaderSpecialization,
+    VertexBufferLayout,
 };
+use post_process::{Bloom, ColorGrading, Tonemapping, TonemappingOperator};
+use primitives::{Aabb, Frustum};
 use render_graph::{
     base::{self, BaseRenderGraphConfig, MainPass},
-    RenderGraph,
+    ComputeReadbacks, RenderGraph,
 };
 use renderer::{AssetRenderResourceBindings, RenderResourceBindings, RenderResourceContext};
 use shader::ShaderLoader;
+#[cfg(feature = "dds")]
+use texture::DdsTextureLoader;
 #[cfg(feature = "hdr")]
 use texture::HdrTextureLoader;
-#[cfg(any(
-    feature = "png",
+#[cfg(any(feature = "png", feature = "tga", feature = "jpeg", feature = "bmp"))]
+use texture::ImageTextureLoader;
+#[cfg(feature = "ktx2")]
+use texture::Ktx2TextureLoader;
+#[cfg(feature = "png")]
+use texture::PngTextureSaver;
+#[cfg(all(
     feature = "dds",
-    feature = "tga",
-    feature = "jpeg",
-    feature = "bmp"
+    not(target_arch = "wasm32"),
+    not(target_os = "android")
 ))]
-use texture::ImageTextureLoader;
+use texture::TextureCompressionProcessor;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
 pub enum RenderSystem {
     VisibleEntities,
+    UpdateFrusta,
+    DecompressTextures,
+    DrawRenderPipelines,
 }
 
 /// The names of "render" App stages
@@ -103,20 +118,34 @@ impl Default for RenderPlugin {
 
 impl Plugin for RenderPlugin {
     fn build(&self, app: &mut App) {
-        #[cfg(any(
-            feature = "png",
-            feature = "dds",
-            feature = "tga",
-            feature = "jpeg",
-            feature = "bmp"
-        ))]
+        #[cfg(any(feature = "png", feature = "tga", feature = "jpeg", feature = "bmp"))]
         {
             app.init_asset_loader::<ImageTextureLoader>();
         }
+        #[cfg(feature = "png")]
+        {
+            app.init_asset_saver::<PngTextureSaver>();
+        }
         #[cfg(feature = "hdr")]
         {
             app.init_asset_loader::<HdrTextureLoader>();
         }
+        #[cfg(feature = "ktx2")]
+        {
+            app.init_asset_loader::<Ktx2TextureLoader>();
+        }
+        #[cfg(feature = "dds")]
+        {
+            app.init_asset_loader::<DdsTextureLoader>();
+        }
+        #[cfg(all(
+            feature = "dds",
+            not(target_arch = "wasm32"),
+            not(target_os = "android")
+        ))]
+        {
+            app.add_asset_processor(TextureCompressionProcessor);
+        }
 
         app.add_stage_after(
             AssetStage::AssetEvents,
@@ -148,11 +177,15 @@ impl Plugin for RenderPlugin {
         .add_asset::<Texture>()
         .add_asset::<Shader>()
         .add_asset::<PipelineDescriptor>()
+        .add_asset::<ComputePipelineDescriptor>()
         .register_type::<Camera>()
         .register_type::<DepthCalculation>()
         .register_type::<Draw>()
         .register_type::<Visible>()
         .register_type::<OutsideFrustum>()
+        .register_type::<NoFrustumCulling>()
+        .register_type::<Aabb>()
+        .register_type::<Frustum>()
         .register_type::<RenderPipelines>()
         .register_type::<OrthographicProjection>()
         .register_type::<PerspectiveProjection>()
@@ -167,10 +200,17 @@ impl Plugin for RenderPlugin {
         .register_type::<ScalingMode>()
         .register_type::<VertexBufferLayout>()
         .register_type::<WindowOrigin>()
+        .register_type::<Tonemapping>()
+        .register_type::<TonemappingOperator>()
+        .register_type::<Bloom>()
+        .register_type::<ColorGrading>()
         .init_resource::<ClearColor>()
         .init_resource::<RenderGraph>()
         .init_resource::<PipelineCompiler>()
         .init_resource::<Msaa>()
+        .init_resource::<Hdr>()
+        .init_resource::<InstancedPipelines>()
+        .init_resource::<ComputeReadbacks>()
         .init_resource::<RenderResourceBindings>()
         .init_resource::<AssetRenderResourceBindings>()
         .init_resource::<ActiveCameras>()
@@ -191,6 +231,25 @@ impl Plugin for RenderPlugin {
                 .label(RenderSystem::VisibleEntities)
                 .after(TransformSystem::TransformPropagate),
         )
+        .add_system_to_stage(CoreStage::PostUpdate, camera::calculate_bounds_system)
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            camera::update_frusta_system::<OrthographicProjection>
+                .label(RenderSystem::UpdateFrusta)
+                .after(TransformSystem::TransformPropagate),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            camera::update_frusta_system::<PerspectiveProjection>
+                .label(RenderSystem::UpdateFrusta)
+                .after(TransformSystem::TransformPropagate),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            camera::mesh_frustum_culling_system
+                .after(RenderSystem::UpdateFrusta)
+                .before(RenderSystem::VisibleEntities),
+        )
         .add_system_to_stage(RenderStage::RenderResource, shader::shader_update_system)
         .add_system_to_stage(
             RenderStage::RenderResource,
@@ -198,13 +257,20 @@ impl Plugin for RenderPlugin {
         )
         .add_system_to_stage(
             RenderStage::RenderResource,
-            Texture::texture_resource_system,
+            texture::decompress_unsupported_textures_system.label(RenderSystem::DecompressTextures),
+        )
+        .add_system_to_stage(
+            RenderStage::RenderResource,
+            Texture::texture_resource_system.after(RenderSystem::DecompressTextures),
         )
         .add_system_to_stage(
             RenderStage::RenderGraphSystems,
             render_graph::render_graph_schedule_executor_system.exclusive_system(),
         )
-        .add_system_to_stage(RenderStage::Draw, pipeline::draw_render_pipelines_system)
+        .add_system_to_stage(
+            RenderStage::Draw,
+            pipeline::draw_render_pipelines_system.label(RenderSystem::DrawRenderPipelines),
+        )
         .add_system_to_stage(RenderStage::PostRender, shader::clear_shader_defs_system);
 
         if let Some(ref config) = self.base_render_graph_config {
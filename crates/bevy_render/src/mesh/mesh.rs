@@ -1,10 +1,14 @@
 mod conversions;
+mod tangents;
+
+pub use tangents::GenerateTangentsError;
 
 use crate::{
     pipeline::{IndexFormat, PrimitiveTopology, RenderPipelines, VertexFormat},
+    primitives::Aabb,
     renderer::{BufferInfo, BufferUsage, RenderResourceContext, RenderResourceId},
 };
-use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_asset::{AssetEvent, AssetSize, Assets, Handle};
 use bevy_core::cast_slice;
 use bevy_ecs::{
     entity::Entity,
@@ -469,6 +473,22 @@ impl Mesh {
 
         self.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
     }
+
+    /// Computes the bounding box of the [`Mesh::ATTRIBUTE_POSITION`] of a mesh.
+    ///
+    /// Returns `None` if the mesh has no `float3` positions.
+    pub fn compute_aabb(&self) -> Option<Aabb> {
+        let positions = self.attribute(Mesh::ATTRIBUTE_POSITION)?.as_float3()?;
+        let (minimum, maximum) = positions.iter().map(|&p| Vec3::from(p)).fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(minimum, maximum), p| (minimum.min(p), maximum.max(p)),
+        );
+        if positions.is_empty() {
+            None
+        } else {
+            Some(Aabb::from_min_max(minimum, maximum))
+        }
+    }
 }
 
 fn face_normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
@@ -496,6 +516,18 @@ fn remove_current_mesh_resources(
     remove_resource_save(render_resource_context, handle, INDEX_BUFFER_ASSET_INDEX);
 }
 
+impl AssetSize for Mesh {
+    fn asset_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self
+                .attributes
+                .values()
+                .map(|values| values.get_bytes().len())
+                .sum::<usize>()
+            + self.get_index_buffer_bytes().map_or(0, |bytes| bytes.len())
+    }
+}
+
 #[derive(Default)]
 pub struct MeshEntities {
     entities: HashSet<Entity>,
@@ -534,6 +566,7 @@ pub fn mesh_resource_provider_system(
                 // events are ordered so future modification events are ok
                 changed_meshes.remove(handle);
             }
+            AssetEvent::LoadedWithDependencies { .. } => {}
         }
     }
 
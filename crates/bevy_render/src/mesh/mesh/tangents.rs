@@ -0,0 +1,418 @@
+use super::{Mesh, VertexAttributeValues};
+use crate::pipeline::{PrimitiveTopology, VertexFormat};
+use bevy_math::{Vec2, Vec3};
+use bevy_utils::HashMap;
+use thiserror::Error;
+
+/// An error that occurs when generating the tangents of a [`Mesh`].
+#[derive(Debug, Clone, Error)]
+pub enum GenerateTangentsError {
+    #[error("cannot generate tangents for {0:?}, only `TriangleList`s are supported")]
+    UnsupportedTopology(PrimitiveTopology),
+    #[error("missing vertex attribute '{0}'")]
+    MissingVertexAttribute(&'static str),
+    #[error("vertex attribute '{0}' has the unsupported format {1:?}")]
+    InvalidVertexAttributeFormat(&'static str, VertexFormat),
+}
+
+impl Mesh {
+    /// Generates the [`Mesh::ATTRIBUTE_TANGENT`] of a mesh with the MikkTSpace algorithm, from its
+    /// positions, normals and [`Mesh::ATTRIBUTE_UV_0`] texture coordinates.
+    ///
+    /// The bitangent, `cross(normal, tangent.xyz) * tangent.w`, points towards decreasing V like
+    /// the tangents of glTF files. Only `TriangleList`s are supported, indexed or not. Vertices
+    /// shared by faces with mirrored texture coordinates keep the tangent of one of them.
+    pub fn generate_tangents(&mut self) -> Result<(), GenerateTangentsError> {
+        let tangents = generate_tangents(self)?;
+        self.set_attribute(Mesh::ATTRIBUTE_TANGENT, tangents);
+        Ok(())
+    }
+}
+
+struct Triangle {
+    /// The vertex indices of the corners.
+    corners: [usize; 3],
+    /// The vertex indices of the corners, where identical vertices share the same index.
+    welded: [usize; 3],
+    /// The normalized derivative of the position with respect to U.
+    tangent: Vec3,
+    orientation_preserving: bool,
+    /// The texture coordinates are degenerate, so the triangle takes the orientation of the first
+    /// group it joins and does not contribute to its tangent.
+    group_with_any: bool,
+    /// Two corners are the same vertex.
+    degenerate: bool,
+    /// The triangles sharing the edge from each corner to the next.
+    neighbors: [Option<usize>; 3],
+    /// The group of each corner.
+    groups: [Option<usize>; 3],
+}
+
+impl Triangle {
+    fn corner_of(&self, welded_vertex: usize) -> Option<usize> {
+        self.welded
+            .iter()
+            .position(|&vertex| vertex == welded_vertex)
+    }
+}
+
+/// The triangles around a vertex which are connected by their edges and have the same
+/// orientation, which share a tangent.
+struct Group {
+    welded_vertex: usize,
+    orientation_preserving: bool,
+    triangles: Vec<usize>,
+}
+
+fn not_zero(value: f32) -> bool {
+    value.abs() > f32::MIN_POSITIVE
+}
+
+fn project_on_plane(vector: Vec3, normal: Vec3) -> Vec3 {
+    (vector - normal * normal.dot(vector)).normalize_or_zero()
+}
+
+fn generate_tangents(mesh: &Mesh) -> Result<Vec<[f32; 4]>, GenerateTangentsError> {
+    if mesh.primitive_topology() != PrimitiveTopology::TriangleList {
+        return Err(GenerateTangentsError::UnsupportedTopology(
+            mesh.primitive_topology(),
+        ));
+    }
+    let positions = match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(values)) => values,
+        Some(values) => {
+            return Err(GenerateTangentsError::InvalidVertexAttributeFormat(
+                Mesh::ATTRIBUTE_POSITION,
+                values.into(),
+            ))
+        }
+        None => {
+            return Err(GenerateTangentsError::MissingVertexAttribute(
+                Mesh::ATTRIBUTE_POSITION,
+            ))
+        }
+    };
+    let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+        Some(VertexAttributeValues::Float32x3(values)) => values,
+        Some(values) => {
+            return Err(GenerateTangentsError::InvalidVertexAttributeFormat(
+                Mesh::ATTRIBUTE_NORMAL,
+                values.into(),
+            ))
+        }
+        None => {
+            return Err(GenerateTangentsError::MissingVertexAttribute(
+                Mesh::ATTRIBUTE_NORMAL,
+            ))
+        }
+    };
+    let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0) {
+        Some(VertexAttributeValues::Float32x2(values)) => values,
+        Some(values) => {
+            return Err(GenerateTangentsError::InvalidVertexAttributeFormat(
+                Mesh::ATTRIBUTE_UV_0,
+                values.into(),
+            ))
+        }
+        None => {
+            return Err(GenerateTangentsError::MissingVertexAttribute(
+                Mesh::ATTRIBUTE_UV_0,
+            ))
+        }
+    };
+
+    let position = |vertex: usize| Vec3::from(positions[vertex]);
+    let normal = |vertex: usize| Vec3::from(normals[vertex]);
+    // MikkTSpace expects the origin of the texture coordinates at the bottom left
+    let uv = |vertex: usize| Vec2::new(uvs[vertex][0], 1.0 - uvs[vertex][1]);
+
+    let mut welded_vertices = HashMap::default();
+    let welded = (0..positions.len())
+        .map(|vertex| {
+            let mut key = [0; 8];
+            let values = positions[vertex]
+                .iter()
+                .chain(normals[vertex].iter())
+                .chain(uvs[vertex].iter());
+            for (bits, value) in key.iter_mut().zip(values) {
+                *bits = value.to_bits();
+            }
+            *welded_vertices.entry(key).or_insert(vertex)
+        })
+        .collect::<Vec<_>>();
+
+    let corners = match mesh.indices() {
+        Some(indices) => indices.iter().collect::<Vec<_>>(),
+        None => (0..positions.len()).collect(),
+    };
+    let mut triangles = corners
+        .chunks_exact(3)
+        .map(|corners| {
+            let corners = [corners[0], corners[1], corners[2]];
+            let welded = [welded[corners[0]], welded[corners[1]], welded[corners[2]]];
+            let d1 = position(corners[1]) - position(corners[0]);
+            let d2 = position(corners[2]) - position(corners[0]);
+            let t21 = uv(corners[1]) - uv(corners[0]);
+            let t31 = uv(corners[2]) - uv(corners[0]);
+            let signed_area = t21.x * t31.y - t21.y * t31.x;
+            let os = d1 * t31.y - d2 * t21.y;
+            let ot = d2 * t21.x - d1 * t31.x;
+            let orientation_preserving = signed_area > 0.0;
+
+            let mut tangent = Vec3::ZERO;
+            let mut group_with_any = true;
+            if not_zero(signed_area) {
+                let sign = if orientation_preserving { 1.0 } else { -1.0 };
+                let (length_s, length_t) = (os.length(), ot.length());
+                if not_zero(length_s) {
+                    tangent = os * (sign / length_s);
+                }
+                group_with_any = !(not_zero(length_s / signed_area.abs())
+                    && not_zero(length_t / signed_area.abs()));
+            }
+
+            Triangle {
+                corners,
+                welded,
+                tangent,
+                orientation_preserving,
+                group_with_any,
+                degenerate: welded[0] == welded[1]
+                    || welded[0] == welded[2]
+                    || welded[1] == welded[2],
+                neighbors: [None; 3],
+                groups: [None; 3],
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // pair the edges of triangles with the opposite edge of another triangle
+    let mut edges = HashMap::default();
+    for (index, triangle) in triangles.iter().enumerate() {
+        if triangle.degenerate {
+            continue;
+        }
+        for corner in 0..3 {
+            let edge = (triangle.welded[corner], triangle.welded[(corner + 1) % 3]);
+            edges.entry(edge).or_insert((index, corner));
+        }
+    }
+    for index in 0..triangles.len() {
+        if triangles[index].degenerate {
+            continue;
+        }
+        for corner in 0..3 {
+            if triangles[index].neighbors[corner].is_some() {
+                continue;
+            }
+            let welded = triangles[index].welded;
+            let opposite = (welded[(corner + 1) % 3], welded[corner]);
+            if let Some(&(other, other_corner)) = edges.get(&opposite) {
+                if other != index && triangles[other].neighbors[other_corner].is_none() {
+                    triangles[index].neighbors[corner] = Some(other);
+                    triangles[other].neighbors[other_corner] = Some(index);
+                }
+            }
+        }
+    }
+
+    let mut groups = Vec::new();
+    for index in 0..triangles.len() {
+        if triangles[index].degenerate || triangles[index].group_with_any {
+            continue;
+        }
+        for corner in 0..3 {
+            if triangles[index].groups[corner].is_some() {
+                continue;
+            }
+            let mut group = Group {
+                welded_vertex: triangles[index].welded[corner],
+                orientation_preserving: triangles[index].orientation_preserving,
+                triangles: Vec::new(),
+            };
+            let mut pending = vec![index];
+            while let Some(index) = pending.pop() {
+                let triangle = &mut triangles[index];
+                let corner = match triangle.corner_of(group.welded_vertex) {
+                    Some(corner) => corner,
+                    None => continue,
+                };
+                if triangle.groups[corner].is_some() {
+                    continue;
+                }
+                if triangle.group_with_any && triangle.groups.iter().all(Option::is_none) {
+                    triangle.orientation_preserving = group.orientation_preserving;
+                }
+                if triangle.orientation_preserving != group.orientation_preserving {
+                    continue;
+                }
+                triangle.groups[corner] = Some(groups.len());
+                group.triangles.push(index);
+                pending.extend(triangle.neighbors[(corner + 2) % 3]);
+                pending.extend(triangle.neighbors[corner]);
+            }
+            groups.push(group);
+        }
+    }
+
+    let mut tangents = vec![[1.0, 0.0, 0.0, 1.0]; positions.len()];
+    for group in groups.iter() {
+        // average the tangents of the triangles, weighted by their angle at the vertex
+        let mut sum = Vec3::ZERO;
+        for &index in group.triangles.iter() {
+            let triangle = &triangles[index];
+            if triangle.group_with_any {
+                continue;
+            }
+            let corner = triangle.corner_of(group.welded_vertex).unwrap();
+            let vertex = triangle.corners[corner];
+            let previous = triangle.corners[(corner + 2) % 3];
+            let next = triangle.corners[(corner + 1) % 3];
+            let n = normal(vertex);
+            let tangent = project_on_plane(triangle.tangent, n);
+            let edge1 = project_on_plane(position(previous) - position(vertex), n);
+            let edge2 = project_on_plane(position(next) - position(vertex), n);
+            let angle = edge1.dot(edge2).clamp(-1.0, 1.0).acos();
+            sum += tangent * angle;
+        }
+        let tangent = sum.normalize_or_zero();
+        let sign = if group.orientation_preserving {
+            1.0
+        } else {
+            -1.0
+        };
+
+        for &index in group.triangles.iter() {
+            let triangle = &triangles[index];
+            let corner = triangle.corner_of(group.welded_vertex).unwrap();
+            tangents[triangle.corners[corner]] = [tangent.x, tangent.y, tangent.z, sign];
+        }
+    }
+    Ok(tangents)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mesh::{shape, Indices};
+
+    fn assert_tangents_eq(mesh: &Mesh, expected: &[[f32; 4]]) {
+        let tangents = match mesh.attribute(Mesh::ATTRIBUTE_TANGENT) {
+            Some(VertexAttributeValues::Float32x4(tangents)) => tangents,
+            _ => panic!("missing tangents"),
+        };
+        assert_eq!(tangents.len(), expected.len());
+        for (vertex, (tangent, expected)) in tangents.iter().zip(expected.iter()).enumerate() {
+            let close = tangent
+                .iter()
+                .zip(expected.iter())
+                .all(|(a, b)| (a - b).abs() < 1e-4);
+            assert!(
+                close,
+                "vertex {}: expected {:?}, got {:?}",
+                vertex, expected, tangent
+            );
+        }
+    }
+
+    #[test]
+    fn quad_tangents() {
+        let mut mesh = Mesh::from(shape::Quad::new(Vec2::new(2.0, 1.0)));
+        mesh.generate_tangents().unwrap();
+        assert_tangents_eq(&mesh, &[[1.0, 0.0, 0.0, 1.0]; 4]);
+    }
+
+    #[test]
+    fn box_tangents() {
+        let mut mesh = Mesh::from(shape::Box::default());
+        mesh.generate_tangents().unwrap();
+        let faces: [[f32; 4]; 6] = [
+            // top, bottom, right, left, front, back
+            [1.0, 0.0, 0.0, -1.0],
+            [-1.0, 0.0, 0.0, 1.0],
+            [0.0, 1.0, 0.0, -1.0],
+            [0.0, -1.0, 0.0, 1.0],
+            [1.0, 0.0, 0.0, 1.0],
+            [-1.0, 0.0, 0.0, -1.0],
+        ];
+        let expected = faces
+            .iter()
+            .flat_map(|&tangent| std::array::IntoIter::new([tangent; 4]))
+            .collect::<Vec<_>>();
+        assert_tangents_eq(&mesh, &expected);
+
+        // the same tangents are generated without indices
+        mesh.duplicate_vertices();
+        mesh.generate_tangents().unwrap();
+        let expected = faces
+            .iter()
+            .flat_map(|&tangent| std::array::IntoIter::new([tangent; 6]))
+            .collect::<Vec<_>>();
+        assert_tangents_eq(&mesh, &expected);
+    }
+
+    #[test]
+    fn shared_vertices_are_weighted_by_angle() {
+        // two triangles on the XY plane with different tangents, +X and +X+Y, sharing the edge
+        // from the first to the third vertex with angles of 90° and 135° at the first vertex and
+        // 45° and 26.57° at the third vertex
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [-1.0, -1.0, 0.0],
+            ],
+        );
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; 4]);
+        mesh.set_attribute(
+            Mesh::ATTRIBUTE_UV_0,
+            vec![[0.0, 1.0], [1.0, 1.0], [0.0, 0.0], [-1.0, 1.0]],
+        );
+        mesh.set_indices(Some(Indices::U16(vec![0, 1, 2, 0, 2, 3])));
+        mesh.generate_tangents().unwrap();
+
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        assert_tangents_eq(
+            &mesh,
+            &[
+                [0.889_131, 0.457_652, 0.0, 1.0],
+                [1.0, 0.0, 0.0, 1.0],
+                [0.959_267, 0.282_502, 0.0, 1.0],
+                [diagonal, diagonal, 0.0, 1.0],
+            ],
+        );
+    }
+
+    #[test]
+    fn unsupported_meshes() {
+        let mut mesh = Mesh::from(shape::Box::default());
+        mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0, 0.0]; 24]);
+        assert!(matches!(
+            mesh.generate_tangents(),
+            Err(GenerateTangentsError::InvalidVertexAttributeFormat(
+                Mesh::ATTRIBUTE_NORMAL,
+                VertexFormat::Float32x4
+            ))
+        ));
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, vec![[0.0, 0.0, 0.0]; 3]);
+        assert!(matches!(
+            mesh.generate_tangents(),
+            Err(GenerateTangentsError::MissingVertexAttribute(
+                Mesh::ATTRIBUTE_NORMAL
+            ))
+        ));
+
+        let mut mesh = Mesh::new(PrimitiveTopology::LineList);
+        assert!(matches!(
+            mesh.generate_tangents(),
+            Err(GenerateTangentsError::UnsupportedTopology(
+                PrimitiveTopology::LineList
+            ))
+        ));
+    }
+}
@@ -0,0 +1,33 @@
+use super::PipelineLayout;
+use crate::shader::Shader;
+use bevy_asset::Handle;
+use bevy_reflect::TypeUuid;
+
+/// Describes a compute pipeline: a single compute shader and the layout of its bind groups.
+///
+/// If `layout` is `None`, it is reflected from the shader when the pipeline is first used.
+#[derive(Clone, Debug, TypeUuid)]
+#[uuid = "4a2b6fd4-9c4e-4c62-9a1b-55f6b3a1d0f2"]
+pub struct ComputePipelineDescriptor {
+    pub name: Option<String>,
+    pub layout: Option<PipelineLayout>,
+    pub shader: Handle<Shader>,
+}
+
+impl ComputePipelineDescriptor {
+    pub fn new(shader: Handle<Shader>) -> Self {
+        ComputePipelineDescriptor {
+            name: None,
+            layout: None,
+            shader,
+        }
+    }
+
+    pub fn get_layout(&self) -> Option<&PipelineLayout> {
+        self.layout.as_ref()
+    }
+
+    pub fn get_layout_mut(&mut self) -> Option<&mut PipelineLayout> {
+        self.layout.as_mut()
+    }
+}
@@ -0,0 +1,49 @@
+use super::{InputStepMode, PipelineDescriptor, VertexAttribute, VertexBufferLayout, VertexFormat};
+use bevy_asset::Handle;
+use bevy_utils::HashSet;
+
+/// The shader def the pipelines in [`InstancedPipelines`] are specialized with when their entities
+/// are drawn with GPU instancing.
+pub const INSTANCING_SHADER_DEF: &str = "INSTANCING";
+
+/// The pipelines whose shaders support GPU instancing.
+///
+/// With the [`INSTANCING_SHADER_DEF`] shader def, their vertex shader reads the model matrix of an
+/// entity from the `I_Model_0` to `I_Model_3` instance attributes (its columns) instead of the
+/// `Transform` uniform, and doesn't use any other per-entity bindings. Entities drawn with them
+/// that share a mesh, material and pipeline are then drawn with a single draw call.
+#[derive(Debug, Default)]
+pub struct InstancedPipelines {
+    pipelines: HashSet<Handle<PipelineDescriptor>>,
+}
+
+impl InstancedPipelines {
+    pub fn add(&mut self, pipeline: Handle<PipelineDescriptor>) {
+        self.pipelines.insert(pipeline);
+    }
+
+    pub fn contains(&self, pipeline: &Handle<PipelineDescriptor>) -> bool {
+        self.pipelines.contains(pipeline)
+    }
+}
+
+/// Returns the layout of the buffer the `I_` instance attributes of a pipeline are read from: the
+/// columns of the model matrix of each instance.
+pub fn instance_buffer_layout() -> VertexBufferLayout {
+    let attributes =
+        std::array::IntoIter::new(["I_Model_0", "I_Model_1", "I_Model_2", "I_Model_3"])
+            .enumerate()
+            .map(|(i, name)| VertexAttribute {
+                name: name.into(),
+                format: VertexFormat::Float32x4,
+                offset: i as u64 * VertexFormat::Float32x4.get_size(),
+                shader_location: 0,
+            })
+            .collect::<Vec<_>>();
+    VertexBufferLayout {
+        name: "Instance".into(),
+        stride: attributes.len() as u64 * VertexFormat::Float32x4.get_size(),
+        step_mode: InputStepMode::Instance,
+        attributes,
+    }
+}
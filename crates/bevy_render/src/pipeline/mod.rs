@@ -1,5 +1,7 @@
 mod bind_group;
 mod binding;
+mod compute_pipeline;
+mod instancing;
 #[allow(clippy::module_inception)]
 mod pipeline;
 mod pipeline_compiler;
@@ -11,6 +13,8 @@ mod vertex_format;
 
 pub use bind_group::*;
 pub use binding::*;
+pub use compute_pipeline::*;
+pub use instancing::*;
 pub use pipeline::*;
 pub use pipeline_compiler::*;
 pub use pipeline_layout::*;
@@ -1,8 +1,9 @@
 use super::{state_descriptors::PrimitiveTopology, IndexFormat, PipelineDescriptor};
 use crate::{
-    pipeline::{BindType, VertexBufferLayout},
+    pipeline::{instance_buffer_layout, BindType, InputStepMode, VertexBufferLayout},
+    render_graph::base::Hdr,
     renderer::RenderResourceContext,
-    shader::{Shader, ShaderError},
+    shader::{specialize_shader, Shader, ShaderError},
 };
 use bevy_asset::{Assets, Handle};
 use bevy_reflect::{Reflect, ReflectDeserialize};
@@ -19,6 +20,9 @@ pub struct PipelineSpecialization {
     pub strip_index_format: Option<IndexFormat>,
     pub vertex_buffer_layout: VertexBufferLayout,
     pub sample_count: u32,
+    /// Renders into the HDR texture of the main pass instead of the swap chain, see
+    /// [`Hdr`](crate::render_graph::base::Hdr).
+    pub hdr: bool,
 }
 
 impl Default for PipelineSpecialization {
@@ -30,6 +34,7 @@ impl Default for PipelineSpecialization {
             primitive_topology: Default::default(),
             dynamic_bindings: Default::default(),
             vertex_buffer_layout: Default::default(),
+            hdr: false,
         }
     }
 }
@@ -39,6 +44,18 @@ impl PipelineSpecialization {
         pub static EMPTY: Lazy<PipelineSpecialization> = Lazy::new(PipelineSpecialization::default);
         &EMPTY
     }
+
+    /// Returns the shader specialization the shaders of the pipeline are compiled with, which
+    /// also defines [`Hdr::SHADER_DEF`] for HDR pipelines.
+    pub fn get_shader_specialization(&self) -> ShaderSpecialization {
+        let mut shader_specialization = self.shader_specialization.clone();
+        if self.hdr {
+            shader_specialization
+                .shader_defs
+                .insert(Hdr::SHADER_DEF.to_string());
+        }
+        shader_specialization
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Default, Reflect, Serialize, Deserialize)]
@@ -79,8 +96,6 @@ impl PipelineCompiler {
             .entry(shader_handle.clone_weak())
             .or_insert_with(Vec::new);
 
-        let shader = shaders.get(shader_handle).unwrap();
-
         if let Some(specialized_shader) =
             specialized_shaders
                 .iter()
@@ -97,8 +112,12 @@ impl PipelineCompiler {
                 .iter()
                 .cloned()
                 .collect::<Vec<String>>();
-            let compiled_shader =
-                render_resource_context.get_specialized_shader(shader, Some(&shader_def_vec))?;
+            let compiled_shader = specialize_shader(
+                render_resource_context,
+                shaders.get(shader_handle).unwrap(),
+                shaders,
+                Some(&shader_def_vec),
+            )?;
             let specialized_handle = shaders.add(compiled_shader);
             let weak_specialized_handle = specialized_handle.clone_weak();
             specialized_shaders.push(SpecializedShader {
@@ -136,12 +155,13 @@ impl PipelineCompiler {
     ) -> Handle<PipelineDescriptor> {
         let source_descriptor = pipelines.get(source_pipeline).unwrap();
         let mut specialized_descriptor = source_descriptor.clone();
+        let shader_specialization = pipeline_specialization.get_shader_specialization();
         let specialized_vertex_shader = self
             .compile_shader(
                 render_resource_context,
                 shaders,
                 &specialized_descriptor.shader_stages.vertex,
-                &pipeline_specialization.shader_specialization,
+                &shader_specialization,
             )
             .unwrap_or_else(|e| panic_shader_error(e));
         specialized_descriptor.shader_stages.vertex = specialized_vertex_shader.clone_weak();
@@ -156,7 +176,7 @@ impl PipelineCompiler {
                         render_resource_context,
                         shaders,
                         fragment,
-                        &pipeline_specialization.shader_specialization,
+                        &shader_specialization,
                     )
                     .unwrap_or_else(|e| panic_shader_error(e));
                 specialized_fragment_shader = Some(shader.clone_weak());
@@ -209,14 +229,38 @@ impl PipelineCompiler {
             stride: mesh_vertex_buffer_layout.stride,
             ..Default::default()
         };
+        // the instance attributes are read from a separate buffer, after the mesh vertex buffer
+        let instance_buffer_layout = instance_buffer_layout();
+        let mut compiled_instance_buffer_descriptor = VertexBufferLayout {
+            attributes: Vec::new(),
+            ..instance_buffer_layout.clone()
+        };
 
-        for shader_vertex_attribute in pipeline_layout.vertex_buffer_descriptors.iter() {
-            let shader_vertex_attribute = shader_vertex_attribute
+        for shader_vertex_buffer in pipeline_layout.vertex_buffer_descriptors.iter() {
+            let shader_vertex_attribute = shader_vertex_buffer
                 .attributes
                 .get(0)
                 .expect("Reflected layout has no attributes.");
 
-            if let Some(target_vertex_attribute) = mesh_vertex_buffer_layout
+            if shader_vertex_buffer.step_mode == InputStepMode::Instance {
+                if let Some(target_instance_attribute) = instance_buffer_layout
+                    .attributes
+                    .iter()
+                    .find(|x| x.name == shader_vertex_attribute.name)
+                {
+                    let mut compiled_instance_attribute = target_instance_attribute.clone();
+                    compiled_instance_attribute.shader_location =
+                        shader_vertex_attribute.shader_location;
+                    compiled_instance_buffer_descriptor
+                        .attributes
+                        .push(compiled_instance_attribute);
+                } else {
+                    panic!(
+                        "Instance attribute {} is required by shader, but is not an instance attribute supported by bevy. Instance attributes are the columns of the model matrix, I_Model_0 to I_Model_3.",
+                        shader_vertex_attribute.name,
+                    );
+                }
+            } else if let Some(target_vertex_attribute) = mesh_vertex_buffer_layout
                 .attributes
                 .iter()
                 .find(|x| x.name == shader_vertex_attribute.name)
@@ -236,17 +280,24 @@ impl PipelineCompiler {
             }
         }
 
-        // TODO: add other buffers (like instancing) here
         let mut vertex_buffer_descriptors = Vec::<VertexBufferLayout>::default();
-        if !pipeline_layout.vertex_buffer_descriptors.is_empty() {
+        if !compiled_vertex_buffer_descriptor.attributes.is_empty() {
             vertex_buffer_descriptors.push(compiled_vertex_buffer_descriptor);
         }
+        if !compiled_instance_buffer_descriptor.attributes.is_empty() {
+            vertex_buffer_descriptors.push(compiled_instance_buffer_descriptor);
+        }
 
         pipeline_layout.vertex_buffer_descriptors = vertex_buffer_descriptors;
         specialized_descriptor.multisample.count = pipeline_specialization.sample_count;
         specialized_descriptor.primitive.topology = pipeline_specialization.primitive_topology;
         specialized_descriptor.primitive.strip_index_format =
             pipeline_specialization.strip_index_format;
+        if pipeline_specialization.hdr {
+            for color_target_state in specialized_descriptor.color_target_states.iter_mut() {
+                color_target_state.format = Hdr::TEXTURE_FORMAT;
+            }
+        }
 
         let specialized_pipeline_handle = pipelines.add(specialized_descriptor);
         render_resource_context.create_render_pipeline(
@@ -322,11 +373,12 @@ impl PipelineCompiler {
                     .iter()
                     .cloned()
                     .collect::<Vec<String>>();
-                let new_handle =
-                    shaders.add(render_resource_context.get_specialized_shader(
-                        shaders.get(shader).unwrap(),
-                        Some(&shader_def_vec),
-                    )?);
+                let new_handle = shaders.add(specialize_shader(
+                    render_resource_context,
+                    shaders.get(shader).unwrap(),
+                    shaders,
+                    Some(&shader_def_vec),
+                )?);
 
                 // Replace handle and remove old from assets.
                 let old_handle = std::mem::replace(&mut specialized_shader.shader, new_handle);
@@ -62,6 +62,25 @@ impl PipelineLayout {
         // with bevy and not with wgpu TODO: try removing this
         bind_groups_result.sort_by(|a, b| a.index.partial_cmp(&b.index).unwrap());
 
+        // bind groups are bound to a pipeline by their position, so the indices that aren't used by
+        // any shader (for example, the per-entity bind group of instanced shaders) get an empty one
+        let bind_group_count = bind_groups_result
+            .last()
+            .map_or(0, |bind_group| bind_group.index as usize + 1);
+        if bind_groups_result.len() < bind_group_count {
+            let mut bind_groups = std::mem::take(&mut bind_groups_result)
+                .into_iter()
+                .peekable();
+            for index in 0..bind_group_count as u32 {
+                match bind_groups.peek() {
+                    Some(bind_group) if bind_group.index == index => {
+                        bind_groups_result.push(bind_groups.next().unwrap())
+                    }
+                    _ => bind_groups_result.push(BindGroupDescriptor::new(index, Vec::new())),
+                }
+            }
+        }
+
         PipelineLayout {
             bind_groups: bind_groups_result,
             vertex_buffer_descriptors,
@@ -103,3 +122,35 @@ impl UniformProperty {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unused_bind_groups_are_empty() {
+        let bind_group = |index| BindGroupDescriptor::new(index, Vec::new());
+        let mut shader_layouts = [
+            ShaderLayout {
+                bind_groups: vec![bind_group(0), bind_group(2)],
+                vertex_buffer_layout: Vec::new(),
+                entry_point: "main".to_string(),
+            },
+            ShaderLayout {
+                bind_groups: vec![bind_group(3)],
+                vertex_buffer_layout: Vec::new(),
+                entry_point: "main".to_string(),
+            },
+        ];
+        let layout = PipelineLayout::from_shader_layouts(&mut shader_layouts);
+        assert_eq!(
+            layout
+                .bind_groups
+                .iter()
+                .map(|bind_group| bind_group.index)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        assert!(layout.bind_groups[1].bindings.is_empty());
+    }
+}
@@ -1,8 +1,11 @@
-use super::{PipelineDescriptor, PipelineSpecialization};
+use super::{
+    InstancedPipelines, PipelineDescriptor, PipelineSpecialization, INSTANCING_SHADER_DEF,
+};
 use crate::{
     draw::{Draw, DrawContext, OutsideFrustum},
     mesh::{Indices, Mesh},
-    prelude::{Msaa, Visible},
+    prelude::{Hdr, Msaa, Visible},
+    render_graph::base::MainPass,
     renderer::RenderResourceBindings,
 };
 use bevy_asset::{Assets, Handle};
@@ -12,6 +15,7 @@ use bevy_ecs::{
     system::{Query, Res, ResMut},
 };
 use bevy_reflect::Reflect;
+use bevy_transform::prelude::GlobalTransform;
 use bevy_utils::HashSet;
 
 #[derive(Debug, Default, Clone, Reflect)]
@@ -82,17 +86,29 @@ impl Default for RenderPipelines {
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn draw_render_pipelines_system(
     mut draw_context: DrawContext,
     mut render_resource_bindings: ResMut<RenderResourceBindings>,
     msaa: Res<Msaa>,
+    hdr: Res<Hdr>,
+    instanced_pipelines: Res<InstancedPipelines>,
     meshes: Res<Assets<Mesh>>,
     mut query: Query<
-        (&mut Draw, &mut RenderPipelines, &Handle<Mesh>, &Visible),
+        (
+            &mut Draw,
+            &mut RenderPipelines,
+            &Handle<Mesh>,
+            &Visible,
+            Option<&MainPass>,
+            Option<&GlobalTransform>,
+        ),
         Without<OutsideFrustum>,
     >,
 ) {
-    for (mut draw, mut render_pipelines, mesh_handle, visible) in query.iter_mut() {
+    for (mut draw, mut render_pipelines, mesh_handle, visible, main_pass, global_transform) in
+        query.iter_mut()
+    {
         if !visible.is_visible {
             continue;
         }
@@ -110,9 +126,27 @@ pub fn draw_render_pipelines_system(
             None => None,
         };
 
+        // entities are only drawn with instancing if all of their pipelines support it
         let render_pipelines = &mut *render_pipelines;
+        draw.instance = global_transform
+            .filter(|_| {
+                !render_pipelines.pipelines.is_empty()
+                    && render_pipelines
+                        .pipelines
+                        .iter()
+                        .all(|pipeline| instanced_pipelines.contains(&pipeline.pipeline))
+            })
+            .map(|global_transform| global_transform.compute_matrix());
         for pipeline in render_pipelines.pipelines.iter_mut() {
             pipeline.specialization.sample_count = msaa.samples;
+            pipeline.specialization.hdr = hdr.enabled && main_pass.is_some();
+            if draw.instance.is_some() {
+                pipeline
+                    .specialization
+                    .shader_specialization
+                    .shader_defs
+                    .insert(INSTANCING_SHADER_DEF.to_string());
+            }
             if pipeline.dynamic_bindings_generation
                 != render_pipelines.bindings.dynamic_bindings_generation()
             {
@@ -0,0 +1,79 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 0, binding = 0) uniform texture2D Bloom_source;
+layout(set = 0, binding = 1) uniform sampler Bloom_sampler;
+
+# ifdef BLOOM_THRESHOLD
+// (threshold, threshold - knee, 2 * knee, 0.25 / knee)
+layout(set = 0, binding = 2) uniform Bloom_threshold {
+    vec4 ThresholdCurve;
+};
+# endif
+
+vec3 sample_source(vec2 uv) {
+    return texture(sampler2D(Bloom_source, Bloom_sampler), uv).rgb;
+}
+
+# ifdef BLOOM_UPSAMPLE
+// 3x3 tent filter
+vec3 upsample(vec2 uv, vec2 texel) {
+    vec3 color = sample_source(uv) * 4.0;
+    color += (sample_source(uv + vec2(-texel.x, 0.0)) + sample_source(uv + vec2(texel.x, 0.0))
+            + sample_source(uv + vec2(0.0, -texel.y)) + sample_source(uv + vec2(0.0, texel.y))) * 2.0;
+    color += sample_source(uv - texel) + sample_source(uv + texel)
+            + sample_source(uv + vec2(-texel.x, texel.y)) + sample_source(uv + vec2(texel.x, -texel.y));
+    return color / 16.0;
+}
+# else
+// 13 tap filter, weighting five overlapping 2x2 boxes to avoid flickering
+vec3 downsample(vec2 uv, vec2 texel) {
+    vec3 a = sample_source(uv + texel * vec2(-2.0, -2.0));
+    vec3 b = sample_source(uv + texel * vec2(0.0, -2.0));
+    vec3 c = sample_source(uv + texel * vec2(2.0, -2.0));
+    vec3 d = sample_source(uv + texel * vec2(-1.0, -1.0));
+    vec3 e = sample_source(uv + texel * vec2(1.0, -1.0));
+    vec3 f = sample_source(uv + texel * vec2(-2.0, 0.0));
+    vec3 g = sample_source(uv);
+    vec3 h = sample_source(uv + texel * vec2(2.0, 0.0));
+    vec3 i = sample_source(uv + texel * vec2(-1.0, 1.0));
+    vec3 j = sample_source(uv + texel * vec2(1.0, 1.0));
+    vec3 k = sample_source(uv + texel * vec2(-2.0, 2.0));
+    vec3 l = sample_source(uv + texel * vec2(0.0, 2.0));
+    vec3 m = sample_source(uv + texel * vec2(2.0, 2.0));
+
+    vec3 color = (d + e + i + j) * 0.125;
+    color += (a + b + f + g) * 0.03125;
+    color += (b + c + g + h) * 0.03125;
+    color += (f + g + k + l) * 0.03125;
+    color += (g + h + l + m) * 0.03125;
+    return color;
+}
+# endif
+
+# ifdef BLOOM_THRESHOLD
+// quadratic soft threshold, only keeping the colors brighter than the threshold
+vec3 threshold(vec3 color) {
+    float brightness = max(color.r, max(color.g, color.b));
+    float soft = clamp(brightness - ThresholdCurve.y, 0.0, ThresholdCurve.z);
+    soft = soft * soft * ThresholdCurve.w;
+    float contribution = max(soft, brightness - ThresholdCurve.x) / max(brightness, 0.0001);
+    return color * contribution;
+}
+# endif
+
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(sampler2D(Bloom_source, Bloom_sampler), 0));
+# ifdef BLOOM_UPSAMPLE
+    vec3 color = upsample(v_Uv, texel);
+# else
+    vec3 color = downsample(v_Uv, texel);
+# endif
+# ifdef BLOOM_THRESHOLD
+    color = threshold(color);
+# endif
+    o_Target = vec4(color, 1.0);
+}
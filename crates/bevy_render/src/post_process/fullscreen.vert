@@ -0,0 +1,9 @@
+#version 450
+
+layout(location = 0) out vec2 v_Uv;
+
+// a single triangle covering the whole target, without vertex buffers
+void main() {
+    v_Uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(v_Uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+}
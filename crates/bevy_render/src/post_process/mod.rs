@@ -0,0 +1,252 @@
+mod post_process_node;
+
+pub use post_process_node::*;
+
+use crate::{
+    pipeline::{
+        BlendComponent, BlendFactor, BlendOperation, BlendState, ColorTargetState, ColorWrite,
+        PipelineDescriptor,
+    },
+    render_graph::base::Hdr,
+    shader::{Shader, ShaderStage, ShaderStages},
+    texture::{Texture, TextureFormat},
+};
+use bevy_asset::{Assets, Handle, HandleUntyped};
+use bevy_ecs::reflect::ReflectComponent;
+use bevy_math::Vec4;
+use bevy_reflect::{Reflect, TypeUuid};
+use serde::{Deserialize, Serialize};
+
+pub const BLOOM_DOWNSAMPLE_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 0x2d3b8a5d6c1e4f07);
+pub const BLOOM_UPSAMPLE_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 0x5f0c9e2b7a4d3816);
+pub const TONEMAPPING_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 0x7a61d4c3e58b2f90);
+
+/// The maximum number of times the bright parts of the image are downsampled and blurred by
+/// [`Bloom`].
+pub const BLOOM_MAX_MIP_LEVELS: usize = 6;
+
+/// Maps the HDR colors rendered by the camera to the displayable range.
+///
+/// Only used when [`Hdr`] is enabled, otherwise colors are clamped.
+#[derive(Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Tonemapping {
+    pub operator: TonemappingOperator,
+    /// The exposure in stops, colors are scaled by `2^exposure` before tonemapping.
+    pub exposure: f32,
+}
+
+impl Default for Tonemapping {
+    fn default() -> Self {
+        Tonemapping {
+            operator: TonemappingOperator::Aces,
+            exposure: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+pub enum TonemappingOperator {
+    /// A fit of the ACES reference rendering transform and output device transform, with a
+    /// filmic contrast and desaturated highlights.
+    Aces,
+    /// `color / (1 + color)` on each channel.
+    Reinhard,
+}
+
+impl Default for TonemappingOperator {
+    fn default() -> Self {
+        TonemappingOperator::Aces
+    }
+}
+
+/// Makes the bright parts of the image bleed into their surroundings.
+///
+/// Colors brighter than the `threshold` are repeatedly downsampled and blurred, then added back
+/// to the image scaled by the `intensity`. Only used when [`Hdr`] is enabled.
+#[derive(Debug, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Bloom {
+    pub threshold: f32,
+    pub intensity: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom {
+            threshold: 1.0,
+            intensity: 0.3,
+        }
+    }
+}
+
+impl Bloom {
+    /// The width of the soft transition around the threshold, relative to the threshold.
+    const KNEE: f32 = 0.5;
+
+    /// Returns the `(threshold, threshold - knee, 2 * knee, 0.25 / knee)` curve of the quadratic
+    /// soft threshold used by the shader.
+    pub fn threshold_curve(&self) -> Vec4 {
+        let knee = (self.threshold * Self::KNEE).max(1e-4);
+        Vec4::new(
+            self.threshold,
+            self.threshold - knee,
+            2.0 * knee,
+            0.25 / knee,
+        )
+    }
+}
+
+/// Returns the sizes of the textures the bright parts of an image of the given size are
+/// downsampled into, starting at half of its size.
+pub fn bloom_mip_sizes(width: u32, height: u32) -> Vec<(u32, u32)> {
+    let mut sizes = Vec::new();
+    let (mut width, mut height) = (width / 2, height / 2);
+    while sizes.len() < BLOOM_MAX_MIP_LEVELS && width > 0 && height > 0 {
+        sizes.push((width, height));
+        width /= 2;
+        height /= 2;
+    }
+    sizes
+}
+
+/// Grades the tonemapped colors with a lookup table.
+///
+/// The `lut` is a strip of `size` square slices of `size` by `size` texels, one per blue value,
+/// where red increases along X and green along Y. It is indexed with sRGB encoded colors, and like
+/// other color textures should have an sRGB format. Only used when [`Hdr`] is enabled.
+#[derive(Debug, Clone, Default, Reflect)]
+#[reflect(Component)]
+pub struct ColorGrading {
+    pub lut: Handle<Texture>,
+}
+
+pub(crate) fn add_post_process_pipelines(
+    shaders: &mut Assets<Shader>,
+    pipelines: &mut Assets<PipelineDescriptor>,
+) {
+    let fullscreen = shaders.add(Shader::from_glsl(
+        ShaderStage::Vertex,
+        include_str!("fullscreen.vert"),
+    ));
+    let bloom = shaders.add(Shader::from_glsl(
+        ShaderStage::Fragment,
+        include_str!("bloom.frag"),
+    ));
+    let tonemapping = shaders.add(Shader::from_glsl(
+        ShaderStage::Fragment,
+        include_str!("tonemapping.frag"),
+    ));
+
+    let additive = BlendState {
+        color: BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        },
+        alpha: BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        },
+    };
+    pipelines.set_untracked(
+        BLOOM_DOWNSAMPLE_PIPELINE_HANDLE,
+        build_fullscreen_pipeline(
+            "bloom_downsample",
+            ShaderStages {
+                vertex: fullscreen.clone(),
+                fragment: Some(bloom.clone()),
+            },
+            Hdr::TEXTURE_FORMAT,
+            None,
+        ),
+    );
+    pipelines.set_untracked(
+        BLOOM_UPSAMPLE_PIPELINE_HANDLE,
+        build_fullscreen_pipeline(
+            "bloom_upsample",
+            ShaderStages {
+                vertex: fullscreen.clone(),
+                fragment: Some(bloom),
+            },
+            Hdr::TEXTURE_FORMAT,
+            Some(additive),
+        ),
+    );
+    pipelines.set_untracked(
+        TONEMAPPING_PIPELINE_HANDLE,
+        build_fullscreen_pipeline(
+            "tonemapping",
+            ShaderStages {
+                vertex: fullscreen,
+                fragment: Some(tonemapping),
+            },
+            TextureFormat::default(),
+            None,
+        ),
+    );
+}
+
+/// Builds a pipeline that draws a triangle covering its target, without vertex buffers or depth.
+fn build_fullscreen_pipeline(
+    name: &str,
+    shader_stages: ShaderStages,
+    format: TextureFormat,
+    blend: Option<BlendState>,
+) -> PipelineDescriptor {
+    let mut descriptor = PipelineDescriptor::default_config(shader_stages);
+    descriptor.name = Some(name.into());
+    descriptor.primitive.cull_mode = None;
+    descriptor.depth_stencil = None;
+    descriptor.color_target_states = vec![ColorTargetState {
+        format,
+        blend,
+        write_mask: ColorWrite::ALL,
+    }];
+    descriptor
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bloom_mip_chain() {
+        assert_eq!(
+            bloom_mip_sizes(1280, 720),
+            vec![
+                (640, 360),
+                (320, 180),
+                (160, 90),
+                (80, 45),
+                (40, 22),
+                (20, 11)
+            ]
+        );
+        assert_eq!(bloom_mip_sizes(9, 4), vec![(4, 2), (2, 1)]);
+        assert!(bloom_mip_sizes(1, 1).is_empty());
+    }
+
+    #[test]
+    fn bloom_threshold_curve() {
+        let curve = Bloom {
+            threshold: 2.0,
+            intensity: 1.0,
+        }
+        .threshold_curve();
+        assert!((curve - Vec4::new(2.0, 1.0, 2.0, 0.25)).abs().max_element() < 1e-6);
+
+        // a threshold of zero keeps the curve finite
+        let curve = Bloom {
+            threshold: 0.0,
+            intensity: 1.0,
+        }
+        .threshold_curve();
+        assert!(curve.is_finite());
+    }
+}
@@ -0,0 +1,450 @@
+use super::{
+    bloom_mip_sizes, Bloom, ColorGrading, Tonemapping, TonemappingOperator,
+    BLOOM_DOWNSAMPLE_PIPELINE_HANDLE, BLOOM_UPSAMPLE_PIPELINE_HANDLE, TONEMAPPING_PIPELINE_HANDLE,
+};
+use crate::{
+    camera::ActiveCameras,
+    pass::{LoadOp, Operations, PassDescriptor, RenderPassColorAttachment, TextureAttachment},
+    pipeline::{
+        PipelineCompiler, PipelineDescriptor, PipelineSpecialization, ShaderSpecialization,
+    },
+    render_graph::{base::Hdr, Node, ResourceSlotInfo, ResourceSlots},
+    renderer::{
+        BindGroup, BufferId, BufferInfo, BufferUsage, RenderContext, RenderResourceBindings,
+        RenderResourceContext, RenderResourceType, SamplerId, TextureId,
+    },
+    shader::Shader,
+    texture::{
+        Extent3d, FilterMode, SamplerDescriptor, TextureDescriptor, TextureDimension, TextureUsage,
+        TEXTURE_ASSET_INDEX,
+    },
+    Color,
+};
+use bevy_asset::{Assets, Handle};
+use bevy_core::cast_slice;
+use bevy_ecs::{
+    query::QueryState,
+    world::{Mut, World},
+};
+use bevy_math::Vec4;
+use bevy_window::{WindowId, Windows};
+use std::{borrow::Cow, fmt};
+
+type PostProcessQuery = (
+    Option<&'static Tonemapping>,
+    Option<&'static Bloom>,
+    Option<&'static ColorGrading>,
+);
+
+struct PreparedBloom {
+    threshold_pipeline: Handle<PipelineDescriptor>,
+    downsample_pipeline: Handle<PipelineDescriptor>,
+    upsample_pipeline: Handle<PipelineDescriptor>,
+    threshold: BufferId,
+}
+
+struct PreparedPostProcess {
+    bloom: Option<PreparedBloom>,
+    tonemapping_pipeline: Handle<PipelineDescriptor>,
+    params: BufferId,
+    lut: Option<TextureId>,
+}
+
+/// A uniform buffer holding a single `vec4`, recreated when its value changes.
+#[derive(Default)]
+struct UniformVec4 {
+    buffer: Option<(BufferId, Vec4)>,
+}
+
+impl UniformVec4 {
+    fn update(
+        &mut self,
+        render_resource_context: &dyn RenderResourceContext,
+        value: Vec4,
+    ) -> BufferId {
+        match self.buffer {
+            Some((buffer, current)) if current == value => buffer,
+            _ => {
+                if let Some((buffer, _)) = self.buffer.take() {
+                    render_resource_context.remove_buffer(buffer);
+                }
+                let buffer = render_resource_context.create_buffer_with_data(
+                    BufferInfo {
+                        size: std::mem::size_of::<Vec4>(),
+                        buffer_usage: BufferUsage::UNIFORM,
+                        ..Default::default()
+                    },
+                    cast_slice(&value.to_array()),
+                );
+                self.buffer = Some((buffer, value));
+                buffer
+            }
+        }
+    }
+}
+
+/// A Render Graph [Node] that post-processes the HDR texture the main pass renders into when
+/// [`Hdr`] is enabled, and writes the result into its color attachment.
+///
+/// The post-processing chain is configured by the [`Bloom`], [`Tonemapping`] and
+/// [`ColorGrading`] components of its camera. Without them, the HDR colors are clamped.
+pub struct PostProcessNode {
+    window_id: WindowId,
+    camera_name: String,
+    query_state: Option<QueryState<PostProcessQuery>>,
+    sampler: Option<SamplerId>,
+    bloom_textures: Vec<TextureId>,
+    bloom_window_size: (u32, u32),
+    threshold: UniformVec4,
+    params: UniformVec4,
+    prepared: Option<PreparedPostProcess>,
+}
+
+impl fmt::Debug for PostProcessNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PostProcessNode")
+            .field("window_id", &self.window_id)
+            .field("camera_name", &self.camera_name)
+            .field("bloom_textures", &self.bloom_textures)
+            .finish()
+    }
+}
+
+impl PostProcessNode {
+    pub const IN_HDR_TEXTURE: &'static str = "hdr_texture";
+    pub const IN_COLOR_ATTACHMENT: &'static str = "color_attachment";
+
+    pub fn new(window_id: WindowId, camera_name: &str) -> Self {
+        PostProcessNode {
+            window_id,
+            camera_name: camera_name.to_string(),
+            query_state: None,
+            sampler: None,
+            bloom_textures: Vec::new(),
+            bloom_window_size: (0, 0),
+            threshold: Default::default(),
+            params: Default::default(),
+            prepared: None,
+        }
+    }
+
+    /// Creates the bloom textures for the size of the window, if it changed.
+    fn resize_bloom_textures(
+        &mut self,
+        render_resource_context: &dyn RenderResourceContext,
+        window_size: (u32, u32),
+    ) {
+        if self.bloom_window_size == window_size && !self.bloom_textures.is_empty() {
+            return;
+        }
+        self.remove_bloom_textures(render_resource_context);
+        self.bloom_window_size = window_size;
+        for (width, height) in bloom_mip_sizes(window_size.0, window_size.1) {
+            self.bloom_textures
+                .push(render_resource_context.create_texture(TextureDescriptor {
+                    size: Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: Hdr::TEXTURE_FORMAT,
+                    usage: TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::SAMPLED,
+                }));
+        }
+    }
+
+    fn remove_bloom_textures(&mut self, render_resource_context: &dyn RenderResourceContext) {
+        for texture in self.bloom_textures.drain(..) {
+            render_resource_context.remove_texture(texture);
+        }
+    }
+}
+
+fn specialize_pipeline(
+    pipeline_compiler: &mut PipelineCompiler,
+    render_resource_context: &dyn RenderResourceContext,
+    pipelines: &mut Assets<PipelineDescriptor>,
+    shaders: &mut Assets<Shader>,
+    pipeline: &Handle<PipelineDescriptor>,
+    shader_defs: &[&str],
+) -> Handle<PipelineDescriptor> {
+    let specialization = PipelineSpecialization {
+        shader_specialization: ShaderSpecialization {
+            shader_defs: shader_defs.iter().map(|def| def.to_string()).collect(),
+        },
+        ..Default::default()
+    };
+    pipeline_compiler
+        .get_specialized_pipeline(pipeline, &specialization)
+        .unwrap_or_else(|| {
+            pipeline_compiler.compile_pipeline(
+                render_resource_context,
+                pipelines,
+                shaders,
+                pipeline,
+                &specialization,
+            )
+        })
+}
+
+/// Draws a triangle covering the `target` with the pipeline, which reads from the bind group.
+fn fullscreen_pass(
+    render_context: &mut dyn RenderContext,
+    render_resource_bindings: &RenderResourceBindings,
+    pipelines: &Assets<PipelineDescriptor>,
+    pipeline: &Handle<PipelineDescriptor>,
+    bind_group: &BindGroup,
+    target: TextureId,
+    load: LoadOp<Color>,
+) {
+    let layout = pipelines.get(pipeline).unwrap().get_layout().unwrap();
+    let bind_group_descriptor_id = layout.get_bind_group(0).unwrap().id;
+    render_context
+        .resources()
+        .create_bind_group(bind_group_descriptor_id, bind_group);
+    let descriptor = PassDescriptor {
+        color_attachments: vec![RenderPassColorAttachment {
+            attachment: TextureAttachment::Id(target),
+            resolve_target: None,
+            ops: Operations { load, store: true },
+        }],
+        depth_stencil_attachment: None,
+        sample_count: 1,
+    };
+    render_context.begin_pass(&descriptor, render_resource_bindings, &mut |render_pass| {
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, bind_group_descriptor_id, bind_group.id, None);
+        render_pass.draw(0..3, 0..1);
+    });
+}
+
+impl Node for PostProcessNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        static INPUT: &[ResourceSlotInfo] = &[
+            ResourceSlotInfo {
+                name: Cow::Borrowed(PostProcessNode::IN_HDR_TEXTURE),
+                resource_type: RenderResourceType::Texture,
+            },
+            ResourceSlotInfo {
+                name: Cow::Borrowed(PostProcessNode::IN_COLOR_ATTACHMENT),
+                resource_type: RenderResourceType::Texture,
+            },
+        ];
+        INPUT
+    }
+
+    fn prepare(&mut self, world: &mut World) {
+        self.prepared = None;
+        let query_state = self.query_state.get_or_insert_with(|| world.query());
+        let camera = world
+            .get_resource::<ActiveCameras>()
+            .unwrap()
+            .get(&self.camera_name)
+            .and_then(|active_camera| active_camera.entity);
+        let (tonemapping, bloom, color_grading) =
+            match camera.and_then(|camera| query_state.get(world, camera).ok()) {
+                Some((tonemapping, bloom, color_grading)) => {
+                    (tonemapping.copied(), bloom.copied(), color_grading.cloned())
+                }
+                None => (None, None, None),
+            };
+        let window_size = match world
+            .get_resource::<Windows>()
+            .and_then(|windows| windows.get(self.window_id))
+        {
+            Some(window) => (window.physical_width(), window.physical_height()),
+            None => return,
+        };
+
+        world.resource_scope(|world, mut pipeline_compiler: Mut<PipelineCompiler>| {
+            world.resource_scope(|world, mut pipelines: Mut<Assets<PipelineDescriptor>>| {
+                world.resource_scope(|world, mut shaders: Mut<Assets<Shader>>| {
+                    let render_resource_context = &**world
+                        .get_resource::<Box<dyn RenderResourceContext>>()
+                        .unwrap();
+                    let mut specialize =
+                        |pipeline: &Handle<PipelineDescriptor>, shader_defs: &[&str]| {
+                            specialize_pipeline(
+                                &mut pipeline_compiler,
+                                render_resource_context,
+                                &mut pipelines,
+                                &mut shaders,
+                                pipeline,
+                                shader_defs,
+                            )
+                        };
+
+                    if self.sampler.is_none() {
+                        self.sampler =
+                            Some(render_resource_context.create_sampler(&SamplerDescriptor {
+                                mag_filter: FilterMode::Linear,
+                                min_filter: FilterMode::Linear,
+                                ..Default::default()
+                            }));
+                    }
+
+                    match bloom {
+                        Some(_) => self.resize_bloom_textures(render_resource_context, window_size),
+                        None => self.remove_bloom_textures(render_resource_context),
+                    }
+                    let bloom = match bloom {
+                        Some(bloom) if !self.bloom_textures.is_empty() => Some(bloom),
+                        _ => None,
+                    };
+
+                    let mut shader_defs = Vec::new();
+                    if let Some(tonemapping) = tonemapping {
+                        shader_defs.push(match tonemapping.operator {
+                            TonemappingOperator::Aces => "TONEMAP_ACES",
+                            TonemappingOperator::Reinhard => "TONEMAP_REINHARD",
+                        });
+                    }
+                    if bloom.is_some() {
+                        shader_defs.push("BLOOM");
+                    }
+                    // the lookup table is skipped until it is loaded
+                    let lut = color_grading.and_then(|color_grading| {
+                        render_resource_context
+                            .get_asset_resource(&color_grading.lut, TEXTURE_ASSET_INDEX)
+                            .and_then(|resource| resource.get_texture())
+                    });
+                    if lut.is_some() {
+                        shader_defs.push("COLOR_GRADING");
+                    }
+
+                    let params = Vec4::new(
+                        tonemapping.map_or(1.0, |tonemapping| tonemapping.exposure.exp2()),
+                        bloom.map_or(0.0, |bloom| bloom.intensity),
+                        0.0,
+                        0.0,
+                    );
+                    self.prepared = Some(PreparedPostProcess {
+                        bloom: bloom.map(|bloom| PreparedBloom {
+                            threshold_pipeline: specialize(
+                                &BLOOM_DOWNSAMPLE_PIPELINE_HANDLE.typed(),
+                                &["BLOOM_THRESHOLD"],
+                            ),
+                            downsample_pipeline: specialize(
+                                &BLOOM_DOWNSAMPLE_PIPELINE_HANDLE.typed(),
+                                &[],
+                            ),
+                            upsample_pipeline: specialize(
+                                &BLOOM_UPSAMPLE_PIPELINE_HANDLE.typed(),
+                                &["BLOOM_UPSAMPLE"],
+                            ),
+                            threshold: self
+                                .threshold
+                                .update(render_resource_context, bloom.threshold_curve()),
+                        }),
+                        tonemapping_pipeline: specialize(
+                            &TONEMAPPING_PIPELINE_HANDLE.typed(),
+                            &shader_defs,
+                        ),
+                        params: self.params.update(render_resource_context, params),
+                        lut,
+                    });
+                });
+            });
+        });
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        const HDR_TEXTURE: usize = 0;
+        const COLOR_ATTACHMENT: usize = 1;
+        let prepared = match self.prepared.take() {
+            Some(prepared) => prepared,
+            None => return,
+        };
+        let (hdr_texture, color_attachment) = match (
+            input
+                .get(HDR_TEXTURE)
+                .and_then(|resource| resource.get_texture()),
+            input
+                .get(COLOR_ATTACHMENT)
+                .and_then(|resource| resource.get_texture()),
+        ) {
+            (Some(hdr_texture), Some(color_attachment)) => (hdr_texture, color_attachment),
+            _ => return,
+        };
+        let sampler = self.sampler.unwrap();
+        let render_resource_bindings = world.get_resource::<RenderResourceBindings>().unwrap();
+        let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
+
+        if let Some(bloom) = &prepared.bloom {
+            // downsample the bright parts of the image through the bloom textures
+            let mut source = hdr_texture;
+            for (i, &target) in self.bloom_textures.iter().enumerate() {
+                let (pipeline, bind_group) = if i == 0 {
+                    let bind_group = BindGroup::build()
+                        .add_texture(0, source)
+                        .add_sampler(1, sampler)
+                        .add_buffer(2, bloom.threshold, 0..std::mem::size_of::<Vec4>() as u64)
+                        .finish();
+                    (&bloom.threshold_pipeline, bind_group)
+                } else {
+                    let bind_group = BindGroup::build()
+                        .add_texture(0, source)
+                        .add_sampler(1, sampler)
+                        .finish();
+                    (&bloom.downsample_pipeline, bind_group)
+                };
+                fullscreen_pass(
+                    render_context,
+                    render_resource_bindings,
+                    pipelines,
+                    pipeline,
+                    &bind_group,
+                    target,
+                    LoadOp::Clear(Color::BLACK),
+                );
+                source = target;
+            }
+
+            // blur them back up, adding each texture to the next larger one
+            for i in (1..self.bloom_textures.len()).rev() {
+                let bind_group = BindGroup::build()
+                    .add_texture(0, self.bloom_textures[i])
+                    .add_sampler(1, sampler)
+                    .finish();
+                fullscreen_pass(
+                    render_context,
+                    render_resource_bindings,
+                    pipelines,
+                    &bloom.upsample_pipeline,
+                    &bind_group,
+                    self.bloom_textures[i - 1],
+                    LoadOp::Load,
+                );
+            }
+        }
+
+        let mut bind_group = BindGroup::build()
+            .add_texture(0, hdr_texture)
+            .add_sampler(1, sampler)
+            .add_buffer(2, prepared.params, 0..std::mem::size_of::<Vec4>() as u64);
+        if prepared.bloom.is_some() {
+            bind_group = bind_group.add_texture(3, self.bloom_textures[0]);
+        }
+        if let Some(lut) = prepared.lut {
+            bind_group = bind_group.add_texture(4, lut);
+        }
+        fullscreen_pass(
+            render_context,
+            render_resource_bindings,
+            pipelines,
+            &prepared.tonemapping_pipeline,
+            &bind_group.finish(),
+            color_attachment,
+            LoadOp::Clear(Color::BLACK),
+        );
+    }
+}
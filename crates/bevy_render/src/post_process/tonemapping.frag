@@ -0,0 +1,86 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 0, binding = 0) uniform texture2D PostProcess_hdr_texture;
+layout(set = 0, binding = 1) uniform sampler PostProcess_sampler;
+// (2^exposure, bloom intensity, unused, unused)
+layout(set = 0, binding = 2) uniform PostProcess_params {
+    vec4 Params;
+};
+# ifdef BLOOM
+layout(set = 0, binding = 3) uniform texture2D PostProcess_bloom;
+# endif
+# ifdef COLOR_GRADING
+layout(set = 0, binding = 4) uniform texture2D PostProcess_lut;
+# endif
+
+# ifdef TONEMAP_ACES
+// Stephen Hill's fit of the ACES RRT and ODT, from sRGB primaries to sRGB primaries
+const mat3 ACES_INPUT = mat3(
+    0.59719, 0.07600, 0.02840,
+    0.35458, 0.90834, 0.13383,
+    0.04823, 0.01566, 0.83777
+);
+const mat3 ACES_OUTPUT = mat3(
+    1.60475, -0.10208, -0.00327,
+    -0.53108, 1.10813, -0.07276,
+    -0.07367, -0.00605, 1.07602
+);
+
+vec3 rrt_and_odt_fit(vec3 v) {
+    vec3 a = v * (v + 0.0245786) - 0.000090537;
+    vec3 b = v * (0.983729 * v + 0.4329510) + 0.238081;
+    return a / b;
+}
+
+vec3 tonemap(vec3 color) {
+    color = ACES_INPUT * color;
+    color = rrt_and_odt_fit(color);
+    return ACES_OUTPUT * color;
+}
+# endif
+
+# ifdef TONEMAP_REINHARD
+vec3 tonemap(vec3 color) {
+    return color / (1.0 + color);
+}
+# endif
+
+# ifdef COLOR_GRADING
+vec3 encode_srgb(vec3 color) {
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, vec3(lessThanEqual(color, vec3(0.0031308))));
+}
+
+// looks up the sRGB encoded color in a strip of blue slices, blending between the two nearest
+vec3 grade(vec3 color) {
+    float size = float(textureSize(sampler2D(PostProcess_lut, PostProcess_sampler), 0).y);
+    vec3 coords = encode_srgb(color) * (size - 1.0);
+    float slice = floor(coords.b);
+    float next_slice = min(slice + 1.0, size - 1.0);
+    vec2 uv = vec2((coords.r + 0.5) / (size * size), (coords.g + 0.5) / size);
+    vec3 a = texture(sampler2D(PostProcess_lut, PostProcess_sampler), uv + vec2(slice / size, 0.0)).rgb;
+    vec3 b = texture(sampler2D(PostProcess_lut, PostProcess_sampler), uv + vec2(next_slice / size, 0.0)).rgb;
+    return mix(a, b, coords.b - slice);
+}
+# endif
+
+void main() {
+    vec3 color = texture(sampler2D(PostProcess_hdr_texture, PostProcess_sampler), v_Uv).rgb;
+    color *= Params.x;
+# ifdef BLOOM
+    color += texture(sampler2D(PostProcess_bloom, PostProcess_sampler), v_Uv).rgb * Params.y;
+# endif
+# if defined(TONEMAP_ACES) || defined(TONEMAP_REINHARD)
+    color = tonemap(color);
+# endif
+    color = clamp(color, 0.0, 1.0);
+# ifdef COLOR_GRADING
+    color = grade(color);
+# endif
+    o_Target = vec4(color, 1.0);
+}
@@ -0,0 +1,166 @@
+use bevy_ecs::reflect::ReflectComponent;
+use bevy_math::{Mat4, Vec3, Vec4};
+use bevy_reflect::Reflect;
+
+/// An axis-aligned bounding box in the local space of an entity, used for frustum culling.
+///
+/// It is computed from the positions of the entity's [`Mesh`](crate::mesh::Mesh), see
+/// [`calculate_bounds_system`](crate::camera::calculate_bounds_system).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Aabb {
+    pub center: Vec3,
+    pub half_extents: Vec3,
+}
+
+impl Aabb {
+    pub fn from_min_max(minimum: Vec3, maximum: Vec3) -> Self {
+        Self {
+            center: (maximum + minimum) * 0.5,
+            half_extents: (maximum - minimum) * 0.5,
+        }
+    }
+
+    pub fn min(&self) -> Vec3 {
+        self.center - self.half_extents
+    }
+
+    pub fn max(&self) -> Vec3 {
+        self.center + self.half_extents
+    }
+
+    /// Returns the radius of the box projected onto the normal of the plane, once the box is
+    /// transformed by the given model matrix.
+    fn projected_radius(&self, model: &Mat4, normal: Vec3) -> f32 {
+        (model.x_axis.truncate() * self.half_extents.x)
+            .dot(normal)
+            .abs()
+            + (model.y_axis.truncate() * self.half_extents.y)
+                .dot(normal)
+                .abs()
+            + (model.z_axis.truncate() * self.half_extents.z)
+                .dot(normal)
+                .abs()
+    }
+}
+
+/// A plane, where the points `p` with `normal.dot(p) + d == 0` are on the plane and the points
+/// on the side of the normal are inside.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub d: f32,
+}
+
+impl Plane {
+    /// Creates a plane from the `(a, b, c, d)` coefficients of `ax + by + cz + d = 0`, normalizing
+    /// them.
+    pub fn from_coefficients(coefficients: Vec4) -> Self {
+        let normal = coefficients.truncate();
+        let length = normal.length();
+        Self {
+            normal: normal / length,
+            d: coefficients.w / length,
+        }
+    }
+
+    /// Returns the signed distance of the point to the plane, positive on the inside.
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) + self.d
+    }
+}
+
+/// The view frustum of a camera in world space, used for frustum culling.
+///
+/// It is updated from the camera's projection and `GlobalTransform` by
+/// [`update_frusta_system`](crate::camera::update_frusta_system).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Frustum {
+    /// The left, right, bottom, top, near and far planes
+    #[reflect(ignore)]
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the frustum planes from a view projection matrix, with the depth range of the
+    /// clip space from 0 to 1.
+    pub fn from_view_projection(view_projection: &Mat4) -> Self {
+        let rows = view_projection.transpose();
+        let (x, y, z, w) = (rows.x_axis, rows.y_axis, rows.z_axis, rows.w_axis);
+        Self {
+            planes: [
+                Plane::from_coefficients(w + x),
+                Plane::from_coefficients(w - x),
+                Plane::from_coefficients(w + y),
+                Plane::from_coefficients(w - y),
+                Plane::from_coefficients(z),
+                Plane::from_coefficients(w - z),
+            ],
+        }
+    }
+
+    /// Returns true if the point is inside of the frustum.
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Returns true if the bounding box, transformed by the given model matrix, intersects the
+    /// frustum. Boxes close to the corners of the frustum may be reported as intersecting.
+    pub fn intersects_obb(&self, aabb: &Aabb, model: &Mat4) -> bool {
+        let center = model.transform_point3(aabb.center);
+        self.planes.iter().all(|plane| {
+            plane.signed_distance(center) + aabb.projected_radius(model, plane.normal) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn perspective_frustum() -> Frustum {
+        // a camera at the origin looking towards -Z
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 100.0);
+        Frustum::from_view_projection(&projection)
+    }
+
+    #[test]
+    fn perspective_frustum_planes() {
+        let frustum = perspective_frustum();
+        assert!(frustum.contains_point(Vec3::new(0.0, 0.0, -10.0)));
+        assert!(frustum.contains_point(Vec3::new(9.0, -9.0, -10.0)));
+        assert!(!frustum.contains_point(Vec3::new(11.0, 0.0, -10.0)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 10.0)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -0.5)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -101.0)));
+    }
+
+    #[test]
+    fn boxes_intersect_frustum() {
+        let frustum = perspective_frustum();
+        let aabb = Aabb::from_min_max(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let at = |x: f32, y: f32, z: f32| Mat4::from_translation(Vec3::new(x, y, z));
+
+        assert!(frustum.intersects_obb(&aabb, &at(0.0, 0.0, -10.0)));
+        // the center is outside, but a corner is inside
+        assert!(frustum.intersects_obb(&aabb, &at(10.5, 0.0, -10.0)));
+        assert!(!frustum.intersects_obb(&aabb, &at(13.0, 0.0, -10.0)));
+        assert!(!frustum.intersects_obb(&aabb, &at(0.0, 0.0, 5.0)));
+        // scaling grows the box
+        let scaled = at(13.0, 0.0, -10.0) * Mat4::from_scale(Vec3::splat(3.0));
+        assert!(frustum.intersects_obb(&aabb, &scaled));
+    }
+
+    #[test]
+    fn orthographic_frustum() {
+        let projection = Mat4::orthographic_rh(-10.0, 10.0, -5.0, 5.0, 0.0, 100.0);
+        let view = Mat4::from_translation(Vec3::new(100.0, 0.0, 0.0)).inverse();
+        let frustum = Frustum::from_view_projection(&(projection * view));
+        assert!(frustum.contains_point(Vec3::new(105.0, 4.0, -50.0)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -50.0)));
+        assert!(!frustum.contains_point(Vec3::new(100.0, 6.0, -50.0)));
+    }
+}
@@ -7,12 +7,17 @@ use crate::{
         LoadOp, Operations, PassDescriptor, RenderPassColorAttachment,
         RenderPassDepthStencilAttachment, TextureAttachment,
     },
+    pipeline::PipelineDescriptor,
+    post_process::{self, PostProcessNode},
+    shader::Shader,
     texture::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage},
     Color,
 };
+use bevy_asset::Assets;
 use bevy_ecs::{reflect::ReflectComponent, world::World};
 use bevy_reflect::Reflect;
 use bevy_window::WindowId;
+use std::borrow::Cow;
 
 /// A component that indicates that an entity should be drawn in the "main pass"
 #[derive(Clone, Debug, Default, Reflect)]
@@ -53,6 +58,40 @@ impl Msaa {
     }
 }
 
+/// Renders the main pass into an HDR texture instead of the swap chain. The texture is then
+/// tonemapped into the swap chain by the [`PostProcessNode`], following the
+/// [`Tonemapping`](crate::post_process::Tonemapping), [`Bloom`](crate::post_process::Bloom) and
+/// [`ColorGrading`](crate::post_process::ColorGrading) components of the entity of `camera`.
+///
+/// Unlike the post-processing components, HDR itself is a resource rather than a camera
+/// component: the attachments of the main pass are wired into the base render graph, and the
+/// pipelines of [`MainPass`] entities are specialized for their texture format, before any camera
+/// exists. Like [`Msaa`], it has to be inserted before the `RenderPlugin` is added. The pipelines
+/// of [`MainPass`] entities render into [`Hdr::TEXTURE_FORMAT`], with the [`Hdr::SHADER_DEF`]
+/// shader def so that their shaders leave tonemapping to the post-processing.
+#[derive(Debug)]
+pub struct Hdr {
+    pub enabled: bool,
+    /// The name of the camera the post-processing components are read from,
+    /// [`camera::CAMERA_3D`] by default.
+    pub camera: Cow<'static, str>,
+}
+
+impl Default for Hdr {
+    fn default() -> Self {
+        Hdr {
+            enabled: false,
+            camera: Cow::Borrowed(camera::CAMERA_3D),
+        }
+    }
+}
+
+impl Hdr {
+    pub const TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+    /// The shader def of pipelines that render into the HDR texture.
+    pub const SHADER_DEF: &'static str = "HDR";
+}
+
 #[derive(Debug)]
 pub struct BaseRenderGraphConfig {
     pub add_2d_camera: bool,
@@ -70,6 +109,9 @@ pub mod node {
     pub const TEXTURE_COPY: &str = "texture_copy";
     pub const MAIN_DEPTH_TEXTURE: &str = "main_pass_depth_texture";
     pub const MAIN_SAMPLED_COLOR_ATTACHMENT: &str = "main_pass_sampled_color_attachment";
+    pub const MAIN_HDR_TEXTURE: &str = "main_pass_hdr_texture";
+    pub const MAIN_HDR_SAMPLED_COLOR_ATTACHMENT: &str = "main_pass_hdr_sampled_color_attachment";
+    pub const POST_PROCESS: &str = "post_process";
     pub const MAIN_PASS: &str = "main_pass";
     pub const SHARED_BUFFERS: &str = "shared_buffers";
 }
@@ -99,6 +141,7 @@ pub(crate) fn add_base_graph(config: &BaseRenderGraphConfig, world: &mut World)
     let world = world.cell();
     let mut graph = world.get_resource_mut::<RenderGraph>().unwrap();
     let msaa = world.get_resource::<Msaa>().unwrap();
+    let hdr = world.get_resource::<Hdr>().unwrap();
 
     graph.add_node(node::TEXTURE_COPY, TextureCopyNode::default());
     if config.add_3d_camera {
@@ -190,19 +233,30 @@ pub(crate) fn add_base_graph(config: &BaseRenderGraphConfig, world: &mut World)
         WindowSwapChainNode::new(WindowId::primary()),
     );
 
+    // the main pass only renders into the hdr texture if it would render into the swap chain
+    let render_to_hdr_texture = hdr.enabled && config.connect_main_pass_to_swapchain;
     if config.connect_main_pass_to_swapchain {
-        graph
-            .add_slot_edge(
-                node::PRIMARY_SWAP_CHAIN,
-                WindowSwapChainNode::OUT_TEXTURE,
-                node::MAIN_PASS,
-                if msaa.samples > 1 {
-                    "color_resolve_target"
-                } else {
-                    "color_attachment"
-                },
-            )
-            .unwrap();
+        if render_to_hdr_texture {
+            let mut shaders = world.get_resource_mut::<Assets<Shader>>().unwrap();
+            let mut pipelines = world
+                .get_resource_mut::<Assets<PipelineDescriptor>>()
+                .unwrap();
+            post_process::add_post_process_pipelines(&mut shaders, &mut pipelines);
+            add_hdr_main_pass(&mut graph, &msaa, &hdr);
+        } else {
+            graph
+                .add_slot_edge(
+                    node::PRIMARY_SWAP_CHAIN,
+                    WindowSwapChainNode::OUT_TEXTURE,
+                    node::MAIN_PASS,
+                    if msaa.samples > 1 {
+                        "color_resolve_target"
+                    } else {
+                        "color_attachment"
+                    },
+                )
+                .unwrap();
+        }
     }
 
     if msaa.samples > 1 {
@@ -225,24 +279,113 @@ pub(crate) fn add_base_graph(config: &BaseRenderGraphConfig, world: &mut World)
             ),
         );
 
+        if !render_to_hdr_texture {
+            graph
+                .add_slot_edge(
+                    node::MAIN_SAMPLED_COLOR_ATTACHMENT,
+                    WindowSwapChainNode::OUT_TEXTURE,
+                    node::MAIN_PASS,
+                    "color_attachment",
+                )
+                .unwrap();
+        }
+    }
+
+    if config.connect_main_pass_to_main_depth_texture {
         graph
             .add_slot_edge(
-                node::MAIN_SAMPLED_COLOR_ATTACHMENT,
-                WindowSwapChainNode::OUT_TEXTURE,
+                node::MAIN_DEPTH_TEXTURE,
+                WindowTextureNode::OUT_TEXTURE,
                 node::MAIN_PASS,
-                "color_attachment",
+                "depth",
             )
             .unwrap();
     }
+}
 
-    if config.connect_main_pass_to_main_depth_texture {
+/// Renders the main pass into the HDR texture, which is then post-processed into the swap chain.
+fn add_hdr_main_pass(graph: &mut RenderGraph, msaa: &Msaa, hdr: &Hdr) {
+    graph.add_node(
+        node::MAIN_HDR_TEXTURE,
+        WindowTextureNode::new(
+            WindowId::primary(),
+            TextureDescriptor {
+                size: Extent3d {
+                    depth_or_array_layers: 1,
+                    width: 1,
+                    height: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: Hdr::TEXTURE_FORMAT,
+                usage: TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::SAMPLED,
+            },
+        ),
+    );
+    graph
+        .add_slot_edge(
+            node::MAIN_HDR_TEXTURE,
+            WindowTextureNode::OUT_TEXTURE,
+            node::MAIN_PASS,
+            if msaa.samples > 1 {
+                "color_resolve_target"
+            } else {
+                "color_attachment"
+            },
+        )
+        .unwrap();
+
+    if msaa.samples > 1 {
+        graph.add_node(
+            node::MAIN_HDR_SAMPLED_COLOR_ATTACHMENT,
+            WindowTextureNode::new(
+                WindowId::primary(),
+                TextureDescriptor {
+                    size: Extent3d {
+                        depth_or_array_layers: 1,
+                        width: 1,
+                        height: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: msaa.samples,
+                    dimension: TextureDimension::D2,
+                    format: Hdr::TEXTURE_FORMAT,
+                    usage: TextureUsage::OUTPUT_ATTACHMENT,
+                },
+            ),
+        );
         graph
             .add_slot_edge(
-                node::MAIN_DEPTH_TEXTURE,
+                node::MAIN_HDR_SAMPLED_COLOR_ATTACHMENT,
                 WindowTextureNode::OUT_TEXTURE,
                 node::MAIN_PASS,
-                "depth",
+                "color_attachment",
             )
             .unwrap();
     }
+
+    graph.add_node(
+        node::POST_PROCESS,
+        PostProcessNode::new(WindowId::primary(), &hdr.camera),
+    );
+    graph
+        .add_slot_edge(
+            node::MAIN_HDR_TEXTURE,
+            WindowTextureNode::OUT_TEXTURE,
+            node::POST_PROCESS,
+            PostProcessNode::IN_HDR_TEXTURE,
+        )
+        .unwrap();
+    graph
+        .add_slot_edge(
+            node::PRIMARY_SWAP_CHAIN,
+            WindowSwapChainNode::OUT_TEXTURE,
+            node::POST_PROCESS,
+            PostProcessNode::IN_COLOR_ATTACHMENT,
+        )
+        .unwrap();
+    graph
+        .add_node_edge(node::MAIN_PASS, node::POST_PROCESS)
+        .unwrap();
 }
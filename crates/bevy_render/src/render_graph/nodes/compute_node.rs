@@ -0,0 +1,557 @@
+use crate::{
+    pipeline::{BindGroupDescriptorId, ComputePipelineDescriptor, PipelineLayout},
+    render_graph::{Node, ResourceSlots},
+    renderer::{
+        BindGroup, BufferId, BufferInfo, BufferMapMode, BufferUsage, RenderContext,
+        RenderResourceBinding, RenderResourceBindings, RenderResourceContext,
+    },
+    shader::{get_dependent_shaders, specialize_shader, Shader},
+};
+use bevy_app::{Events, ManualEventReader};
+use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_ecs::world::{Mut, World};
+use bevy_utils::{tracing::error, HashMap};
+use std::cell::RefCell;
+
+/// The staging buffers that [`ComputeNode`]s copy the storage buffers they read back to, by the
+/// name of their binding.
+///
+/// The copies happen while the render graph runs, so they can be read once the frame has been
+/// rendered: in [`RenderStage::PostRender`](crate::RenderStage::PostRender) or in a later frame.
+#[derive(Debug, Default)]
+pub struct ComputeReadbacks {
+    staging_buffers: HashMap<String, (BufferId, usize)>,
+}
+
+impl ComputeReadbacks {
+    /// Returns the contents of the storage buffer bound as `name`, as of its last copy.
+    pub fn read(
+        &self,
+        name: &str,
+        render_resource_context: &dyn RenderResourceContext,
+    ) -> Option<Vec<u8>> {
+        let (buffer, size) = *self.staging_buffers.get(name)?;
+        let data = RefCell::new(Vec::with_capacity(size));
+        render_resource_context.map_buffer(buffer, BufferMapMode::Read);
+        render_resource_context.read_mapped_buffer(buffer, 0..size as u64, &|bytes, _| {
+            data.borrow_mut().extend_from_slice(bytes)
+        });
+        render_resource_context.unmap_buffer(buffer);
+        Some(data.into_inner())
+    }
+
+    fn get_or_create_staging_buffer(
+        &mut self,
+        name: &str,
+        size: usize,
+        render_resource_context: &dyn RenderResourceContext,
+    ) -> BufferId {
+        if let Some((buffer, current_size)) = self.staging_buffers.get(name) {
+            if *current_size == size {
+                return *buffer;
+            }
+            render_resource_context.remove_buffer(*buffer);
+        }
+
+        let buffer = render_resource_context.create_buffer(BufferInfo {
+            size,
+            buffer_usage: BufferUsage::MAP_READ | BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.staging_buffers
+            .insert(name.to_string(), (buffer, size));
+        buffer
+    }
+}
+
+struct ReadbackCopy {
+    source: BufferId,
+    source_offset: u64,
+    destination: BufferId,
+    size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PipelineState {
+    Pending,
+    Created,
+    /// The shader couldn't be specialized, nothing is dispatched until it changes.
+    Failed,
+}
+
+/// Dispatches a compute pipeline, binding the resources of the global [`RenderResourceBindings`]
+/// by name.
+///
+/// Nothing is dispatched until the bindings of every bind group of the pipeline are set. The
+/// pipeline is created again when its shader or a shader it imports changes.
+pub struct ComputeNode {
+    pipeline: Handle<ComputePipelineDescriptor>,
+    /// The number of workgroups dispatched in each dimension.
+    pub workgroups: [u32; 3],
+    readbacks: Vec<String>,
+    pipeline_state: PipelineState,
+    /// Whether the layout of the pipeline was reflected from its shader, rather than set by the
+    /// user, so it is reflected again when the shader changes.
+    layout_reflected: bool,
+    shader_event_reader: ManualEventReader<AssetEvent<Shader>>,
+    bind_groups: Option<Vec<(u32, BindGroupDescriptorId, BindGroup)>>,
+    readback_copies: Vec<ReadbackCopy>,
+}
+
+impl ComputeNode {
+    pub fn new(pipeline: Handle<ComputePipelineDescriptor>, workgroups: [u32; 3]) -> Self {
+        ComputeNode {
+            pipeline,
+            workgroups,
+            readbacks: Vec::new(),
+            pipeline_state: PipelineState::Pending,
+            layout_reflected: false,
+            shader_event_reader: Default::default(),
+            bind_groups: None,
+            readback_copies: Vec::new(),
+        }
+    }
+
+    /// Copies the storage buffer bound as `name` to the CPU after each dispatch. Its contents can
+    /// then be read with [`ComputeReadbacks`]. The buffer needs the [`BufferUsage::COPY_SRC`]
+    /// usage.
+    pub fn add_readback(&mut self, name: &str) {
+        self.readbacks.push(name.to_string());
+    }
+}
+
+impl ComputeNode {
+    fn create_pipeline(
+        &mut self,
+        pipelines: &mut Assets<ComputePipelineDescriptor>,
+        shaders: &Assets<Shader>,
+        render_resource_context: &dyn RenderResourceContext,
+    ) -> PipelineState {
+        let pipeline = match pipelines.get(&self.pipeline) {
+            Some(pipeline) => pipeline,
+            None => return PipelineState::Pending,
+        };
+        let shader = match shaders.get(&pipeline.shader) {
+            Some(shader) => shader,
+            None => return PipelineState::Pending,
+        };
+        // The shader module is created from the shader with its imports resolved, so the
+        // pipeline doesn't compile the shader itself.
+        let specialized_shader =
+            match specialize_shader(render_resource_context, shader, shaders, None) {
+                Ok(specialized_shader) => specialized_shader,
+                Err(err) => {
+                    error!(
+                        "Failed to specialize the shader of compute pipeline {:?}: {}",
+                        pipeline.name, err
+                    );
+                    return PipelineState::Failed;
+                }
+            };
+        if pipeline.layout.is_none() || self.layout_reflected {
+            let layout = match specialized_shader.reflect_layout(true) {
+                Some(layout) => layout,
+                None => {
+                    error!(
+                        "Failed to reflect the layout of compute pipeline {:?}",
+                        pipeline.name
+                    );
+                    return PipelineState::Failed;
+                }
+            };
+            pipelines.get_mut(&self.pipeline).unwrap().layout =
+                Some(PipelineLayout::from_shader_layouts(&mut [layout]));
+            self.layout_reflected = true;
+        }
+        let pipeline = pipelines.get(&self.pipeline).unwrap();
+        render_resource_context
+            .create_shader_module_from_source(&pipeline.shader, &specialized_shader);
+        render_resource_context.create_compute_pipeline(
+            self.pipeline.clone_weak(),
+            pipeline,
+            shaders,
+        );
+        PipelineState::Created
+    }
+}
+
+impl Node for ComputeNode {
+    fn prepare(&mut self, world: &mut World) {
+        self.bind_groups = None;
+        self.readback_copies.clear();
+
+        world.resource_scope(
+            |world, mut pipelines: Mut<Assets<ComputePipelineDescriptor>>| {
+                world.resource_scope(
+                    |world, mut render_resource_bindings: Mut<RenderResourceBindings>| {
+                        let render_resource_context = &**world
+                            .get_resource::<Box<dyn RenderResourceContext>>()
+                            .unwrap();
+                        let shaders = world.get_resource::<Assets<Shader>>().unwrap();
+                        if let Some(shader_events) =
+                            world.get_resource::<Events<AssetEvent<Shader>>>()
+                        {
+                            let pipeline_shader = pipelines
+                                .get(&self.pipeline)
+                                .map(|pipeline| pipeline.shader.id);
+                            for event in self.shader_event_reader.iter(shader_events) {
+                                let handle = match event {
+                                    AssetEvent::Created { handle }
+                                    | AssetEvent::Modified { handle } => handle,
+                                    _ => continue,
+                                };
+                                let dependents = get_dependent_shaders(handle.id, shaders);
+                                if pipeline_shader.map_or(false, |id| dependents.contains(&id)) {
+                                    self.pipeline_state = PipelineState::Pending;
+                                }
+                            }
+                        }
+                        if self.pipeline_state == PipelineState::Pending {
+                            self.pipeline_state = self.create_pipeline(
+                                &mut pipelines,
+                                shaders,
+                                render_resource_context,
+                            );
+                        }
+                        if self.pipeline_state != PipelineState::Created {
+                            return;
+                        }
+
+                        let pipeline = match pipelines.get(&self.pipeline) {
+                            Some(pipeline) => pipeline,
+                            None => return,
+                        };
+                        let mut bind_groups = Vec::new();
+                        for bind_group_descriptor in
+                            pipeline.get_layout().unwrap().bind_groups.iter()
+                        {
+                            match render_resource_bindings
+                                .update_bind_group(bind_group_descriptor, render_resource_context)
+                            {
+                                Some(bind_group) => bind_groups.push((
+                                    bind_group_descriptor.index,
+                                    bind_group_descriptor.id,
+                                    bind_group.clone(),
+                                )),
+                                None => return,
+                            }
+                        }
+                        self.bind_groups = Some(bind_groups);
+                    },
+                );
+            },
+        );
+
+        if self.bind_groups.is_none() {
+            return;
+        }
+
+        world.resource_scope(|world, mut readbacks: Mut<ComputeReadbacks>| {
+            let render_resource_context = &**world
+                .get_resource::<Box<dyn RenderResourceContext>>()
+                .unwrap();
+            let render_resource_bindings = world.get_resource::<RenderResourceBindings>().unwrap();
+            for name in self.readbacks.iter() {
+                if let Some(RenderResourceBinding::Buffer { buffer, range, .. }) =
+                    render_resource_bindings.get(name)
+                {
+                    let size = range.end - range.start;
+                    let staging_buffer = readbacks.get_or_create_staging_buffer(
+                        name,
+                        size as usize,
+                        render_resource_context,
+                    );
+                    self.readback_copies.push(ReadbackCopy {
+                        source: *buffer,
+                        source_offset: range.start,
+                        destination: staging_buffer,
+                        size,
+                    });
+                }
+            }
+        });
+    }
+
+    fn update(
+        &mut self,
+        _world: &World,
+        render_context: &mut dyn RenderContext,
+        _input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        let bind_groups = match self.bind_groups {
+            Some(ref bind_groups) => bind_groups,
+            None => return,
+        };
+        let pipeline = &self.pipeline;
+        let [x, y, z] = self.workgroups;
+        render_context.begin_compute_pass(&mut |compute_pass| {
+            compute_pass.set_pipeline(pipeline);
+            for (index, bind_group_descriptor_id, bind_group) in bind_groups.iter() {
+                compute_pass.set_bind_group(
+                    *index,
+                    *bind_group_descriptor_id,
+                    bind_group.id,
+                    bind_group.dynamic_uniform_indices.as_deref(),
+                );
+            }
+            compute_pass.dispatch(x, y, z);
+        });
+
+        for copy in self.readback_copies.iter() {
+            render_context.copy_buffer_to_buffer(
+                copy.source,
+                copy.source_offset,
+                copy.destination,
+                0,
+                copy.size,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        pass::{ComputePass, PassDescriptor, RenderPass},
+        pipeline::BindGroupDescriptorId,
+        renderer::{BindGroupId, HeadlessRenderResourceContext, TextureId},
+        shader::ShaderStage,
+        texture::Extent3d,
+    };
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin};
+    use bevy_core::CorePlugin;
+
+    const DOUBLE_VALUES: &str = r#"
+        [[block]]
+        struct Values {
+            data: [[stride(4)]] array<f32>;
+        };
+
+        [[group(0), binding(0)]]
+        var<storage> values: [[access(read_write)]] Values;
+
+        [[stage(compute), workgroup_size(64)]]
+        fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+            values.data[id.x] = values.data[id.x] * 2.0;
+        }
+    "#;
+
+    #[derive(Default)]
+    struct TestRenderContext {
+        resources: HeadlessRenderResourceContext,
+        dispatches: Vec<[u32; 3]>,
+        copies: Vec<(BufferId, BufferId, u64)>,
+    }
+
+    struct TestComputePass<'a> {
+        render_context: &'a TestRenderContext,
+        dispatches: Vec<[u32; 3]>,
+    }
+
+    impl<'a> ComputePass for TestComputePass<'a> {
+        fn get_render_context(&self) -> &dyn RenderContext {
+            self.render_context
+        }
+
+        fn set_pipeline(&mut self, _pipeline_handle: &Handle<ComputePipelineDescriptor>) {}
+
+        fn set_bind_group(
+            &mut self,
+            _index: u32,
+            _bind_group_descriptor_id: BindGroupDescriptorId,
+            _bind_group: BindGroupId,
+            _dynamic_uniform_indices: Option<&[u32]>,
+        ) {
+        }
+
+        fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+            self.dispatches.push([x, y, z]);
+        }
+    }
+
+    impl RenderContext for TestRenderContext {
+        fn resources(&self) -> &dyn RenderResourceContext {
+            &self.resources
+        }
+
+        fn resources_mut(&mut self) -> &mut dyn RenderResourceContext {
+            &mut self.resources
+        }
+
+        fn copy_buffer_to_buffer(
+            &mut self,
+            source_buffer: BufferId,
+            _source_offset: u64,
+            destination_buffer: BufferId,
+            _destination_offset: u64,
+            size: u64,
+        ) {
+            self.copies.push((source_buffer, destination_buffer, size));
+        }
+
+        fn copy_buffer_to_texture(
+            &mut self,
+            _source_buffer: BufferId,
+            _source_offset: u64,
+            _source_bytes_per_row: u32,
+            _destination_texture: TextureId,
+            _destination_origin: [u32; 3],
+            _destination_mip_level: u32,
+            _size: Extent3d,
+        ) {
+        }
+
+        fn copy_texture_to_buffer(
+            &mut self,
+            _source_texture: TextureId,
+            _source_origin: [u32; 3],
+            _source_mip_level: u32,
+            _destination_buffer: BufferId,
+            _destination_offset: u64,
+            _destination_bytes_per_row: u32,
+            _size: Extent3d,
+        ) {
+        }
+
+        fn copy_texture_to_texture(
+            &mut self,
+            _source_texture: TextureId,
+            _source_origin: [u32; 3],
+            _source_mip_level: u32,
+            _destination_texture: TextureId,
+            _destination_origin: [u32; 3],
+            _destination_mip_level: u32,
+            _size: Extent3d,
+        ) {
+        }
+
+        fn begin_pass(
+            &mut self,
+            _pass_descriptor: &PassDescriptor,
+            _render_resource_bindings: &RenderResourceBindings,
+            _run_pass: &mut dyn FnMut(&mut dyn RenderPass),
+        ) {
+        }
+
+        fn begin_compute_pass(&mut self, run_pass: &mut dyn FnMut(&mut dyn ComputePass)) {
+            let mut compute_pass = TestComputePass {
+                render_context: self,
+                dispatches: Vec::new(),
+            };
+            run_pass(&mut compute_pass);
+            let dispatches = compute_pass.dispatches;
+            self.dispatches.extend(dispatches);
+        }
+    }
+
+    fn setup(shader_source: &str) -> (App, Handle<Shader>, ComputeNode, BufferId) {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Shader>()
+            .add_asset::<ComputePipelineDescriptor>()
+            .init_resource::<RenderResourceBindings>()
+            .init_resource::<ComputeReadbacks>();
+        let world = &mut app.world;
+        world.insert_resource::<Box<dyn RenderResourceContext>>(Box::new(
+            HeadlessRenderResourceContext::default(),
+        ));
+
+        let shader = world
+            .get_resource_mut::<Assets<Shader>>()
+            .unwrap()
+            .add(Shader::from_wgsl(ShaderStage::Compute, shader_source));
+        let pipeline = world
+            .get_resource_mut::<Assets<ComputePipelineDescriptor>>()
+            .unwrap()
+            .add(ComputePipelineDescriptor::new(shader.clone()));
+        let buffer = world
+            .get_resource::<Box<dyn RenderResourceContext>>()
+            .unwrap()
+            .create_buffer(BufferInfo {
+                size: 256,
+                buffer_usage: BufferUsage::STORAGE | BufferUsage::COPY_SRC,
+                mapped_at_creation: false,
+            });
+        world
+            .get_resource_mut::<RenderResourceBindings>()
+            .unwrap()
+            .set(
+                "Values",
+                RenderResourceBinding::Buffer {
+                    buffer,
+                    range: 0..256,
+                    dynamic_index: None,
+                },
+            );
+
+        (app, shader, ComputeNode::new(pipeline, [4, 1, 1]), buffer)
+    }
+
+    fn dispatches(node: &mut ComputeNode, world: &mut World) -> Vec<[u32; 3]> {
+        node.prepare(world);
+        let mut render_context = TestRenderContext::default();
+        node.update(
+            world,
+            &mut render_context,
+            &ResourceSlots::default(),
+            &mut ResourceSlots::default(),
+        );
+        render_context.dispatches
+    }
+
+    #[test]
+    fn dispatches_and_reads_back_storage_buffers() {
+        let (mut app, _, mut node, buffer) = setup(DOUBLE_VALUES);
+        let world = &mut app.world;
+
+        node.add_readback("Values");
+        node.prepare(world);
+
+        let mut render_context = TestRenderContext::default();
+        node.update(
+            world,
+            &mut render_context,
+            &ResourceSlots::default(),
+            &mut ResourceSlots::default(),
+        );
+        assert_eq!(render_context.dispatches, vec![[4, 1, 1]]);
+        assert_eq!(render_context.copies.len(), 1);
+        let (source, staging_buffer, size) = render_context.copies[0];
+        assert_eq!(source, buffer);
+        assert_eq!(size, 256);
+
+        let render_resource_context = &**world
+            .get_resource::<Box<dyn RenderResourceContext>>()
+            .unwrap();
+        let staging_info = render_resource_context
+            .get_buffer_info(staging_buffer)
+            .unwrap();
+        assert_eq!(staging_info.size, 256);
+        assert!(staging_info.buffer_usage.contains(BufferUsage::MAP_READ));
+        let readbacks = world.get_resource::<ComputeReadbacks>().unwrap();
+        assert_eq!(
+            readbacks
+                .read("Values", render_resource_context)
+                .map(|data| data.len()),
+            Some(256)
+        );
+    }
+
+    #[test]
+    fn invalid_shaders_are_skipped_until_they_change() {
+        let (mut app, shader, mut node, _) = setup("fn main( {");
+        assert!(dispatches(&mut node, &mut app.world).is_empty());
+        assert!(dispatches(&mut node, &mut app.world).is_empty());
+
+        app.world.get_resource_mut::<Assets<Shader>>().unwrap().set(
+            &shader,
+            Shader::from_wgsl(ShaderStage::Compute, DOUBLE_VALUES),
+        );
+        app.update();
+        assert_eq!(dispatches(&mut node, &mut app.world), vec![[4, 1, 1]]);
+    }
+}
@@ -1,22 +1,45 @@
 use crate::{
     camera::{ActiveCameras, VisibleEntities},
-    draw::{Draw, RenderCommand},
+    draw::{Draw, RenderCommand, Visible},
     pass::{ClearColor, LoadOp, PassDescriptor, TextureAttachment},
-    pipeline::{IndexFormat, PipelineDescriptor},
+    pipeline::{IndexFormat, InputStepMode, PipelineDescriptor},
     render_graph::{Node, ResourceSlotInfo, ResourceSlots},
     renderer::{
-        BindGroupId, BufferId, RenderContext, RenderResourceBindings, RenderResourceContext,
-        RenderResourceType,
+        BindGroupId, BufferId, BufferInfo, BufferMapMode, BufferUsage, RenderContext,
+        RenderResourceBindings, RenderResourceContext, RenderResourceType,
     },
 };
 use bevy_asset::{Assets, Handle};
+use bevy_core::cast_slice;
 use bevy_ecs::{
     query::{QueryState, ReadOnlyFetch, WorldQuery},
     world::{Mut, World},
 };
+use bevy_math::Mat4;
 use bevy_utils::{tracing::debug, HashMap};
 use std::fmt;
 
+/// The render commands of a visible entity of a camera.
+struct EntityDraw<'a> {
+    render_commands: &'a [RenderCommand],
+    /// The model matrix of the entity, if it's drawn with instancing.
+    instance: Option<Mat4>,
+    transparent: bool,
+}
+
+/// The render commands of the visible entities of a camera, in the order they are drawn.
+#[derive(Debug)]
+enum PassDraw<'a> {
+    /// An entity that isn't drawn with instancing.
+    Single(&'a [RenderCommand]),
+    /// Entities drawn with instancing, which share their render commands.
+    Instanced {
+        render_commands: &'a [RenderCommand],
+        models: Vec<Mat4>,
+        transparent: bool,
+    },
+}
+
 pub struct PassNode<Q: WorldQuery> {
     descriptor: PassDescriptor,
     inputs: Vec<ResourceSlotInfo>,
@@ -27,6 +50,122 @@ pub struct PassNode<Q: WorldQuery> {
     default_clear_color_inputs: Vec<usize>,
     query_state: Option<QueryState<Q>>,
     commands: Vec<RenderCommand>,
+    instance_buffer: InstanceBuffer,
+}
+
+/// Batches the draws of the visible entities of a camera, in the order they are drawn.
+///
+/// Opaque entities drawn with instancing are batched with any entity drawn the same way, but
+/// transparent entities only with the one drawn right before them to keep them sorted.
+fn batch_draws<'a>(entity_draws: impl IntoIterator<Item = EntityDraw<'a>>) -> Vec<PassDraw<'a>> {
+    let mut draws = Vec::new();
+    let mut opaque_batches = HashMap::<&[RenderCommand], usize>::default();
+    for entity_draw in entity_draws {
+        let EntityDraw {
+            render_commands,
+            instance,
+            transparent,
+        } = entity_draw;
+        let model = if let Some(model) = instance {
+            model
+        } else {
+            draws.push(PassDraw::Single(render_commands));
+            continue;
+        };
+
+        let batch = if transparent {
+            match draws.last() {
+                Some(PassDraw::Instanced {
+                    render_commands: batch_render_commands,
+                    transparent: true,
+                    ..
+                }) if *batch_render_commands == render_commands => Some(draws.len() - 1),
+                _ => None,
+            }
+        } else {
+            opaque_batches.get(render_commands).copied()
+        };
+
+        match batch {
+            Some(batch) => {
+                if let PassDraw::Instanced { models, .. } = &mut draws[batch] {
+                    models.push(model);
+                }
+            }
+            None => {
+                if !transparent {
+                    opaque_batches.insert(render_commands, draws.len());
+                }
+                draws.push(PassDraw::Instanced {
+                    render_commands,
+                    models: vec![model],
+                    transparent,
+                });
+            }
+        }
+    }
+    draws
+}
+
+/// The vertex buffer instanced draws read the models of their instances from. It is written
+/// through a staging buffer every frame, and only reallocated when it needs to grow.
+#[derive(Debug, Default)]
+struct InstanceBuffer {
+    buffer: Option<BufferId>,
+    staging_buffer: Option<BufferId>,
+    capacity: usize,
+    len: usize,
+}
+
+impl InstanceBuffer {
+    fn write(&mut self, render_resource_context: &dyn RenderResourceContext, data: &[u8]) {
+        self.len = data.len();
+        if data.is_empty() {
+            return;
+        }
+
+        if data.len() > self.capacity {
+            for buffer in self
+                .buffer
+                .take()
+                .into_iter()
+                .chain(self.staging_buffer.take())
+            {
+                render_resource_context.remove_buffer(buffer);
+            }
+            self.capacity = data.len().next_power_of_two();
+            self.buffer = Some(render_resource_context.create_buffer(BufferInfo {
+                size: self.capacity,
+                buffer_usage: BufferUsage::VERTEX | BufferUsage::COPY_DST,
+                ..Default::default()
+            }));
+            self.staging_buffer = Some(render_resource_context.create_buffer(BufferInfo {
+                size: self.capacity,
+                buffer_usage: BufferUsage::COPY_SRC | BufferUsage::MAP_WRITE,
+                ..Default::default()
+            }));
+        }
+
+        let staging_buffer = self.staging_buffer.unwrap();
+        render_resource_context.map_buffer(staging_buffer, BufferMapMode::Write);
+        render_resource_context.write_mapped_buffer(
+            staging_buffer,
+            0..data.len() as u64,
+            &mut |buffer, _render_resource_context| {
+                buffer[..data.len()].copy_from_slice(data);
+            },
+        );
+        render_resource_context.unmap_buffer(staging_buffer);
+    }
+
+    /// Copies the data written this frame from the staging buffer into the vertex buffer.
+    fn copy(&self, render_context: &mut dyn RenderContext) {
+        if let (Some(staging_buffer), Some(buffer)) = (self.staging_buffer, self.buffer) {
+            if self.len > 0 {
+                render_context.copy_buffer_to_buffer(staging_buffer, 0, buffer, 0, self.len as u64);
+            }
+        }
+    }
 }
 
 impl<Q: WorldQuery> fmt::Debug for PassNode<Q> {
@@ -103,6 +242,7 @@ impl<Q: WorldQuery> PassNode<Q> {
             default_clear_color_inputs: Vec::new(),
             query_state: None,
             commands: Vec::new(),
+            instance_buffer: InstanceBuffer::default(),
         }
     }
 
@@ -127,6 +267,7 @@ where
         let query_state = self.query_state.get_or_insert_with(|| world.query());
         let cameras = &self.cameras;
         let commands = &mut self.commands;
+        let instance_buffer = &mut self.instance_buffer;
         world.resource_scope(|world, mut active_cameras: Mut<ActiveCameras>| {
             let mut pipeline_camera_commands = HashMap::default();
             let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
@@ -134,41 +275,120 @@ where
                 .get_resource::<Box<dyn RenderResourceContext>>()
                 .unwrap();
 
+            // collect the draws of all cameras first, so their instances share a buffer
+            let mut camera_draws = Vec::new();
             for camera_name in cameras.iter() {
-                let active_camera = if let Some(active_camera) = active_cameras.get_mut(camera_name)
+                let visible_entities = if let Some(entity) = active_cameras
+                    .get(camera_name)
+                    .and_then(|active_camera| active_camera.entity)
                 {
-                    active_camera
-                } else {
-                    continue;
-                };
-
-                let visible_entities = if let Some(entity) = active_camera.entity {
                     world.get::<VisibleEntities>(entity).unwrap()
                 } else {
                     continue;
                 };
-                for visible_entity in visible_entities.iter() {
-                    if query_state.get(world, visible_entity.entity).is_err() {
-                        // visible entity does not match the Pass query
-                        continue;
-                    }
 
-                    let draw = if let Some(draw) = world.get::<Draw>(visible_entity.entity) {
-                        draw
-                    } else {
-                        continue;
+                let entity_draws = visible_entities
+                    .iter()
+                    // visible entities that don't match the Pass query aren't drawn
+                    .filter(|visible_entity| query_state.get(world, visible_entity.entity).is_ok())
+                    .filter_map(|visible_entity| {
+                        let draw = world.get::<Draw>(visible_entity.entity)?;
+                        Some(EntityDraw {
+                            render_commands: &draw.render_commands,
+                            instance: draw.instance,
+                            transparent: world
+                                .get::<Visible>(visible_entity.entity)
+                                .map_or(false, |visible| visible.is_transparent),
+                        })
+                    });
+                camera_draws.push((camera_name, batch_draws(entity_draws)));
+            }
+
+            let instance_data = camera_draws
+                .iter()
+                .flat_map(|(_, draws)| draws.iter())
+                .filter_map(|draw| match draw {
+                    PassDraw::Instanced { models, .. } => Some(models),
+                    PassDraw::Single(_) => None,
+                })
+                .flatten()
+                .flat_map(|model| std::array::IntoIter::new(model.to_cols_array()))
+                .collect::<Vec<f32>>();
+            instance_buffer.write(render_resource_context, cast_slice(&instance_data));
+
+            let mut next_instance = 0;
+            for (camera_name, draws) in camera_draws {
+                let active_camera = active_cameras.get_mut(camera_name).unwrap();
+                for draw in draws {
+                    let (render_commands, instances) = match draw {
+                        PassDraw::Single(render_commands) => (render_commands, None),
+                        PassDraw::Instanced {
+                            render_commands,
+                            models,
+                            ..
+                        } => {
+                            let instances = next_instance..next_instance + models.len() as u32;
+                            next_instance = instances.end;
+                            (render_commands, Some(instances))
+                        }
                     };
 
-                    for render_command in draw.render_commands.iter() {
+                    let mut instance_buffer_slot = None;
+                    for render_command in render_commands.iter() {
+                        match (render_command, &instances, instance_buffer_slot) {
+                            // instanced draws read the models of their instances from the
+                            // instance buffer
+                            (
+                                RenderCommand::DrawIndexed {
+                                    indices,
+                                    base_vertex,
+                                    ..
+                                },
+                                Some(instances),
+                                Some(slot),
+                            ) => {
+                                commands.push(RenderCommand::SetVertexBuffer {
+                                    slot,
+                                    buffer: instance_buffer.buffer.unwrap(),
+                                    offset: 0,
+                                });
+                                commands.push(RenderCommand::DrawIndexed {
+                                    indices: indices.clone(),
+                                    base_vertex: *base_vertex,
+                                    instances: instances.clone(),
+                                });
+                                continue;
+                            }
+                            (RenderCommand::Draw { vertices, .. }, Some(instances), Some(slot)) => {
+                                commands.push(RenderCommand::SetVertexBuffer {
+                                    slot,
+                                    buffer: instance_buffer.buffer.unwrap(),
+                                    offset: 0,
+                                });
+                                commands.push(RenderCommand::Draw {
+                                    vertices: vertices.clone(),
+                                    instances: instances.clone(),
+                                });
+                                continue;
+                            }
+                            _ => {}
+                        }
+
                         commands.push(render_command.clone());
                         // whenever a new pipeline is set, ensure the relevant camera bind groups
                         // are set
                         if let RenderCommand::SetPipeline { pipeline } = render_command {
+                            let layout = pipelines.get(pipeline).unwrap().get_layout().unwrap();
+                            instance_buffer_slot = layout
+                                .vertex_buffer_descriptors
+                                .iter()
+                                .position(|descriptor| {
+                                    descriptor.step_mode == InputStepMode::Instance
+                                })
+                                .map(|slot| slot as u32);
                             let bind_groups = pipeline_camera_commands
                                 .entry(pipeline.clone_weak())
                                 .or_insert_with(|| {
-                                    let descriptor = pipelines.get(pipeline).unwrap();
-                                    let layout = descriptor.get_layout().unwrap();
                                     let mut commands = Vec::new();
                                     for bind_group_descriptor in layout.bind_groups.iter() {
                                         if let Some(bind_group) =
@@ -233,6 +453,8 @@ where
         let render_resource_bindings = world.get_resource::<RenderResourceBindings>().unwrap();
         let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
 
+        self.instance_buffer.copy(render_context);
+
         let mut draw_state = DrawState::default();
         let commands = &mut self.commands;
         render_context.begin_pass(
@@ -382,3 +604,140 @@ impl DrawState {
             .resize(layout.vertex_buffer_descriptors.len(), None);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::renderer::HeadlessRenderResourceContext;
+    use bevy_asset::HandleId;
+    use bevy_reflect::TypeUuid;
+
+    /// The render commands of a sprite drawn with instancing: the sprite pipeline, its material
+    /// and the quad mesh shared by all sprites.
+    fn sprite_commands(material: u64, quad: BufferId) -> Vec<RenderCommand> {
+        vec![
+            RenderCommand::SetPipeline {
+                pipeline: Handle::weak(HandleId::new(PipelineDescriptor::TYPE_UUID, 0)),
+            },
+            RenderCommand::SetBindGroup {
+                index: 2,
+                bind_group: BindGroupId(material),
+                dynamic_uniform_indices: None,
+            },
+            RenderCommand::SetVertexBuffer {
+                slot: 0,
+                buffer: quad,
+                offset: 0,
+            },
+            RenderCommand::SetIndexBuffer {
+                buffer: quad,
+                offset: 0,
+                index_format: IndexFormat::Uint32,
+            },
+            RenderCommand::DrawIndexed {
+                indices: 0..6,
+                base_vertex: 0,
+                instances: 0..1,
+            },
+        ]
+    }
+
+    fn instanced(render_commands: &[RenderCommand], transparent: bool) -> EntityDraw {
+        EntityDraw {
+            render_commands,
+            instance: Some(Mat4::IDENTITY),
+            transparent,
+        }
+    }
+
+    /// Returns the number of instances of each draw, with `0` for draws without instancing.
+    fn instance_counts(draws: &[PassDraw]) -> Vec<usize> {
+        draws
+            .iter()
+            .map(|draw| match draw {
+                PassDraw::Single(_) => 0,
+                PassDraw::Instanced { models, .. } => models.len(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn opaque_draws_are_batched_with_any_matching_draw() {
+        let quad = BufferId::new();
+        let (a, b) = (sprite_commands(0, quad), sprite_commands(1, quad));
+        let draws = batch_draws(vec![
+            instanced(&a, false),
+            instanced(&b, false),
+            instanced(&a, false),
+            EntityDraw {
+                render_commands: &a,
+                instance: None,
+                transparent: false,
+            },
+            instanced(&b, false),
+        ]);
+        assert_eq!(instance_counts(&draws), vec![2, 2, 0]);
+    }
+
+    #[test]
+    fn transparent_draws_are_only_batched_with_the_previous_draw() {
+        let quad = BufferId::new();
+        let (a, b) = (sprite_commands(0, quad), sprite_commands(1, quad));
+        let draws = batch_draws(vec![
+            instanced(&a, true),
+            instanced(&a, true),
+            instanced(&b, true),
+            instanced(&a, true),
+        ]);
+        assert_eq!(instance_counts(&draws), vec![2, 1, 1]);
+    }
+
+    #[test]
+    fn many_sprites_collapse_into_a_single_draw() {
+        // examples/2d/many_sprites.rs: a grid of sprites sharing one material
+        let sprite = sprite_commands(0, BufferId::new());
+        let draws = batch_draws((0..128 * 128).map(|_| instanced(&sprite, true)));
+        assert_eq!(instance_counts(&draws), vec![128 * 128]);
+    }
+
+    #[test]
+    fn bevymark_collapses_into_a_draw_per_material() {
+        // examples/tools/bevymark.rs: each click spawns birds with a new material, further back
+        // than the birds spawned before them, so the birds of a material are sorted together
+        let quad = BufferId::new();
+        let materials = (0..3)
+            .map(|material| sprite_commands(material, quad))
+            .collect::<Vec<_>>();
+        let draws = batch_draws(
+            materials
+                .iter()
+                .flat_map(|bird| (0..5000).map(move |_| instanced(bird, true))),
+        );
+        assert_eq!(instance_counts(&draws), vec![5000, 5000, 5000]);
+    }
+
+    #[test]
+    fn instance_buffer_only_grows_when_needed() {
+        let context = HeadlessRenderResourceContext::default();
+        let mut instance_buffer = InstanceBuffer::default();
+
+        instance_buffer.write(&context, &[1; 100]);
+        let buffer = instance_buffer.buffer.unwrap();
+        assert_eq!(context.get_buffer_info(buffer).unwrap().size, 128);
+
+        instance_buffer.write(&context, &[2; 128]);
+        assert_eq!(instance_buffer.buffer, Some(buffer));
+        assert_eq!(instance_buffer.len, 128);
+
+        instance_buffer.write(&context, &[3; 200]);
+        let grown = instance_buffer.buffer.unwrap();
+        assert_ne!(grown, buffer);
+        assert!(context.get_buffer_info(buffer).is_none());
+        assert_eq!(context.get_buffer_info(grown).unwrap().size, 256);
+
+        // an empty frame keeps the buffer for the next one
+        instance_buffer.write(&context, &[]);
+        assert_eq!(instance_buffer.buffer, Some(grown));
+        assert_eq!(instance_buffer.len, 0);
+    }
+}
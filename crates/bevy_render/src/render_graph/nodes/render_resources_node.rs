@@ -647,6 +647,7 @@ fn asset_render_resources_node_system<T: RenderResources + Asset>(
                 // events are ordered so future modification events are ok
                 changed_assets.remove(&handle.id);
             }
+            AssetEvent::LoadedWithDependencies { .. } => {}
         }
     }
 
@@ -1,7 +1,7 @@
 use crate::{
     render_graph::{Node, ResourceSlots},
     renderer::{BufferInfo, BufferUsage, RenderContext},
-    texture::{Texture, TextureDescriptor, TEXTURE_ASSET_INDEX},
+    texture::{Extent3d, Texture, TEXTURE_ASSET_INDEX},
 };
 use bevy_app::{Events, ManualEventReader};
 use bevy_asset::{AssetEvent, Assets};
@@ -32,56 +32,71 @@ impl Node for TextureCopyNode {
                             continue;
                         }
 
-                        let texture_descriptor: TextureDescriptor = texture.into();
-                        let width = texture.size.width as usize;
-                        let aligned_width =
-                            render_context.resources().get_aligned_texture_size(width);
-                        let format_size = texture.format.pixel_size();
-                        let mut aligned_data = vec![
-                            0;
-                            format_size
-                                * aligned_width
-                                * texture.size.height as usize
-                                * texture.size.depth_or_array_layers
-                                    as usize
-                        ];
-                        texture
-                            .data
-                            .chunks_exact(format_size * width)
-                            .enumerate()
-                            .for_each(|(index, row)| {
-                                let offset = index * aligned_width * format_size;
-                                aligned_data[offset..(offset + width * format_size)]
-                                    .copy_from_slice(row);
-                            });
-                        let texture_buffer = render_context.resources().create_buffer_with_data(
-                            BufferInfo {
-                                buffer_usage: BufferUsage::COPY_SRC,
-                                ..Default::default()
-                            },
-                            &aligned_data,
-                        );
-
-                        let texture_resource = render_context
+                        let texture_resource = match render_context
                             .resources()
                             .get_asset_resource(handle, TEXTURE_ASSET_INDEX)
-                            .unwrap();
+                        {
+                            // textures in unsupported formats have no resource
+                            Some(texture_resource) => texture_resource.get_texture().unwrap(),
+                            None => continue,
+                        };
+
+                        // compressed formats are copied in rows of blocks, with the size rounded
+                        // up to whole blocks
+                        let (block_width, block_height) = texture.format.block_dimensions();
+                        let block_size = texture.format.block_size();
+                        for mip_level in 0..texture.mip_level_count {
+                            let size = texture.mip_level_size(mip_level);
+                            let blocks_x = ((size.width + block_width - 1) / block_width) as usize;
+                            let blocks_y = (size.height + block_height - 1) / block_height;
+                            let aligned_blocks_x = render_context
+                                .resources()
+                                .get_aligned_texture_size(blocks_x);
+                            let row_size = blocks_x * block_size;
+                            let aligned_row_size = aligned_blocks_x * block_size;
+                            let mut aligned_data = vec![
+                                0;
+                                aligned_row_size
+                                    * blocks_y as usize
+                                    * size.depth_or_array_layers as usize
+                            ];
+                            texture
+                                .mip_level_data(mip_level)
+                                .chunks_exact(row_size)
+                                .enumerate()
+                                .for_each(|(index, row)| {
+                                    let offset = index * aligned_row_size;
+                                    aligned_data[offset..(offset + row_size)].copy_from_slice(row);
+                                });
+                            let texture_buffer =
+                                render_context.resources().create_buffer_with_data(
+                                    BufferInfo {
+                                        buffer_usage: BufferUsage::COPY_SRC,
+                                        ..Default::default()
+                                    },
+                                    &aligned_data,
+                                );
 
-                        render_context.copy_buffer_to_texture(
-                            texture_buffer,
-                            0,
-                            (format_size * aligned_width) as u32,
-                            texture_resource.get_texture().unwrap(),
-                            [0, 0, 0],
-                            0,
-                            texture_descriptor.size,
-                        );
-                        render_context.resources().remove_buffer(texture_buffer);
+                            render_context.copy_buffer_to_texture(
+                                texture_buffer,
+                                0,
+                                aligned_row_size as u32,
+                                texture_resource,
+                                [0, 0, 0],
+                                mip_level,
+                                Extent3d::new(
+                                    blocks_x as u32 * block_width,
+                                    blocks_y * block_height,
+                                    size.depth_or_array_layers,
+                                ),
+                            );
+                            render_context.resources().remove_buffer(texture_buffer);
+                        }
 
                         copied_textures.insert(&handle.id);
                     }
                 }
-                AssetEvent::Removed { .. } => {}
+                AssetEvent::Removed { .. } | AssetEvent::LoadedWithDependencies { .. } => {}
             }
         }
     }
@@ -1,11 +1,11 @@
 use super::RenderResourceContext;
 use crate::{
-    pipeline::{BindGroupDescriptorId, PipelineDescriptor},
+    pipeline::{BindGroupDescriptorId, ComputePipelineDescriptor, PipelineDescriptor},
     renderer::{
         BindGroup, BufferId, BufferInfo, BufferMapMode, RenderResourceId, SamplerId, TextureId,
     },
-    shader::{Shader, ShaderError},
-    texture::{SamplerDescriptor, TextureDescriptor},
+    shader::{Shader, ShaderError, ShaderSource},
+    texture::{SamplerDescriptor, TextureDescriptor, TextureFormat},
 };
 use bevy_asset::{Assets, Handle, HandleUntyped};
 use bevy_utils::HashMap;
@@ -28,6 +28,10 @@ impl HeadlessRenderResourceContext {
     pub fn add_texture_descriptor(&self, texture: TextureId, descriptor: TextureDescriptor) {
         self.texture_descriptors.write().insert(texture, descriptor);
     }
+
+    pub fn get_texture_descriptor(&self, texture: TextureId) -> Option<TextureDescriptor> {
+        self.texture_descriptors.read().get(&texture).copied()
+    }
 }
 
 impl RenderResourceContext for HeadlessRenderResourceContext {
@@ -128,6 +132,14 @@ impl RenderResourceContext for HeadlessRenderResourceContext {
     ) {
     }
 
+    fn create_compute_pipeline(
+        &self,
+        _pipeline_handle: Handle<ComputePipelineDescriptor>,
+        _pipeline_descriptor: &ComputePipelineDescriptor,
+        _shaders: &Assets<Shader>,
+    ) {
+    }
+
     fn create_bind_group(
         &self,
         _bind_group_descriptor_id: BindGroupDescriptorId,
@@ -162,12 +174,19 @@ impl RenderResourceContext for HeadlessRenderResourceContext {
         size
     }
 
+    fn is_texture_format_supported(&self, _format: TextureFormat) -> bool {
+        true
+    }
+
     fn get_specialized_shader(
         &self,
         shader: &Shader,
-        _macros: Option<&[String]>,
+        macros: Option<&[String]>,
     ) -> Result<Shader, ShaderError> {
-        Ok(shader.clone())
+        match shader.source {
+            ShaderSource::Wgsl(_) => Ok(Shader::from_wgsl(shader.stage, &shader.get_wgsl(macros)?)),
+            _ => Ok(shader.clone()),
+        }
     }
 
     fn remove_stale_bind_groups(&self) {}
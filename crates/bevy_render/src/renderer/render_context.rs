@@ -2,10 +2,12 @@ use downcast_rs::{impl_downcast, Downcast};
 
 use super::RenderResourceContext;
 use crate::{
-    pass::{PassDescriptor, RenderPass},
+    pass::{ComputePass, PassDescriptor, RenderPass},
     renderer::{BufferId, RenderResourceBindings, TextureId},
     texture::Extent3d,
 };
+use bevy_utils::tracing::warn;
+use std::sync::Once;
 
 pub trait RenderContext: Downcast {
     fn resources(&self) -> &dyn RenderResourceContext;
@@ -57,6 +59,14 @@ pub trait RenderContext: Downcast {
         render_resource_bindings: &RenderResourceBindings,
         run_pass: &mut dyn FnMut(&mut dyn RenderPass),
     );
+    /// Runs a compute pass. Backends without compute support keep the default, which warns the
+    /// first time and doesn't run the pass.
+    fn begin_compute_pass(&mut self, _run_pass: &mut dyn FnMut(&mut dyn ComputePass)) {
+        static WARN_UNSUPPORTED: Once = Once::new();
+        WARN_UNSUPPORTED.call_once(|| {
+            warn!("The render backend doesn't support compute passes, they are skipped.")
+        });
+    }
 }
 
 impl_downcast!(RenderContext);
@@ -1,12 +1,15 @@
 use crate::{
-    pipeline::{BindGroupDescriptorId, PipelineDescriptor, PipelineLayout},
+    pipeline::{
+        BindGroupDescriptorId, ComputePipelineDescriptor, PipelineDescriptor, PipelineLayout,
+    },
     renderer::{
         BindGroup, BufferId, BufferInfo, BufferMapMode, RenderResourceId, SamplerId, TextureId,
     },
     shader::{Shader, ShaderError, ShaderLayout, ShaderStages},
-    texture::{SamplerDescriptor, TextureDescriptor},
+    texture::{SamplerDescriptor, TextureDescriptor, TextureFormat},
 };
 use bevy_asset::{Asset, Assets, Handle, HandleUntyped};
+use bevy_utils::tracing::warn;
 use bevy_window::Window;
 use downcast_rs::{impl_downcast, Downcast};
 use std::ops::Range;
@@ -48,6 +51,11 @@ pub trait RenderResourceContext: Downcast + Send + Sync + 'static {
     fn get_buffer_info(&self, buffer: BufferId) -> Option<BufferInfo>;
     fn get_aligned_uniform_size(&self, size: usize, dynamic: bool) -> usize;
     fn get_aligned_texture_size(&self, data_size: usize) -> usize;
+    /// Returns true if textures in the given format can be created. Compressed formats need GPU
+    /// features that not every device has, so by default only uncompressed formats are supported.
+    fn is_texture_format_supported(&self, format: TextureFormat) -> bool {
+        !format.is_compressed()
+    }
     fn set_asset_resource_untyped(
         &self,
         handle: HandleUntyped,
@@ -66,6 +74,21 @@ pub trait RenderResourceContext: Downcast + Send + Sync + 'static {
         pipeline_descriptor: &PipelineDescriptor,
         shaders: &Assets<Shader>,
     );
+    /// Creates the compute pipeline of the given descriptor, whose layout must be set. A pipeline
+    /// previously created for the same handle is replaced.
+    ///
+    /// Backends without compute support keep the default, which only logs a warning.
+    fn create_compute_pipeline(
+        &self,
+        _pipeline_handle: Handle<ComputePipelineDescriptor>,
+        pipeline_descriptor: &ComputePipelineDescriptor,
+        _shaders: &Assets<Shader>,
+    ) {
+        warn!(
+            "The render backend doesn't support compute pipelines, {:?} isn't created.",
+            pipeline_descriptor.name
+        );
+    }
     fn bind_group_descriptor_exists(&self, bind_group_descriptor_id: BindGroupDescriptorId)
         -> bool;
     fn create_bind_group(
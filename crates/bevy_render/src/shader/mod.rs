@@ -1,12 +1,16 @@
+mod preprocessor;
 #[allow(clippy::module_inception)]
 mod shader;
 mod shader_defs;
+mod wgsl_reflect;
 
 #[cfg(not(target_arch = "wasm32"))]
 mod shader_reflect;
 
+pub use preprocessor::*;
 pub use shader::*;
 pub use shader_defs::*;
+pub use wgsl_reflect::*;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use shader_reflect::*;
@@ -0,0 +1,360 @@
+use super::{Shader, ShaderError, ShaderSource};
+use bevy_asset::{AssetPath, Assets, HandleId};
+use bevy_utils::HashSet;
+
+/// A shader that another shader imports.
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ShaderImport {
+    /// `#include "path"` imports the shader asset at this asset path.
+    AssetPath(String),
+    /// `#import some::module` imports the shader that declares `#define_import_path some::module`.
+    Custom(String),
+}
+
+struct Conditional {
+    line: usize,
+    parent_active: bool,
+    condition: bool,
+    in_else: bool,
+}
+
+impl Conditional {
+    fn is_active(&self) -> bool {
+        self.parent_active && self.condition != self.in_else
+    }
+}
+
+/// Returns the name and argument of a preprocessor directive line, like `#ifdef SHADER_DEF` or
+/// `# include "path"`.
+fn parse_directive(line: &str) -> Option<(&str, Option<&str>)> {
+    let directive = line.trim_start().strip_prefix('#')?.trim_start();
+    let (name, argument) = match directive.find(char::is_whitespace) {
+        Some(index) => (&directive[..index], directive[index..].trim()),
+        None => (directive, ""),
+    };
+    if name.is_empty() {
+        None
+    } else if argument.is_empty() {
+        Some((name, None))
+    } else {
+        Some((name, Some(argument)))
+    }
+}
+
+/// Returns the `#define_import_path` and the `#import` and `#include` directives of a shader
+/// source.
+pub(crate) fn parse_imports(source: &str) -> (Option<String>, Vec<ShaderImport>) {
+    let mut import_path = None;
+    let mut imports = Vec::new();
+    for line in source.lines() {
+        match parse_directive(line) {
+            Some(("define_import_path", Some(path))) => import_path = Some(path.to_string()),
+            Some(("import", Some(module))) => {
+                imports.push(ShaderImport::Custom(module.to_string()))
+            }
+            Some(("include", Some(path))) => {
+                imports.push(ShaderImport::AssetPath(path.trim_matches('"').to_string()))
+            }
+            _ => {}
+        }
+    }
+    (import_path, imports)
+}
+
+impl ShaderImport {
+    /// Returns the id of the shader asset this import resolves to. `#include`s resolve to the id of
+    /// their asset path whether or not it's loaded yet, `#import`s only resolve once a loaded shader
+    /// declares their import path.
+    pub fn get_handle_id(&self, shaders: &Assets<Shader>) -> Option<HandleId> {
+        match self {
+            ShaderImport::AssetPath(path) => Some(HandleId::from(AssetPath::from(path.as_str()))),
+            ShaderImport::Custom(module) => shaders
+                .iter()
+                .find(|(_, shader)| shader.import_path.as_deref() == Some(module))
+                .map(|(id, _)| id),
+        }
+    }
+}
+
+/// Replaces the `#import` and `#include` directives of a shader with the sources of the shaders
+/// they import, recursively. Each shader is only imported once.
+pub fn resolve_imports(shader: &Shader, shaders: &Assets<Shader>) -> Result<Shader, ShaderError> {
+    if shader.imports.is_empty() {
+        return Ok(shader.clone());
+    }
+
+    let mut imported = HashSet::default();
+    if let Some(ref import_path) = shader.import_path {
+        imported.insert(ShaderImport::Custom(import_path.clone()));
+    }
+    let mut output = String::new();
+    append_imported_source(shader, shaders, &mut imported, &mut output)?;
+    Ok(Shader::new(
+        shader.stage,
+        match shader.source {
+            ShaderSource::Wgsl(_) => ShaderSource::Wgsl(output),
+            _ => ShaderSource::Glsl(output),
+        },
+    ))
+}
+
+fn append_imported_source(
+    shader: &Shader,
+    shaders: &Assets<Shader>,
+    imported: &mut HashSet<ShaderImport>,
+    output: &mut String,
+) -> Result<(), ShaderError> {
+    let source = match shader.source {
+        ShaderSource::Glsl(ref source) | ShaderSource::Wgsl(ref source) => source,
+        ShaderSource::Spirv(_) => return Err(ShaderError::SpirvImport),
+    };
+    // `shader.imports` follows the order of the directives, with the paths of `#include`s already
+    // resolved relative to the shader by the `ShaderLoader`.
+    let mut imports = shader.imports.iter();
+    for line in source.lines() {
+        let import = match parse_directive(line) {
+            Some(("import", Some(module))) => ShaderImport::Custom(module.to_string()),
+            Some(("include", Some(path))) => {
+                ShaderImport::AssetPath(path.trim_matches('"').to_string())
+            }
+            Some(("define_import_path", _)) => continue,
+            _ => {
+                output.push_str(line);
+                output.push('\n');
+                continue;
+            }
+        };
+        let import = imports.next().cloned().unwrap_or(import);
+        if imported.insert(import.clone()) {
+            let imported_shader = import
+                .get_handle_id(shaders)
+                .and_then(|id| shaders.get(id))
+                .ok_or(ShaderError::UnresolvedImport(import))?;
+            append_imported_source(imported_shader, shaders, imported, output)?;
+        }
+    }
+    Ok(())
+}
+
+/// Applies the `#ifdef`, `#ifndef`, `#else` and `#endif` directives of a shader source for the
+/// given shader defs, for shader languages without a preprocessor of their own, like WGSL.
+///
+/// Directives and the lines of branches that aren't taken are replaced with empty lines, so line
+/// numbers in compilation errors still match the source.
+pub fn apply_shader_defs(source: &str, shader_defs: &[String]) -> Result<String, ShaderError> {
+    let mut conditionals: Vec<Conditional> = Vec::new();
+    let mut output = String::with_capacity(source.len());
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let active = conditionals
+            .last()
+            .map_or(true, |conditional| conditional.is_active());
+        let invalid_directive = |directive: &str| ShaderError::InvalidDirective {
+            directive: directive.to_string(),
+            line: line_number,
+        };
+
+        match parse_directive(line) {
+            Some((directive @ "ifdef", shader_def)) | Some((directive @ "ifndef", shader_def)) => {
+                let shader_def = shader_def
+                    .and_then(|shader_def| shader_def.split_whitespace().next())
+                    .ok_or_else(|| invalid_directive(directive))?;
+                let defined = shader_defs.iter().any(|def| def == shader_def);
+                conditionals.push(Conditional {
+                    line: line_number,
+                    parent_active: active,
+                    condition: defined == (directive == "ifdef"),
+                    in_else: false,
+                });
+            }
+            Some((directive @ "else", _)) => match conditionals.last_mut() {
+                Some(conditional) if !conditional.in_else => conditional.in_else = true,
+                _ => return Err(invalid_directive(directive)),
+            },
+            Some((directive @ "endif", _)) => {
+                conditionals
+                    .pop()
+                    .ok_or_else(|| invalid_directive(directive))?;
+            }
+            _ if active => output.push_str(line),
+            _ => {}
+        }
+        output.push('\n');
+    }
+
+    match conditionals.last() {
+        Some(conditional) => Err(ShaderError::MissingEndif(conditional.line)),
+        None => Ok(output),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shader::ShaderStage;
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin, HandleUntyped};
+    use bevy_core::CorePlugin;
+    use bevy_reflect::TypeUuid;
+
+    const SOURCE: &str = r"
+a
+#ifdef FOO
+b
+# ifdef BAR
+c
+#else
+d
+#endif
+#endif
+#ifndef FOO
+e
+#endif
+";
+
+    fn lines(source: &str) -> Vec<&str> {
+        source.lines().filter(|line| !line.is_empty()).collect()
+    }
+
+    #[test]
+    fn shader_defs_select_branches() {
+        let no_defs = apply_shader_defs(SOURCE, &[]).unwrap();
+        assert_eq!(lines(&no_defs), vec!["a", "e"]);
+
+        let foo = apply_shader_defs(SOURCE, &["FOO".to_string()]).unwrap();
+        assert_eq!(lines(&foo), vec!["a", "b", "d"]);
+
+        let foo_bar = apply_shader_defs(SOURCE, &["FOO".to_string(), "BAR".to_string()]).unwrap();
+        assert_eq!(lines(&foo_bar), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn line_numbers_are_preserved() {
+        let output = apply_shader_defs(SOURCE, &[]).unwrap();
+        assert_eq!(output.lines().count(), SOURCE.lines().count());
+        assert_eq!(output.lines().nth(11), Some("e"));
+    }
+
+    #[test]
+    fn unbalanced_directives() {
+        assert!(matches!(
+            apply_shader_defs("#ifdef FOO\na\n", &[]),
+            Err(ShaderError::MissingEndif(1))
+        ));
+        assert!(matches!(
+            apply_shader_defs("a\n#endif\n", &[]),
+            Err(ShaderError::InvalidDirective { line: 2, .. })
+        ));
+        assert!(matches!(
+            apply_shader_defs("#ifdef FOO\n#else\n#else\n#endif\n", &[]),
+            Err(ShaderError::InvalidDirective { line: 3, .. })
+        ));
+    }
+
+    fn shader_assets() -> App {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Shader>();
+        app
+    }
+
+    fn glsl(shader: &Shader) -> &str {
+        match shader.source {
+            ShaderSource::Glsl(ref source) => source,
+            _ => panic!("expected a GLSL shader"),
+        }
+    }
+
+    #[test]
+    fn parses_imports() {
+        let shader = Shader::from_glsl(
+            ShaderStage::Fragment,
+            "#define_import_path foo::bar\n#import foo::baz\n#include \"shaders/qux.glsl\"\n",
+        );
+        assert_eq!(shader.import_path.as_deref(), Some("foo::bar"));
+        assert_eq!(
+            shader.imports,
+            vec![
+                ShaderImport::Custom("foo::baz".to_string()),
+                ShaderImport::AssetPath("shaders/qux.glsl".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_are_resolved_once() {
+        const COMMON: HandleUntyped = HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 1);
+        const LIGHTING: HandleUntyped = HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2);
+
+        let mut app = shader_assets();
+        let mut shaders = app.world.get_resource_mut::<Assets<Shader>>().unwrap();
+        shaders.set_untracked(
+            COMMON,
+            Shader::from_glsl(
+                ShaderStage::Fragment,
+                "#define_import_path common\ncommon\n",
+            ),
+        );
+        shaders.set_untracked(
+            LIGHTING,
+            Shader::from_glsl(
+                ShaderStage::Fragment,
+                "#define_import_path lighting\n#import common\nlighting\n",
+            ),
+        );
+        let shader = Shader::from_glsl(
+            ShaderStage::Fragment,
+            "#import common\n#import lighting\nmain\n",
+        );
+
+        let resolved = resolve_imports(&shader, &shaders).unwrap();
+        assert_eq!(lines(glsl(&resolved)), vec!["common", "lighting", "main"]);
+        assert!(resolved.imports.is_empty());
+    }
+
+    #[test]
+    fn includes_resolve_by_asset_path() {
+        let mut app = shader_assets();
+        let mut shaders = app.world.get_resource_mut::<Assets<Shader>>().unwrap();
+        shaders.set_untracked(
+            HandleId::from(AssetPath::from("shaders/common.glsl")),
+            Shader::from_glsl(ShaderStage::Fragment, "common\n"),
+        );
+        let shader = Shader::from_glsl(
+            ShaderStage::Fragment,
+            "#include \"shaders/common.glsl\"\nmain\n",
+        );
+
+        let resolved = resolve_imports(&shader, &shaders).unwrap();
+        assert_eq!(lines(glsl(&resolved)), vec!["common", "main"]);
+    }
+
+    #[test]
+    fn includes_use_the_resolved_import_paths() {
+        let mut app = shader_assets();
+        let mut shaders = app.world.get_resource_mut::<Assets<Shader>>().unwrap();
+        shaders.set_untracked(
+            HandleId::from(AssetPath::from("shaders/common.glsl")),
+            Shader::from_glsl(ShaderStage::Fragment, "common\n"),
+        );
+        // The `ShaderLoader` resolves `#include`s relative to the including shader.
+        let mut shader =
+            Shader::from_glsl(ShaderStage::Fragment, "#include \"common.glsl\"\nmain\n");
+        shader.imports = vec![ShaderImport::AssetPath("shaders/common.glsl".to_string())];
+
+        let resolved = resolve_imports(&shader, &shaders).unwrap();
+        assert_eq!(lines(glsl(&resolved)), vec!["common", "main"]);
+    }
+
+    #[test]
+    fn unresolved_import() {
+        let app = shader_assets();
+        let shaders = app.world.get_resource::<Assets<Shader>>().unwrap();
+        let shader = Shader::from_glsl(ShaderStage::Fragment, "#import missing\n");
+        assert!(matches!(
+            resolve_imports(&shader, shaders),
+            Err(ShaderError::UnresolvedImport(ShaderImport::Custom(ref module))) if module == "missing"
+        ));
+    }
+}
@@ -3,13 +3,22 @@ use crate::{
     renderer::RenderResourceContext,
 };
 
-use super::ShaderLayout;
+use super::{
+    apply_shader_defs, parse_imports, parse_wgsl, resolve_imports, wgsl_entry_point_stage,
+    wgsl_shader_stage, ShaderImport, ShaderLayout,
+};
 use bevy_app::EventReader;
-use bevy_asset::{AssetEvent, AssetLoader, Assets, Handle, LoadContext, LoadedAsset};
-use bevy_ecs::system::{Res, ResMut};
+use bevy_asset::{
+    AssetEvent, AssetLoader, AssetPath, AssetServer, Assets, Handle, HandleId, HandleUntyped,
+    LoadContext, LoadedAsset,
+};
+use bevy_ecs::system::{Local, Res, ResMut};
 use bevy_reflect::TypeUuid;
-use bevy_utils::{tracing::error, BoxedFuture};
-use std::marker::Copy;
+use bevy_utils::{tracing::error, BoxedFuture, HashMap, HashSet};
+use std::{
+    marker::Copy,
+    path::{Component, Path},
+};
 use thiserror::Error;
 
 /// The stage of a shader
@@ -27,47 +36,88 @@ pub enum ShaderError {
     #[error("Shader compilation error:\n{0}")]
     Compilation(String),
 
-    #[cfg(not(any(
-        target_arch = "wasm32",
-        all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
-        all(target_arch = "x86_64", target_os = "macos"),
-        all(target_arch = "aarch64", target_os = "android"),
-        all(target_arch = "armv7", target_os = "androidabi"),
-        all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
-    )))]
+    /// WGSL parsing error.
+    #[error("WGSL parsing error:\n{0}")]
+    WgslParse(String),
+
+    /// WGSL shaders are loaded as the stage of their entry point, so they need exactly one.
+    #[error("WGSL shaders need exactly one entry point, found {0}")]
+    WgslEntryPoints(usize),
+
+    /// A preprocessor directive without its shader def, or without a matching `#ifdef`.
+    #[error("Invalid `#{directive}` directive on line {line}")]
+    InvalidDirective { directive: String, line: usize },
+
+    /// An `#ifdef` or `#ifndef` directive without a matching `#endif`.
+    #[error("Missing `#endif` for the directive on line {0}")]
+    MissingEndif(usize),
+
+    /// An `#import` or `#include` directive for a shader that isn't loaded (yet).
+    #[error("Unresolved shader import: {0:?}")]
+    UnresolvedImport(ShaderImport),
+
+    /// SPIR-V shaders have no source to import.
+    #[error("SPIR-V shaders can't be imported")]
+    SpirvImport,
+
+    #[cfg(all(
+        feature = "glsl",
+        not(any(
+            target_arch = "wasm32",
+            all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
+            all(target_arch = "x86_64", target_os = "macos"),
+            all(target_arch = "aarch64", target_os = "android"),
+            all(target_arch = "armv7", target_os = "androidabi"),
+            all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
+        ))
+    ))]
     /// shaderc error.
     #[error("shaderc error: {0}")]
     ShaderC(#[from] shaderc::Error),
 
-    #[cfg(not(any(
-        target_arch = "wasm32",
-        all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
-        all(target_arch = "x86_64", target_os = "macos"),
-        all(target_arch = "aarch64", target_os = "android"),
-        all(target_arch = "armv7", target_os = "androidabi"),
-        all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
-    )))]
+    #[cfg(all(
+        feature = "glsl",
+        not(any(
+            target_arch = "wasm32",
+            all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
+            all(target_arch = "x86_64", target_os = "macos"),
+            all(target_arch = "aarch64", target_os = "android"),
+            all(target_arch = "armv7", target_os = "androidabi"),
+            all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
+        ))
+    ))]
     #[error("Error initializing shaderc Compiler")]
     ErrorInitializingShadercCompiler,
 
-    #[cfg(not(any(
-        target_arch = "wasm32",
+    #[cfg(all(
+        feature = "glsl",
+        not(any(
+            target_arch = "wasm32",
+            all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
+            all(target_arch = "x86_64", target_os = "macos"),
+            all(target_arch = "aarch64", target_os = "android"),
+            all(target_arch = "armv7", target_os = "androidabi"),
+            all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
+        ))
+    ))]
+    #[error("Error initializing shaderc CompileOptions")]
+    ErrorInitializingShadercCompileOptions,
+
+    /// GLSL shaders are compiled with shaderc or `bevy_glsl_to_spirv`, behind the `glsl` feature.
+    #[cfg(not(feature = "glsl"))]
+    #[error("GLSL shaders can't be compiled without the `glsl` feature")]
+    GlslUnsupported,
+}
+
+#[cfg(all(
+    feature = "glsl",
+    any(
         all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
         all(target_arch = "x86_64", target_os = "macos"),
         all(target_arch = "aarch64", target_os = "android"),
         all(target_arch = "armv7", target_os = "androidabi"),
         all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
-    )))]
-    #[error("Error initializing shaderc CompileOptions")]
-    ErrorInitializingShadercCompileOptions,
-}
-
-#[cfg(any(
-    all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
-    all(target_arch = "x86_64", target_os = "macos"),
-    all(target_arch = "aarch64", target_os = "android"),
-    all(target_arch = "armv7", target_os = "androidabi"),
-    all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
+    )
 ))]
 impl From<ShaderStage> for bevy_glsl_to_spirv::ShaderType {
     fn from(s: ShaderStage) -> bevy_glsl_to_spirv::ShaderType {
@@ -79,12 +129,15 @@ impl From<ShaderStage> for bevy_glsl_to_spirv::ShaderType {
     }
 }
 
-#[cfg(any(
-    all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
-    all(target_arch = "x86_64", target_os = "macos"),
-    all(target_arch = "aarch64", target_os = "android"),
-    all(target_arch = "armv7", target_os = "androidabi"),
-    all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
+#[cfg(all(
+    feature = "glsl",
+    any(
+        all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
+        all(target_arch = "x86_64", target_os = "macos"),
+        all(target_arch = "aarch64", target_os = "android"),
+        all(target_arch = "armv7", target_os = "androidabi"),
+        all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
+    )
 ))]
 pub fn glsl_to_spirv(
     glsl_source: &str,
@@ -95,14 +148,17 @@ pub fn glsl_to_spirv(
         .map_err(ShaderError::Compilation)
 }
 
-#[cfg(not(any(
-    target_arch = "wasm32",
-    all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
-    all(target_arch = "x86_64", target_os = "macos"),
-    all(target_arch = "aarch64", target_os = "android"),
-    all(target_arch = "armv7", target_os = "androidabi"),
-    all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
-)))]
+#[cfg(all(
+    feature = "glsl",
+    not(any(
+        target_arch = "wasm32",
+        all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
+        all(target_arch = "x86_64", target_os = "macos"),
+        all(target_arch = "aarch64", target_os = "android"),
+        all(target_arch = "armv7", target_os = "androidabi"),
+        all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
+    ))
+))]
 impl Into<shaderc::ShaderKind> for ShaderStage {
     fn into(self) -> shaderc::ShaderKind {
         match self {
@@ -113,14 +169,17 @@ impl Into<shaderc::ShaderKind> for ShaderStage {
     }
 }
 
-#[cfg(not(any(
-    target_arch = "wasm32",
-    all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
-    all(target_arch = "x86_64", target_os = "macos"),
-    all(target_arch = "aarch64", target_os = "android"),
-    all(target_arch = "armv7", target_os = "androidabi"),
-    all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
-)))]
+#[cfg(all(
+    feature = "glsl",
+    not(any(
+        target_arch = "wasm32",
+        all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"),
+        all(target_arch = "x86_64", target_os = "macos"),
+        all(target_arch = "aarch64", target_os = "android"),
+        all(target_arch = "armv7", target_os = "androidabi"),
+        all(target_arch = "x86_64", target_os = "windows", target_env = "msvc"),
+    ))
+))]
 pub fn glsl_to_spirv(
     glsl_source: &str,
     stage: ShaderStage,
@@ -147,6 +206,17 @@ pub fn glsl_to_spirv(
     Ok(binary_result.as_binary().to_vec())
 }
 
+/// Without the `glsl` feature only SPIR-V and WGSL shaders are supported, so Bevy can be built
+/// without linking a GLSL compiler.
+#[cfg(not(feature = "glsl"))]
+pub fn glsl_to_spirv(
+    _glsl_source: &str,
+    _stage: ShaderStage,
+    _shader_defs: Option<&[String]>,
+) -> Result<Vec<u32>, ShaderError> {
+    Err(ShaderError::GlslUnsupported)
+}
+
 fn bytes_to_words(bytes: &[u8]) -> Vec<u32> {
     let mut words = Vec::new();
     for bytes4 in bytes.chunks(4) {
@@ -163,6 +233,7 @@ fn bytes_to_words(bytes: &[u8]) -> Vec<u32> {
 pub enum ShaderSource {
     Spirv(Vec<u32>),
     Glsl(String),
+    Wgsl(String),
 }
 
 impl ShaderSource {
@@ -177,11 +248,26 @@ impl ShaderSource {
 pub struct Shader {
     pub source: ShaderSource,
     pub stage: ShaderStage,
+    /// The path other shaders `#import` this shader by, declared with `#define_import_path`.
+    pub import_path: Option<String>,
+    /// The shaders this shader imports with `#import` and `#include`.
+    pub imports: Vec<ShaderImport>,
 }
 
 impl Shader {
     pub fn new(stage: ShaderStage, source: ShaderSource) -> Shader {
-        Shader { source, stage }
+        let (import_path, imports) = match source {
+            ShaderSource::Glsl(ref source) | ShaderSource::Wgsl(ref source) => {
+                parse_imports(source)
+            }
+            ShaderSource::Spirv(_) => (None, Vec::new()),
+        };
+        Shader {
+            source,
+            stage,
+            import_path,
+            imports,
+        }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -196,17 +282,15 @@ impl Shader {
             other => panic!("cannot load {:?} shader", other),
         };
 
-        Ok(Shader {
-            source: ShaderSource::spirv_from_bytes(spirv),
-            stage,
-        })
+        Ok(Shader::new(stage, ShaderSource::spirv_from_bytes(spirv)))
     }
 
     pub fn from_glsl(stage: ShaderStage, glsl: &str) -> Shader {
-        Shader {
-            source: ShaderSource::Glsl(glsl.to_string()),
-            stage,
-        }
+        Shader::new(stage, ShaderSource::Glsl(glsl.to_string()))
+    }
+
+    pub fn from_wgsl(stage: ShaderStage, wgsl: &str) -> Shader {
+        Shader::new(stage, ShaderSource::Wgsl(wgsl.to_string()))
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -214,32 +298,59 @@ impl Shader {
         match self.source {
             ShaderSource::Spirv(ref bytes) => Ok(bytes.clone()),
             ShaderSource::Glsl(ref source) => glsl_to_spirv(source, self.stage, macros),
+            ShaderSource::Wgsl(_) => Err(ShaderError::Compilation(
+                "WGSL shaders are not compiled to SPIR-V.".to_string(),
+            )),
+        }
+    }
+
+    /// Returns the source of a WGSL shader with the given shader defs applied.
+    pub fn get_wgsl(&self, shader_defs: Option<&[String]>) -> Result<String, ShaderError> {
+        match self.source {
+            ShaderSource::Wgsl(ref source) => {
+                apply_shader_defs(source, shader_defs.unwrap_or_default())
+            }
+            _ => Err(ShaderError::Compilation(
+                "Only WGSL shaders have WGSL sources.".to_string(),
+            )),
         }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     pub fn get_spirv_shader(&self, macros: Option<&[String]>) -> Result<Shader, ShaderError> {
-        Ok(Shader {
-            source: ShaderSource::Spirv(self.get_spirv(macros)?),
-            stage: self.stage,
-        })
+        Ok(Shader::new(
+            self.stage,
+            ShaderSource::Spirv(self.get_spirv(macros)?),
+        ))
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     pub fn reflect_layout(&self, enforce_bevy_conventions: bool) -> Option<ShaderLayout> {
-        if let ShaderSource::Spirv(ref spirv) = self.source {
-            Some(ShaderLayout::from_spirv(
+        match self.source {
+            ShaderSource::Spirv(ref spirv) => Some(ShaderLayout::from_spirv(
                 spirv.as_slice(),
                 enforce_bevy_conventions,
-            ))
-        } else {
-            panic!("Cannot reflect layout of non-SpirV shader. Try compiling this shader to SpirV first using self.get_spirv_shader().");
+            )),
+            ShaderSource::Wgsl(ref wgsl) => Some(ShaderLayout::from_wgsl(
+                wgsl,
+                self.stage,
+                enforce_bevy_conventions,
+            )),
+            ShaderSource::Glsl(_) => panic!("Cannot reflect layout of GLSL shader. Try compiling this shader to SpirV first using self.get_spirv_shader()."),
         }
     }
 
     #[cfg(target_arch = "wasm32")]
-    pub fn reflect_layout(&self, _enforce_bevy_conventions: bool) -> Option<ShaderLayout> {
-        panic!("Cannot reflect layout on wasm32.");
+    pub fn reflect_layout(&self, enforce_bevy_conventions: bool) -> Option<ShaderLayout> {
+        if let ShaderSource::Wgsl(ref wgsl) = self.source {
+            Some(ShaderLayout::from_wgsl(
+                wgsl,
+                self.stage,
+                enforce_bevy_conventions,
+            ))
+        } else {
+            panic!("Cannot reflect layout of non-WGSL shader on wasm32.");
+        }
     }
 }
 
@@ -306,9 +417,24 @@ impl AssetLoader for ShaderLoader {
         Box::pin(async move {
             let ext = load_context.path().extension().unwrap().to_str().unwrap();
 
-            let shader = match ext {
+            let mut shader = match ext {
                 "vert" => Shader::from_glsl(ShaderStage::Vertex, std::str::from_utf8(bytes)?),
                 "frag" => Shader::from_glsl(ShaderStage::Fragment, std::str::from_utf8(bytes)?),
+                // GLSL libraries are only imported by other shaders, so their stage is unused.
+                "glsl" => Shader::from_glsl(ShaderStage::Fragment, std::str::from_utf8(bytes)?),
+                "wgsl" => {
+                    let wgsl = std::str::from_utf8(bytes)?;
+                    let (import_path, imports) = parse_imports(wgsl);
+                    if import_path.is_none() && imports.is_empty() {
+                        let module = parse_wgsl(&apply_shader_defs(wgsl, &[])?)?;
+                        Shader::from_wgsl(wgsl_shader_stage(&module)?, wgsl)
+                    } else {
+                        // Shaders that import or are imported can only be parsed once their
+                        // imports are resolved, so `specialize_shader` determines their stage
+                        // when their pipelines are compiled.
+                        Shader::from_wgsl(ShaderStage::Fragment, wgsl)
+                    }
+                }
                 #[cfg(not(target_arch = "wasm32"))]
                 "spv" => Shader::from_spirv(bytes)?,
                 #[cfg(target_arch = "wasm32")]
@@ -316,14 +442,136 @@ impl AssetLoader for ShaderLoader {
                 _ => panic!("unhandled extension: {}", ext),
             };
 
-            load_context.set_default_asset(LoadedAsset::new(shader));
+            for import in shader.imports.iter_mut() {
+                if let ShaderImport::AssetPath(path) = import {
+                    *path = resolve_include_path(load_context.source(), load_context.path(), path);
+                }
+            }
+            let dependencies = shader
+                .imports
+                .iter()
+                .filter_map(|import| match import {
+                    ShaderImport::AssetPath(path) => {
+                        Some(AssetPath::from(path.as_str()).to_owned())
+                    }
+                    ShaderImport::Custom(_) => None,
+                })
+                .collect();
+            load_context
+                .set_default_asset(LoadedAsset::new(shader).with_dependencies(dependencies));
             Ok(())
         })
     }
 
     fn extensions(&self) -> &[&str] {
-        &["vert", "frag", "spv"]
+        &["vert", "frag", "glsl", "spv", "wgsl"]
+    }
+}
+
+/// Returns the asset path of the `#include "include"` directive of the shader at `path` in the
+/// asset source `source`.
+///
+/// Includes are relative to the directory of the including shader, or to the root of its asset
+/// source if they start with `/`. Includes of the form `"source://path"` are left as they are.
+fn resolve_include_path(source: Option<&str>, path: &Path, include: &str) -> String {
+    if include.contains("://") {
+        return include.to_string();
+    }
+    let mut components = Vec::new();
+    if !include.starts_with('/') {
+        if let Some(directory) = path.parent() {
+            for component in directory.components() {
+                if let Component::Normal(component) = component {
+                    components.push(component.to_str().unwrap());
+                }
+            }
+        }
     }
+    for component in include.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+    let path = components.join("/");
+    match source {
+        Some(source) => format!("{}://{}", source, path),
+        None => path,
+    }
+}
+
+/// Resolves the imports of a shader and specializes it for the given shader defs, ready for
+/// pipelines to use.
+///
+/// The stage of a WGSL shader is determined here from its entry point, as shaders that import
+/// others can't be parsed before their imports are resolved.
+pub(crate) fn specialize_shader(
+    render_resource_context: &dyn RenderResourceContext,
+    shader: &Shader,
+    shaders: &Assets<Shader>,
+    shader_defs: Option<&[String]>,
+) -> Result<Shader, ShaderError> {
+    let resolved = resolve_imports(shader, shaders)?;
+    let mut specialized = render_resource_context.get_specialized_shader(&resolved, shader_defs)?;
+    if let ShaderSource::Wgsl(ref wgsl) = specialized.source {
+        specialized.stage = wgsl_entry_point_stage(&parse_wgsl(wgsl)?, specialized.stage)?;
+    }
+    Ok(specialized)
+}
+
+/// Returns the shaders that import any of `modified`, directly or through other imports,
+/// including `modified` itself.
+pub(crate) fn get_dependent_shaders(
+    modified: HandleId,
+    shaders: &Assets<Shader>,
+) -> HashSet<HandleId> {
+    let mut dependents = HashSet::default();
+    dependents.insert(modified);
+    loop {
+        let new_dependents = shaders
+            .iter()
+            .filter(|(id, shader)| {
+                !dependents.contains(id)
+                    && shader.imports.iter().any(|import| {
+                        import
+                            .get_handle_id(shaders)
+                            .map_or(false, |import_id| dependents.contains(&import_id))
+                    })
+            })
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        if new_dependents.is_empty() {
+            return dependents;
+        }
+        dependents.extend(new_dependents);
+    }
+}
+
+/// Returns handles to the `#include`d shaders of a shader, loading the ones that aren't loaded yet
+/// through the asset server.
+fn load_includes(
+    shader: &Shader,
+    shaders: &Assets<Shader>,
+    asset_server: &AssetServer,
+) -> Vec<HandleUntyped> {
+    shader
+        .imports
+        .iter()
+        .filter_map(|import| match import {
+            ShaderImport::AssetPath(path) => {
+                let id = import.get_handle_id(shaders)?;
+                Some(if shaders.get(id).is_some() {
+                    asset_server.get_handle_untyped(id)
+                } else {
+                    asset_server.load_untyped(path.as_str())
+                })
+            }
+            ShaderImport::Custom(_) => None,
+        })
+        .collect()
 }
 
 pub fn shader_update_system(
@@ -331,25 +579,134 @@ pub fn shader_update_system(
     mut pipelines: ResMut<Assets<PipelineDescriptor>>,
     mut shader_events: EventReader<AssetEvent<Shader>>,
     mut pipeline_compiler: ResMut<PipelineCompiler>,
+    mut includes: Local<HashMap<HandleId, Vec<HandleUntyped>>>,
+    asset_server: Res<AssetServer>,
     render_resource_context: Res<Box<dyn RenderResourceContext>>,
 ) {
     for event in shader_events.iter() {
         match event {
-            AssetEvent::Modified { handle } => {
-                if let Err(e) = pipeline_compiler.update_shader(
-                    handle,
-                    &mut pipelines,
-                    &mut shaders,
-                    &**render_resource_context,
-                ) {
-                    error!("Failed to update shader: {}", e);
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                // Included shaders are kept loaded as long as the shaders that include them.
+                match shaders.get(handle) {
+                    Some(shader) if !shader.imports.is_empty() => {
+                        includes.insert(handle.id, load_includes(shader, &shaders, &asset_server));
+                    }
+                    _ => {
+                        includes.remove(&handle.id);
+                    }
+                }
+                // Shaders that import the new or modified shader are recompiled with it.
+                for id in get_dependent_shaders(handle.id, &shaders) {
+                    if let Err(e) = pipeline_compiler.update_shader(
+                        &Handle::weak(id),
+                        &mut pipelines,
+                        &mut shaders,
+                        &**render_resource_context,
+                    ) {
+                        error!("Failed to update shader: {}", e);
+                    }
                 }
             }
-            // Creating shaders on the fly is unhandled since they
-            // have to exist already when assigned to a pipeline. If a
-            // shader is removed the pipeline keeps using its
+            // If a shader is removed the pipeline keeps using its
             // specialized version. Maybe this should be a warning?
-            AssetEvent::Created { .. } | AssetEvent::Removed { .. } => (),
+            AssetEvent::Removed { handle } => {
+                includes.remove(&handle.id);
+            }
+            AssetEvent::LoadedWithDependencies { .. } => (),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::renderer::HeadlessRenderResourceContext;
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin, LoadState};
+    use bevy_core::CorePlugin;
+
+    fn shader_assets() -> App {
+        let mut app = App::new();
+        app.add_plugin(CorePlugin)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Shader>();
+        app
+    }
+
+    #[test]
+    fn include_paths_are_relative_to_the_including_shader() {
+        let path = Path::new("shaders/pbr/pbr.wgsl");
+        assert_eq!(
+            resolve_include_path(None, path, "lights.wgsl"),
+            "shaders/pbr/lights.wgsl"
+        );
+        assert_eq!(
+            resolve_include_path(None, path, "./../common/math.wgsl"),
+            "shaders/common/math.wgsl"
+        );
+        assert_eq!(
+            resolve_include_path(None, path, "/common.wgsl"),
+            "common.wgsl"
+        );
+        assert_eq!(
+            resolve_include_path(Some("embedded"), path, "lights.wgsl"),
+            "embedded://shaders/pbr/lights.wgsl"
+        );
+        assert_eq!(
+            resolve_include_path(Some("embedded"), path, "other://lights.wgsl"),
+            "other://lights.wgsl"
+        );
+    }
+
+    #[test]
+    fn wgsl_stage_is_determined_after_imports_resolve() {
+        let mut app = shader_assets();
+        let mut shaders = app.world.get_resource_mut::<Assets<Shader>>().unwrap();
+        shaders.add(Shader::from_wgsl(
+            ShaderStage::Fragment,
+            "#define_import_path common\nfn one() -> f32 { return 1.0; }\n",
+        ));
+        // Loaded with a provisional stage, as it can't be parsed before its import is resolved.
+        let vertex = Shader::from_wgsl(
+            ShaderStage::Fragment,
+            r"#import common
+// Not a [[stage(fragment)]] shader, despite this comment.
+[[stage(vertex)]]
+fn main() -> [[builtin(position)]] vec4<f32> {
+    return vec4<f32>(0.0, 0.0, 0.0, one());
+}
+",
+        );
+        let library = Shader::from_wgsl(ShaderStage::Fragment, "#import common\n");
+
+        let context = HeadlessRenderResourceContext::default();
+        let specialized = specialize_shader(&context, &vertex, &shaders, None).unwrap();
+        assert_eq!(specialized.stage, ShaderStage::Vertex);
+        assert!(matches!(
+            specialize_shader(&context, &library, &shaders, None),
+            Err(ShaderError::WgslEntryPoints(0))
+        ));
+    }
+
+    #[test]
+    fn unresolved_includes_are_loaded_through_the_asset_server() {
+        let app = shader_assets();
+        let shaders = app.world.get_resource::<Assets<Shader>>().unwrap();
+        let asset_server = app.world.get_resource::<AssetServer>().unwrap();
+        let shader = Shader::from_wgsl(
+            ShaderStage::Fragment,
+            "#include \"shaders/common.wgsl\"\n#import common\n",
+        );
+
+        let handles = load_includes(&shader, shaders, asset_server);
+        assert_eq!(handles.len(), 1);
+        assert_eq!(
+            handles[0].id,
+            HandleId::from(AssetPath::from("shaders/common.wgsl"))
+        );
+        assert_ne!(
+            asset_server.get_load_state(&handles[0]),
+            LoadState::NotLoaded
+        );
+    }
+}
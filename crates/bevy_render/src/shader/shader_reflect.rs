@@ -9,8 +9,9 @@ use crate::{
 use bevy_core::cast_slice;
 use spirv_reflect::{
     types::{
-        ReflectDescriptorBinding, ReflectDescriptorSet, ReflectDescriptorType, ReflectDimension,
-        ReflectShaderStageFlags, ReflectTypeDescription, ReflectTypeFlags,
+        ReflectBlockVariable, ReflectDecorationFlags, ReflectDescriptorBinding,
+        ReflectDescriptorSet, ReflectDescriptorType, ReflectDimension, ReflectShaderStageFlags,
+        ReflectTypeDescription, ReflectTypeFlags,
     },
     ShaderModule,
 };
@@ -28,9 +29,15 @@ impl ShaderLayout {
                     bind_groups.push(bind_group);
                 }
 
-                // obtain attribute descriptors from reflection
+                // obtain attribute descriptors from reflection. the inputs of compute shaders are
+                // all builtins, like gl_GlobalInvocationID
                 let mut vertex_attributes = Vec::new();
-                for input_variable in module.enumerate_input_variables(None).unwrap() {
+                let input_variables = if shader_stage == ReflectShaderStageFlags::COMPUTE {
+                    Vec::new()
+                } else {
+                    module.enumerate_input_variables(None).unwrap()
+                };
+                for input_variable in input_variables {
                     if input_variable.name == GL_VERTEX_INDEX
                         || input_variable.name == GL_INSTANCE_INDEX
                         || input_variable.name == GL_FRONT_FACING
@@ -101,6 +108,30 @@ fn reflect_bind_group(
         bindings.push(binding);
     }
 
+    // depth textures are sampled with comparison samplers, which SPIR-V doesn't distinguish from
+    // other samplers. by convention the sampler of a texture is named "{texture}_sampler"
+    let depth_samplers = bindings
+        .iter()
+        .filter(|binding| {
+            matches!(
+                binding.bind_type,
+                BindType::Texture {
+                    sample_type: TextureSampleType::Depth,
+                    ..
+                }
+            )
+        })
+        .map(|binding| format!("{}_sampler", binding.name))
+        .collect::<Vec<_>>();
+    for binding in bindings.iter_mut() {
+        if let BindType::Sampler {
+            ref mut comparison, ..
+        } = binding.bind_type
+        {
+            *comparison = depth_samplers.contains(&binding.name);
+        }
+    }
+
     BindGroupDescriptor::new(descriptor_set.set, bindings)
 }
 
@@ -131,7 +162,11 @@ fn reflect_binding(
             &binding.name,
             BindType::Texture {
                 view_dimension: reflect_dimension(type_description),
-                sample_type: TextureSampleType::Float { filterable: true },
+                sample_type: if type_description.traits.image.depth == 1 {
+                    TextureSampleType::Depth
+                } else {
+                    TextureSampleType::Float { filterable: true }
+                },
                 multisampled: false,
             },
         ),
@@ -139,10 +174,13 @@ fn reflect_binding(
             &type_description.type_name,
             BindType::StorageBuffer {
                 has_dynamic_offset: false,
-                readonly: true,
+                // only compute shaders can write to storage buffers without the
+                // VERTEX_WRITABLE_STORAGE feature, so other stages keep them read-only
+                readonly: shader_stage != ReflectShaderStageFlags::COMPUTE
+                    || is_readonly(&binding.block),
             },
         ),
-        // TODO: detect comparison "true" case: https://github.com/gpuweb/gpuweb/issues/552
+        // comparison samplers are detected from the texture they sample in `reflect_bind_group`
         // TODO: detect filtering "true" case
         ReflectDescriptorType::Sampler => (
             &binding.name,
@@ -181,6 +219,20 @@ fn reflect_binding(
     }
 }
 
+/// A storage buffer is read only if it is declared `readonly`, which decorates either the buffer or
+/// all of its members.
+fn is_readonly(block: &ReflectBlockVariable) -> bool {
+    block
+        .decoration_flags
+        .contains(ReflectDecorationFlags::NON_WRITABLE)
+        || (!block.members.is_empty()
+            && block.members.iter().all(|member| {
+                member
+                    .decoration_flags
+                    .contains(ReflectDecorationFlags::NON_WRITABLE)
+            }))
+}
+
 #[derive(Debug)]
 enum NumberType {
     Int,
@@ -310,7 +362,7 @@ fn reflect_vertex_format(type_description: &ReflectTypeDescription) -> VertexFor
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "glsl"))]
 mod tests {
     use super::*;
     use crate::shader::{Shader, ShaderStage};
@@ -413,4 +465,89 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_compute_reflection() {
+        let compute_shader = Shader::from_glsl(
+            ShaderStage::Compute,
+            r#"
+            #version 450
+            layout(local_size_x = 64) in;
+
+            layout(set = 0, binding = 0) buffer Output {
+                float[] Output_values;
+            };
+            layout(set = 0, binding = 1) readonly buffer Input {
+                float[] Input_values;
+            };
+
+            void main() {
+                uint index = gl_GlobalInvocationID.x;
+                Output_values[index] = Input_values[index] * 2.0;
+            }
+        "#,
+        )
+        .get_spirv_shader(None)
+        .unwrap();
+
+        let layout = compute_shader.reflect_layout(true).unwrap();
+        assert_eq!(
+            layout,
+            ShaderLayout {
+                entry_point: "main".into(),
+                vertex_buffer_layout: Vec::new(),
+                bind_groups: vec![BindGroupDescriptor::new(
+                    0,
+                    vec![
+                        BindingDescriptor {
+                            index: 0,
+                            name: "Output".into(),
+                            bind_type: BindType::StorageBuffer {
+                                has_dynamic_offset: false,
+                                readonly: false,
+                            },
+                            shader_stage: BindingShaderStage::COMPUTE,
+                        },
+                        BindingDescriptor {
+                            index: 1,
+                            name: "Input".into(),
+                            bind_type: BindType::StorageBuffer {
+                                has_dynamic_offset: false,
+                                readonly: true,
+                            },
+                            shader_stage: BindingShaderStage::COMPUTE,
+                        },
+                    ]
+                )]
+            }
+        );
+    }
+
+    #[test]
+    fn test_vertex_storage_buffers_are_readonly() {
+        let vertex_shader = Shader::from_glsl(
+            ShaderStage::Vertex,
+            r#"
+            #version 450
+            layout(set = 0, binding = 0) buffer Positions {
+                vec4[] Positions_values;
+            };
+
+            void main() {
+                gl_Position = Positions_values[gl_VertexIndex];
+            }
+        "#,
+        )
+        .get_spirv_shader(None)
+        .unwrap();
+
+        let layout = vertex_shader.reflect_layout(true).unwrap();
+        assert_eq!(
+            layout.bind_groups[0].bindings[0].bind_type,
+            BindType::StorageBuffer {
+                has_dynamic_offset: false,
+                readonly: true,
+            }
+        );
+    }
 }
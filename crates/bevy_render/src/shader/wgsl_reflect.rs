@@ -0,0 +1,440 @@
+use crate::{
+    pipeline::{
+        BindGroupDescriptor, BindType, BindingDescriptor, BindingShaderStage, InputStepMode,
+        UniformProperty, VertexAttribute, VertexBufferLayout, VertexFormat,
+    },
+    shader::{ShaderError, ShaderLayout, ShaderStage},
+    texture::{TextureSampleType, TextureViewDimension},
+};
+use bevy_utils::HashMap;
+use naga::{
+    ArraySize, Binding, ConstantInner, GlobalVariable, ImageClass, ImageDimension, Module,
+    ScalarKind, ScalarValue, StorageAccess, StorageClass, Type, TypeInner, VectorSize,
+};
+
+/// Parses a WGSL shader, after its shader defs have been applied.
+pub fn parse_wgsl(source: &str) -> Result<Module, ShaderError> {
+    naga::front::wgsl::parse_str(source).map_err(|err| ShaderError::WgslParse(format!("{:?}", err)))
+}
+
+fn from_naga_stage(stage: naga::ShaderStage) -> ShaderStage {
+    match stage {
+        naga::ShaderStage::Vertex => ShaderStage::Vertex,
+        naga::ShaderStage::Fragment => ShaderStage::Fragment,
+        naga::ShaderStage::Compute => ShaderStage::Compute,
+    }
+}
+
+/// Returns the stage of the single entry point of a WGSL shader.
+pub fn wgsl_shader_stage(module: &Module) -> Result<ShaderStage, ShaderError> {
+    match module.entry_points.as_slice() {
+        [entry_point] => Ok(from_naga_stage(entry_point.stage)),
+        entry_points => Err(ShaderError::WgslEntryPoints(entry_points.len())),
+    }
+}
+
+/// Returns the stage a WGSL shader is compiled as: `stage` if the shader has an entry point of
+/// that stage, otherwise the stage of its single entry point.
+pub fn wgsl_entry_point_stage(
+    module: &Module,
+    stage: ShaderStage,
+) -> Result<ShaderStage, ShaderError> {
+    if module
+        .entry_points
+        .iter()
+        .any(|entry_point| from_naga_stage(entry_point.stage) == stage)
+    {
+        Ok(stage)
+    } else {
+        wgsl_shader_stage(module)
+    }
+}
+
+impl ShaderLayout {
+    pub fn from_wgsl(wgsl: &str, stage: ShaderStage, bevy_conventions: bool) -> ShaderLayout {
+        let module = match parse_wgsl(wgsl) {
+            Ok(module) => module,
+            Err(err) => panic!("Failed to reflect shader layout: {}", err),
+        };
+        let entry_point = module
+            .entry_points
+            .iter()
+            .find(|entry_point| {
+                matches!(
+                    (entry_point.stage, stage),
+                    (naga::ShaderStage::Vertex, ShaderStage::Vertex)
+                        | (naga::ShaderStage::Fragment, ShaderStage::Fragment)
+                        | (naga::ShaderStage::Compute, ShaderStage::Compute)
+                )
+            })
+            .unwrap_or_else(|| panic!("WGSL shader has no {:?} entry point.", stage));
+        let shader_stage = match stage {
+            ShaderStage::Vertex => BindingShaderStage::VERTEX,
+            ShaderStage::Fragment => BindingShaderStage::FRAGMENT,
+            ShaderStage::Compute => BindingShaderStage::COMPUTE,
+        };
+
+        let mut bind_groups = HashMap::<u32, Vec<BindingDescriptor>>::default();
+        for (_, global_variable) in module.global_variables.iter() {
+            if let Some(ref binding) = global_variable.binding {
+                bind_groups
+                    .entry(binding.group)
+                    .or_insert_with(Vec::new)
+                    .push(BindingDescriptor {
+                        index: binding.binding,
+                        name: reflect_binding_name(&module, global_variable),
+                        bind_type: reflect_bind_type(&module, global_variable),
+                        shader_stage,
+                    });
+            }
+        }
+        let mut bind_groups = bind_groups
+            .drain()
+            .map(|(index, mut bindings)| {
+                bindings.sort_by_key(|binding| binding.index);
+                BindGroupDescriptor::new(index, bindings)
+            })
+            .collect::<Vec<_>>();
+        bind_groups.sort_by_key(|bind_group| bind_group.index);
+
+        // vertex attributes are the arguments of the entry point with a location, or the members
+        // with a location of its struct arguments
+        let mut vertex_attributes = Vec::new();
+        if stage == ShaderStage::Vertex {
+            for argument in entry_point.function.arguments.iter() {
+                let ty = &module.types[argument.ty];
+                match (&argument.binding, &ty.inner) {
+                    (Some(Binding::Location { location, .. }), _) => vertex_attributes.push(
+                        reflect_vertex_attribute(argument.name.as_deref(), ty, *location),
+                    ),
+                    (None, TypeInner::Struct { members, .. }) => {
+                        for member in members.iter() {
+                            if let Some(Binding::Location { location, .. }) = member.binding {
+                                vertex_attributes.push(reflect_vertex_attribute(
+                                    member.name.as_deref(),
+                                    &module.types[member.ty],
+                                    location,
+                                ));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        vertex_attributes.sort_by(|a, b| a.shader_location.cmp(&b.shader_location));
+
+        let vertex_buffer_layout = vertex_attributes
+            .drain(..)
+            .map(|vertex_attribute| {
+                // obtain buffer name and instancing flag
+                let (name, instance) = if bevy_conventions {
+                    (
+                        vertex_attribute.name.to_string(),
+                        vertex_attribute.name.starts_with("I_"),
+                    )
+                } else {
+                    ("DefaultVertex".to_string(), false)
+                };
+
+                // create a new buffer descriptor, per attribute!
+                VertexBufferLayout {
+                    attributes: vec![vertex_attribute],
+                    name: name.into(),
+                    step_mode: if instance {
+                        InputStepMode::Instance
+                    } else {
+                        InputStepMode::Vertex
+                    },
+                    stride: 0,
+                }
+            })
+            .collect();
+
+        ShaderLayout {
+            bind_groups,
+            vertex_buffer_layout,
+            entry_point: entry_point.name.clone(),
+        }
+    }
+}
+
+/// Buffers are bound by the name of their type, like the blocks of GLSL shaders, and textures and
+/// samplers by the name of their variable.
+fn reflect_binding_name(module: &Module, global_variable: &GlobalVariable) -> String {
+    let name = match global_variable.class {
+        StorageClass::Uniform | StorageClass::Storage => &module.types[global_variable.ty].name,
+        _ => &global_variable.name,
+    };
+    name.clone().unwrap_or_default()
+}
+
+fn reflect_bind_type(module: &Module, global_variable: &GlobalVariable) -> BindType {
+    let ty = &module.types[global_variable.ty];
+    match (global_variable.class, &ty.inner) {
+        (StorageClass::Uniform, _) => BindType::Uniform {
+            has_dynamic_offset: false,
+            property: reflect_uniform(module, ty),
+        },
+        (StorageClass::Storage, _) => BindType::StorageBuffer {
+            has_dynamic_offset: false,
+            readonly: !global_variable
+                .storage_access
+                .contains(StorageAccess::STORE),
+        },
+        (
+            StorageClass::Handle,
+            TypeInner::Image {
+                dim,
+                arrayed,
+                class,
+            },
+        ) => {
+            let view_dimension = match (dim, arrayed) {
+                (ImageDimension::D1, _) => TextureViewDimension::D1,
+                (ImageDimension::D2, false) => TextureViewDimension::D2,
+                (ImageDimension::D2, true) => TextureViewDimension::D2Array,
+                (ImageDimension::D3, _) => TextureViewDimension::D3,
+                (ImageDimension::Cube, false) => TextureViewDimension::Cube,
+                (ImageDimension::Cube, true) => TextureViewDimension::CubeArray,
+            };
+            let (sample_type, multisampled) = match class {
+                ImageClass::Sampled { kind, multi } => (
+                    match kind {
+                        ScalarKind::Sint => TextureSampleType::Sint,
+                        ScalarKind::Uint => TextureSampleType::Uint,
+                        _ => TextureSampleType::Float { filterable: true },
+                    },
+                    *multi,
+                ),
+                ImageClass::Depth { .. } => (TextureSampleType::Depth, false),
+                ImageClass::Storage { .. } => panic!(
+                    "Unsupported shader bind type: storage texture '{}'",
+                    global_variable.name.as_deref().unwrap_or_default()
+                ),
+            };
+            BindType::Texture {
+                multisampled,
+                view_dimension,
+                sample_type,
+            }
+        }
+        (StorageClass::Handle, TypeInner::Sampler { comparison }) => BindType::Sampler {
+            comparison: *comparison,
+            filtering: true,
+        },
+        (class, inner) => panic!(
+            "Unsupported shader bind type {:?} {:?} (name '{}')",
+            class,
+            inner,
+            global_variable.name.as_deref().unwrap_or_default()
+        ),
+    }
+}
+
+fn reflect_uniform(module: &Module, ty: &Type) -> UniformProperty {
+    match ty.inner {
+        TypeInner::Struct { ref members, .. } => UniformProperty::Struct(
+            members
+                .iter()
+                .map(|member| reflect_uniform(module, &module.types[member.ty]))
+                .collect(),
+        ),
+        TypeInner::Array {
+            base,
+            size: ArraySize::Constant(size),
+            ..
+        } => {
+            let length = match module.constants[size].inner {
+                ConstantInner::Scalar {
+                    value: ScalarValue::Uint(length),
+                    ..
+                } => length as usize,
+                ConstantInner::Scalar {
+                    value: ScalarValue::Sint(length),
+                    ..
+                } => length as usize,
+                ref inner => panic!("Unexpected array length {:?}.", inner),
+            };
+            UniformProperty::Array(
+                Box::new(reflect_uniform(module, &module.types[base])),
+                length,
+            )
+        }
+        TypeInner::Scalar { kind, .. } => match kind {
+            ScalarKind::Float => UniformProperty::Float,
+            ScalarKind::Uint => UniformProperty::UInt,
+            ScalarKind::Sint => UniformProperty::Int,
+            ScalarKind::Bool => panic!("Unexpected uniform property format bool."),
+        },
+        TypeInner::Vector { size, kind, .. } => match (kind, size) {
+            (ScalarKind::Float, VectorSize::Bi) => UniformProperty::Vec2,
+            (ScalarKind::Float, VectorSize::Tri) => UniformProperty::Vec3,
+            (ScalarKind::Float, VectorSize::Quad) => UniformProperty::Vec4,
+            (ScalarKind::Sint, VectorSize::Bi) => UniformProperty::IVec2,
+            (ScalarKind::Uint, VectorSize::Quad) => UniformProperty::UVec4,
+            (kind, size) => panic!("Unexpected uniform property format {:?} {:?}.", kind, size),
+        },
+        TypeInner::Matrix { columns, rows, .. } => match (columns, rows) {
+            (VectorSize::Tri, VectorSize::Tri) => UniformProperty::Mat3,
+            (VectorSize::Quad, VectorSize::Quad) => UniformProperty::Mat4,
+            (columns, rows) => panic!(
+                "Unexpected uniform property format mat{:?}x{:?}.",
+                columns, rows
+            ),
+        },
+        ref inner => panic!("Unexpected uniform property format {:?}.", inner),
+    }
+}
+
+fn reflect_vertex_attribute(name: Option<&str>, ty: &Type, location: u32) -> VertexAttribute {
+    let format = match ty.inner {
+        TypeInner::Scalar { kind, width: 4 } => match kind {
+            ScalarKind::Float => VertexFormat::Float32,
+            ScalarKind::Uint => VertexFormat::Uint32,
+            ScalarKind::Sint => VertexFormat::Sint32,
+            ScalarKind::Bool => panic!("Unexpected vertex format bool."),
+        },
+        TypeInner::Vector {
+            size,
+            kind,
+            width: 4,
+        } => match (kind, size) {
+            (ScalarKind::Float, VectorSize::Bi) => VertexFormat::Float32x2,
+            (ScalarKind::Float, VectorSize::Tri) => VertexFormat::Float32x3,
+            (ScalarKind::Float, VectorSize::Quad) => VertexFormat::Float32x4,
+            (ScalarKind::Uint, VectorSize::Bi) => VertexFormat::Uint32x2,
+            (ScalarKind::Uint, VectorSize::Tri) => VertexFormat::Uint32x3,
+            (ScalarKind::Uint, VectorSize::Quad) => VertexFormat::Uint32x4,
+            (ScalarKind::Sint, VectorSize::Bi) => VertexFormat::Sint32x2,
+            (ScalarKind::Sint, VectorSize::Tri) => VertexFormat::Sint32x3,
+            (ScalarKind::Sint, VectorSize::Quad) => VertexFormat::Sint32x4,
+            (kind, size) => panic!("Unexpected vertex format {:?} {:?}.", kind, size),
+        },
+        ref inner => panic!("Unexpected vertex format {:?}.", inner),
+    };
+    VertexAttribute {
+        name: name.unwrap_or_default().to_string().into(),
+        format,
+        offset: 0,
+        shader_location: location,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wgsl_reflection() {
+        let layout = ShaderLayout::from_wgsl(
+            r#"
+            [[block]]
+            struct CameraViewProj {
+                ViewProj: mat4x4<f32>;
+            };
+            [[group(0), binding(0)]]
+            var<uniform> camera: CameraViewProj;
+            [[group(1), binding(0)]]
+            var Texture: texture_2d<f32>;
+
+            struct VertexInput {
+                [[location(1)]] Vertex_Normal: vec3<f32>;
+                [[location(2)]] I_TestInstancing_Property: vec4<u32>;
+            };
+
+            [[stage(vertex)]]
+            fn main(
+                [[location(0)]] Vertex_Position: vec4<f32>,
+                [[builtin(vertex_index)]] index: u32,
+                input: VertexInput,
+            ) -> [[builtin(position)]] vec4<f32> {
+                return camera.ViewProj * Vertex_Position;
+            }
+            "#,
+            ShaderStage::Vertex,
+            true,
+        );
+
+        let vertex_buffer = |name: &str, format, shader_location, step_mode| {
+            let mut layout = VertexBufferLayout::new_from_attribute(
+                VertexAttribute {
+                    name: name.to_string().into(),
+                    format,
+                    offset: 0,
+                    shader_location,
+                },
+                step_mode,
+            );
+            layout.stride = 0;
+            layout
+        };
+        assert_eq!(
+            layout,
+            ShaderLayout {
+                entry_point: "main".into(),
+                vertex_buffer_layout: vec![
+                    vertex_buffer(
+                        "Vertex_Position",
+                        VertexFormat::Float32x4,
+                        0,
+                        InputStepMode::Vertex
+                    ),
+                    vertex_buffer(
+                        "Vertex_Normal",
+                        VertexFormat::Float32x3,
+                        1,
+                        InputStepMode::Vertex
+                    ),
+                    vertex_buffer(
+                        "I_TestInstancing_Property",
+                        VertexFormat::Uint32x4,
+                        2,
+                        InputStepMode::Instance
+                    ),
+                ],
+                bind_groups: vec![
+                    BindGroupDescriptor::new(
+                        0,
+                        vec![BindingDescriptor {
+                            index: 0,
+                            name: "CameraViewProj".into(),
+                            bind_type: BindType::Uniform {
+                                has_dynamic_offset: false,
+                                property: UniformProperty::Struct(vec![UniformProperty::Mat4]),
+                            },
+                            shader_stage: BindingShaderStage::VERTEX,
+                        }]
+                    ),
+                    BindGroupDescriptor::new(
+                        1,
+                        vec![BindingDescriptor {
+                            index: 0,
+                            name: "Texture".into(),
+                            bind_type: BindType::Texture {
+                                multisampled: false,
+                                view_dimension: TextureViewDimension::D2,
+                                sample_type: TextureSampleType::Float { filterable: true },
+                            },
+                            shader_stage: BindingShaderStage::VERTEX,
+                        }]
+                    ),
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_wgsl_shader_stage() {
+        let module = parse_wgsl(
+            r#"
+            [[stage(fragment)]]
+            fn main() -> [[location(0)]] vec4<f32> {
+                return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(wgsl_shader_stage(&module).unwrap(), ShaderStage::Fragment);
+    }
+}
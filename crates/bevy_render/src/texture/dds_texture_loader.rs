@@ -0,0 +1,279 @@
+use super::{Extent3d, Texture, TextureDimension, TextureFormat};
+use anyhow::Result;
+use bevy_asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy_utils::BoxedFuture;
+use std::convert::TryInto;
+use thiserror::Error;
+
+/// Loads DDS textures as Texture assets, keeping their mip levels and compressed formats.
+///
+/// Both the legacy header and the DX10 header extension are read. Array elements and cube
+/// faces become array layers of the texture.
+#[derive(Clone, Default)]
+pub struct DdsTextureLoader;
+
+impl AssetLoader for DdsTextureLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let texture = dds_to_texture(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(texture));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["dds"]
+    }
+}
+
+/// An error that occurs when loading a DDS texture
+#[derive(Error, Debug)]
+pub enum DdsTextureError {
+    #[error("not a DDS file")]
+    InvalidMagic,
+    #[error("the file ends before the data of its header or images")]
+    UnexpectedEnd,
+    #[error("unsupported pixel format with FourCC {0:?}")]
+    UnsupportedFourCc([u8; 4]),
+    #[error("unsupported uncompressed pixel format")]
+    UnsupportedPixelFormat,
+    #[error("unsupported DXGI format {0}")]
+    UnsupportedDxgiFormat(u32),
+}
+
+const DDS_MAGIC: &[u8; 4] = b"DDS ";
+const HEADER_OFFSET: usize = 4;
+const DX10_HEADER_OFFSET: usize = HEADER_OFFSET + 124;
+
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+const DDSD_DEPTH: u32 = 0x80_0000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+const D3D10_RESOURCE_DIMENSION_TEXTURE1D: u32 = 2;
+const D3D10_RESOURCE_DIMENSION_TEXTURE3D: u32 = 4;
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, DdsTextureError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(DdsTextureError::UnexpectedEnd)
+}
+
+/// Reads a DDS file into a [`Texture`].
+pub fn dds_to_texture(bytes: &[u8]) -> Result<Texture, DdsTextureError> {
+    if bytes.get(..4) != Some(&DDS_MAGIC[..]) {
+        return Err(DdsTextureError::InvalidMagic);
+    }
+    let header = |offset: usize| read_u32(bytes, HEADER_OFFSET + offset);
+    let flags = header(4)?;
+    let height = header(8)?;
+    let width = header(12)?;
+    let depth = header(20)?;
+    let mip_level_count = if flags & DDSD_MIPMAPCOUNT != 0 {
+        header(24)?.max(1)
+    } else {
+        1
+    };
+    let pixel_format_flags = header(76)?;
+    let four_cc: [u8; 4] = header(80)?.to_le_bytes();
+    let caps2 = header(108)?;
+
+    let mut dimension = if flags & DDSD_DEPTH != 0 && depth > 1 {
+        TextureDimension::D3
+    } else {
+        TextureDimension::D2
+    };
+    let mut element_count = 1;
+    let mut face_count = if caps2 & DDSCAPS2_CUBEMAP != 0 { 6 } else { 1 };
+    let mut data_offset = DX10_HEADER_OFFSET;
+
+    let format = if pixel_format_flags & DDPF_FOURCC != 0 {
+        match &four_cc {
+            b"DXT1" => TextureFormat::Bc1RgbaUnorm,
+            b"DXT2" | b"DXT3" => TextureFormat::Bc2RgbaUnorm,
+            b"DXT4" | b"DXT5" => TextureFormat::Bc3RgbaUnorm,
+            b"ATI1" | b"BC4U" => TextureFormat::Bc4RUnorm,
+            b"BC4S" => TextureFormat::Bc4RSnorm,
+            b"ATI2" | b"BC5U" => TextureFormat::Bc5RgUnorm,
+            b"BC5S" => TextureFormat::Bc5RgSnorm,
+            b"DX10" => {
+                let dx10_header = |offset: usize| read_u32(bytes, DX10_HEADER_OFFSET + offset);
+                let dxgi_format = dx10_header(0)?;
+                match dx10_header(4)? {
+                    D3D10_RESOURCE_DIMENSION_TEXTURE1D => dimension = TextureDimension::D1,
+                    D3D10_RESOURCE_DIMENSION_TEXTURE3D => dimension = TextureDimension::D3,
+                    _ => {}
+                }
+                face_count = if dx10_header(8)? & DDS_RESOURCE_MISC_TEXTURECUBE != 0 {
+                    6
+                } else {
+                    1
+                };
+                element_count = dx10_header(12)?.max(1);
+                data_offset += 20;
+                dxgi_format_to_texture_format(dxgi_format)
+                    .ok_or(DdsTextureError::UnsupportedDxgiFormat(dxgi_format))?
+            }
+            _ => return Err(DdsTextureError::UnsupportedFourCc(four_cc)),
+        }
+    } else if pixel_format_flags & DDPF_RGB != 0 && header(84)? == 32 {
+        match (header(88)?, header(92)?, header(96)?) {
+            (0xff, 0xff00, 0xff_0000) => TextureFormat::Rgba8Unorm,
+            (0xff_0000, 0xff00, 0xff) => TextureFormat::Bgra8Unorm,
+            _ => return Err(DdsTextureError::UnsupportedPixelFormat),
+        }
+    } else {
+        return Err(DdsTextureError::UnsupportedPixelFormat);
+    };
+
+    let size = Extent3d::new(
+        width,
+        height.max(1),
+        if dimension == TextureDimension::D3 {
+            depth
+        } else {
+            element_count * face_count
+        },
+    );
+    let mut texture = Texture {
+        size,
+        format,
+        dimension,
+        mip_level_count,
+        ..Default::default()
+    };
+
+    // DDS files store the mip chain of every layer after the other, while textures store each
+    // mip level with all of its layers
+    let layer_count = if dimension == TextureDimension::D3 {
+        1
+    } else {
+        size.depth_or_array_layers as usize
+    };
+    let layer_mip_sizes = (0..mip_level_count)
+        .map(|level| {
+            let level_size = texture.mip_level_offset(level + 1) - texture.mip_level_offset(level);
+            level_size / layer_count
+        })
+        .collect::<Vec<_>>();
+    let layer_size: usize = layer_mip_sizes.iter().sum();
+    let images = bytes
+        .get(data_offset..data_offset + layer_size * layer_count)
+        .ok_or(DdsTextureError::UnexpectedEnd)?;
+    let mut level_offset = 0;
+    for level_size in layer_mip_sizes {
+        for layer in images.chunks_exact(layer_size) {
+            texture
+                .data
+                .extend_from_slice(&layer[level_offset..level_offset + level_size]);
+        }
+        level_offset += level_size;
+    }
+
+    Ok(texture)
+}
+
+fn dxgi_format_to_texture_format(dxgi_format: u32) -> Option<TextureFormat> {
+    Some(match dxgi_format {
+        2 => TextureFormat::Rgba32Float,
+        10 => TextureFormat::Rgba16Float,
+        28 => TextureFormat::Rgba8Unorm,
+        29 => TextureFormat::Rgba8UnormSrgb,
+        41 => TextureFormat::R32Float,
+        49 => TextureFormat::Rg8Unorm,
+        54 => TextureFormat::R16Float,
+        61 => TextureFormat::R8Unorm,
+        71 => TextureFormat::Bc1RgbaUnorm,
+        72 => TextureFormat::Bc1RgbaUnormSrgb,
+        74 => TextureFormat::Bc2RgbaUnorm,
+        75 => TextureFormat::Bc2RgbaUnormSrgb,
+        77 => TextureFormat::Bc3RgbaUnorm,
+        78 => TextureFormat::Bc3RgbaUnormSrgb,
+        80 => TextureFormat::Bc4RUnorm,
+        81 => TextureFormat::Bc4RSnorm,
+        83 => TextureFormat::Bc5RgUnorm,
+        84 => TextureFormat::Bc5RgSnorm,
+        87 => TextureFormat::Bgra8Unorm,
+        91 => TextureFormat::Bgra8UnormSrgb,
+        95 => TextureFormat::Bc6hRgbUfloat,
+        96 => TextureFormat::Bc6hRgbSfloat,
+        98 => TextureFormat::Bc7RgbaUnorm,
+        99 => TextureFormat::Bc7RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn dds_header(four_cc: &[u8; 4], width: u32, height: u32, mip_level_count: u32) -> Vec<u8> {
+        let mut header = vec![0; 128];
+        header[..4].copy_from_slice(DDS_MAGIC);
+        let mut write = |offset: usize, value: u32| {
+            header[HEADER_OFFSET + offset..HEADER_OFFSET + offset + 4]
+                .copy_from_slice(&value.to_le_bytes())
+        };
+        write(0, 124);
+        write(4, DDSD_MIPMAPCOUNT);
+        write(8, height);
+        write(12, width);
+        write(24, mip_level_count);
+        write(72, 32);
+        write(76, DDPF_FOURCC);
+        write(80, u32::from_le_bytes(*four_cc));
+        header
+    }
+
+    #[test]
+    fn loads_legacy_header() {
+        let mut bytes = dds_header(b"DXT5", 4, 4, 1);
+        bytes.extend(&[7; 16]);
+        let texture = dds_to_texture(&bytes).unwrap();
+        assert_eq!(texture.format, TextureFormat::Bc3RgbaUnorm);
+        assert_eq!(texture.size, Extent3d::new(4, 4, 1));
+        assert_eq!(texture.data, vec![7; 16]);
+    }
+
+    #[test]
+    fn reorders_layers_into_mip_levels() {
+        // an array of two 8x8 BC1 layers with two mip levels each
+        let mut bytes = dds_header(b"DX10", 8, 8, 2);
+        for value in &[71u32, 3, 0, 2, 0] {
+            bytes.extend(&value.to_le_bytes());
+        }
+        for layer in 0..2 {
+            bytes.extend(&[layer; 32]);
+            bytes.extend(&[layer + 10; 8]);
+        }
+        let texture = dds_to_texture(&bytes).unwrap();
+        assert_eq!(texture.format, TextureFormat::Bc1RgbaUnorm);
+        assert_eq!(texture.size, Extent3d::new(8, 8, 2));
+        assert_eq!(texture.mip_level_count, 2);
+        let mut expected = vec![0; 32];
+        expected.extend(&[1; 32]);
+        assert_eq!(texture.mip_level_data(0), &expected[..]);
+        let mut expected = vec![10; 8];
+        expected.extend(&[11; 8]);
+        assert_eq!(texture.mip_level_data(1), &expected[..]);
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        assert!(matches!(
+            dds_to_texture(b"PNG file"),
+            Err(DdsTextureError::InvalidMagic)
+        ));
+        let bytes = dds_header(b"DXT1", 4, 4, 1);
+        assert!(matches!(
+            dds_to_texture(&bytes),
+            Err(DdsTextureError::UnexpectedEnd)
+        ));
+    }
+}
@@ -1,18 +1,20 @@
-use super::texture::{ImageType, Texture, TextureError};
+use super::{
+    texture::{ImageType, Texture, TextureError},
+    AddressMode, FilterMode, TextureFormat,
+};
 use anyhow::Result;
-use bevy_asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy_asset::{deserialize_ron_settings, AssetLoader, LoadContext, LoadedAsset, LoaderSettings};
 use bevy_utils::BoxedFuture;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Loader for images that can be read by the `image` crate.
 #[derive(Clone, Default)]
 pub struct ImageTextureLoader;
 
-const FILE_EXTENSIONS: &[&str] = &[
+pub(crate) const FILE_EXTENSIONS: &[&str] = &[
     #[cfg(feature = "png")]
     "png",
-    #[cfg(feature = "dds")]
-    "dds",
     #[cfg(feature = "tga")]
     "tga",
     #[cfg(feature = "jpeg")]
@@ -23,6 +25,58 @@ const FILE_EXTENSIONS: &[&str] = &[
     "bmp",
 ];
 
+/// Settings of the [`ImageTextureLoader`] for a single image, read from the image's `.meta` file.
+///
+/// ```ron
+/// (
+///     is_srgb: false,
+///     mag_filter: Some(Linear),
+/// )
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageTextureSettings {
+    /// Whether the color data is sRGB encoded. Disable this for data textures like normal maps.
+    pub is_srgb: bool,
+    pub address_mode: Option<AddressMode>,
+    pub mag_filter: Option<FilterMode>,
+    pub min_filter: Option<FilterMode>,
+}
+
+impl Default for ImageTextureSettings {
+    fn default() -> Self {
+        Self {
+            is_srgb: true,
+            address_mode: None,
+            mag_filter: None,
+            min_filter: None,
+        }
+    }
+}
+
+impl ImageTextureSettings {
+    fn apply(&self, texture: &mut Texture) {
+        if !self.is_srgb {
+            texture.format = match texture.format {
+                TextureFormat::Rgba8UnormSrgb => TextureFormat::Rgba8Unorm,
+                TextureFormat::Bgra8UnormSrgb => TextureFormat::Bgra8Unorm,
+                TextureFormat::Bc1RgbaUnormSrgb => TextureFormat::Bc1RgbaUnorm,
+                TextureFormat::Bc3RgbaUnormSrgb => TextureFormat::Bc3RgbaUnorm,
+                format => format,
+            };
+        }
+        if let Some(address_mode) = self.address_mode {
+            texture.sampler.set_address_mode(address_mode);
+        }
+        if let Some(mag_filter) = self.mag_filter {
+            texture.sampler.mag_filter = mag_filter;
+        }
+        if let Some(min_filter) = self.min_filter {
+            texture.sampler.min_filter = min_filter;
+        }
+    }
+}
+
 impl AssetLoader for ImageTextureLoader {
     fn load<'a>(
         &'a self,
@@ -33,7 +87,15 @@ impl AssetLoader for ImageTextureLoader {
             // use the file extension for the image type
             let ext = load_context.path().extension().unwrap().to_str().unwrap();
 
-            let dyn_img =
+            // images compressed by the `TextureCompressionProcessor` are stored as DDS files
+            #[cfg(feature = "dds")]
+            let ext = if bytes.starts_with(b"DDS ") {
+                "dds"
+            } else {
+                ext
+            };
+
+            let mut dyn_img =
                 Texture::from_buffer(bytes, ImageType::Extension(ext)).map_err(|err| {
                     FileTextureError {
                         error: err,
@@ -41,6 +103,10 @@ impl AssetLoader for ImageTextureLoader {
                     }
                 })?;
 
+            if let Some(settings) = load_context.settings::<ImageTextureSettings>() {
+                settings.apply(&mut dyn_img);
+            }
+
             load_context.set_default_asset(LoadedAsset::new(dyn_img));
             Ok(())
         })
@@ -49,6 +115,14 @@ impl AssetLoader for ImageTextureLoader {
     fn extensions(&self) -> &[&str] {
         FILE_EXTENSIONS
     }
+
+    fn has_settings(&self) -> bool {
+        true
+    }
+
+    fn deserialize_settings(&self, bytes: &[u8]) -> Result<Option<Box<dyn LoaderSettings>>> {
+        deserialize_ron_settings::<ImageTextureSettings>(bytes)
+    }
 }
 
 /// An error that occurs when loading a texture from a file
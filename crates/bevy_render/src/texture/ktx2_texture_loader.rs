@@ -0,0 +1,286 @@
+use super::{Extent3d, Texture, TextureDimension, TextureFormat};
+use anyhow::Result;
+use bevy_asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy_utils::BoxedFuture;
+use std::convert::TryInto;
+use thiserror::Error;
+
+/// Loads KTX2 textures as Texture assets, keeping their mip levels and compressed formats.
+///
+/// Array layers and cube faces become array layers of the texture. Supercompressed files are
+/// not supported.
+#[derive(Clone, Default)]
+pub struct Ktx2TextureLoader;
+
+impl AssetLoader for Ktx2TextureLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let texture = ktx2_to_texture(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(texture));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ktx2"]
+    }
+}
+
+/// An error that occurs when loading a KTX2 texture
+#[derive(Error, Debug)]
+pub enum Ktx2TextureError {
+    #[error("not a KTX2 file")]
+    InvalidIdentifier,
+    #[error("the file ends before the data of its header or levels")]
+    UnexpectedEnd,
+    #[error("unsupported Vulkan format {0}")]
+    UnsupportedFormat(u32),
+    #[error("unsupported supercompression scheme {0}")]
+    UnsupportedSupercompression(u32),
+    #[error("mip level {level} has {actual} bytes instead of {expected}")]
+    InvalidLevelSize {
+        level: u32,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+const KTX2_IDENTIFIER: [u8; 12] = [
+    0xab, 0x4b, 0x54, 0x58, 0x20, 0x32, 0x30, 0xbb, 0x0d, 0x0a, 0x1a, 0x0a,
+];
+const HEADER_SIZE: usize = 80;
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, Ktx2TextureError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+        .ok_or(Ktx2TextureError::UnexpectedEnd)
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<usize, Ktx2TextureError> {
+    bytes
+        .get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()) as usize)
+        .ok_or(Ktx2TextureError::UnexpectedEnd)
+}
+
+/// Reads a KTX2 file into a [`Texture`].
+pub fn ktx2_to_texture(bytes: &[u8]) -> Result<Texture, Ktx2TextureError> {
+    if bytes.get(..KTX2_IDENTIFIER.len()) != Some(&KTX2_IDENTIFIER[..]) {
+        return Err(Ktx2TextureError::InvalidIdentifier);
+    }
+    let vk_format = read_u32(bytes, 12)?;
+    let width = read_u32(bytes, 20)?;
+    let height = read_u32(bytes, 24)?;
+    let depth = read_u32(bytes, 28)?;
+    let layer_count = read_u32(bytes, 32)?;
+    let face_count = read_u32(bytes, 36)?;
+    let level_count = read_u32(bytes, 40)?;
+    let supercompression = read_u32(bytes, 44)?;
+    if supercompression != 0 {
+        return Err(Ktx2TextureError::UnsupportedSupercompression(
+            supercompression,
+        ));
+    }
+    let format = vk_format_to_texture_format(vk_format)
+        .ok_or(Ktx2TextureError::UnsupportedFormat(vk_format))?;
+
+    let (dimension, depth_or_array_layers) = if depth > 0 {
+        (TextureDimension::D3, depth)
+    } else if height > 0 {
+        (TextureDimension::D2, layer_count.max(1) * face_count.max(1))
+    } else {
+        (TextureDimension::D1, layer_count.max(1))
+    };
+    let size = Extent3d::new(width, height.max(1), depth_or_array_layers);
+    // a level count of zero asks for the mips to be generated, which isn't supported
+    let level_count = level_count.max(1);
+
+    let mut texture = Texture {
+        size,
+        format,
+        dimension,
+        mip_level_count: level_count,
+        ..Default::default()
+    };
+    for level in 0..level_count {
+        let entry = HEADER_SIZE + level as usize * LEVEL_INDEX_ENTRY_SIZE;
+        let offset = read_u64(bytes, entry)?;
+        let length = read_u64(bytes, entry + 8)?;
+        let expected = texture.mip_level_offset(level + 1) - texture.mip_level_offset(level);
+        if length != expected {
+            return Err(Ktx2TextureError::InvalidLevelSize {
+                level,
+                expected,
+                actual: length,
+            });
+        }
+        let level_data = bytes
+            .get(offset..offset + length)
+            .ok_or(Ktx2TextureError::UnexpectedEnd)?;
+        texture.data.extend_from_slice(level_data);
+    }
+
+    Ok(texture)
+}
+
+fn vk_format_to_texture_format(vk_format: u32) -> Option<TextureFormat> {
+    Some(match vk_format {
+        9 => TextureFormat::R8Unorm,
+        10 => TextureFormat::R8Snorm,
+        13 => TextureFormat::R8Uint,
+        14 => TextureFormat::R8Sint,
+        16 => TextureFormat::Rg8Unorm,
+        17 => TextureFormat::Rg8Snorm,
+        20 => TextureFormat::Rg8Uint,
+        21 => TextureFormat::Rg8Sint,
+        37 => TextureFormat::Rgba8Unorm,
+        38 => TextureFormat::Rgba8Snorm,
+        41 => TextureFormat::Rgba8Uint,
+        42 => TextureFormat::Rgba8Sint,
+        43 => TextureFormat::Rgba8UnormSrgb,
+        44 => TextureFormat::Bgra8Unorm,
+        50 => TextureFormat::Bgra8UnormSrgb,
+        64 => TextureFormat::Rgb10a2Unorm,
+        74 => TextureFormat::R16Uint,
+        75 => TextureFormat::R16Sint,
+        76 => TextureFormat::R16Float,
+        81 => TextureFormat::Rg16Uint,
+        82 => TextureFormat::Rg16Sint,
+        83 => TextureFormat::Rg16Float,
+        95 => TextureFormat::Rgba16Uint,
+        96 => TextureFormat::Rgba16Sint,
+        97 => TextureFormat::Rgba16Float,
+        98 => TextureFormat::R32Uint,
+        99 => TextureFormat::R32Sint,
+        100 => TextureFormat::R32Float,
+        101 => TextureFormat::Rg32Uint,
+        102 => TextureFormat::Rg32Sint,
+        103 => TextureFormat::Rg32Float,
+        107 => TextureFormat::Rgba32Uint,
+        108 => TextureFormat::Rgba32Sint,
+        109 => TextureFormat::Rgba32Float,
+        122 => TextureFormat::Rg11b10Float,
+        // BC1 without alpha is decoded the same way
+        131 | 133 => TextureFormat::Bc1RgbaUnorm,
+        132 | 134 => TextureFormat::Bc1RgbaUnormSrgb,
+        135 => TextureFormat::Bc2RgbaUnorm,
+        136 => TextureFormat::Bc2RgbaUnormSrgb,
+        137 => TextureFormat::Bc3RgbaUnorm,
+        138 => TextureFormat::Bc3RgbaUnormSrgb,
+        139 => TextureFormat::Bc4RUnorm,
+        140 => TextureFormat::Bc4RSnorm,
+        141 => TextureFormat::Bc5RgUnorm,
+        142 => TextureFormat::Bc5RgSnorm,
+        143 => TextureFormat::Bc6hRgbUfloat,
+        144 => TextureFormat::Bc6hRgbSfloat,
+        145 => TextureFormat::Bc7RgbaUnorm,
+        146 => TextureFormat::Bc7RgbaUnormSrgb,
+        147 => TextureFormat::Etc2RgbUnorm,
+        148 => TextureFormat::Etc2RgbUnormSrgb,
+        149 => TextureFormat::Etc2RgbA1Unorm,
+        150 => TextureFormat::Etc2RgbA1UnormSrgb,
+        151 => TextureFormat::Etc2RgbA8Unorm,
+        152 => TextureFormat::Etc2RgbA8UnormSrgb,
+        153 => TextureFormat::EacRUnorm,
+        154 => TextureFormat::EacRSnorm,
+        155 => TextureFormat::EacRgUnorm,
+        156 => TextureFormat::EacRgSnorm,
+        157 => TextureFormat::Astc4x4RgbaUnorm,
+        158 => TextureFormat::Astc4x4RgbaUnormSrgb,
+        159 => TextureFormat::Astc5x4RgbaUnorm,
+        160 => TextureFormat::Astc5x4RgbaUnormSrgb,
+        161 => TextureFormat::Astc5x5RgbaUnorm,
+        162 => TextureFormat::Astc5x5RgbaUnormSrgb,
+        163 => TextureFormat::Astc6x5RgbaUnorm,
+        164 => TextureFormat::Astc6x5RgbaUnormSrgb,
+        165 => TextureFormat::Astc6x6RgbaUnorm,
+        166 => TextureFormat::Astc6x6RgbaUnormSrgb,
+        167 => TextureFormat::Astc8x5RgbaUnorm,
+        168 => TextureFormat::Astc8x5RgbaUnormSrgb,
+        169 => TextureFormat::Astc8x6RgbaUnorm,
+        170 => TextureFormat::Astc8x6RgbaUnormSrgb,
+        171 => TextureFormat::Astc8x8RgbaUnorm,
+        172 => TextureFormat::Astc8x8RgbaUnormSrgb,
+        173 => TextureFormat::Astc10x5RgbaUnorm,
+        174 => TextureFormat::Astc10x5RgbaUnormSrgb,
+        175 => TextureFormat::Astc10x6RgbaUnorm,
+        176 => TextureFormat::Astc10x6RgbaUnormSrgb,
+        177 => TextureFormat::Astc10x8RgbaUnorm,
+        178 => TextureFormat::Astc10x8RgbaUnormSrgb,
+        179 => TextureFormat::Astc10x10RgbaUnorm,
+        180 => TextureFormat::Astc10x10RgbaUnormSrgb,
+        181 => TextureFormat::Astc12x10RgbaUnorm,
+        182 => TextureFormat::Astc12x10RgbaUnormSrgb,
+        183 => TextureFormat::Astc12x12RgbaUnorm,
+        184 => TextureFormat::Astc12x12RgbaUnormSrgb,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ktx2_file(vk_format: u32, width: u32, height: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = KTX2_IDENTIFIER.to_vec();
+        for value in &[vk_format, 1, width, height, 0, 0, 1, levels.len() as u32, 0] {
+            bytes.extend(&value.to_le_bytes());
+        }
+        bytes.resize(HEADER_SIZE, 0);
+        let mut offset = HEADER_SIZE + levels.len() * LEVEL_INDEX_ENTRY_SIZE;
+        for level in levels {
+            for value in &[offset as u64, level.len() as u64, level.len() as u64] {
+                bytes.extend(&value.to_le_bytes());
+            }
+            offset += level.len();
+        }
+        for level in levels {
+            bytes.extend(level);
+        }
+        bytes
+    }
+
+    #[test]
+    fn loads_mip_levels() {
+        let bytes = ktx2_file(43, 2, 2, &[vec![1; 16], vec![2; 4]]);
+        let texture = ktx2_to_texture(&bytes).unwrap();
+        assert_eq!(texture.format, TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(texture.size, Extent3d::new(2, 2, 1));
+        assert_eq!(texture.mip_level_count, 2);
+        assert_eq!(texture.mip_level_data(0), &[1; 16][..]);
+        assert_eq!(texture.mip_level_data(1), &[2; 4][..]);
+    }
+
+    #[test]
+    fn loads_compressed_formats() {
+        // a 6x6 BC1 texture takes 2x2 blocks, its 3x3 mip a single block
+        let bytes = ktx2_file(133, 6, 6, &[vec![0; 32], vec![0; 8]]);
+        let texture = ktx2_to_texture(&bytes).unwrap();
+        assert_eq!(texture.format, TextureFormat::Bc1RgbaUnorm);
+        assert_eq!(texture.data.len(), 40);
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        assert!(matches!(
+            ktx2_to_texture(b"not a ktx2 file"),
+            Err(Ktx2TextureError::InvalidIdentifier)
+        ));
+        let bytes = ktx2_file(1000, 1, 1, &[vec![0; 4]]);
+        assert!(matches!(
+            ktx2_to_texture(&bytes),
+            Err(Ktx2TextureError::UnsupportedFormat(1000))
+        ));
+        let bytes = ktx2_file(37, 2, 2, &[vec![0; 4]]);
+        assert!(matches!(
+            ktx2_to_texture(&bytes),
+            Err(Ktx2TextureError::InvalidLevelSize { level: 0, .. })
+        ));
+    }
+}
@@ -1,18 +1,44 @@
+#[cfg(feature = "dds")]
+mod dds_texture_loader;
 #[cfg(feature = "hdr")]
 mod hdr_texture_loader;
 mod image_texture_loader;
+#[cfg(feature = "ktx2")]
+mod ktx2_texture_loader;
+#[cfg(feature = "png")]
+mod png_texture_saver;
 mod sampler_descriptor;
 #[allow(clippy::module_inception)]
 mod texture;
+mod texture_decompression;
 mod texture_descriptor;
 mod texture_dimension;
+#[cfg(all(
+    feature = "dds",
+    not(target_arch = "wasm32"),
+    not(target_os = "android")
+))]
+mod texture_processor;
 
 pub(crate) mod image_texture_conversion;
 
+#[cfg(feature = "dds")]
+pub use dds_texture_loader::*;
 #[cfg(feature = "hdr")]
 pub use hdr_texture_loader::*;
 pub use image_texture_loader::*;
+#[cfg(feature = "ktx2")]
+pub use ktx2_texture_loader::*;
+#[cfg(feature = "png")]
+pub use png_texture_saver::*;
 pub use sampler_descriptor::*;
 pub use texture::*;
+pub use texture_decompression::*;
 pub use texture_descriptor::*;
 pub use texture_dimension::*;
+#[cfg(all(
+    feature = "dds",
+    not(target_arch = "wasm32"),
+    not(target_os = "android")
+))]
+pub use texture_processor::*;
@@ -0,0 +1,36 @@
+use super::{Texture, TextureFormat};
+use anyhow::Result;
+use bevy_asset::AssetSaver;
+use std::convert::TryFrom;
+
+/// Saves 8 bit textures as PNG images.
+#[derive(Clone, Default)]
+pub struct PngTextureSaver;
+
+impl AssetSaver for PngTextureSaver {
+    type Asset = Texture;
+
+    fn save(&self, texture: &Texture) -> Result<Vec<u8>> {
+        let mut texture = texture.clone();
+        // PNG stores the bytes as they are, whether or not they are sRGB encoded
+        texture.format = match texture.format {
+            TextureFormat::Rgba8Unorm => TextureFormat::Rgba8UnormSrgb,
+            TextureFormat::Bgra8Unorm => TextureFormat::Bgra8UnormSrgb,
+            format => format,
+        };
+        let image = match image::DynamicImage::try_from(texture)? {
+            image @ image::DynamicImage::ImageBgra8(_) => {
+                image::DynamicImage::ImageRgba8(image.into_rgba8())
+            }
+            image => image,
+        };
+
+        let mut bytes = Vec::new();
+        image.write_to(&mut bytes, image::ImageOutputFormat::Png)?;
+        Ok(bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["png"]
+    }
+}
@@ -4,7 +4,7 @@ use super::{Extent3d, SamplerDescriptor, TextureDescriptor, TextureDimension, Te
 use crate::renderer::{
     RenderResource, RenderResourceContext, RenderResourceId, RenderResourceType,
 };
-use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_asset::{AssetEvent, AssetSize, Assets, Handle};
 use bevy_ecs::{event::EventReader, system::Res};
 use bevy_reflect::TypeUuid;
 use bevy_utils::HashSet;
@@ -21,6 +21,9 @@ pub struct Texture {
     pub format: TextureFormat,
     pub dimension: TextureDimension,
     pub sampler: SamplerDescriptor,
+    /// The number of mip levels in `data`, which holds every level after the other, starting
+    /// with the full size level. Each level holds all of its array layers.
+    pub mip_level_count: u32,
 }
 
 impl Default for Texture {
@@ -35,10 +38,17 @@ impl Default for Texture {
             format: TextureFormat::Rgba8UnormSrgb,
             dimension: TextureDimension::D2,
             sampler: Default::default(),
+            mip_level_count: 1,
         }
     }
 }
 
+impl AssetSize for Texture {
+    fn asset_size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.data.len()
+    }
+}
+
 impl Texture {
     pub fn new(
         size: Extent3d,
@@ -46,20 +56,63 @@ impl Texture {
         data: Vec<u8>,
         format: TextureFormat,
     ) -> Self {
-        debug_assert_eq!(
-            size.volume() * format.pixel_size(),
-            data.len(),
-            "Pixel data, size and format have to match",
-        );
-        Self {
+        Self::new_with_mips(size, dimension, data, format, 1)
+    }
+
+    /// Creates a texture with a mip chain, see [`Texture::mip_level_count`] for the layout of
+    /// `data`. Compressed formats hold whole blocks for each level.
+    pub fn new_with_mips(
+        size: Extent3d,
+        dimension: TextureDimension,
+        data: Vec<u8>,
+        format: TextureFormat,
+        mip_level_count: u32,
+    ) -> Self {
+        let texture = Self {
             data,
             size,
             format,
             dimension,
+            mip_level_count,
             ..Default::default()
+        };
+        debug_assert_eq!(
+            texture.mip_level_offset(mip_level_count),
+            texture.data.len(),
+            "Pixel data, size and format have to match",
+        );
+        texture
+    }
+
+    /// Returns the size in pixels of the given mip level.
+    pub fn mip_level_size(&self, level: u32) -> Extent3d {
+        Extent3d {
+            width: (self.size.width >> level).max(1),
+            height: (self.size.height >> level).max(1),
+            depth_or_array_layers: if self.dimension == TextureDimension::D3 {
+                (self.size.depth_or_array_layers >> level).max(1)
+            } else {
+                self.size.depth_or_array_layers
+            },
         }
     }
 
+    /// Returns the offset in `data` of the given mip level.
+    pub fn mip_level_offset(&self, level: u32) -> usize {
+        (0..level)
+            .map(|level| {
+                let size = self.mip_level_size(level);
+                self.format.image_size(size.width, size.height)
+                    * size.depth_or_array_layers as usize
+            })
+            .sum()
+    }
+
+    /// Returns the data of the given mip level.
+    pub fn mip_level_data(&self, level: u32) -> &[u8] {
+        &self.data[self.mip_level_offset(level)..self.mip_level_offset(level + 1)]
+    }
+
     pub fn new_fill(
         size: Extent3d,
         dimension: TextureDimension,
@@ -93,10 +146,15 @@ impl Texture {
         self.size.height as f32 / self.size.width as f32
     }
 
+    /// Resizes the texture to `size`, truncating its data or filling it up with zeros. Any mip
+    /// chain is dropped, as it no longer matches the base level.
     pub fn resize(&mut self, size: Extent3d) {
         self.size = size;
-        self.data
-            .resize(size.volume() * self.format.pixel_size(), 0);
+        self.mip_level_count = 1;
+        self.data.resize(
+            self.format.image_size(size.width, size.height) * size.depth_or_array_layers as usize,
+            0,
+        );
     }
 
     /// Changes the `size`, asserting that the total number of data elements (pixels) remains the
@@ -176,11 +234,15 @@ impl Texture {
                     // events are ok
                     changed_textures.remove(handle);
                 }
+                AssetEvent::LoadedWithDependencies { .. } => {}
             }
         }
 
         for texture_handle in changed_textures.iter() {
             if let Some(texture) = textures.get(*texture_handle) {
+                if !render_resource_context.is_texture_format_supported(texture.format) {
+                    continue;
+                }
                 let texture_descriptor: TextureDescriptor = texture.into();
                 let texture_resource = render_resource_context.create_texture(texture_descriptor);
 
@@ -221,10 +283,18 @@ impl Texture {
     /// Load a bytes buffer in a [`Texture`], according to type `image_type`, using the `image`
     /// crate`
     pub fn from_buffer(buffer: &[u8], image_type: ImageType) -> Result<Texture, TextureError> {
+        // DDS files are read by Bevy itself, as they can hold compressed formats
+        #[cfg(feature = "dds")]
+        if matches!(
+            image_type,
+            ImageType::MimeType("image/vnd-ms.dds") | ImageType::Extension("dds")
+        ) {
+            return Ok(super::dds_to_texture(buffer)?);
+        }
+
         let format = match image_type {
             ImageType::MimeType(mime_type) => match mime_type {
                 "image/png" => Ok(image::ImageFormat::Png),
-                "image/vnd-ms.dds" => Ok(image::ImageFormat::Dds),
                 "image/x-targa" => Ok(image::ImageFormat::Tga),
                 "image/x-tga" => Ok(image::ImageFormat::Tga),
                 "image/jpeg" => Ok(image::ImageFormat::Jpeg),
@@ -288,6 +358,9 @@ pub enum TextureError {
     InvalidImageExtension(String),
     #[error("failed to load an image: {0}")]
     ImageError(#[from] image::ImageError),
+    #[cfg(feature = "dds")]
+    #[error("failed to load a DDS texture: {0}")]
+    DdsError(#[from] super::DdsTextureError),
 }
 
 /// Type of a raw image buffer
@@ -297,3 +370,41 @@ pub enum ImageType<'a> {
     /// Extension of an image file, for example `"png"`
     Extension(&'a str),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resize_drops_mip_chain() {
+        let size = Extent3d::new(4, 4, 1);
+        // 4x4, 2x2 and 1x1 levels
+        let data = vec![255; (16 + 4 + 1) * 4];
+        let mut texture = Texture::new_with_mips(
+            size,
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            3,
+        );
+
+        texture.resize(Extent3d::new(8, 8, 1));
+        assert_eq!(texture.mip_level_count, 1);
+        assert_eq!(texture.data.len(), 8 * 8 * 4);
+        assert_eq!(texture.mip_level_offset(1), texture.data.len());
+    }
+
+    #[test]
+    fn resize_compressed_texture_to_whole_blocks() {
+        let mut texture = Texture::new(
+            Extent3d::new(4, 4, 1),
+            TextureDimension::D2,
+            vec![0; 8],
+            TextureFormat::Bc1RgbaUnorm,
+        );
+
+        // 2 by 2 blocks of 8 bytes in each of the 2 layers
+        texture.resize(Extent3d::new(6, 8, 2));
+        assert_eq!(texture.data.len(), 2 * 2 * 8 * 2);
+    }
+}
@@ -0,0 +1,620 @@
+use super::{Texture, TextureFormat};
+use crate::renderer::RenderResourceContext;
+use bevy_asset::{AssetEvent, AssetServer, Assets, HandleId};
+use bevy_ecs::{
+    event::EventReader,
+    system::{Local, Res, ResMut},
+};
+use bevy_utils::{tracing::error, HashSet};
+
+impl Texture {
+    /// Decodes a texture in a block compressed format into RGBA8 on the CPU, keeping its mip
+    /// levels. This is the fallback for GPUs that lack support for the format.
+    ///
+    /// Returns `None` for uncompressed formats and for the BC6H, BC7 and ASTC formats, which
+    /// can't be decoded on the CPU yet.
+    pub fn decompress(&self) -> Option<Texture> {
+        let decode_block: fn(&[u8], &mut [[u8; 4]; 16]) = match self.format {
+            TextureFormat::Bc1RgbaUnorm | TextureFormat::Bc1RgbaUnormSrgb => decode_bc1,
+            TextureFormat::Bc2RgbaUnorm | TextureFormat::Bc2RgbaUnormSrgb => decode_bc2,
+            TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb => decode_bc3,
+            TextureFormat::Bc4RUnorm => decode_bc4_unorm,
+            TextureFormat::Bc4RSnorm => decode_bc4_snorm,
+            TextureFormat::Bc5RgUnorm => decode_bc5_unorm,
+            TextureFormat::Bc5RgSnorm => decode_bc5_snorm,
+            TextureFormat::Etc2RgbUnorm | TextureFormat::Etc2RgbUnormSrgb => decode_etc2_rgb,
+            TextureFormat::Etc2RgbA1Unorm | TextureFormat::Etc2RgbA1UnormSrgb => decode_etc2_rgb_a1,
+            TextureFormat::Etc2RgbA8Unorm | TextureFormat::Etc2RgbA8UnormSrgb => decode_etc2_rgba8,
+            TextureFormat::EacRUnorm => decode_eac_r_unorm,
+            TextureFormat::EacRSnorm => decode_eac_r_snorm,
+            TextureFormat::EacRgUnorm => decode_eac_rg_unorm,
+            TextureFormat::EacRgSnorm => decode_eac_rg_snorm,
+            _ => return None,
+        };
+        let format = match self.format {
+            TextureFormat::Bc4RSnorm
+            | TextureFormat::Bc5RgSnorm
+            | TextureFormat::EacRSnorm
+            | TextureFormat::EacRgSnorm => TextureFormat::Rgba8Snorm,
+            format if format.is_srgb() => TextureFormat::Rgba8UnormSrgb,
+            _ => TextureFormat::Rgba8Unorm,
+        };
+
+        let block_size = self.format.block_size();
+        let mut data = Vec::new();
+        let mut pixels = [[0; 4]; 16];
+        for level in 0..self.mip_level_count {
+            let size = self.mip_level_size(level);
+            let (width, height) = (size.width as usize, size.height as usize);
+            let blocks_x = (width + 3) / 4;
+            let blocks_y = (height + 3) / 4;
+            let image_size = blocks_x * blocks_y * block_size;
+            for image in self.mip_level_data(level).chunks_exact(image_size) {
+                let mut rgba = vec![0; width * height * 4];
+                for (block_index, block) in image.chunks_exact(block_size).enumerate() {
+                    decode_block(block, &mut pixels);
+                    let block_x = block_index % blocks_x * 4;
+                    let block_y = block_index / blocks_x * 4;
+                    for (i, pixel) in pixels.iter().enumerate() {
+                        let (x, y) = (block_x + i % 4, block_y + i / 4);
+                        if x < width && y < height {
+                            let offset = (y * width + x) * 4;
+                            rgba[offset..offset + 4].copy_from_slice(pixel);
+                        }
+                    }
+                }
+                data.extend(rgba);
+            }
+        }
+
+        Some(Texture {
+            data,
+            size: self.size,
+            format,
+            dimension: self.dimension,
+            sampler: self.sampler.clone(),
+            mip_level_count: self.mip_level_count,
+        })
+    }
+}
+
+/// Decompresses textures in formats the GPU lacks support for, before their GPU resources are
+/// created.
+///
+/// Textures that can't be decompressed are reported once and aren't drawn.
+pub fn decompress_unsupported_textures_system(
+    render_resource_context: Res<Box<dyn RenderResourceContext>>,
+    asset_server: Option<Res<AssetServer>>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut texture_events: EventReader<AssetEvent<Texture>>,
+    mut reported_textures: Local<HashSet<HandleId>>,
+) {
+    let mut changed_textures = HashSet::default();
+    for event in texture_events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                changed_textures.insert(handle.clone_weak());
+            }
+            AssetEvent::Removed { handle } => {
+                changed_textures.remove(handle);
+                reported_textures.remove(&handle.id);
+            }
+            AssetEvent::LoadedWithDependencies { .. } => {}
+        }
+    }
+
+    for handle in changed_textures {
+        let texture = match textures.get(&handle) {
+            Some(texture) => texture,
+            None => continue,
+        };
+        if render_resource_context.is_texture_format_supported(texture.format) {
+            continue;
+        }
+        match texture.decompress() {
+            Some(decompressed) => {
+                textures.set_untracked(&handle, decompressed);
+            }
+            None => {
+                if !reported_textures.insert(handle.id) {
+                    continue;
+                }
+                let name = asset_server
+                    .as_ref()
+                    .and_then(|asset_server| asset_server.get_handle_path(&handle))
+                    .map_or_else(
+                        || format!("{:?}", handle.id),
+                        |path| path.path().display().to_string(),
+                    );
+                error!(
+                    "Texture format {:?} of texture {} is not supported by the GPU and can't be decompressed",
+                    texture.format, name
+                );
+            }
+        }
+    }
+}
+
+fn decode_rgb565(color: u16) -> [u8; 3] {
+    let r = (color >> 11) & 0x1f;
+    let g = (color >> 5) & 0x3f;
+    let b = color & 0x1f;
+    [
+        ((r << 3) | (r >> 2)) as u8,
+        ((g << 2) | (g >> 4)) as u8,
+        ((b << 3) | (b >> 2)) as u8,
+    ]
+}
+
+/// Decodes the color part of BC1, BC2 and BC3 blocks. Only BC1 blocks have a three color mode
+/// with transparent pixels.
+fn decode_bc1_colors(block: &[u8], pixels: &mut [[u8; 4]; 16], three_color_mode: bool) {
+    let color0 = u16::from_le_bytes([block[0], block[1]]);
+    let color1 = u16::from_le_bytes([block[2], block[3]]);
+    let [r0, g0, b0] = decode_rgb565(color0);
+    let [r1, g1, b1] = decode_rgb565(color1);
+    let mix = |a: u8, b: u8, wa: u16, wb: u16| ((a as u16 * wa + b as u16 * wb) / (wa + wb)) as u8;
+    let colors = if three_color_mode && color0 <= color1 {
+        [
+            [r0, g0, b0, 255],
+            [r1, g1, b1, 255],
+            [mix(r0, r1, 1, 1), mix(g0, g1, 1, 1), mix(b0, b1, 1, 1), 255],
+            [0, 0, 0, 0],
+        ]
+    } else {
+        [
+            [r0, g0, b0, 255],
+            [r1, g1, b1, 255],
+            [mix(r0, r1, 2, 1), mix(g0, g1, 2, 1), mix(b0, b1, 2, 1), 255],
+            [mix(r0, r1, 1, 2), mix(g0, g1, 1, 2), mix(b0, b1, 1, 2), 255],
+        ]
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = colors[(indices >> (2 * i)) as usize & 0b11];
+    }
+}
+
+/// Decodes a BC4 block, which is also the alpha part of BC3 blocks, into the given channel.
+fn decode_bc4_channel(block: &[u8], pixels: &mut [[u8; 4]; 16], channel: usize, signed: bool) {
+    let mut values = [0i32; 8];
+    let (value0, value1, min, max) = if signed {
+        let value = |byte: u8| (byte as i8).max(-127) as i32;
+        (value(block[0]), value(block[1]), -127, 127)
+    } else {
+        (block[0] as i32, block[1] as i32, 0, 255)
+    };
+    values[0] = value0;
+    values[1] = value1;
+    if value0 > value1 {
+        for (i, value) in values.iter_mut().enumerate().skip(2) {
+            let i = i as i32;
+            *value = ((8 - i) * value0 + (i - 1) * value1) / 7;
+        }
+    } else {
+        for (i, value) in values.iter_mut().enumerate().skip(2).take(4) {
+            let i = i as i32;
+            *value = ((6 - i) * value0 + (i - 1) * value1) / 5;
+        }
+        values[6] = min;
+        values[7] = max;
+    }
+
+    let mut index_bytes = [0; 8];
+    index_bytes[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(index_bytes);
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let value = values[(indices >> (3 * i)) as usize & 0b111];
+        pixel[channel] = if signed {
+            value as i8 as u8
+        } else {
+            value as u8
+        };
+    }
+}
+
+fn decode_bc1(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    decode_bc1_colors(block, pixels, true);
+}
+
+fn decode_bc2(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    decode_bc1_colors(&block[8..], pixels, false);
+    let alphas = u64::from_le_bytes([
+        block[0], block[1], block[2], block[3], block[4], block[5], block[6], block[7],
+    ]);
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        pixel[3] = ((alphas >> (4 * i)) & 0xf) as u8 * 17;
+    }
+}
+
+fn decode_bc3(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    decode_bc1_colors(&block[8..], pixels, false);
+    decode_bc4_channel(&block[..8], pixels, 3, false);
+}
+
+fn clear_pixels(pixels: &mut [[u8; 4]; 16], alpha: u8) {
+    for pixel in pixels.iter_mut() {
+        *pixel = [0, 0, 0, alpha];
+    }
+}
+
+fn decode_bc4_unorm(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    clear_pixels(pixels, 255);
+    decode_bc4_channel(block, pixels, 0, false);
+}
+
+fn decode_bc4_snorm(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    clear_pixels(pixels, 127);
+    decode_bc4_channel(block, pixels, 0, true);
+}
+
+fn decode_bc5_unorm(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    clear_pixels(pixels, 255);
+    decode_bc4_channel(&block[..8], pixels, 0, false);
+    decode_bc4_channel(&block[8..], pixels, 1, false);
+}
+
+fn decode_bc5_snorm(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    clear_pixels(pixels, 127);
+    decode_bc4_channel(&block[..8], pixels, 0, true);
+    decode_bc4_channel(&block[8..], pixels, 1, true);
+}
+
+const ETC1_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+const ETC2_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+fn bits(block: u64, low: u32, count: u32) -> i32 {
+    ((block >> low) & ((1 << count) - 1)) as i32
+}
+
+fn extend_4(value: i32) -> i32 {
+    (value << 4) | value
+}
+
+fn extend_5(value: i32) -> i32 {
+    (value << 3) | (value >> 2)
+}
+
+fn clamp_color(color: [i32; 3]) -> [u8; 4] {
+    [
+        color[0].max(0).min(255) as u8,
+        color[1].max(0).min(255) as u8,
+        color[2].max(0).min(255) as u8,
+        255,
+    ]
+}
+
+fn offset_color(color: [i32; 3], offset: i32) -> [u8; 4] {
+    clamp_color([color[0] + offset, color[1] + offset, color[2] + offset])
+}
+
+/// Decodes an ETC2 color block. With `punchthrough_alpha`, the differential bit is the opaque
+/// bit of ETC2 RGB8A1 blocks instead.
+fn decode_etc2_colors(block: &[u8], pixels: &mut [[u8; 4]; 16], punchthrough_alpha: bool) {
+    let block = u64::from_be_bytes([
+        block[0], block[1], block[2], block[3], block[4], block[5], block[6], block[7],
+    ]);
+    let differential = punchthrough_alpha || bits(block, 33, 1) == 1;
+    let opaque = !punchthrough_alpha || bits(block, 33, 1) == 1;
+    // the index of each pixel, with the pixels in column major order
+    let index = |pixel: usize| {
+        let column_major = pixel % 4 * 4 + pixel / 4;
+        (bits(block, 16 + column_major as u32, 1) << 1 | bits(block, column_major as u32, 1))
+            as usize
+    };
+
+    let r = bits(block, 59, 5);
+    let g = bits(block, 51, 5);
+    let b = bits(block, 43, 5);
+    let r2 = r + (bits(block, 56, 3) << 29 >> 29);
+    let g2 = g + (bits(block, 48, 3) << 29 >> 29);
+    let b2 = b + (bits(block, 40, 3) << 29 >> 29);
+
+    if differential && !(0..32).contains(&r2) {
+        // T mode
+        let color1 = [
+            extend_4(bits(block, 59, 2) << 2 | bits(block, 56, 2)),
+            extend_4(bits(block, 52, 4)),
+            extend_4(bits(block, 48, 4)),
+        ];
+        let color2 = [
+            extend_4(bits(block, 44, 4)),
+            extend_4(bits(block, 40, 4)),
+            extend_4(bits(block, 36, 4)),
+        ];
+        let distance = ETC2_DISTANCES[(bits(block, 34, 2) << 1 | bits(block, 32, 1)) as usize];
+        let paints = [
+            clamp_color(color1),
+            offset_color(color2, distance),
+            clamp_color(color2),
+            offset_color(color2, -distance),
+        ];
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let index = index(i);
+            *pixel = if !opaque && index == 2 {
+                [0, 0, 0, 0]
+            } else {
+                paints[index]
+            };
+        }
+    } else if differential && !(0..32).contains(&g2) {
+        // H mode
+        let color1 = [
+            extend_4(bits(block, 59, 4)),
+            extend_4(bits(block, 56, 3) << 1 | bits(block, 52, 1)),
+            extend_4(bits(block, 51, 1) << 3 | bits(block, 47, 3)),
+        ];
+        let color2 = [
+            extend_4(bits(block, 43, 4)),
+            extend_4(bits(block, 39, 4)),
+            extend_4(bits(block, 35, 4)),
+        ];
+        let value = |color: [i32; 3]| color[0] << 16 | color[1] << 8 | color[2];
+        let distance_index = bits(block, 34, 1) << 2
+            | bits(block, 32, 1) << 1
+            | (value(color1) >= value(color2)) as i32;
+        let distance = ETC2_DISTANCES[distance_index as usize];
+        let paints = [
+            offset_color(color1, distance),
+            offset_color(color1, -distance),
+            offset_color(color2, distance),
+            offset_color(color2, -distance),
+        ];
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let index = index(i);
+            *pixel = if !opaque && index == 2 {
+                [0, 0, 0, 0]
+            } else {
+                paints[index]
+            };
+        }
+    } else if differential && !(0..32).contains(&b2) {
+        // planar mode
+        let extend_6 = |value: i32| (value << 2) | (value >> 4);
+        let extend_7 = |value: i32| (value << 1) | (value >> 6);
+        let origin = [
+            extend_6(bits(block, 57, 6)),
+            extend_7(bits(block, 56, 1) << 6 | bits(block, 49, 6)),
+            extend_6(bits(block, 48, 1) << 5 | bits(block, 43, 2) << 3 | bits(block, 39, 3)),
+        ];
+        let horizontal = [
+            extend_6(bits(block, 34, 5) << 1 | bits(block, 32, 1)),
+            extend_7(bits(block, 25, 7)),
+            extend_6(bits(block, 19, 6)),
+        ];
+        let vertical = [
+            extend_6(bits(block, 13, 6)),
+            extend_7(bits(block, 6, 7)),
+            extend_6(bits(block, 0, 6)),
+        ];
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let (x, y) = ((i % 4) as i32, (i / 4) as i32);
+            let channel = |c: usize| {
+                (x * (horizontal[c] - origin[c])
+                    + y * (vertical[c] - origin[c])
+                    + 4 * origin[c]
+                    + 2)
+                    >> 2
+            };
+            *pixel = clamp_color([channel(0), channel(1), channel(2)]);
+        }
+    } else {
+        // individual or differential mode, with two sub-blocks
+        let (base1, base2) = if differential {
+            (
+                [extend_5(r), extend_5(g), extend_5(b)],
+                [extend_5(r2), extend_5(g2), extend_5(b2)],
+            )
+        } else {
+            (
+                [
+                    extend_4(bits(block, 60, 4)),
+                    extend_4(bits(block, 52, 4)),
+                    extend_4(bits(block, 44, 4)),
+                ],
+                [
+                    extend_4(bits(block, 56, 4)),
+                    extend_4(bits(block, 48, 4)),
+                    extend_4(bits(block, 40, 4)),
+                ],
+            )
+        };
+        let tables = [bits(block, 37, 3) as usize, bits(block, 34, 3) as usize];
+        let flip = bits(block, 32, 1) == 1;
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            let (x, y) = (i % 4, i / 4);
+            let second = if flip { y >= 2 } else { x >= 2 };
+            let (base, table) = if second {
+                (base2, tables[1])
+            } else {
+                (base1, tables[0])
+            };
+            let index = index(i);
+            let [small, large] = ETC1_MODIFIERS[table];
+            *pixel = match index {
+                0 if !opaque => clamp_color(base),
+                2 if !opaque => [0, 0, 0, 0],
+                0 => offset_color(base, small),
+                1 => offset_color(base, large),
+                2 => offset_color(base, -small),
+                _ => offset_color(base, -large),
+            };
+        }
+    }
+}
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// Decodes an EAC block into the given channel. Alpha blocks of ETC2 RGBA8 use the 8 bit mode,
+/// R11 and RG11 blocks the 11 bit mode, which is reduced to 8 bits.
+fn decode_eac_channel(
+    block: &[u8],
+    pixels: &mut [[u8; 4]; 16],
+    channel: usize,
+    eleven_bit: bool,
+    signed: bool,
+) {
+    let block = u64::from_be_bytes([
+        block[0], block[1], block[2], block[3], block[4], block[5], block[6], block[7],
+    ]);
+    let base = bits(block, 56, 8);
+    let multiplier = bits(block, 52, 4);
+    let modifiers = EAC_MODIFIERS[bits(block, 48, 4) as usize];
+    for (i, pixel) in pixels.iter_mut().enumerate() {
+        let column_major = (i % 4 * 4 + i / 4) as u32;
+        let modifier = modifiers[bits(block, 45 - 3 * column_major, 3) as usize];
+        pixel[channel] = if !eleven_bit {
+            (base + modifier * multiplier).max(0).min(255) as u8
+        } else if signed {
+            let base = (base as u8 as i8).max(-127) as i32;
+            let offset = if multiplier == 0 {
+                modifier
+            } else {
+                modifier * multiplier * 8
+            };
+            let value = (base * 8 + offset).max(-1023).min(1023);
+            (value >> 3) as i8 as u8
+        } else {
+            let offset = if multiplier == 0 {
+                modifier
+            } else {
+                modifier * multiplier * 8
+            };
+            ((base * 8 + 4 + offset).max(0).min(2047) >> 3) as u8
+        };
+    }
+}
+
+fn decode_etc2_rgb(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    decode_etc2_colors(block, pixels, false);
+}
+
+fn decode_etc2_rgb_a1(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    decode_etc2_colors(block, pixels, true);
+}
+
+fn decode_etc2_rgba8(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    decode_etc2_colors(&block[8..], pixels, false);
+    decode_eac_channel(&block[..8], pixels, 3, false, false);
+}
+
+fn decode_eac_r_unorm(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    clear_pixels(pixels, 255);
+    decode_eac_channel(block, pixels, 0, true, false);
+}
+
+fn decode_eac_r_snorm(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    clear_pixels(pixels, 127);
+    decode_eac_channel(block, pixels, 0, true, true);
+}
+
+fn decode_eac_rg_unorm(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    clear_pixels(pixels, 255);
+    decode_eac_channel(&block[..8], pixels, 0, true, false);
+    decode_eac_channel(&block[8..], pixels, 1, true, false);
+}
+
+fn decode_eac_rg_snorm(block: &[u8], pixels: &mut [[u8; 4]; 16]) {
+    clear_pixels(pixels, 127);
+    decode_eac_channel(&block[..8], pixels, 0, true, true);
+    decode_eac_channel(&block[8..], pixels, 1, true, true);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::texture::{Extent3d, TextureDimension};
+
+    fn decode(format: TextureFormat, width: u32, height: u32, data: Vec<u8>) -> Texture {
+        Texture::new(
+            Extent3d::new(width, height, 1),
+            TextureDimension::D2,
+            data,
+            format,
+        )
+        .decompress()
+        .unwrap()
+    }
+
+    #[test]
+    fn bc1_blocks() {
+        // red and blue end points, the pixels use the four colors in order
+        let block = vec![0x00, 0xf8, 0x1f, 0x00, 0b1110_0100, 0, 0, 0];
+        let texture = decode(TextureFormat::Bc1RgbaUnormSrgb, 4, 4, block);
+        assert_eq!(texture.format, TextureFormat::Rgba8UnormSrgb);
+        assert_eq!(&texture.data[0..4], &[255, 0, 0, 255]);
+        assert_eq!(&texture.data[4..8], &[0, 0, 255, 255]);
+        assert_eq!(&texture.data[8..12], &[170, 0, 85, 255]);
+        assert_eq!(&texture.data[12..16], &[85, 0, 170, 255]);
+
+        // with the end points swapped, the fourth color is transparent
+        let block = vec![0x1f, 0x00, 0x00, 0xf8, 0b1100_0000, 0, 0, 0];
+        let texture = decode(TextureFormat::Bc1RgbaUnorm, 4, 4, block);
+        assert_eq!(&texture.data[12..16], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn bc4_blocks_keep_partial_blocks() {
+        // a 2x2 texture still uses a whole block
+        let block = vec![200, 100, 0b0000_1010, 0, 0, 0, 0, 0];
+        let texture = decode(TextureFormat::Bc4RUnorm, 2, 2, block);
+        assert_eq!(texture.data.len(), 2 * 2 * 4);
+        assert_eq!(&texture.data[0..4], &[185, 0, 0, 255]);
+        assert_eq!(&texture.data[4..8], &[100, 0, 0, 255]);
+    }
+
+    #[test]
+    fn etc2_individual_block() {
+        // both sub-blocks use 0x8 extended to 0x88, with the smallest positive modifier of table 0
+        let block = vec![0x88, 0x88, 0x88, 0, 0, 0, 0, 0];
+        let texture = decode(TextureFormat::Etc2RgbUnorm, 4, 4, block);
+        assert!(texture
+            .data
+            .chunks_exact(4)
+            .all(|pixel| pixel == [0x8a, 0x8a, 0x8a, 255]));
+    }
+
+    #[test]
+    fn eac_alpha_block() {
+        // base 128, multiplier 1, table 0 and every pixel uses modifier index 4, which is 2
+        let mut block = vec![
+            128,
+            0x10,
+            0b1001_0010,
+            0b0100_1001,
+            0b0010_0100,
+            0b1001_0010,
+        ];
+        block.extend(&[0b0100_1001, 0b0010_0100]);
+        block.extend(&[0x88, 0x88, 0x88, 0, 0, 0, 0, 0]);
+        let texture = decode(TextureFormat::Etc2RgbA8Unorm, 4, 4, block);
+        assert!(texture.data.chunks_exact(4).all(|pixel| pixel[3] == 130));
+    }
+}
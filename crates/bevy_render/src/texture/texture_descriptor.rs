@@ -15,7 +15,7 @@ impl From<&Texture> for TextureDescriptor {
     fn from(texture: &Texture) -> Self {
         TextureDescriptor {
             size: texture.size,
-            mip_level_count: 1,
+            mip_level_count: texture.mip_level_count,
             sample_count: 1,
             dimension: texture.dimension,
             format: texture.format,
@@ -154,6 +154,64 @@ pub enum TextureFormat {
     Depth32Float = 35,
     Depth24Plus = 36,
     Depth24PlusStencil8 = 37,
+
+    // BC compressed formats, 4x4 blocks
+    Bc1RgbaUnorm = 38,
+    Bc1RgbaUnormSrgb = 39,
+    Bc2RgbaUnorm = 40,
+    Bc2RgbaUnormSrgb = 41,
+    Bc3RgbaUnorm = 42,
+    Bc3RgbaUnormSrgb = 43,
+    Bc4RUnorm = 44,
+    Bc4RSnorm = 45,
+    Bc5RgUnorm = 46,
+    Bc5RgSnorm = 47,
+    Bc6hRgbUfloat = 48,
+    Bc6hRgbSfloat = 49,
+    Bc7RgbaUnorm = 50,
+    Bc7RgbaUnormSrgb = 51,
+
+    // ETC2 and EAC compressed formats, 4x4 blocks
+    Etc2RgbUnorm = 52,
+    Etc2RgbUnormSrgb = 53,
+    Etc2RgbA1Unorm = 54,
+    Etc2RgbA1UnormSrgb = 55,
+    Etc2RgbA8Unorm = 56,
+    Etc2RgbA8UnormSrgb = 57,
+    EacRUnorm = 58,
+    EacRSnorm = 59,
+    EacRgUnorm = 60,
+    EacRgSnorm = 61,
+
+    // ASTC compressed formats, with the block size in the name
+    Astc4x4RgbaUnorm = 62,
+    Astc4x4RgbaUnormSrgb = 63,
+    Astc5x4RgbaUnorm = 64,
+    Astc5x4RgbaUnormSrgb = 65,
+    Astc5x5RgbaUnorm = 66,
+    Astc5x5RgbaUnormSrgb = 67,
+    Astc6x5RgbaUnorm = 68,
+    Astc6x5RgbaUnormSrgb = 69,
+    Astc6x6RgbaUnorm = 70,
+    Astc6x6RgbaUnormSrgb = 71,
+    Astc8x5RgbaUnorm = 72,
+    Astc8x5RgbaUnormSrgb = 73,
+    Astc8x6RgbaUnorm = 74,
+    Astc8x6RgbaUnormSrgb = 75,
+    Astc8x8RgbaUnorm = 76,
+    Astc8x8RgbaUnormSrgb = 77,
+    Astc10x5RgbaUnorm = 78,
+    Astc10x5RgbaUnormSrgb = 79,
+    Astc10x6RgbaUnorm = 80,
+    Astc10x6RgbaUnormSrgb = 81,
+    Astc10x8RgbaUnorm = 82,
+    Astc10x8RgbaUnormSrgb = 83,
+    Astc10x10RgbaUnorm = 84,
+    Astc10x10RgbaUnormSrgb = 85,
+    Astc12x10RgbaUnorm = 86,
+    Astc12x10RgbaUnormSrgb = 87,
+    Astc12x12RgbaUnorm = 88,
+    Astc12x12RgbaUnormSrgb = 89,
 }
 
 impl TextureFormat {
@@ -204,6 +262,9 @@ impl TextureFormat {
             TextureFormat::Rg11b10Float => 4,
             TextureFormat::Depth24Plus => 3, // FIXME is this correct?
             TextureFormat::Depth24PlusStencil8 => 4,
+
+            // a whole block for compressed formats
+            format => format.block_size(),
         };
 
         let components = match self {
@@ -249,6 +310,9 @@ impl TextureFormat {
             | TextureFormat::Depth32Float
             | TextureFormat::Depth24Plus
             | TextureFormat::Depth24PlusStencil8 => 1,
+
+            // compressed formats
+            _ => 1,
         };
 
         PixelInfo {
@@ -261,6 +325,101 @@ impl TextureFormat {
         let info = self.pixel_info();
         info.type_size * info.num_components
     }
+
+    /// Returns the width and height in pixels of a block of a compressed format, or `(1, 1)` for
+    /// uncompressed formats.
+    pub fn block_dimensions(&self) -> (u32, u32) {
+        match self {
+            TextureFormat::Astc4x4RgbaUnorm | TextureFormat::Astc4x4RgbaUnormSrgb => (4, 4),
+            TextureFormat::Astc5x4RgbaUnorm | TextureFormat::Astc5x4RgbaUnormSrgb => (5, 4),
+            TextureFormat::Astc5x5RgbaUnorm | TextureFormat::Astc5x5RgbaUnormSrgb => (5, 5),
+            TextureFormat::Astc6x5RgbaUnorm | TextureFormat::Astc6x5RgbaUnormSrgb => (6, 5),
+            TextureFormat::Astc6x6RgbaUnorm | TextureFormat::Astc6x6RgbaUnormSrgb => (6, 6),
+            TextureFormat::Astc8x5RgbaUnorm | TextureFormat::Astc8x5RgbaUnormSrgb => (8, 5),
+            TextureFormat::Astc8x6RgbaUnorm | TextureFormat::Astc8x6RgbaUnormSrgb => (8, 6),
+            TextureFormat::Astc8x8RgbaUnorm | TextureFormat::Astc8x8RgbaUnormSrgb => (8, 8),
+            TextureFormat::Astc10x5RgbaUnorm | TextureFormat::Astc10x5RgbaUnormSrgb => (10, 5),
+            TextureFormat::Astc10x6RgbaUnorm | TextureFormat::Astc10x6RgbaUnormSrgb => (10, 6),
+            TextureFormat::Astc10x8RgbaUnorm | TextureFormat::Astc10x8RgbaUnormSrgb => (10, 8),
+            TextureFormat::Astc10x10RgbaUnorm | TextureFormat::Astc10x10RgbaUnormSrgb => (10, 10),
+            TextureFormat::Astc12x10RgbaUnorm | TextureFormat::Astc12x10RgbaUnormSrgb => (12, 10),
+            TextureFormat::Astc12x12RgbaUnorm | TextureFormat::Astc12x12RgbaUnormSrgb => (12, 12),
+            format if format.is_compressed() => (4, 4),
+            _ => (1, 1),
+        }
+    }
+
+    /// Returns the size in bytes of a block of a compressed format, or of a pixel for
+    /// uncompressed formats.
+    pub fn block_size(&self) -> usize {
+        match self.compression() {
+            None => self.pixel_size(),
+            Some(TextureCompression::Astc) => 16,
+            Some(_) => match self {
+                TextureFormat::Bc1RgbaUnorm
+                | TextureFormat::Bc1RgbaUnormSrgb
+                | TextureFormat::Bc4RUnorm
+                | TextureFormat::Bc4RSnorm
+                | TextureFormat::Etc2RgbUnorm
+                | TextureFormat::Etc2RgbUnormSrgb
+                | TextureFormat::Etc2RgbA1Unorm
+                | TextureFormat::Etc2RgbA1UnormSrgb
+                | TextureFormat::EacRUnorm
+                | TextureFormat::EacRSnorm => 8,
+                _ => 16,
+            },
+        }
+    }
+
+    /// Returns the block compression of the format, or `None` if it is uncompressed.
+    pub fn compression(&self) -> Option<TextureCompression> {
+        match *self as u32 {
+            38..=51 => Some(TextureCompression::Bc),
+            52..=61 => Some(TextureCompression::Etc2),
+            62..=89 => Some(TextureCompression::Astc),
+            _ => None,
+        }
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compression().is_some()
+    }
+
+    /// Returns true if the format stores sRGB encoded colors.
+    pub fn is_srgb(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Rgba8UnormSrgb
+                | TextureFormat::Bgra8UnormSrgb
+                | TextureFormat::Bc1RgbaUnormSrgb
+                | TextureFormat::Bc2RgbaUnormSrgb
+                | TextureFormat::Bc3RgbaUnormSrgb
+                | TextureFormat::Bc7RgbaUnormSrgb
+                | TextureFormat::Etc2RgbUnormSrgb
+                | TextureFormat::Etc2RgbA1UnormSrgb
+                | TextureFormat::Etc2RgbA8UnormSrgb
+        ) || (self.compression() == Some(TextureCompression::Astc) && *self as u32 % 2 == 1)
+    }
+
+    /// Returns the number of bytes of an image of the given size in pixels, rounded up to whole
+    /// blocks for compressed formats.
+    pub fn image_size(&self, width: u32, height: u32) -> usize {
+        let (block_width, block_height) = self.block_dimensions();
+        let blocks_x = (width + block_width - 1) / block_width;
+        let blocks_y = (height + block_height - 1) / block_height;
+        blocks_x as usize * blocks_y as usize * self.block_size()
+    }
+}
+
+/// The kind of block compression of a [`TextureFormat`]
+///
+/// Each kind needs a GPU feature, textures in formats the GPU lacks are decompressed when they
+/// are loaded, see [`Texture::decompress`](super::Texture::decompress).
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum TextureCompression {
+    Bc,
+    Etc2,
+    Astc,
 }
 
 impl Default for TextureFormat {
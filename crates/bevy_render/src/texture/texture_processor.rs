@@ -0,0 +1,262 @@
+use super::{image_texture_loader::FILE_EXTENSIONS, ImageType, Texture, TextureFormat};
+use bevy_asset::{AssetProcessor, ProcessContext};
+use bevy_utils::BoxedFuture;
+
+/// Compresses images into BC1 textures, or BC3 textures if they have transparent pixels
+///
+/// The compressed textures are stored as DDS files in place of the images, which the
+/// [`ImageTextureLoader`](super::ImageTextureLoader) reads like the images themselves. GPUs
+/// without support for BC formats decompress them on load. Images whose width or height is not
+/// a multiple of 4 or that aren't RGBA8 after decoding are kept as they are.
+#[derive(Clone, Default)]
+pub struct TextureCompressionProcessor;
+
+impl AssetProcessor for TextureCompressionProcessor {
+    fn process<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        process_context: &'a mut ProcessContext,
+    ) -> BoxedFuture<'a, Result<Vec<u8>, anyhow::Error>> {
+        Box::pin(async move {
+            let ext = process_context
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or_default();
+            let texture = Texture::from_buffer(bytes, ImageType::Extension(ext))?;
+            Ok(compress_texture(&texture).unwrap_or_else(|| bytes.to_vec()))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        FILE_EXTENSIONS
+    }
+
+    fn version(&self) -> u32 {
+        1
+    }
+}
+
+/// Compresses an RGBA8 texture into a DDS file, returning `None` if it can't be compressed.
+fn compress_texture(texture: &Texture) -> Option<Vec<u8>> {
+    let srgb = match texture.format {
+        TextureFormat::Rgba8UnormSrgb => true,
+        TextureFormat::Rgba8Unorm => false,
+        _ => return None,
+    };
+    let (width, height) = (texture.size.width as usize, texture.size.height as usize);
+    if width == 0 || height == 0 || width % 4 != 0 || height % 4 != 0 {
+        return None;
+    }
+    if texture.size.depth_or_array_layers != 1 || texture.mip_level_count != 1 {
+        return None;
+    }
+
+    let transparent = texture.data.chunks_exact(4).any(|pixel| pixel[3] < 255);
+    let mut data = Vec::with_capacity(width * height / if transparent { 1 } else { 2 });
+    let mut pixels = [[0; 4]; 16];
+    for block_y in (0..height).step_by(4) {
+        for block_x in (0..width).step_by(4) {
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                let offset = ((block_y + i / 4) * width + block_x + i % 4) * 4;
+                pixel.copy_from_slice(&texture.data[offset..offset + 4]);
+            }
+            if transparent {
+                data.extend_from_slice(&encode_bc4_alpha(&pixels));
+            }
+            data.extend_from_slice(&encode_bc1_colors(&pixels));
+        }
+    }
+
+    // the DXGI formats of BC1 and BC3, which are one above their UNORM variants in sRGB
+    let dxgi_format = if transparent { 77 } else { 71 } + srgb as u32;
+    Some(write_dds(width as u32, height as u32, dxgi_format, &data))
+}
+
+/// Writes a DDS file with a DX10 header, holding a single 2D image.
+fn write_dds(width: u32, height: u32, dxgi_format: u32, data: &[u8]) -> Vec<u8> {
+    const DDSD_CAPS: u32 = 0x1;
+    const DDSD_HEIGHT: u32 = 0x2;
+    const DDSD_WIDTH: u32 = 0x4;
+    const DDSD_PIXELFORMAT: u32 = 0x1000;
+    const DDSD_LINEARSIZE: u32 = 0x8_0000;
+    const DDPF_FOURCC: u32 = 0x4;
+    const DDSCAPS_TEXTURE: u32 = 0x1000;
+    const D3D10_RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+
+    let mut header = [0u32; 31];
+    header[0] = 124;
+    header[1] = DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT | DDSD_LINEARSIZE;
+    header[2] = height;
+    header[3] = width;
+    header[4] = data.len() as u32;
+    // the pixel format starts at the 19th field
+    header[18] = 32;
+    header[19] = DDPF_FOURCC;
+    header[20] = u32::from_le_bytes(*b"DX10");
+    header[26] = DDSCAPS_TEXTURE;
+    let dx10_header = [dxgi_format, D3D10_RESOURCE_DIMENSION_TEXTURE2D, 0, 1, 0];
+
+    let mut bytes = Vec::with_capacity(4 + 124 + 20 + data.len());
+    bytes.extend_from_slice(b"DDS ");
+    for field in header.iter().chain(dx10_header.iter()) {
+        bytes.extend_from_slice(&field.to_le_bytes());
+    }
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+fn encode_rgb565(color: [u8; 3]) -> u16 {
+    let r = (color[0] as u16 * 31 + 127) / 255;
+    let g = (color[1] as u16 * 63 + 127) / 255;
+    let b = (color[2] as u16 * 31 + 127) / 255;
+    (r << 11) | (g << 5) | b
+}
+
+fn decode_rgb565(color: u16) -> [i32; 3] {
+    let r = (color >> 11) as i32 & 0x1f;
+    let g = (color >> 5) as i32 & 0x3f;
+    let b = color as i32 & 0x1f;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+/// Encodes the colors of a block with the corners of their bounding box as endpoints.
+fn encode_bc1_colors(pixels: &[[u8; 4]; 16]) -> [u8; 8] {
+    let mut min = [255u8; 3];
+    let mut max = [0u8; 3];
+    for pixel in pixels.iter() {
+        for channel in 0..3 {
+            min[channel] = min[channel].min(pixel[channel]);
+            max[channel] = max[channel].max(pixel[channel]);
+        }
+    }
+    let mut color0 = encode_rgb565(max);
+    let mut color1 = encode_rgb565(min);
+    // the first endpoint has to be larger, or the block is decoded with three colors
+    if color0 < color1 {
+        std::mem::swap(&mut color0, &mut color1);
+    }
+
+    let mut indices = 0u32;
+    if color0 != color1 {
+        let endpoint0 = decode_rgb565(color0);
+        let endpoint1 = decode_rgb565(color1);
+        let mut palette = [endpoint0, endpoint1, [0; 3], [0; 3]];
+        for channel in 0..3 {
+            palette[2][channel] = (2 * endpoint0[channel] + endpoint1[channel]) / 3;
+            palette[3][channel] = (endpoint0[channel] + 2 * endpoint1[channel]) / 3;
+        }
+        for (i, pixel) in pixels.iter().enumerate() {
+            let index = nearest(palette.iter().map(|color| {
+                (0..3)
+                    .map(|channel| (color[channel] - pixel[channel] as i32).pow(2))
+                    .sum()
+            }));
+            indices |= (index as u32) << (2 * i);
+        }
+    }
+
+    let mut block = [0; 8];
+    block[0..2].copy_from_slice(&color0.to_le_bytes());
+    block[2..4].copy_from_slice(&color1.to_le_bytes());
+    block[4..8].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+/// Encodes the alpha channel of a block like the alpha of BC3 blocks.
+fn encode_bc4_alpha(pixels: &[[u8; 4]; 16]) -> [u8; 8] {
+    let alpha0 = pixels.iter().map(|pixel| pixel[3]).max().unwrap();
+    let alpha1 = pixels.iter().map(|pixel| pixel[3]).min().unwrap();
+
+    let mut indices = 0u64;
+    if alpha0 != alpha1 {
+        let (alpha0, alpha1) = (alpha0 as i32, alpha1 as i32);
+        let mut palette = [alpha0, alpha1, 0, 0, 0, 0, 0, 0];
+        for (i, value) in palette.iter_mut().enumerate().skip(2) {
+            let weight = i as i32 - 1;
+            *value = ((7 - weight) * alpha0 + weight * alpha1) / 7;
+        }
+        for (i, pixel) in pixels.iter().enumerate() {
+            let index = nearest(palette.iter().map(|alpha| (alpha - pixel[3] as i32).pow(2)));
+            indices |= (index as u64) << (3 * i);
+        }
+    }
+
+    let mut block = [0; 8];
+    block[0] = alpha0;
+    block[1] = alpha1;
+    block[2..8].copy_from_slice(&indices.to_le_bytes()[..6]);
+    block
+}
+
+/// Returns the index of the smallest of `distances`.
+fn nearest(distances: impl Iterator<Item = i32>) -> usize {
+    distances
+        .enumerate()
+        .min_by_key(|(_, distance)| *distance)
+        .map_or(0, |(index, _)| index)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::texture::{dds_to_texture, Extent3d, TextureDimension};
+
+    fn texture(width: u32, height: u32, alpha: u8) -> Texture {
+        let mut data = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                data.extend_from_slice(&[(x * 16) as u8, (y * 16) as u8, 128, alpha]);
+            }
+        }
+        Texture::new(
+            Extent3d::new(width, height, 1),
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+        )
+    }
+
+    fn max_error(a: &Texture, b: &Texture) -> i32 {
+        a.data
+            .iter()
+            .zip(b.data.iter())
+            .map(|(a, b)| (*a as i32 - *b as i32).abs())
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn compresses_opaque_images_to_bc1() {
+        let source = texture(8, 8, 255);
+        let compressed = dds_to_texture(&compress_texture(&source).unwrap()).unwrap();
+        assert_eq!(compressed.format, TextureFormat::Bc1RgbaUnormSrgb);
+        assert_eq!(compressed.size, source.size);
+        assert_eq!(compressed.data.len(), 4 * 8);
+
+        let decompressed = compressed.decompress().unwrap();
+        assert!(max_error(&source, &decompressed) <= 16);
+    }
+
+    #[test]
+    fn compresses_transparent_images_to_bc3() {
+        let mut source = texture(4, 4, 255);
+        source.data[3] = 0;
+        let compressed = dds_to_texture(&compress_texture(&source).unwrap()).unwrap();
+        assert_eq!(compressed.format, TextureFormat::Bc3RgbaUnormSrgb);
+
+        let decompressed = compressed.decompress().unwrap();
+        assert_eq!(decompressed.data[3], 0);
+        assert_eq!(decompressed.data[7], 255);
+        assert!(max_error(&source, &decompressed) <= 16);
+    }
+
+    #[test]
+    fn keeps_images_that_are_not_made_of_blocks() {
+        assert!(compress_texture(&texture(6, 4, 255)).is_none());
+    }
+}
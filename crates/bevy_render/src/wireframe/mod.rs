@@ -3,6 +3,7 @@ use crate::{
     mesh::Indices,
     pipeline::{PipelineDescriptor, PipelineSpecialization, RenderPipeline},
     prelude::*,
+    render_graph::base::MainPass,
     shader::Shader,
 };
 use bevy_app::prelude::*;
@@ -59,18 +60,35 @@ impl Default for WireframeConfig {
 pub fn draw_wireframes_system(
     mut draw_context: DrawContext,
     msaa: Res<Msaa>,
+    hdr: Res<Hdr>,
     meshes: Res<Assets<Mesh>>,
     wireframe_config: Res<WireframeConfig>,
     mut query: QuerySet<(
-        QueryState<(&mut Draw, &mut RenderPipelines, &Handle<Mesh>, &Visible)>,
-        QueryState<(&mut Draw, &mut RenderPipelines, &Handle<Mesh>, &Visible), With<Wireframe>>,
+        QueryState<(
+            &mut Draw,
+            &mut RenderPipelines,
+            &Handle<Mesh>,
+            &Visible,
+            Option<&MainPass>,
+        )>,
+        QueryState<
+            (
+                &mut Draw,
+                &mut RenderPipelines,
+                &Handle<Mesh>,
+                &Visible,
+                Option<&MainPass>,
+            ),
+            With<Wireframe>,
+        >,
     )>,
 ) {
-    let iterator = |(mut draw, mut render_pipelines, mesh_handle, visible): (
+    let iterator = |(mut draw, mut render_pipelines, mesh_handle, visible, main_pass): (
         Mut<Draw>,
         Mut<RenderPipelines>,
         &Handle<Mesh>,
         &Visible,
+        Option<&MainPass>,
     )| {
         if !visible.is_visible {
             return;
@@ -96,6 +114,7 @@ pub fn draw_wireframes_system(
                     .map(|name| name.to_string())
                     .collect::<HashSet<String>>(),
                 vertex_buffer_layout: mesh.get_vertex_buffer_layout(),
+                hdr: hdr.enabled && main_pass.is_some(),
             },
         );
         render_pipeline.dynamic_bindings_generation =
@@ -2,6 +2,7 @@ mod command;
 mod dynamic_scene;
 mod scene;
 mod scene_loader;
+mod scene_saver;
 mod scene_spawner;
 pub mod serde;
 
@@ -9,6 +10,7 @@ pub use command::*;
 pub use dynamic_scene::*;
 pub use scene::*;
 pub use scene_loader::*;
+pub use scene_saver::*;
 pub use scene_spawner::*;
 
 pub mod prelude {
@@ -30,6 +32,7 @@ impl Plugin for ScenePlugin {
         app.add_asset::<DynamicScene>()
             .add_asset::<Scene>()
             .init_asset_loader::<SceneLoader>()
+            .init_asset_saver::<SceneSaver>()
             .init_resource::<SceneSpawner>()
             .add_system_to_stage(
                 CoreStage::PreUpdate,
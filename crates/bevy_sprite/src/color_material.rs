@@ -127,6 +127,7 @@ pub(crate) fn material_texture_detection_system(
                         .remove(handle);
                 }
             }
+            AssetEvent::LoadedWithDependencies { .. } => {}
         }
     }
 
@@ -138,6 +139,7 @@ pub(crate) fn material_texture_detection_system(
             | AssetEvent::Removed { handle } => {
                 changed_textures.insert(handle);
             }
+            AssetEvent::LoadedWithDependencies { .. } => {}
         }
     }
 
@@ -5,6 +5,7 @@ use crate::{
 use bevy_asset::Handle;
 use bevy_ecs::bundle::Bundle;
 use bevy_render::{
+    draw::NoFrustumCulling,
     mesh::Mesh,
     pipeline::{RenderPipeline, RenderPipelines},
     prelude::{Draw, Visible},
@@ -16,6 +17,8 @@ use bevy_transform::prelude::{GlobalTransform, Transform};
 pub struct SpriteBundle {
     pub sprite: Sprite,
     pub mesh: Handle<Mesh>, // TODO: maybe abstract this out
+    /// The quad mesh is resized when drawn, so it can't be used for frustum culling
+    pub no_frustum_culling: NoFrustumCulling,
     pub material: Handle<ColorMaterial>,
     pub main_pass: MainPass,
     pub draw: Draw,
@@ -29,6 +32,7 @@ impl Default for SpriteBundle {
     fn default() -> Self {
         Self {
             mesh: QUAD_HANDLE.typed(),
+            no_frustum_culling: NoFrustumCulling,
             render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
                 SPRITE_PIPELINE_HANDLE.typed(),
             )]),
@@ -60,6 +64,8 @@ pub struct SpriteSheetBundle {
     pub render_pipelines: RenderPipelines,
     pub main_pass: MainPass,
     pub mesh: Handle<Mesh>, // TODO: maybe abstract this out
+    /// The quad mesh is resized when drawn, so it can't be used for frustum culling
+    pub no_frustum_culling: NoFrustumCulling,
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 }
@@ -76,6 +82,7 @@ impl Default for SpriteSheetBundle {
             },
             main_pass: MainPass,
             mesh: QUAD_HANDLE.typed(),
+            no_frustum_culling: NoFrustumCulling,
             draw: Default::default(),
             sprite: Default::default(),
             texture_atlas: Default::default(),
@@ -28,17 +28,21 @@ pub use texture_atlas_builder::*;
 
 use bevy_app::prelude::*;
 use bevy_asset::{AddAsset, Assets, Handle, HandleUntyped};
-use bevy_ecs::component::{ComponentDescriptor, StorageType};
+use bevy_ecs::{
+    component::{ComponentDescriptor, StorageType},
+    schedule::ParallelSystemDescriptorCoercion,
+};
 use bevy_math::Vec2;
 use bevy_reflect::TypeUuid;
 use bevy_render::{
     draw::OutsideFrustum,
     mesh::{shape, Mesh},
-    pipeline::PipelineDescriptor,
+    pipeline::{InstancedPipelines, PipelineDescriptor},
     render_graph::RenderGraph,
     shader::{asset_shader_defs_system, Shader},
+    RenderStage, RenderSystem,
 };
-use sprite::sprite_system;
+use sprite::{sprite_instance_system, sprite_system};
 
 #[derive(Debug, Clone)]
 pub struct SpriteSettings {
@@ -70,6 +74,10 @@ impl Plugin for SpritePlugin {
             .register_type::<Sprite>()
             .register_type::<SpriteResizeMode>()
             .add_system_to_stage(CoreStage::PostUpdate, sprite_system)
+            .add_system_to_stage(
+                RenderStage::Draw,
+                sprite_instance_system.after(RenderSystem::DrawRenderPipelines),
+            )
             .add_system_to_stage(CoreStage::PostUpdate, material_texture_detection_system)
             .add_system_to_stage(
                 CoreStage::PostUpdate,
@@ -103,6 +111,10 @@ impl Plugin for SpritePlugin {
             .unwrap();
         let mut shaders = world_cell.get_resource_mut::<Assets<Shader>>().unwrap();
         crate::render::add_sprite_graph(&mut render_graph, &mut pipelines, &mut shaders);
+        world_cell
+            .get_resource_mut::<InstancedPipelines>()
+            .unwrap()
+            .add(SPRITE_PIPELINE_HANDLE.typed());
 
         let mut meshes = world_cell.get_resource_mut::<Assets<Mesh>>().unwrap();
         let mut color_materials = world_cell
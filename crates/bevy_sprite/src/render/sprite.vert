@@ -10,6 +10,13 @@ layout(set = 0, binding = 0) uniform CameraViewProj {
     mat4 ViewProj;
 };
 
+# ifdef INSTANCING
+// the model matrix of instanced sprites is scaled by their size, and mirrored when flipped
+layout(location = 4) in vec4 I_Model_0;
+layout(location = 5) in vec4 I_Model_1;
+layout(location = 6) in vec4 I_Model_2;
+layout(location = 7) in vec4 I_Model_3;
+# else
 layout(set = 2, binding = 0) uniform Transform {
     mat4 Model;
 };
@@ -17,8 +24,14 @@ layout(set = 2, binding = 1) uniform Sprite {
     vec2 size;
     uint flip;
 };
+# endif
 
 void main() {
+# ifdef INSTANCING
+    mat4 Model = mat4(I_Model_0, I_Model_1, I_Model_2, I_Model_3);
+    v_Uv = Vertex_Uv;
+    gl_Position = ViewProj * Model * vec4(Vertex_Position, 1.0);
+# else
     vec2 uv = Vertex_Uv;
 
     // Flip the sprite if necessary by flipping the UVs
@@ -41,4 +54,5 @@ void main() {
 
     vec3 position = Vertex_Position * vec3(size, 1.0);
     gl_Position = ViewProj * Model * vec4(position, 1.0);
+# endif
 }
@@ -5,10 +5,10 @@ use bevy_ecs::{
     query::Without,
     system::{Query, Res},
 };
-use bevy_math::Vec2;
+use bevy_math::{Mat4, Vec2, Vec3};
 use bevy_reflect::{Reflect, ReflectDeserialize, TypeUuid};
 use bevy_render::{
-    draw::OutsideFrustum,
+    draw::{Draw, OutsideFrustum},
     renderer::{RenderResource, RenderResourceType, RenderResources},
     texture::Texture,
 };
@@ -104,3 +104,21 @@ pub fn sprite_system(
         }
     }
 }
+
+/// Scales the model matrix of sprites drawn with GPU instancing by their size, and mirrors it when
+/// they are flipped, as the instanced sprite shader doesn't read the [`Sprite`] uniform.
+pub fn sprite_instance_system(mut query: Query<(&mut Draw, &Sprite)>) {
+    for (mut draw, sprite) in query.iter_mut() {
+        if let Some(model) = draw.instance {
+            let flip = |flipped: bool| if flipped { -1.0 } else { 1.0 };
+            draw.instance = Some(
+                model
+                    * Mat4::from_scale(Vec3::new(
+                        sprite.size.x * flip(sprite.flip_x),
+                        sprite.size.y * flip(sprite.flip_y),
+                        1.0,
+                    )),
+            );
+        }
+    }
+}
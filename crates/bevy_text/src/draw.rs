@@ -20,6 +20,8 @@ pub struct DrawableText<'a> {
     pub sections: &'a [TextSection],
     pub text_glyphs: &'a Vec<PositionedGlyph>,
     pub msaa: &'a Msaa,
+    /// Whether the text is drawn into the HDR texture of the main pass.
+    pub hdr: bool,
     pub font_quad_vertex_layout: &'a VertexBufferLayout,
     pub alignment_offset: Vec3,
 }
@@ -32,6 +34,7 @@ impl<'a> Drawable for DrawableText<'a> {
             &PipelineSpecialization {
                 sample_count: self.msaa.samples,
                 vertex_buffer_layout: self.font_quad_vertex_layout.clone(),
+                hdr: self.hdr,
                 ..Default::default()
             },
         )?;
@@ -9,7 +9,7 @@ use bevy_math::{Size, Vec3};
 use bevy_render::{
     draw::{DrawContext, Drawable, OutsideFrustum},
     mesh::Mesh,
-    prelude::{Draw, Msaa, Texture, Visible},
+    prelude::{Draw, Hdr, Msaa, Texture, Visible},
     render_graph::base::MainPass,
     renderer::RenderResourceBindings,
 };
@@ -61,6 +61,7 @@ impl Default for Text2dBundle {
 pub fn draw_text2d_system(
     mut context: DrawContext,
     msaa: Res<Msaa>,
+    hdr: Res<Hdr>,
     meshes: Res<Assets<Mesh>>,
     windows: Res<Windows>,
     mut render_resource_bindings: ResMut<RenderResourceBindings>,
@@ -109,6 +110,7 @@ pub fn draw_text2d_system(
                 global_transform: *global_transform,
                 scale_factor,
                 msaa: &msaa,
+                hdr: hdr.enabled,
                 text_glyphs: &text_glyphs.glyphs,
                 font_quad_vertex_layout: &font_quad_vertex_layout,
                 sections: &text.sections,
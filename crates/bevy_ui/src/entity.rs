@@ -7,7 +7,7 @@ use bevy_asset::Handle;
 use bevy_ecs::bundle::Bundle;
 use bevy_render::{
     camera::{Camera, DepthCalculation, OrthographicProjection, VisibleEntities, WindowOrigin},
-    draw::Draw,
+    draw::{Draw, NoFrustumCulling},
     mesh::Mesh,
     pipeline::{RenderPipeline, RenderPipelines},
     prelude::Visible,
@@ -21,6 +21,8 @@ pub struct NodeBundle {
     pub node: Node,
     pub style: Style,
     pub mesh: Handle<Mesh>, // TODO: maybe abstract this out
+    /// The quad mesh is resized when drawn, so it can't be used for frustum culling
+    pub no_frustum_culling: NoFrustumCulling,
     pub material: Handle<ColorMaterial>,
     pub draw: Draw,
     pub visible: Visible,
@@ -33,6 +35,7 @@ impl Default for NodeBundle {
     fn default() -> Self {
         NodeBundle {
             mesh: QUAD_HANDLE.typed(),
+            no_frustum_culling: NoFrustumCulling,
             render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
                 UI_PIPELINE_HANDLE.typed(),
             )]),
@@ -57,6 +60,8 @@ pub struct ImageBundle {
     //pub image: Image,
     //pub calculated_size: CalculatedSize,
     pub mesh: Handle<Mesh>, // TODO: maybe abstract this out
+    /// The quad mesh is resized when drawn, so it can't be used for frustum culling
+    pub no_frustum_culling: NoFrustumCulling,
     pub material: Handle<ColorMaterial>,
     pub draw: Draw,
     pub visible: Visible,
@@ -69,6 +74,7 @@ impl Default for ImageBundle {
     fn default() -> Self {
         ImageBundle {
             mesh: QUAD_HANDLE.typed(),
+            no_frustum_culling: NoFrustumCulling,
             render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
                 UI_PIPELINE_HANDLE.typed(),
             )]),
@@ -130,6 +136,8 @@ pub struct ButtonBundle {
     pub interaction: Interaction,
     pub focus_policy: FocusPolicy,
     pub mesh: Handle<Mesh>, // TODO: maybe abstract this out
+    /// The quad mesh is resized when drawn, so it can't be used for frustum culling
+    pub no_frustum_culling: NoFrustumCulling,
     pub material: Handle<ColorMaterial>,
     pub draw: Draw,
     pub visible: Visible,
@@ -143,6 +151,7 @@ impl Default for ButtonBundle {
         ButtonBundle {
             button: Button,
             mesh: QUAD_HANDLE.typed(),
+            no_frustum_culling: NoFrustumCulling,
             render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
                 UI_PIPELINE_HANDLE.typed(),
             )]),
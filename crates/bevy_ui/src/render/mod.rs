@@ -150,6 +150,13 @@ pub(crate) fn add_ui_graph(world: &mut World) {
         .add_node_edge(base::node::MAIN_PASS, node::UI_PASS)
         .unwrap();
 
+    // and after the main pass is post-processed into the swap chain when hdr is enabled
+    if graph.get_node_id(base::node::POST_PROCESS).is_ok() {
+        graph
+            .add_node_edge(base::node::POST_PROCESS, node::UI_PASS)
+            .unwrap();
+    }
+
     // setup ui camera
     graph.add_system_node(node::CAMERA_UI, CameraNode::new(camera::CAMERA_UI));
     graph.add_node_edge(node::CAMERA_UI, node::UI_PASS).unwrap();
@@ -173,6 +173,7 @@ pub fn draw_text_system(
                 global_transform: *global_transform,
                 scale_factor: scale_factor as f32,
                 msaa: &msaa,
+                hdr: false,
                 text_glyphs: &text_glyphs.glyphs,
                 font_quad_vertex_layout: &vertex_buffer_layout,
                 sections: &text.sections,
@@ -1,10 +1,12 @@
 pub mod diagnostic;
 pub mod renderer;
+mod wgpu_compute_pass;
 mod wgpu_render_pass;
 mod wgpu_renderer;
 mod wgpu_resources;
 mod wgpu_type_converter;
 
+pub use wgpu_compute_pass::*;
 pub use wgpu_render_pass::*;
 pub use wgpu_renderer::*;
 pub use wgpu_resources::*;
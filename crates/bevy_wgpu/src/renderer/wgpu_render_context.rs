@@ -1,10 +1,10 @@
 use super::WgpuRenderResourceContext;
-use crate::{wgpu_type_converter::WgpuInto, WgpuRenderPass, WgpuResourceRefs};
+use crate::{wgpu_type_converter::WgpuInto, WgpuComputePass, WgpuRenderPass, WgpuResourceRefs};
 
 use bevy_render::{
     pass::{
-        PassDescriptor, RenderPass, RenderPassColorAttachment, RenderPassDepthStencilAttachment,
-        TextureAttachment,
+        ComputePass, PassDescriptor, RenderPass, RenderPassColorAttachment,
+        RenderPassDepthStencilAttachment, TextureAttachment,
     },
     renderer::{
         BufferId, RenderContext, RenderResourceBinding, RenderResourceBindings,
@@ -198,6 +198,28 @@ impl RenderContext for WgpuRenderContext {
 
         self.command_encoder.set(encoder);
     }
+
+    fn begin_compute_pass(&mut self, run_pass: &mut dyn FnMut(&mut dyn ComputePass)) {
+        if !self.command_encoder.is_some() {
+            self.command_encoder.create(&self.device);
+        }
+        let resource_lock = self.render_resource_context.resources.read();
+        let refs = resource_lock.refs();
+        let mut encoder = self.command_encoder.take().unwrap();
+        {
+            let compute_pass =
+                encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            let mut wgpu_compute_pass = WgpuComputePass {
+                compute_pass,
+                render_context: self,
+                wgpu_resources: refs,
+            };
+
+            run_pass(&mut wgpu_compute_pass);
+        }
+
+        self.command_encoder.set(encoder);
+    }
 }
 
 pub fn create_render_pass<'a, 'b>(
@@ -4,20 +4,23 @@ use crate::wgpu_type_converter::OwnedWgpuVertexBufferLayout;
 use bevy_asset::{Assets, Handle, HandleUntyped};
 use bevy_render::{
     pipeline::{
-        BindGroupDescriptor, BindGroupDescriptorId, BindingShaderStage, PipelineDescriptor,
+        BindGroupDescriptor, BindGroupDescriptorId, BindType, BindingShaderStage,
+        ComputePipelineDescriptor, PipelineDescriptor,
     },
     renderer::{
         BindGroup, BufferId, BufferInfo, BufferMapMode, RenderResourceBinding,
         RenderResourceContext, RenderResourceId, SamplerId, TextureId,
     },
     shader::{glsl_to_spirv, Shader, ShaderError, ShaderSource},
-    texture::{Extent3d, SamplerDescriptor, TextureDescriptor},
+    texture::{
+        Extent3d, SamplerDescriptor, TextureCompression, TextureDescriptor, TextureDimension,
+        TextureFormat, TextureViewDimension,
+    },
 };
 use bevy_utils::tracing::trace;
 use bevy_window::{Window, WindowId};
 use futures_lite::future;
 use std::{
-    borrow::Cow,
     num::{NonZeroU32, NonZeroU64},
     ops::Range,
     sync::Arc,
@@ -164,13 +167,20 @@ impl WgpuRenderResourceContext {
 
         let source = buffers.get(&source_buffer).unwrap();
         let destination = textures.get(&destination_texture).unwrap();
+        // rows of compressed textures are rows of blocks
+        let (_, block_height) = self
+            .resources
+            .texture_descriptors
+            .read()
+            .get(&destination_texture)
+            .map_or((1, 1), |descriptor| descriptor.format.block_dimensions());
         command_encoder.copy_buffer_to_texture(
             wgpu::ImageCopyBuffer {
                 buffer: source,
                 layout: wgpu::ImageDataLayout {
                     offset: source_offset,
                     bytes_per_row: NonZeroU32::new(source_bytes_per_row),
-                    rows_per_image: NonZeroU32::new(size.height),
+                    rows_per_image: NonZeroU32::new(size.height / block_height),
                 },
             },
             wgpu::ImageCopyTexture {
@@ -203,17 +213,19 @@ impl WgpuRenderResourceContext {
             .bindings
             .iter()
             .map(|binding| {
-                let shader_stage = if binding.shader_stage
-                    == BindingShaderStage::VERTEX | BindingShaderStage::FRAGMENT
-                {
-                    wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT
-                } else if binding.shader_stage == BindingShaderStage::VERTEX {
-                    wgpu::ShaderStage::VERTEX
-                } else if binding.shader_stage == BindingShaderStage::FRAGMENT {
-                    wgpu::ShaderStage::FRAGMENT
-                } else {
+                let mut shader_stage = wgpu::ShaderStage::NONE;
+                if binding.shader_stage.contains(BindingShaderStage::VERTEX) {
+                    shader_stage |= wgpu::ShaderStage::VERTEX;
+                }
+                if binding.shader_stage.contains(BindingShaderStage::FRAGMENT) {
+                    shader_stage |= wgpu::ShaderStage::FRAGMENT;
+                }
+                if binding.shader_stage.contains(BindingShaderStage::COMPUTE) {
+                    shader_stage |= wgpu::ShaderStage::COMPUTE;
+                }
+                if shader_stage.is_empty() {
                     panic!("Invalid binding shader stage.")
-                };
+                }
                 wgpu::BindGroupLayoutEntry {
                     binding: binding.index,
                     visibility: shader_stage,
@@ -228,6 +240,48 @@ impl WgpuRenderResourceContext {
         };
         let bind_group_layout = self.device.create_bind_group_layout(&wgpu_descriptor);
         bind_group_layouts.insert(descriptor.id, bind_group_layout);
+
+        let view_dimensions = descriptor
+            .bindings
+            .iter()
+            .filter_map(|binding| match binding.bind_type {
+                BindType::Texture { view_dimension, .. } => Some((binding.index, view_dimension)),
+                _ => None,
+            })
+            .collect();
+        self.resources
+            .bind_group_layout_view_dimensions
+            .write()
+            .insert(descriptor.id, view_dimensions);
+    }
+
+    /// Creates a view of the texture with the given dimension, if it differs from the dimension
+    /// of its default view. Textures with six layers can be bound as cube maps this way.
+    fn create_texture_dimension_view(&self, texture: TextureId, dimension: TextureViewDimension) {
+        let texture_descriptors = self.resources.texture_descriptors.read();
+        let descriptor = match texture_descriptors.get(&texture) {
+            Some(descriptor) => descriptor,
+            None => return,
+        };
+        if default_view_dimension(descriptor) == dimension {
+            return;
+        }
+        let mut texture_dimension_views = self.resources.texture_dimension_views.write();
+        if texture_dimension_views.contains_key(&(texture, dimension)) {
+            return;
+        }
+        let textures = self.resources.textures.read();
+        let texture_view = textures[&texture].create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(dimension.wgpu_into()),
+            // a 2d view only covers the first layer of an array
+            array_layer_count: if dimension == TextureViewDimension::D2 {
+                NonZeroU32::new(1)
+            } else {
+                None
+            },
+            ..Default::default()
+        });
+        texture_dimension_views.insert((texture, dimension), texture_view);
     }
 
     fn try_next_swap_chain_texture(&self, window_id: bevy_window::WindowId) -> Option<TextureId> {
@@ -324,6 +378,10 @@ impl RenderResourceContext for WgpuRenderResourceContext {
         textures.remove(&texture);
         texture_views.remove(&texture);
         texture_descriptors.remove(&texture);
+        self.resources
+            .texture_dimension_views
+            .write()
+            .retain(|(id, _), _| *id != texture);
     }
 
     fn remove_sampler(&self, sampler: SamplerId) {
@@ -333,12 +391,17 @@ impl RenderResourceContext for WgpuRenderResourceContext {
 
     fn create_shader_module_from_source(&self, shader_handle: &Handle<Shader>, shader: &Shader) {
         let mut shader_modules = self.resources.shader_modules.write();
-        let spirv: Cow<[u32]> = shader.get_spirv(None).unwrap().into();
+        let source = match shader.source {
+            ShaderSource::Wgsl(_) => {
+                wgpu::ShaderSource::Wgsl(shader.get_wgsl(None).unwrap().into())
+            }
+            _ => wgpu::ShaderSource::SpirV(shader.get_spirv(None).unwrap().into()),
+        };
         let shader_module = self
             .device
             .create_shader_module(&wgpu::ShaderModuleDescriptor {
                 label: None,
-                source: wgpu::ShaderSource::SpirV(spirv),
+                source,
                 flags: Default::default(),
             });
         shader_modules.insert(shader_handle.clone_weak(), shader_module);
@@ -521,6 +584,47 @@ impl RenderResourceContext for WgpuRenderResourceContext {
         render_pipelines.insert(pipeline_handle, render_pipeline);
     }
 
+    fn create_compute_pipeline(
+        &self,
+        pipeline_handle: Handle<ComputePipelineDescriptor>,
+        pipeline_descriptor: &ComputePipelineDescriptor,
+        shaders: &Assets<Shader>,
+    ) {
+        let layout = pipeline_descriptor.get_layout().unwrap();
+        for bind_group_descriptor in layout.bind_groups.iter() {
+            self.create_bind_group_layout(bind_group_descriptor);
+        }
+
+        let bind_group_layouts = self.resources.bind_group_layouts.read();
+        let bind_group_layouts = layout
+            .bind_groups
+            .iter()
+            .map(|bind_group| bind_group_layouts.get(&bind_group.id).unwrap())
+            .collect::<Vec<&wgpu::BindGroupLayout>>();
+
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: bind_group_layouts.as_slice(),
+                push_constant_ranges: &[],
+            });
+
+        self.create_shader_module(&pipeline_descriptor.shader, shaders);
+
+        let shader_modules = self.resources.shader_modules.read();
+        let compute_pipeline =
+            self.device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: pipeline_descriptor.name.as_deref(),
+                    layout: Some(&pipeline_layout),
+                    module: shader_modules.get(&pipeline_descriptor.shader).unwrap(),
+                    entry_point: "main",
+                });
+        let mut compute_pipelines = self.resources.compute_pipelines.write();
+        compute_pipelines.insert(pipeline_handle, compute_pipeline);
+    }
+
     fn bind_group_descriptor_exists(
         &self,
         bind_group_descriptor_id: BindGroupDescriptorId,
@@ -542,7 +646,21 @@ impl RenderResourceContext for WgpuRenderResourceContext {
                 "start creating bind group for RenderResourceSet {:?}",
                 bind_group.id
             );
+            let view_dimensions = self.resources.bind_group_layout_view_dimensions.read();
+            let view_dimensions = view_dimensions.get(&bind_group_descriptor_id);
+            if let Some(view_dimensions) = view_dimensions {
+                for indexed_binding in bind_group.indexed_bindings.iter() {
+                    if let (RenderResourceBinding::Texture(texture), Some(dimension)) = (
+                        &indexed_binding.entry,
+                        view_dimensions.get(&indexed_binding.index),
+                    ) {
+                        self.create_texture_dimension_view(*texture, *dimension);
+                    }
+                }
+            }
+
             let texture_views = self.resources.texture_views.read();
+            let texture_dimension_views = self.resources.texture_dimension_views.read();
             let samplers = self.resources.samplers.read();
             let buffers = self.resources.buffers.read();
             let bind_group_layouts = self.resources.bind_group_layouts.read();
@@ -554,8 +672,14 @@ impl RenderResourceContext for WgpuRenderResourceContext {
                 .map(|indexed_binding| {
                     let wgpu_resource = match &indexed_binding.entry {
                         RenderResourceBinding::Texture(resource) => {
-                            let texture_view = texture_views
-                                .get(resource)
+                            let texture_view = view_dimensions
+                                .and_then(|view_dimensions| {
+                                    view_dimensions.get(&indexed_binding.index)
+                                })
+                                .and_then(|dimension| {
+                                    texture_dimension_views.get(&(*resource, *dimension))
+                                })
+                                .or_else(|| texture_views.get(resource))
                                 .unwrap_or_else(|| panic!("{:?}", resource));
                             wgpu::BindingResource::TextureView(texture_view)
                         }
@@ -669,6 +793,16 @@ impl RenderResourceContext for WgpuRenderResourceContext {
         (size + COPY_BYTES_PER_ROW_ALIGNMENT - 1) & !(COPY_BYTES_PER_ROW_ALIGNMENT - 1)
     }
 
+    fn is_texture_format_supported(&self, format: TextureFormat) -> bool {
+        let feature = match format.compression() {
+            None => return true,
+            Some(TextureCompression::Bc) => wgpu::Features::TEXTURE_COMPRESSION_BC,
+            Some(TextureCompression::Etc2) => wgpu::Features::TEXTURE_COMPRESSION_ETC2,
+            Some(TextureCompression::Astc) => wgpu::Features::TEXTURE_COMPRESSION_ASTC_LDR,
+        };
+        self.device.features().contains(feature)
+    }
+
     fn get_aligned_uniform_size(&self, size: usize, dynamic: bool) -> usize {
         if dynamic {
             (size + BIND_BUFFER_ALIGNMENT - 1) & !(BIND_BUFFER_ALIGNMENT - 1)
@@ -682,13 +816,25 @@ impl RenderResourceContext for WgpuRenderResourceContext {
         shader: &Shader,
         macros: Option<&[String]>,
     ) -> Result<Shader, ShaderError> {
-        let spirv_data = match shader.source {
-            ShaderSource::Spirv(ref bytes) => bytes.clone(),
-            ShaderSource::Glsl(ref source) => glsl_to_spirv(source, shader.stage, macros)?,
+        let source = match shader.source {
+            ShaderSource::Spirv(ref bytes) => ShaderSource::Spirv(bytes.clone()),
+            ShaderSource::Glsl(ref source) => {
+                ShaderSource::Spirv(glsl_to_spirv(source, shader.stage, macros)?)
+            }
+            ShaderSource::Wgsl(_) => ShaderSource::Wgsl(shader.get_wgsl(macros)?),
         };
-        Ok(Shader {
-            source: ShaderSource::Spirv(spirv_data),
-            ..*shader
-        })
+        Ok(Shader::new(shader.stage, source))
+    }
+}
+
+/// Returns the dimension of the view that wgpu creates by default for a texture.
+fn default_view_dimension(descriptor: &TextureDescriptor) -> TextureViewDimension {
+    match descriptor.dimension {
+        TextureDimension::D1 => TextureViewDimension::D1,
+        TextureDimension::D2 if descriptor.size.depth_or_array_layers > 1 => {
+            TextureViewDimension::D2Array
+        }
+        TextureDimension::D2 => TextureViewDimension::D2,
+        TextureDimension::D3 => TextureViewDimension::D3,
     }
 }
@@ -56,11 +56,19 @@ impl WgpuRenderer {
         #[cfg(not(feature = "trace"))]
         let trace_path = None;
 
+        // texture compression is enabled whenever the adapter supports it, textures in
+        // unsupported formats are decompressed on the CPU instead
+        let texture_compression = wgpu::Features::TEXTURE_COMPRESSION_BC
+            | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+            | wgpu::Features::TEXTURE_COMPRESSION_ASTC_LDR;
+        let features: wgpu::Features = options.features.wgpu_into();
+        let features = features | (adapter.features() & texture_compression);
+
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: options.device_label.as_ref().map(|a| a.as_ref()),
-                    features: options.features.wgpu_into(),
+                    features,
                     limits: options.limits.wgpu_into(),
                 },
                 trace_path,
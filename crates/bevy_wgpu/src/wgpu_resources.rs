@@ -1,9 +1,9 @@
 use bevy_asset::{Handle, HandleUntyped};
 use bevy_render::{
-    pipeline::{BindGroupDescriptorId, PipelineDescriptor},
+    pipeline::{BindGroupDescriptorId, ComputePipelineDescriptor, PipelineDescriptor},
     renderer::{BindGroupId, BufferId, BufferInfo, RenderResourceId, SamplerId, TextureId},
     shader::Shader,
-    texture::TextureDescriptor,
+    texture::{TextureDescriptor, TextureViewDimension},
 };
 use bevy_utils::HashMap;
 use bevy_window::WindowId;
@@ -50,6 +50,8 @@ pub struct WgpuResourcesReadLock<'a> {
     pub swap_chain_frames: RwLockReadGuard<'a, HashMap<TextureId, wgpu::SwapChainFrame>>,
     pub render_pipelines:
         RwLockReadGuard<'a, HashMap<Handle<PipelineDescriptor>, wgpu::RenderPipeline>>,
+    pub compute_pipelines:
+        RwLockReadGuard<'a, HashMap<Handle<ComputePipelineDescriptor>, wgpu::ComputePipeline>>,
     pub bind_groups: RwLockReadGuard<'a, HashMap<BindGroupDescriptorId, WgpuBindGroupInfo>>,
     pub used_bind_group_sender: Sender<BindGroupId>,
 }
@@ -61,6 +63,7 @@ impl<'a> WgpuResourcesReadLock<'a> {
             textures: &self.textures,
             swap_chain_frames: &self.swap_chain_frames,
             render_pipelines: &self.render_pipelines,
+            compute_pipelines: &self.compute_pipelines,
             bind_groups: &self.bind_groups,
             used_bind_group_sender: &self.used_bind_group_sender,
         }
@@ -75,6 +78,7 @@ pub struct WgpuResourceRefs<'a> {
     pub textures: &'a HashMap<TextureId, wgpu::TextureView>,
     pub swap_chain_frames: &'a HashMap<TextureId, wgpu::SwapChainFrame>,
     pub render_pipelines: &'a HashMap<Handle<PipelineDescriptor>, wgpu::RenderPipeline>,
+    pub compute_pipelines: &'a HashMap<Handle<ComputePipelineDescriptor>, wgpu::ComputePipeline>,
     pub bind_groups: &'a HashMap<BindGroupDescriptorId, WgpuBindGroupInfo>,
     pub used_bind_group_sender: &'a Sender<BindGroupId>,
 }
@@ -89,11 +93,20 @@ pub struct WgpuResources {
     pub buffers: Arc<RwLock<HashMap<BufferId, Arc<wgpu::Buffer>>>>,
     pub texture_views: Arc<RwLock<HashMap<TextureId, wgpu::TextureView>>>,
     pub textures: Arc<RwLock<HashMap<TextureId, wgpu::Texture>>>,
+    /// Views of textures with another dimension than their default view, created for the
+    /// bindings that need them, like cube views of textures with six layers
+    pub texture_dimension_views:
+        Arc<RwLock<HashMap<(TextureId, TextureViewDimension), wgpu::TextureView>>>,
     pub samplers: Arc<RwLock<HashMap<SamplerId, wgpu::Sampler>>>,
     pub shader_modules: Arc<RwLock<HashMap<Handle<Shader>, wgpu::ShaderModule>>>,
     pub render_pipelines: Arc<RwLock<HashMap<Handle<PipelineDescriptor>, wgpu::RenderPipeline>>>,
+    pub compute_pipelines:
+        Arc<RwLock<HashMap<Handle<ComputePipelineDescriptor>, wgpu::ComputePipeline>>>,
     pub bind_groups: Arc<RwLock<HashMap<BindGroupDescriptorId, WgpuBindGroupInfo>>>,
     pub bind_group_layouts: Arc<RwLock<HashMap<BindGroupDescriptorId, wgpu::BindGroupLayout>>>,
+    /// The view dimension of the texture bindings of each bind group layout, by binding index
+    pub bind_group_layout_view_dimensions:
+        Arc<RwLock<HashMap<BindGroupDescriptorId, HashMap<u32, TextureViewDimension>>>>,
     pub asset_resources: Arc<RwLock<HashMap<(HandleUntyped, u64), RenderResourceId>>>,
     pub bind_group_counter: BindGroupCounter,
 }
@@ -105,6 +118,7 @@ impl WgpuResources {
             textures: self.texture_views.read(),
             swap_chain_frames: self.swap_chain_frames.read(),
             render_pipelines: self.render_pipelines.read(),
+            compute_pipelines: self.compute_pipelines.read(),
             bind_groups: self.bind_groups.read(),
             used_bind_group_sender: self.bind_group_counter.used_bind_group_sender.clone(),
         }
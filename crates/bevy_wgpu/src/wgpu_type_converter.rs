@@ -342,6 +342,58 @@ impl WgpuFrom<TextureFormat> for wgpu::TextureFormat {
             TextureFormat::Depth32Float => wgpu::TextureFormat::Depth32Float,
             TextureFormat::Depth24Plus => wgpu::TextureFormat::Depth24Plus,
             TextureFormat::Depth24PlusStencil8 => wgpu::TextureFormat::Depth24PlusStencil8,
+            TextureFormat::Bc1RgbaUnorm => wgpu::TextureFormat::Bc1RgbaUnorm,
+            TextureFormat::Bc1RgbaUnormSrgb => wgpu::TextureFormat::Bc1RgbaUnormSrgb,
+            TextureFormat::Bc2RgbaUnorm => wgpu::TextureFormat::Bc2RgbaUnorm,
+            TextureFormat::Bc2RgbaUnormSrgb => wgpu::TextureFormat::Bc2RgbaUnormSrgb,
+            TextureFormat::Bc3RgbaUnorm => wgpu::TextureFormat::Bc3RgbaUnorm,
+            TextureFormat::Bc3RgbaUnormSrgb => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+            TextureFormat::Bc4RUnorm => wgpu::TextureFormat::Bc4RUnorm,
+            TextureFormat::Bc4RSnorm => wgpu::TextureFormat::Bc4RSnorm,
+            TextureFormat::Bc5RgUnorm => wgpu::TextureFormat::Bc5RgUnorm,
+            TextureFormat::Bc5RgSnorm => wgpu::TextureFormat::Bc5RgSnorm,
+            TextureFormat::Bc6hRgbUfloat => wgpu::TextureFormat::Bc6hRgbUfloat,
+            TextureFormat::Bc6hRgbSfloat => wgpu::TextureFormat::Bc6hRgbSfloat,
+            TextureFormat::Bc7RgbaUnorm => wgpu::TextureFormat::Bc7RgbaUnorm,
+            TextureFormat::Bc7RgbaUnormSrgb => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+            TextureFormat::Etc2RgbUnorm => wgpu::TextureFormat::Etc2RgbUnorm,
+            TextureFormat::Etc2RgbUnormSrgb => wgpu::TextureFormat::Etc2RgbUnormSrgb,
+            TextureFormat::Etc2RgbA1Unorm => wgpu::TextureFormat::Etc2RgbA1Unorm,
+            TextureFormat::Etc2RgbA1UnormSrgb => wgpu::TextureFormat::Etc2RgbA1UnormSrgb,
+            TextureFormat::Etc2RgbA8Unorm => wgpu::TextureFormat::Etc2RgbA8Unorm,
+            TextureFormat::Etc2RgbA8UnormSrgb => wgpu::TextureFormat::Etc2RgbA8UnormSrgb,
+            TextureFormat::EacRUnorm => wgpu::TextureFormat::EacRUnorm,
+            TextureFormat::EacRSnorm => wgpu::TextureFormat::EacRSnorm,
+            TextureFormat::EacRgUnorm => wgpu::TextureFormat::EacRgUnorm,
+            TextureFormat::EacRgSnorm => wgpu::TextureFormat::EacRgSnorm,
+            TextureFormat::Astc4x4RgbaUnorm => wgpu::TextureFormat::Astc4x4RgbaUnorm,
+            TextureFormat::Astc4x4RgbaUnormSrgb => wgpu::TextureFormat::Astc4x4RgbaUnormSrgb,
+            TextureFormat::Astc5x4RgbaUnorm => wgpu::TextureFormat::Astc5x4RgbaUnorm,
+            TextureFormat::Astc5x4RgbaUnormSrgb => wgpu::TextureFormat::Astc5x4RgbaUnormSrgb,
+            TextureFormat::Astc5x5RgbaUnorm => wgpu::TextureFormat::Astc5x5RgbaUnorm,
+            TextureFormat::Astc5x5RgbaUnormSrgb => wgpu::TextureFormat::Astc5x5RgbaUnormSrgb,
+            TextureFormat::Astc6x5RgbaUnorm => wgpu::TextureFormat::Astc6x5RgbaUnorm,
+            TextureFormat::Astc6x5RgbaUnormSrgb => wgpu::TextureFormat::Astc6x5RgbaUnormSrgb,
+            TextureFormat::Astc6x6RgbaUnorm => wgpu::TextureFormat::Astc6x6RgbaUnorm,
+            TextureFormat::Astc6x6RgbaUnormSrgb => wgpu::TextureFormat::Astc6x6RgbaUnormSrgb,
+            TextureFormat::Astc8x5RgbaUnorm => wgpu::TextureFormat::Astc8x5RgbaUnorm,
+            TextureFormat::Astc8x5RgbaUnormSrgb => wgpu::TextureFormat::Astc8x5RgbaUnormSrgb,
+            TextureFormat::Astc8x6RgbaUnorm => wgpu::TextureFormat::Astc8x6RgbaUnorm,
+            TextureFormat::Astc8x6RgbaUnormSrgb => wgpu::TextureFormat::Astc8x6RgbaUnormSrgb,
+            TextureFormat::Astc8x8RgbaUnorm => wgpu::TextureFormat::Astc8x8RgbaUnorm,
+            TextureFormat::Astc8x8RgbaUnormSrgb => wgpu::TextureFormat::Astc8x8RgbaUnormSrgb,
+            TextureFormat::Astc10x5RgbaUnorm => wgpu::TextureFormat::Astc10x5RgbaUnorm,
+            TextureFormat::Astc10x5RgbaUnormSrgb => wgpu::TextureFormat::Astc10x5RgbaUnormSrgb,
+            TextureFormat::Astc10x6RgbaUnorm => wgpu::TextureFormat::Astc10x6RgbaUnorm,
+            TextureFormat::Astc10x6RgbaUnormSrgb => wgpu::TextureFormat::Astc10x6RgbaUnormSrgb,
+            TextureFormat::Astc10x8RgbaUnorm => wgpu::TextureFormat::Astc10x8RgbaUnorm,
+            TextureFormat::Astc10x8RgbaUnormSrgb => wgpu::TextureFormat::Astc10x8RgbaUnormSrgb,
+            TextureFormat::Astc10x10RgbaUnorm => wgpu::TextureFormat::Astc10x10RgbaUnorm,
+            TextureFormat::Astc10x10RgbaUnormSrgb => wgpu::TextureFormat::Astc10x10RgbaUnormSrgb,
+            TextureFormat::Astc12x10RgbaUnorm => wgpu::TextureFormat::Astc12x10RgbaUnorm,
+            TextureFormat::Astc12x10RgbaUnormSrgb => wgpu::TextureFormat::Astc12x10RgbaUnormSrgb,
+            TextureFormat::Astc12x12RgbaUnorm => wgpu::TextureFormat::Astc12x12RgbaUnorm,
+            TextureFormat::Astc12x12RgbaUnormSrgb => wgpu::TextureFormat::Astc12x12RgbaUnormSrgb,
         }
     }
 }
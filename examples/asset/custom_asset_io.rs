@@ -1,5 +1,5 @@
 use bevy::{
-    asset::{AssetIo, AssetIoError},
+    asset::{AssetIo, AssetIoError, AssetReader},
     prelude::*,
     utils::BoxedFuture,
 };
@@ -18,6 +18,20 @@ impl AssetIo for CustomAssetIo {
         self.0.load_path(path)
     }
 
+    fn open_path(&self, path: &Path) -> Result<Box<dyn AssetReader>, AssetIoError> {
+        info!("open_path({:?})", path);
+        self.0.open_path(path)
+    }
+
+    fn save_path<'a>(
+        &'a self,
+        path: &'a Path,
+        bytes: &'a [u8],
+    ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+        info!("save_path({:?})", path);
+        self.0.save_path(path, bytes)
+    }
+
     fn read_directory(
         &self,
         path: &Path,